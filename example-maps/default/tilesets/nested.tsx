@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.5" tiledversion="1.7.2" name="NestedTileset" tilewidth="16" tileheight="16" tilecount="256" columns="16">
+ <image source="../tiles.png" trans="ff00ff" width="256" height="256"/>
+</tileset>
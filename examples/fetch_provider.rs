@@ -0,0 +1,101 @@
+//! Loading a map in an environment where files can only be fetched asynchronously,
+//! e.g. a browser game compiled to wasm32-unknown-unknown.
+//!
+//! Providers are called synchronously while the map is loaded, so they can't wait for
+//! a download. Instead, the provider below remembers which files were missing,
+//! the game downloads them and then simply tries again.
+//! All files that were already downloaded are kept, so every file is only fetched once.
+//!
+//! This example doesn't need the `fs` feature. In a browser, `fetch` would be
+//! implemented with `window.fetch()` (e.g. via wasm-bindgen-futures) instead.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Files that were already downloaded, and the files that were missing while loading.
+#[derive(Default)]
+struct Downloads {
+    files: HashMap<PathBuf, Vec<u8>>,
+    missing: Vec<PathBuf>,
+}
+
+/// [tego::Provider] that serves downloaded files.
+#[derive(Clone, Default)]
+struct FetchProvider(Rc<RefCell<Downloads>>);
+
+impl tego::Provider for FetchProvider {
+    fn read(&mut self, path: &Path) -> tego::Result<Vec<u8>> {
+        let mut downloads = self.0.borrow_mut();
+        match downloads.files.get(path) {
+            Some(data) => Ok(data.clone()),
+            None => {
+                downloads.missing.push(path.to_owned());
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("'{}' was not downloaded yet", path.display()),
+                )
+                .into())
+            }
+        }
+    }
+}
+
+impl FetchProvider {
+    /// Download all files that were missing during the last attempt.
+    /// Returns false if no file was missing.
+    async fn fetch_missing(&self) -> tego::Result<bool> {
+        let missing = std::mem::take(&mut self.0.borrow_mut().missing);
+        for path in &missing {
+            let data = fetch(path).await?;
+            self.0.borrow_mut().files.insert(path.clone(), data);
+        }
+        Ok(!missing.is_empty())
+    }
+}
+
+/// Stand-in for an HTTP request.
+async fn fetch(path: &Path) -> tego::Result<Vec<u8>> {
+    println!("Fetching {}", path.display());
+    Ok(std::fs::read(path)?)
+}
+
+async fn load_map(path: &str) -> tego::Result<tego::Map> {
+    let provider = FetchProvider::default();
+    let mut loader = tego::ResourceManager::new(tego::LazyLoader {}, provider.clone());
+    loop {
+        match tego::Map::from_file_with_loader(Path::new(path), &mut loader) {
+            Ok(map) => return Ok(map),
+            Err(e) => {
+                if !provider.fetch_missing().await? {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+fn main() -> tego::Result<()> {
+    let map = block_on(load_map("example-maps/default/with_extern_tileset.tmx"))?;
+    println!(
+        "Loaded map with {} layers and tileset '{}'",
+        map.layers().len(),
+        map.tilesets[0].name
+    );
+    Ok(())
+}
+
+/// Minimal executor, a browser would run the future with `wasm_bindgen_futures::spawn_local`.
+/// The futures in this example never wait, so they are polled to completion right away.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
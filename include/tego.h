@@ -0,0 +1,61 @@
+/* C interface of tego, see src/ffi.rs for the documentation of each function.
+ *
+ * Build the library with
+ *     cargo build --release --features ffi
+ * and link against libtego.so (tego.dll on Windows) or the static libtego.a (tego.lib).
+ */
+#ifndef TEGO_H
+#define TEGO_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct TegoMap TegoMap;
+typedef struct TegoLayer TegoLayer;
+typedef struct TegoObject TegoObject;
+typedef struct TegoProperties TegoProperties;
+
+#define TEGO_LAYER_TILE 0
+#define TEGO_LAYER_OBJECT 1
+#define TEGO_LAYER_IMAGE 2
+#define TEGO_LAYER_GROUP 3
+
+TegoMap *tego_map_load(const char *path);
+void tego_map_free(TegoMap *map);
+const char *tego_last_error(void);
+
+void tego_map_size(const TegoMap *map, int32_t *width, int32_t *height);
+void tego_map_tile_size(const TegoMap *map, int32_t *width, int32_t *height);
+size_t tego_map_layer_count(const TegoMap *map);
+const TegoLayer *tego_map_layer(const TegoMap *map, size_t index);
+const TegoProperties *tego_map_properties(const TegoMap *map);
+
+int tego_layer_kind(const TegoLayer *layer);
+size_t tego_layer_name(const TegoLayer *layer, char *buffer, size_t size);
+const TegoProperties *tego_layer_properties(const TegoLayer *layer);
+size_t tego_group_layer_count(const TegoLayer *layer);
+const TegoLayer *tego_group_layer(const TegoMap *map, const TegoLayer *layer, size_t index);
+uint32_t tego_tile_layer_tile(const TegoLayer *layer, int32_t x, int32_t y);
+size_t tego_object_layer_object_count(const TegoLayer *layer);
+const TegoObject *tego_object_layer_object(const TegoLayer *layer, size_t index);
+
+size_t tego_object_id(const TegoObject *object);
+size_t tego_object_name(const TegoObject *object, char *buffer, size_t size);
+void tego_object_bounds(const TegoObject *object, float *x, float *y, float *width, float *height);
+const TegoProperties *tego_object_properties(const TegoObject *object);
+
+bool tego_property_int(const TegoProperties *properties, const char *name, int64_t *value);
+bool tego_property_float(const TegoProperties *properties, const char *name, double *value);
+bool tego_property_bool(const TegoProperties *properties, const char *name, bool *value);
+ptrdiff_t tego_property_string(const TegoProperties *properties, const char *name, char *buffer, size_t size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,41 @@
+//! Printing tile layers as text for debugging, see [TileLayer::render_ascii].
+
+use crate::{TileLayer, GID};
+
+impl TileLayer {
+    /// Draw this layer as text, one line per row and one character per tile,
+    /// e.g. to look at a loaded or generated map in the terminal.
+    ///
+    /// The `mapping` picks the character for each tile, empty cells are drawn as `.`.
+    /// The GIDs passed to it include the flip flags, use [GID::to_id] to ignore them.
+    ///
+    /// ```no_run
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/island/island.tmx"))?;
+    /// let tego::Layer::Tile(layer) = &map[map.layer_ids()[0]] else { unreachable!() };
+    /// // one character per class of the tiles
+    /// let text = layer.render_ascii(|gid| {
+    ///     match map.tile(gid).map(|tile| tile.class().as_str()) {
+    ///         Some("water") => '~',
+    ///         Some("wall") => '#',
+    ///         _ => ' ',
+    ///     }
+    /// });
+    /// println!("{}", text);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn render_ascii(&self, mut mapping: impl FnMut(GID) -> char) -> String {
+        let width = self.size.x.max(0) as usize;
+        let mut text = String::with_capacity(self.tiles().len() + self.size.y.max(0) as usize);
+        for (index, tile) in self.tiles().enumerate() {
+            if index > 0 && index % width == 0 {
+                text.push('\n');
+            }
+            text.push(tile.map_or('.', &mut mapping));
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+}
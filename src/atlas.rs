@@ -0,0 +1,140 @@
+//! Packing several tilesets of a map into a single texture atlas, see [Map::pack_tilesets].
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use crate::math::{ivec2, Rect};
+use crate::unknown::UnknownXml;
+use crate::{Error, ImageStorage, Map, PropertyContainer, Result, TileSet, GID};
+
+/// Layout of a tileset that combines several other tilesets, created by [Map::pack_tilesets].
+///
+/// tego doesn't touch image data, so composing the image of the atlas is up to the caller:
+/// copy each [AtlasTile] from its source image into the atlas image.
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    /// Size of the atlas image in pixels.
+    pub size: ivec2,
+
+    /// Where the tiles of the packed tilesets end up, in the order of their local ids
+    /// in the atlas.
+    pub tiles: Vec<AtlasTile>,
+}
+
+/// A single tile of an [Atlas].
+#[derive(Debug, Clone)]
+pub struct AtlasTile {
+    /// Path of the sprite sheet that contains the tile, see [TileSet::image_path].
+    pub image_path: PathBuf,
+
+    /// The sprite sheet of the packed tileset, if it was loaded.
+    pub image: ImageStorage,
+
+    /// Pixel rectangle of the tile in its sprite sheet.
+    pub source: Rect,
+
+    /// Pixel rectangle of the tile in the atlas image.
+    pub target: Rect,
+}
+
+impl Map {
+    /// Replace the tilesets with the given indices by a single tileset, e.g. to draw
+    /// the whole map with one texture.
+    ///
+    /// The new tileset is added after the remaining tilesets, with the tiles of the packed
+    /// tilesets in the order in which the indices are given. Its sprite sheet is expected
+    /// at `image_path`, its image is [Pending](ImageStorage::Pending) until the caller
+    /// composed it according to the returned [Atlas].
+    /// All GIDs of the map are rewritten to reference the new tileset.
+    ///
+    /// All packed tilesets must have the same tile size.
+    ///
+    /// # Panics
+    /// If one of the indices is out of range.
+    pub fn pack_tilesets(
+        &mut self,
+        tilesets: &[usize],
+        image_path: impl Into<PathBuf>,
+    ) -> Result<Atlas> {
+        let mut indices: Vec<usize> = Vec::with_capacity(tilesets.len());
+        for &index in tilesets {
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        let packed: Vec<&TileSet> = indices.iter().map(|&index| &self.tilesets[index]).collect();
+        let Some(first) = packed.first() else {
+            return Err(Error::UnsupportedFeature("Packing no tilesets".into()));
+        };
+        let tile_size = first.tile_size;
+        if packed.iter().any(|tileset| tileset.tile_size != tile_size) {
+            return Err(Error::UnsupportedFeature(
+                "Packing tilesets with different tile sizes".into(),
+            ));
+        }
+
+        let tilecount: usize = packed.iter().map(|tileset| tileset.tilecount).sum();
+        let columns = ((tilecount as f64).sqrt().ceil() as usize).max(1);
+        let rows = tilecount.div_ceil(columns);
+        let size = ivec2::new(columns as i32, rows as i32) * tile_size;
+
+        let image_path = image_path.into();
+        let mut atlas = TileSet {
+            firstgid: GID(NonZeroU32::MIN),
+            name: image_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().as_ref().into())
+                .unwrap_or_default(),
+            tile_size,
+            spacing: 0,
+            margin: 0,
+            tilecount,
+            columns,
+            image: ImageStorage::Pending,
+            image_path,
+            transparent: None,
+            image_size: Some(size),
+            source: None,
+            properties: PropertyContainer::new(),
+            tile_data: HashMap::new(),
+            rect_cache: None,
+            unknown: UnknownXml::default(),
+        };
+        let mut tiles = Vec::with_capacity(tilecount);
+        let mut gids = Vec::with_capacity(tilecount);
+        for tileset in &packed {
+            let offset = tiles.len() as u32;
+            for (&local_id, data) in &tileset.tile_data {
+                let mut data = data.clone();
+                for frame in &mut data.animation {
+                    frame.tile_id += offset;
+                }
+                atlas.tile_data.insert(local_id + offset, data);
+            }
+            for local_id in 0..tileset.tilecount as u32 {
+                gids.push(tileset.gid_of(local_id));
+                tiles.push(AtlasTile {
+                    image_path: tileset.image_path.clone(),
+                    image: tileset.image.clone(),
+                    source: tileset.tile_rect(local_id),
+                    target: atlas.tile_rect(tiles.len() as u32),
+                });
+            }
+        }
+
+        let atlas_gid = self.add_tileset(atlas);
+        let mapping = gids
+            .into_iter()
+            .enumerate()
+            .map(|(local_id, gid)| (gid, atlas_gid.with_id(atlas_gid.to_id() + local_id as u32)))
+            .collect();
+        self.remap_gids(&mapping);
+
+        indices.sort_unstable();
+        for index in indices.into_iter().rev() {
+            self.remove_tileset(index);
+        }
+        Ok(Atlas { size, tiles })
+    }
+}
@@ -0,0 +1,167 @@
+//! Command line tool for inspecting Tiled maps, built with the `cli` feature.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use tego::{Layer, Map, ObjectKind, SvgOptions};
+
+const USAGE: &str = "Usage: tego <command> <map.tmx> [output]
+
+Commands:
+  info      Print size, orientation, versions and tilesets of the map
+  layers    Print the layers of the map, including the layers inside of groups
+  objects   Print all objects with their id, position and type
+  validate  Check that all files and tiles referenced by the map exist
+  render    Write an SVG preview of the map to the output path";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, path, output) = match args.as_slice() {
+        [command, path] => (command.as_str(), Path::new(path), None),
+        [command, path, output] => (command.as_str(), Path::new(path), Some(Path::new(output))),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match (command, output) {
+        ("info", None) => Map::from_file(path).map(|map| info(&map)),
+        ("layers", None) => Map::from_file(path).map(|map| layers(&map)),
+        ("objects", None) => Map::from_file(path).map(|map| objects(&map)),
+        ("validate", None) => validate(path),
+        ("render", Some(output)) => Map::from_file(path).and_then(|map| {
+            let svg = map.to_svg(&SvgOptions::default());
+            std::fs::write(output, svg).map_err(tego::Error::from)?;
+            Ok(ExitCode::SUCCESS)
+        }),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        ExitCode::FAILURE
+    })
+}
+
+fn info(map: &Map) -> ExitCode {
+    println!("Version:     {}", map.version);
+    if let Some(editor_version) = map.editor_version {
+        println!("Tiled:       {}", editor_version);
+    }
+    println!("Orientation: {}", map.orientation);
+    println!("Size:        {}x{} tiles", map.size.x, map.size.y);
+    println!(
+        "Tile size:   {}x{} pixels",
+        map.tile_size.x, map.tile_size.y
+    );
+    println!("Layers:      {}", map.iter_layers().count());
+    for tileset in &map.tilesets {
+        println!(
+            "Tileset '{}': {} tiles starting at GID {}, {}",
+            tileset.name,
+            tileset.tilecount,
+            tileset.firstgid.to_id(),
+            tileset.image_path.display()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn layers(map: &Map) -> ExitCode {
+    let mut indent = 0;
+    for (layer, groups_left) in map.iter_layers() {
+        indent -= groups_left;
+        let kind = match layer {
+            Layer::Tile(layer) => format!("tiles, {}x{}", layer.size.x, layer.size.y),
+            Layer::Object(layer) => format!("objects, {}", layer.content.len()),
+            Layer::Image(_) => "image".to_owned(),
+            Layer::Group(layer) => format!("group, {} layers", layer.content.len()),
+            _ => "unknown".to_owned(),
+        };
+        println!("{}{} ({})", "  ".repeat(indent), layer.name(), kind);
+        if let Layer::Group(_) = layer {
+            indent += 1;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn objects(map: &Map) -> ExitCode {
+    for (layer, _) in map.iter_layers() {
+        let Layer::Object(layer) = layer else {
+            continue;
+        };
+        for object in &layer.content {
+            let kind = match object.kind {
+                _ if object.tile_id.is_some() => "tile",
+                ObjectKind::Rect => "rect",
+                ObjectKind::Ellipse => "ellipse",
+                ObjectKind::Point => "point",
+                ObjectKind::Polygon { .. } => "polygon",
+                ObjectKind::Polyline { .. } => "polyline",
+                ObjectKind::Text { .. } => "text",
+                _ => "unknown",
+            };
+            println!(
+                "{}\t{}\t{}\t{} {}\t{}\t{}",
+                layer.name, object.id, kind, object.pos.x, object.pos.y, object.type_, object.name
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Report all problems of the map, fails if there are any.
+fn validate(path: &Path) -> tego::Result<ExitCode> {
+    let mut problems = Vec::new();
+    for dependency in Map::list_dependencies(path)? {
+        if !dependency.path.exists() {
+            problems.push(format!(
+                "missing {:?} file {}",
+                dependency.kind,
+                dependency.path.display()
+            ));
+        }
+    }
+
+    match Map::from_file(path) {
+        Ok(map) => {
+            for (layer, _) in map.iter_layers() {
+                let gids: Vec<_> = match layer {
+                    Layer::Tile(layer) => layer.occupied_tiles().map(|(_, gid)| gid).collect(),
+                    Layer::Object(layer) => {
+                        layer.content.iter().filter_map(|o| o.tile_id).collect()
+                    }
+                    _ => continue,
+                };
+                let mut unknown: Vec<_> = gids
+                    .into_iter()
+                    .filter(|&gid| map.tile(gid).is_none())
+                    .map(|gid| gid.to_id())
+                    .collect();
+                unknown.sort_unstable();
+                unknown.dedup();
+                if !unknown.is_empty() {
+                    problems.push(format!(
+                        "layer '{}' uses tiles without tileset: {:?}",
+                        layer.name(),
+                        unknown
+                    ));
+                }
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    Ok(if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,752 @@
+//! A binary format for maps that loads much faster than TMX, see [Map::to_cache].
+//!
+//! The format is a straight dump of the loaded map: all numbers are stored little endian,
+//! strings and lists are prefixed with their length and tile data is stored
+//! as raw GIDs, so nothing needs to be parsed or decompressed while loading.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::math::{fvec2, ivec2, PointList};
+use crate::property::ObjectReference;
+use crate::tile::{AnimationFrame, TileData};
+use crate::unknown::UnknownXml;
+use crate::{
+    Color, Error, GroupLayer, ImageLayer, ImageStorage, Layer, LayerId, Map, Name, Object,
+    ObjectKind, ObjectLayer, Occupancy, Orientation, Property, PropertyContainer, PropertyValue,
+    Renderorder, ResourceManager, Result, StaggerAxis, StaggerIndex, TileLayer, TileSet,
+    TileStorage, Version, GID, GID_SIZE,
+};
+
+/// First bytes of every cache file.
+const MAGIC: &[u8; 4] = b"TEGO";
+
+/// Version of the format, increased with every change to it.
+/// Caches written with other versions are rejected and need to be created again.
+pub const CACHE_VERSION: u32 = 4;
+
+impl Map {
+    /// Write this map in a binary format that [Map::from_cache] loads an order of magnitude
+    /// faster than TMX, e.g. to compile maps in an asset pipeline.
+    ///
+    /// The cache contains everything that was loaded, but not the images of the tilesets.
+    /// It is only meant to be read by the same version of tego, see [CACHE_VERSION].
+    ///
+    /// Requires the `cache` feature.
+    pub fn to_cache(&self) -> Vec<u8> {
+        let mut out = Encoder(Vec::new());
+        out.0.extend_from_slice(MAGIC);
+        out.u32(CACHE_VERSION);
+        out.version(self.version);
+        out.option(&self.editor_version, |out, &version| out.version(version));
+        out.u8(self.orientation as u8);
+        out.u8(self.renderorder as u8);
+        out.ivec2(self.size);
+        out.ivec2(self.tile_size);
+        out.u8(self.stagger_axis as u8);
+        out.u8(self.stagger_index as u8);
+        out.i32(self.hexsidelength);
+        out.color(self.backgroundcolor);
+        out.properties(&self.properties);
+        out.unknown(&self.unknown);
+        out.list(&self.tilesets, Encoder::tileset);
+        out.list(&self.root_layers, |out, &id| out.layer(self, id));
+        out.0
+    }
+
+    /// Load a map written by [Map::to_cache].
+    ///
+    /// The images of the tilesets are loaded with the given resource manager,
+    /// unless [MapLoadOptions::skip_images](crate::MapLoadOptions::skip_images) is set.
+    /// [compact_tiles](crate::MapLoadOptions::compact_tiles) is respected as well,
+    /// the other load options are ignored.
+    ///
+    /// Returns [Error::InvalidCache] if the data is no cache or was written by another
+    /// version of the format.
+    ///
+    /// Requires the `cache` feature.
+    pub fn from_cache(data: &[u8], resource_manager: &mut ResourceManager) -> Result<Self> {
+        let mut input = Decoder {
+            data,
+            loader: resource_manager,
+        };
+        if input.bytes(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidCache("Data is no map cache".into()));
+        }
+        let version = input.u32()?;
+        if version != CACHE_VERSION {
+            return Err(Error::InvalidCache(format!(
+                "Cache has version {}, expected {}",
+                version, CACHE_VERSION
+            )));
+        }
+
+        let version = input.version()?;
+        let editor_version = input.option(Decoder::version)?;
+        let orientation = match input.u8()? {
+            0 => Orientation::Orthogonal,
+            1 => Orientation::Isometric,
+            2 => Orientation::Staggered,
+            3 => Orientation::Hexagonal,
+            other => return Err(invalid_tag("orientation", other)),
+        };
+        let renderorder = match input.u8()? {
+            0 => Renderorder::RightDown,
+            1 => Renderorder::RightUp,
+            2 => Renderorder::LeftDown,
+            3 => Renderorder::LeftUp,
+            other => return Err(invalid_tag("render order", other)),
+        };
+        let size = input.ivec2()?;
+        input.loader.limits().check_size("Map", size)?;
+        let tile_size = input.ivec2()?;
+        let stagger_axis = match input.u8()? {
+            0 => StaggerAxis::X,
+            1 => StaggerAxis::Y,
+            other => return Err(invalid_tag("stagger axis", other)),
+        };
+        let stagger_index = match input.u8()? {
+            0 => StaggerIndex::Odd,
+            1 => StaggerIndex::Even,
+            other => return Err(invalid_tag("stagger index", other)),
+        };
+        let hexsidelength = input.i32()?;
+        let backgroundcolor = input.color()?;
+        let properties = input.properties()?;
+        let unknown = input.unknown()?;
+        let mut tilesets = input.list(Decoder::tileset)?;
+        if !input.loader.load_options().skip_images {
+            input.loader.load_tileset_images(&mut tilesets)?;
+        }
+        let mut layers = Vec::new();
+        let root_layers = input.list(|input| input.layer(&mut layers))?;
+        if !input.data.is_empty() {
+            return Err(Error::InvalidCache("Unexpected data after the map".into()));
+        }
+
+        Ok(Map {
+            version,
+            editor_version,
+            orientation,
+            renderorder,
+            size,
+            tile_size,
+            stagger_axis,
+            stagger_index,
+            hexsidelength,
+            tilesets,
+            backgroundcolor,
+            layers,
+            root_layers,
+            layer_hashes: HashMap::new(),
+            properties,
+            source: None,
+            unknown,
+        })
+    }
+}
+
+fn invalid_tag(what: &str, tag: u8) -> Error {
+    Error::InvalidCache(format!("Invalid {} {}", what, tag))
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    fn str(&mut self, text: &str) {
+        self.len(text.len());
+        self.0.extend_from_slice(text.as_bytes());
+    }
+
+    fn path(&mut self, path: &std::path::Path) {
+        self.str(&path.to_string_lossy());
+    }
+
+    fn ivec2(&mut self, value: ivec2) {
+        self.i32(value.x);
+        self.i32(value.y);
+    }
+
+    fn fvec2(&mut self, value: fvec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+
+    fn color(&mut self, color: Color) {
+        self.u32(color.to_u32());
+    }
+
+    fn gid(&mut self, gid: Option<GID>) {
+        self.u32(gid.map_or(0, |gid| gid.as_raw()));
+    }
+
+    fn version(&mut self, Version(major, minor, patch): Version) {
+        self.u32(major);
+        self.u32(minor);
+        self.option(&patch, |out, &patch| out.u32(patch));
+    }
+
+    fn option<T>(&mut self, value: &Option<T>, write: impl FnOnce(&mut Self, &T)) {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            write(self, value);
+        }
+    }
+
+    fn list<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.len(items.len());
+        for item in items {
+            write(self, item);
+        }
+    }
+
+    fn properties(&mut self, properties: &PropertyContainer) {
+        // sorted, so that the same map always results in the same cache
+        let mut properties: Vec<&Property> = properties.iter().collect();
+        properties.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        self.list(&properties, |out, property| {
+            out.str(&property.name);
+            match &property.value {
+                PropertyValue::String(text) => {
+                    out.u8(0);
+                    out.str(text);
+                }
+                PropertyValue::Int(value) => {
+                    out.u8(1);
+                    out.i64(*value);
+                }
+                PropertyValue::Float(value) => {
+                    out.u8(2);
+                    out.f64(*value);
+                }
+                PropertyValue::Bool(value) => {
+                    out.u8(3);
+                    out.bool(*value);
+                }
+                PropertyValue::Color(color) => {
+                    out.u8(4);
+                    out.color(*color);
+                }
+                PropertyValue::File(path) => {
+                    out.u8(5);
+                    out.str(path);
+                }
+                PropertyValue::Object(object) => {
+                    out.u8(6);
+                    out.i64(object.id());
+                }
+            }
+        });
+    }
+
+    fn unknown(&mut self, unknown: &UnknownXml) {
+        self.list(&unknown.attributes, |out, (name, value)| {
+            out.str(name);
+            out.str(value);
+        });
+        self.list(&unknown.elements, |out, element| out.str(element));
+    }
+
+    fn tileset(&mut self, tileset: &TileSet) {
+        self.gid(Some(tileset.firstgid));
+        self.str(&tileset.name);
+        self.ivec2(tileset.tile_size);
+        self.len(tileset.spacing);
+        self.len(tileset.margin);
+        self.len(tileset.tilecount);
+        self.len(tileset.columns);
+        self.path(&tileset.image_path);
+        self.option(&tileset.transparent, |out, &color| out.color(color));
+        self.option(&tileset.image_size, |out, &size| out.ivec2(size));
+        self.option(&tileset.source, |out, path| out.path(path));
+        self.properties(&tileset.properties);
+        self.unknown(&tileset.unknown);
+
+        let mut tiles: Vec<_> = tileset.tile_data.iter().collect();
+        tiles.sort_unstable_by_key(|(&id, _)| id);
+        self.list(&tiles, |out, (&id, data)| {
+            out.u32(id);
+            out.str(&data.class);
+            out.f32(data.probability);
+            out.properties(&data.properties);
+            out.list(&data.animation, |out, frame| {
+                out.u32(frame.tile_id);
+                out.u64(frame.duration.as_millis() as u64);
+            });
+            out.option(&data.collision, Self::object_layer);
+        });
+    }
+
+    fn layer(&mut self, map: &Map, id: LayerId) {
+        match &map[id] {
+            Layer::Tile(layer) => {
+                self.u8(0);
+                self.option(&layer.id, |out, &id| out.len(id));
+                self.str(&layer.name);
+                self.ivec2(layer.size);
+                self.bool(layer.visible);
+                self.f32(layer.opacity);
+                self.color(layer.tintcolor);
+                self.properties(&layer.properties);
+                self.unknown(&layer.unknown);
+                for tile in layer.tiles() {
+                    self.gid(tile);
+                }
+            }
+            Layer::Group(group) => {
+                self.u8(1);
+                self.option(&group.id, |out, &id| out.len(id));
+                self.str(&group.name);
+                self.ivec2(group.offset);
+                self.f32(group.opacity);
+                self.bool(group.visible);
+                self.color(group.tintcolor);
+                self.properties(&group.properties);
+                self.unknown(&group.unknown);
+                self.list(&group.content, |out, &child| out.layer(map, child));
+            }
+            Layer::Object(layer) => {
+                self.u8(2);
+                self.object_layer(layer);
+            }
+            Layer::Image(layer) => {
+                self.u8(3);
+                self.option(&layer.id, |out, &id| out.len(id));
+                self.str(&layer.name);
+                self.ivec2(layer.offset);
+                self.f32(layer.opacity);
+                self.bool(layer.visible);
+                self.color(layer.tintcolor);
+                self.properties(&layer.properties);
+                self.unknown(&layer.unknown);
+            }
+        }
+    }
+
+    fn object_layer(&mut self, layer: &ObjectLayer) {
+        self.option(&layer.id, |out, &id| out.len(id));
+        self.str(&layer.name);
+        self.color(layer.color);
+        self.f32(layer.opacity);
+        self.bool(layer.visible);
+        self.color(layer.tintcolor);
+        self.ivec2(layer.offset);
+        self.properties(&layer.properties);
+        self.unknown(&layer.unknown);
+        self.list(&layer.content, Self::object);
+    }
+
+    fn object(&mut self, object: &Object) {
+        self.len(object.id);
+        self.str(&object.name);
+        self.str(&object.type_);
+        self.fvec2(object.pos);
+        self.fvec2(object.size);
+        self.f32(object.rotation);
+        self.gid(object.tile_id);
+        self.bool(object.visible);
+        self.properties(&object.properties);
+        self.unknown(&object.unknown);
+        match &object.kind {
+            ObjectKind::Rect => self.u8(0),
+            ObjectKind::Ellipse => self.u8(1),
+            ObjectKind::Point => self.u8(2),
+            ObjectKind::Polygon { points } => {
+                self.u8(3);
+                self.list(points, |out, &point| out.fvec2(point));
+            }
+            ObjectKind::Polyline { points } => {
+                self.u8(4);
+                self.list(points, |out, &point| out.fvec2(point));
+            }
+            ObjectKind::Text {
+                content,
+                pixelsize,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+            } => {
+                self.u8(5);
+                self.str(content);
+                self.i32(*pixelsize);
+                self.color(*color);
+                for flag in [wrap, bold, italic, underline, strikeout, kerning] {
+                    self.bool(*flag);
+                }
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    /// The data that was not read yet.
+    data: &'a [u8],
+    loader: &'a mut ResourceManager,
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() {
+            return Err(Error::InvalidCache("Unexpected end of data".into()));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(invalid_tag("bool", other)),
+        }
+    }
+
+    /// Read a length, making sure that it fits into the remaining data.
+    fn len(&mut self) -> Result<usize> {
+        let len = self.u64()?;
+        if len > self.data.len() as u64 {
+            return Err(Error::InvalidCache("Unexpected end of data".into()));
+        }
+        Ok(len as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.len()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::InvalidCache(e.to_string()))
+    }
+
+    fn name(&mut self) -> Result<Name> {
+        let text = self.string()?;
+        Ok(self.loader.intern(&text))
+    }
+
+    fn ivec2(&mut self) -> Result<ivec2> {
+        Ok(ivec2::new(self.i32()?, self.i32()?))
+    }
+
+    fn fvec2(&mut self) -> Result<fvec2> {
+        Ok(fvec2::new(self.f32()?, self.f32()?))
+    }
+
+    fn color(&mut self) -> Result<Color> {
+        Ok(Color(self.u32()?))
+    }
+
+    fn gid(&mut self) -> Result<Option<GID>> {
+        Ok(NonZeroU32::new(self.u32()?).map(GID))
+    }
+
+    fn version(&mut self) -> Result<Version> {
+        Ok(Version(self.u32()?, self.u32()?, self.option(Self::u32)?))
+    }
+
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        self.bool()?.then(|| read(self)).transpose()
+    }
+
+    fn list<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.len()?;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(read(self)?);
+        }
+        Ok(items)
+    }
+
+    fn properties(&mut self) -> Result<PropertyContainer> {
+        let mut properties = PropertyContainer::new();
+        for _ in 0..self.len()? {
+            let name = self.name()?;
+            let value = match self.u8()? {
+                0 => PropertyValue::String(self.string()?),
+                1 => PropertyValue::Int(self.i64()?),
+                2 => PropertyValue::Float(self.f64()?),
+                3 => PropertyValue::Bool(self.bool()?),
+                4 => PropertyValue::Color(self.color()?),
+                5 => PropertyValue::File(self.string()?),
+                6 => PropertyValue::Object(ObjectReference(self.i64()?)),
+                other => return Err(invalid_tag("property type", other)),
+            };
+            properties.insert(Property { name, value });
+        }
+        Ok(properties)
+    }
+
+    fn unknown(&mut self) -> Result<UnknownXml> {
+        Ok(UnknownXml {
+            attributes: self.list(|input| Ok((input.string()?, input.string()?)))?,
+            elements: self.list(Self::string)?,
+        })
+    }
+
+    fn tileset(&mut self) -> Result<TileSet> {
+        let Some(firstgid) = self.gid()? else {
+            return Err(Error::InvalidCache("Tileset without firstgid".into()));
+        };
+        let mut tileset = TileSet {
+            firstgid,
+            name: self.name()?,
+            tile_size: self.ivec2()?,
+            spacing: self.u64()? as usize,
+            margin: self.u64()? as usize,
+            tilecount: self.u64()? as usize,
+            columns: self.u64()? as usize,
+            image: ImageStorage::Pending,
+            image_path: PathBuf::from(self.string()?),
+            transparent: self.option(Self::color)?,
+            image_size: self.option(Self::ivec2)?,
+            source: self.option(|input| input.string().map(PathBuf::from))?,
+            properties: self.properties()?,
+            tile_data: HashMap::new(),
+            rect_cache: None,
+            unknown: self.unknown()?,
+        };
+        for _ in 0..self.len()? {
+            let id = self.u32()?;
+            let data = TileData {
+                class: self.name()?,
+                probability: self.f32()?,
+                properties: self.properties()?,
+                animation: self.list(|input| {
+                    Ok(AnimationFrame {
+                        tile_id: input.u32()?,
+                        duration: Duration::from_millis(input.u64()?),
+                    })
+                })?,
+                collision: self.option(Self::object_layer)?,
+            };
+            tileset.tile_data.insert(id, data);
+        }
+        Ok(tileset)
+    }
+
+    fn layer(&mut self, layers: &mut Vec<Option<Layer>>) -> Result<LayerId> {
+        let layer = match self.u8()? {
+            0 => {
+                let id = self.option(Self::len_value)?;
+                let name = self.name()?;
+                let size = self.ivec2()?;
+                self.loader.limits().check_size("Layer", size)?;
+                let visible = self.bool()?;
+                let opacity = self.f32()?;
+                let tintcolor = self.color()?;
+                let properties = self.properties()?;
+                let unknown = self.unknown()?;
+                let count = size.x.max(0) as usize * size.y.max(0) as usize;
+                let bytes = self.bytes(count.saturating_mul(GID_SIZE))?;
+                let tiles: Vec<Option<GID>> = bytes
+                    .chunks_exact(GID_SIZE)
+                    .map(|gid| {
+                        NonZeroU32::new(u32::from_le_bytes(gid.try_into().unwrap())).map(GID)
+                    })
+                    .collect();
+                Layer::Tile(TileLayer {
+                    id,
+                    name,
+                    size,
+                    visible,
+                    opacity,
+                    tintcolor,
+                    properties,
+                    occupancy: Occupancy::from_tiles(&tiles),
+                    tiles: TileStorage::new(tiles, self.loader.load_options().compact_tiles),
+                    unknown,
+                })
+            }
+            1 => {
+                let mut group = GroupLayer {
+                    id: self.option(Self::len_value)?,
+                    name: self.name()?,
+                    offset: self.ivec2()?,
+                    opacity: self.f32()?,
+                    visible: self.bool()?,
+                    tintcolor: self.color()?,
+                    content: Vec::new(),
+                    properties: self.properties()?,
+                    unknown: self.unknown()?,
+                };
+                group.content = self.list(|input| input.layer(layers))?;
+                Layer::Group(group)
+            }
+            2 => Layer::Object(self.object_layer()?),
+            3 => Layer::Image(ImageLayer {
+                id: self.option(Self::len_value)?,
+                name: self.name()?,
+                offset: self.ivec2()?,
+                opacity: self.f32()?,
+                visible: self.bool()?,
+                tintcolor: self.color()?,
+                properties: self.properties()?,
+                unknown: self.unknown()?,
+            }),
+            other => return Err(invalid_tag("layer type", other)),
+        };
+        Ok(LayerId::push(layers, layer))
+    }
+
+    /// Read a number that was written with [Encoder::len], but isn't a length.
+    fn len_value(&mut self) -> Result<usize> {
+        Ok(self.u64()? as usize)
+    }
+
+    fn object_layer(&mut self) -> Result<ObjectLayer> {
+        Ok(ObjectLayer {
+            id: self.option(Self::len_value)?,
+            name: self.name()?,
+            color: self.color()?,
+            opacity: self.f32()?,
+            visible: self.bool()?,
+            tintcolor: self.color()?,
+            offset: self.ivec2()?,
+            properties: self.properties()?,
+            unknown: self.unknown()?,
+            content: self.list(Self::object)?,
+        })
+    }
+
+    fn object(&mut self) -> Result<Object> {
+        let mut object = Object {
+            id: self.len_value()?,
+            name: self.name()?,
+            type_: self.name()?,
+            pos: self.fvec2()?,
+            size: self.fvec2()?,
+            rotation: self.f32()?,
+            tile_id: self.gid()?,
+            visible: self.bool()?,
+            kind: ObjectKind::Rect,
+            properties: self.properties()?,
+            unknown: self.unknown()?,
+        };
+        let points = |input: &mut Self| input.list(Self::fvec2).map(PointList::from);
+        object.kind = match self.u8()? {
+            0 => ObjectKind::Rect,
+            1 => ObjectKind::Ellipse,
+            2 => ObjectKind::Point,
+            3 => ObjectKind::Polygon {
+                points: points(self)?,
+            },
+            4 => ObjectKind::Polyline {
+                points: points(self)?,
+            },
+            5 => ObjectKind::Text {
+                content: self.string()?,
+                pixelsize: self.i32()?,
+                color: self.color()?,
+                wrap: self.bool()?,
+                bold: self.bool()?,
+                italic: self.bool()?,
+                underline: self.bool()?,
+                strikeout: self.bool()?,
+                kerning: self.bool()?,
+            },
+            other => return Err(invalid_tag("object kind", other)),
+        };
+        Ok(object)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reject_invalid_data() {
+        let mut loader = ResourceManager::default();
+        let error = |data: &[u8], loader: &mut ResourceManager| {
+            Map::from_cache(data, loader).unwrap_err().to_string()
+        };
+        assert_eq!(
+            error(b"<map/>", &mut loader),
+            "Invalid map cache: Data is no map cache"
+        );
+
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        assert!(error(&data, &mut loader).contains("version"));
+
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        data.push(1);
+        assert_eq!(
+            error(&data, &mut loader),
+            "Invalid map cache: Unexpected end of data"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Tile data as comma separated values, see [TileLayer::to_csv] and [TileLayer::from_csv].
+
+use std::num::NonZeroU32;
+
+use crate::math::ivec2;
+use crate::{Error, Result, TileLayer, GID, GID_SIZE};
+
+impl TileLayer {
+    /// Write the tiles of this layer as comma separated GIDs, in the format Tiled uses for
+    /// `<data encoding="csv">`. This is useful to store maps in a diff-friendly way
+    /// or to feed them to scripts.
+    ///
+    /// Each row of the layer is written on its own line, empty cells are written as `0`.
+    /// The flip flags are part of the GIDs, just like in Tiled.
+    ///
+    /// ```
+    /// let mut layer = tego::TileLayer::new("Ground", tego::math::ivec2::new(3, 2))?;
+    /// layer.set_tile(tego::math::ivec2::new(1, 0), Some("7".parse()?))?;
+    /// assert_eq!(layer.to_csv(), "0,7,0,\n0,0,0\n");
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let width = self.size.x.max(0) as usize;
+        let mut csv = String::new();
+        for (index, tile) in self.tiles().enumerate() {
+            if index > 0 {
+                csv.push(',');
+                if index % width == 0 {
+                    csv.push('\n');
+                }
+            }
+            csv.push_str(&tile.map_or(0, |gid| gid.as_raw()).to_string());
+        }
+        csv.push('\n');
+        csv
+    }
+
+    /// Create a tile layer from comma separated GIDs as written by [TileLayer::to_csv].
+    ///
+    /// Every line of the text is a row of the layer, the size of the layer is taken from
+    /// the number of rows and their length. Commas at the end of a line are optional,
+    /// but all rows must have the same number of tiles.
+    pub fn from_csv(name: &str, csv: &str) -> Result<Self> {
+        let mut width = None;
+        let mut tiles = Vec::new();
+        let mut height = 0;
+        for line in csv.lines().filter(|line| !line.trim().is_empty()) {
+            let row = read_csv_gids(line, usize::MAX)?;
+            if *width.get_or_insert(row.len()) != row.len() {
+                return Err(Error::StructureError {
+                    tag: "data".into(),
+                    msg: format!(
+                        "Row {} has {} tiles instead of {}",
+                        height,
+                        row.len(),
+                        width.unwrap_or_default()
+                    ),
+                });
+            }
+            tiles.extend(row);
+            height += 1;
+        }
+        let size = ivec2::new(width.unwrap_or_default() as i32, height);
+        let mut layer = TileLayer::new(name, size)?;
+        for (index, tile) in tiles.into_iter().enumerate() {
+            let pos = ivec2::new(index as i32 % size.x, index as i32 / size.x);
+            layer.set_tile(pos, tile)?;
+        }
+        Ok(layer)
+    }
+}
+
+/// Read comma separated GIDs, failing if they take more than `max_bytes` bytes.
+/// Whitespace around the GIDs and a trailing comma are ignored.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "decode_tile_data",
+        level = "debug",
+        skip_all,
+        fields(encoding = "csv")
+    )
+)]
+pub(crate) fn read_csv_gids(text: &str, max_bytes: usize) -> Result<Vec<Option<GID>>> {
+    let text = text.trim();
+    let text = text.strip_suffix(',').unwrap_or(text);
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut gids = Vec::new();
+    for value in text.split(',') {
+        if (gids.len() + 1) * GID_SIZE > max_bytes {
+            return Err(Error::LimitExceeded(format!(
+                "Layer data is bigger than {} bytes",
+                max_bytes
+            )));
+        }
+        gids.push(NonZeroU32::new(value.trim().parse()?).map(GID));
+    }
+    Ok(gids)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_gids() {
+        let gid = |id| NonZeroU32::new(id).map(GID);
+        assert_eq!(
+            read_csv_gids("\n1,0,3,\n0,2,0\n", 1024).unwrap(),
+            vec![gid(1), None, gid(3), None, gid(2), None]
+        );
+        assert_eq!(read_csv_gids(" \n", 1024).unwrap(), vec![]);
+        assert!(read_csv_gids("1,x", 1024).is_err());
+        assert!(matches!(
+            read_csv_gids("1,2,3", 8),
+            Err(Error::LimitExceeded(_))
+        ));
+    }
+}
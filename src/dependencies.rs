@@ -0,0 +1,65 @@
+//! Scanning of maps for the external files they reference, see [Map::list_dependencies].
+
+use std::path::PathBuf;
+
+use crate::xml::{Document, Node, XmlNode};
+use crate::{ResourceManager, Result};
+
+#[cfg(doc)]
+use crate::Map;
+
+/// The different kinds of external files a map can reference.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DependencyKind {
+    /// An external tileset file (`*.tsx`)
+    TileSet,
+    /// An object template file (`*.tx`)
+    Template,
+    /// An image, e.g. the sprite sheet of a tileset
+    Image,
+}
+
+/// An external file that is referenced by a map, see [Map::list_dependencies].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Dependency {
+    pub kind: DependencyKind,
+
+    /// Path of the file, including the base path of the [ResourceManager].
+    pub path: PathBuf,
+}
+
+/// Collect all external files referenced by the given node and its children.
+/// Referenced tilesets and templates are scanned recursively,
+/// but no images are loaded and no tile data is decoded.
+pub(crate) fn collect(
+    node: &Node,
+    loader: &mut ResourceManager,
+    dependencies: &mut Vec<Dependency>,
+) -> Result<()> {
+    for child in node.descendants() {
+        let (kind, relpath) = match (child.tag(), child.attribute("source")) {
+            ("tileset", Some(source)) => (DependencyKind::TileSet, source),
+            ("image", Some(source)) => (DependencyKind::Image, source),
+            ("object", _) => match child.attribute("template") {
+                Some(template) => (DependencyKind::Template, template),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let path = loader.resolve_path(relpath);
+        if dependencies.iter().any(|d| d.path == path) {
+            continue;
+        }
+        dependencies.push(Dependency { kind, path });
+
+        if kind != DependencyKind::Image {
+            let text = loader.load_text(relpath)?;
+            let document = Document::parse(&text)?;
+            loader.with_document(relpath, |loader| {
+                collect(&document.root_element(), loader, dependencies)
+            })?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,328 @@
+//! Comparing two versions of a map, see [diff].
+
+use std::fmt;
+
+use crate::math::ivec2;
+use crate::{
+    Layer, Map, Name, Object, ObjectLayer, PropertyContainer, PropertyValue, TileLayer, GID,
+};
+
+/// A single difference between two versions of a map, as found by [diff].
+///
+/// Layers are identified by their path, the names of the layer and its groups separated by
+/// slashes (e.g. `"Objects/Doors"`).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// The size of the map changed.
+    SizeChanged { old: ivec2, new: ivec2 },
+
+    /// A layer only exists in the new map.
+    LayerAdded { layer: String },
+
+    /// A layer only exists in the old map.
+    LayerRemoved { layer: String },
+
+    /// A cell of a tile layer changed.
+    TileChanged {
+        layer: String,
+        pos: ivec2,
+        old: Option<GID>,
+        new: Option<GID>,
+    },
+
+    /// An object with this id only exists in the new map.
+    ObjectAdded { layer: String, id: usize },
+
+    /// An object with this id only exists in the old map.
+    ObjectRemoved { layer: String, id: usize },
+
+    /// An object changed, apart from its properties.
+    ObjectChanged { layer: String, id: usize },
+
+    /// A custom property was added (old is None), removed (new is None) or changed.
+    PropertyChanged {
+        owner: PropertyOwner,
+        name: Name,
+        old: Option<PropertyValue>,
+        new: Option<PropertyValue>,
+    },
+}
+
+/// The element of a map that a [Difference::PropertyChanged] belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyOwner {
+    Map,
+    Layer(String),
+    Object { layer: String, id: usize },
+}
+
+/// Compare two versions of a map, e.g. to check that an edit only changed what it should.
+///
+/// Layers are matched by their path and kind, objects by their id.
+/// Layers with the same path and kind are matched in the order they appear in the maps.
+/// Tilesets and the appearance of layers (e.g. opacity) are not compared.
+///
+/// ```no_run
+/// # use std::path::Path;
+/// let old = tego::Map::from_file(Path::new("old/level.tmx"))?;
+/// let new = tego::Map::from_file(Path::new("new/level.tmx"))?;
+/// for difference in tego::diff(&old, &new) {
+///     println!("{}", difference);
+/// }
+/// # Ok::<(),tego::Error>(())
+/// ```
+pub fn diff(old: &Map, new: &Map) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    if old.size != new.size {
+        differences.push(Difference::SizeChanged {
+            old: old.size,
+            new: new.size,
+        });
+    }
+    diff_properties(
+        &mut differences,
+        PropertyOwner::Map,
+        &old.properties,
+        &new.properties,
+    );
+
+    let old_layers = layer_paths(old);
+    let new_layers = layer_paths(new);
+    for (index, (path, old_layer)) in old_layers.iter().enumerate() {
+        match counterpart(&old_layers, index, &new_layers) {
+            Some(new_layer) => diff_layer(&mut differences, path, old_layer, new_layer),
+            None => differences.push(Difference::LayerRemoved {
+                layer: path.clone(),
+            }),
+        }
+    }
+    for (index, (path, _)) in new_layers.iter().enumerate() {
+        if counterpart(&new_layers, index, &old_layers).is_none() {
+            differences.push(Difference::LayerAdded {
+                layer: path.clone(),
+            });
+        }
+    }
+    differences
+}
+
+/// The layer in `others` that matches the layer at `index` of `layers`: the layer with the
+/// same path and kind, or the n-th of them if the path is used n times before in `layers`.
+fn counterpart<'a>(
+    layers: &[(String, &Layer)],
+    index: usize,
+    others: &[(String, &'a Layer)],
+) -> Option<&'a Layer> {
+    let (path, layer) = &layers[index];
+    let same = |(other_path, other): &&(String, &Layer)| {
+        other_path == path && std::mem::discriminant(*other) == std::mem::discriminant(*layer)
+    };
+    let occurrence = layers[..index].iter().filter(same).count();
+    others
+        .iter()
+        .filter(same)
+        .nth(occurrence)
+        .map(|(_, other)| *other)
+}
+
+/// All layers of the map together with their path.
+fn layer_paths(map: &Map) -> Vec<(String, &Layer)> {
+    let mut groups: Vec<&str> = Vec::new();
+    let mut layers = Vec::new();
+    for (layer, pops) in map.iter_layers() {
+        groups.truncate(groups.len() - pops);
+        let mut path = groups.join("/");
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(layer.name());
+        if let Layer::Group(group) = layer {
+            groups.push(&group.name);
+        }
+        layers.push((path, layer));
+    }
+    layers
+}
+
+fn diff_layer(differences: &mut Vec<Difference>, path: &str, old: &Layer, new: &Layer) {
+    let (old_properties, new_properties) = match (old, new) {
+        (Layer::Tile(old), Layer::Tile(new)) => {
+            diff_tiles(differences, path, old, new);
+            (&old.properties, &new.properties)
+        }
+        (Layer::Object(old), Layer::Object(new)) => {
+            diff_objects(differences, path, old, new);
+            (&old.properties, &new.properties)
+        }
+        (Layer::Group(old), Layer::Group(new)) => (&old.properties, &new.properties),
+        (Layer::Image(old), Layer::Image(new)) => (&old.properties, &new.properties),
+        _ => return,
+    };
+    diff_properties(
+        differences,
+        PropertyOwner::Layer(path.into()),
+        old_properties,
+        new_properties,
+    );
+}
+
+fn diff_tiles(differences: &mut Vec<Difference>, path: &str, old: &TileLayer, new: &TileLayer) {
+    for y in 0..old.size.y.max(new.size.y) {
+        for x in 0..old.size.x.max(new.size.x) {
+            let pos = ivec2::new(x, y);
+            let (old, new) = (old.tile(pos), new.tile(pos));
+            if old != new {
+                differences.push(Difference::TileChanged {
+                    layer: path.into(),
+                    pos,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+}
+
+fn diff_objects(
+    differences: &mut Vec<Difference>,
+    path: &str,
+    old: &ObjectLayer,
+    new: &ObjectLayer,
+) {
+    for old_object in &old.content {
+        let id = old_object.id;
+        let Some(new_object) = new.content.iter().find(|o| o.id == id) else {
+            differences.push(Difference::ObjectRemoved {
+                layer: path.into(),
+                id,
+            });
+            continue;
+        };
+        if !same_object(old_object, new_object) {
+            differences.push(Difference::ObjectChanged {
+                layer: path.into(),
+                id,
+            });
+        }
+        diff_properties(
+            differences,
+            PropertyOwner::Object {
+                layer: path.into(),
+                id,
+            },
+            &old_object.properties,
+            &new_object.properties,
+        );
+    }
+    for new_object in &new.content {
+        if !old.content.iter().any(|o| o.id == new_object.id) {
+            differences.push(Difference::ObjectAdded {
+                layer: path.into(),
+                id: new_object.id,
+            });
+        }
+    }
+}
+
+/// Compare two objects, ignoring their properties.
+fn same_object(a: &Object, b: &Object) -> bool {
+    a.name == b.name
+        && a.type_ == b.type_
+        && a.pos == b.pos
+        && a.size == b.size
+        && a.rotation == b.rotation
+        && a.tile_id == b.tile_id
+        && a.visible == b.visible
+        && a.kind == b.kind
+}
+
+fn diff_properties(
+    differences: &mut Vec<Difference>,
+    owner: PropertyOwner,
+    old: &PropertyContainer,
+    new: &PropertyContainer,
+) {
+    let mut changed = |name: &Name, old: Option<&PropertyValue>, new: Option<&PropertyValue>| {
+        differences.push(Difference::PropertyChanged {
+            owner: owner.clone(),
+            name: name.clone(),
+            old: old.cloned(),
+            new: new.cloned(),
+        })
+    };
+    // sorted by name like in saved maps, the containers don't keep the order of the file
+    let mut names: Vec<_> = old.iter().chain(new.iter()).map(|p| &p.name).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let (old_value, new_value) = (old.get(name), new.get(name));
+        if old_value != new_value {
+            changed(name, old_value, new_value);
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeChanged { old, new } => write!(
+                f,
+                "Map size changed from {}x{} to {}x{}",
+                old.x, old.y, new.x, new.y
+            ),
+            Self::LayerAdded { layer } => write!(f, "Layer '{}' was added", layer),
+            Self::LayerRemoved { layer } => write!(f, "Layer '{}' was removed", layer),
+            Self::TileChanged {
+                layer,
+                pos,
+                old,
+                new,
+            } => write!(
+                f,
+                "Tile ({}, {}) in layer '{}' changed from {} to {}",
+                pos.x,
+                pos.y,
+                layer,
+                tile_string(*old),
+                tile_string(*new)
+            ),
+            Self::ObjectAdded { layer, id } => {
+                write!(f, "Object {} in layer '{}' was added", id, layer)
+            }
+            Self::ObjectRemoved { layer, id } => {
+                write!(f, "Object {} in layer '{}' was removed", id, layer)
+            }
+            Self::ObjectChanged { layer, id } => {
+                write!(f, "Object {} in layer '{}' changed", id, layer)
+            }
+            Self::PropertyChanged {
+                owner,
+                name,
+                old,
+                new,
+            } => {
+                write!(f, "Property '{}' of ", name)?;
+                match owner {
+                    PropertyOwner::Map => write!(f, "the map")?,
+                    PropertyOwner::Layer(layer) => write!(f, "layer '{}'", layer)?,
+                    PropertyOwner::Object { layer, id } => {
+                        write!(f, "object {} in layer '{}'", id, layer)?
+                    }
+                }
+                match (old, new) {
+                    (None, _) => write!(f, " was added"),
+                    (_, None) => write!(f, " was removed"),
+                    (Some(old), Some(new)) => write!(f, " changed from {:?} to {:?}", old, new),
+                }
+            }
+        }
+    }
+}
+
+fn tile_string(tile: Option<GID>) -> String {
+    match tile {
+        Some(gid) => gid.to_id().to_string(),
+        None => "empty".into(),
+    }
+}
@@ -0,0 +1,330 @@
+//! Operations that restructure a map, e.g. merging several maps into one.
+
+use std::collections::HashMap;
+
+use crate::math::{fvec2, ivec2, Rect};
+use crate::{Error, Layer, LayerId, Map, Occupancy, Result, TileLayer, TileSet, TileStorage, GID};
+
+impl Map {
+    /// Add the layers of another map on top of the layers of this map,
+    /// with the upper left corner of the other map at the given tile position.
+    ///
+    /// The map grows to fit both maps. If the offset is negative,
+    /// the layers of this map are moved, so that the map still starts at the origin.
+    /// Tilesets of the other map are only added if this map doesn't have them yet
+    /// (see [Map::add_tileset]), and the GIDs of the merged tiles are rewritten to match.
+    ///
+    /// The merged layers and objects get new ids.
+    /// References to objects in properties are not updated.
+    ///
+    /// Both maps must have the same orientation and tile size.
+    pub fn merge(&mut self, other: &Map, offset: ivec2) -> Result<()> {
+        if self.tile_size != other.tile_size || self.orientation != other.orientation {
+            return Err(Error::UnsupportedFeature(
+                "Merging maps with different orientations or tile sizes".into(),
+            ));
+        }
+
+        let shift = ivec2::new((-offset.x).max(0), (-offset.y).max(0));
+        let end = offset + shift + other.size;
+        let size = ivec2::new(
+            (self.size.x + shift.x).max(end.x),
+            (self.size.y + shift.y).max(end.y),
+        );
+        let tile_size = self.tile_size;
+        let ids: Vec<_> = self.iter_layer_ids().map(|(id, _)| id).collect();
+        for id in ids {
+            place_layer(&mut self[id], shift, size, tile_size);
+        }
+        self.size = size;
+
+        let mut merge = Merge {
+            offset: offset + shift,
+            size,
+            tile_size,
+            gids: self.merge_tilesets(&other.tilesets),
+            next_object_id: self.next_object_id(),
+        };
+        for &id in other.layer_ids() {
+            self.copy_layer(other, id, None, &mut merge)?;
+        }
+        Ok(())
+    }
+
+    /// Create a new map containing only the given rectangle (in tiles) of this map,
+    /// e.g. to split a huge map into chunks.
+    ///
+    /// Tiles and objects are moved, so that the upper left corner of the rectangle
+    /// becomes the origin of the new map. Objects are kept if their position is inside
+    /// of the rectangle. Image layers are moved, but not cut.
+    /// The new map keeps all layers (even if they end up empty), tilesets and ids.
+    /// Parts of the rectangle outside of this map are empty.
+    pub fn crop(&self, rect: Rect) -> Map {
+        let size = ivec2::new(rect.size.x.max(0), rect.size.y.max(0));
+        let start = rect.upper_left * self.tile_size;
+        let end = (rect.upper_left + size) * self.tile_size;
+        let inside = |pos: fvec2| {
+            (start.x as f32..end.x as f32).contains(&pos.x)
+                && (start.y as f32..end.y as f32).contains(&pos.y)
+        };
+
+        let mut layers = self.layers.clone();
+        for layer in layers.iter_mut().flatten() {
+            if let Layer::Object(layer) = layer {
+                layer.content.retain(|object| inside(object.pos));
+            }
+            place_layer(
+                layer,
+                ivec2::new(0, 0) - rect.upper_left,
+                size,
+                self.tile_size,
+            );
+        }
+
+        Map {
+            version: self.version,
+            editor_version: self.editor_version,
+            orientation: self.orientation,
+            renderorder: self.renderorder,
+            size,
+            tile_size: self.tile_size,
+            stagger_axis: self.stagger_axis,
+            stagger_index: self.stagger_index,
+            hexsidelength: self.hexsidelength,
+            tilesets: self.tilesets.clone(),
+            backgroundcolor: self.backgroundcolor,
+            layers,
+            root_layers: self.root_layers.clone(),
+            layer_hashes: HashMap::new(),
+            properties: self.properties.clone(),
+            source: None,
+            unknown: self.unknown.clone(),
+        }
+    }
+
+    /// Change the size of this map, like "Resize Map" in Tiled.
+    ///
+    /// The anchor decides which part of the map stays in place, e.g. with [Anchor::Center]
+    /// the map grows or shrinks evenly on all sides. Tile layers are filled with empty tiles
+    /// or cut off, objects and image layers are moved along with the tiles.
+    /// Objects outside of the new bounds are kept.
+    pub fn resize(&mut self, size: ivec2, anchor: Anchor) {
+        let size = ivec2::new(size.x.max(0), size.y.max(0));
+        let offset = anchor.offset(self.size, size);
+        let tile_size = self.tile_size;
+        for layer in self.layers_mut() {
+            place_layer(layer, offset, size, tile_size);
+        }
+        self.size = size;
+    }
+
+    /// Combine the tile layers for which `filter` returns true into a single layer
+    /// of the size of the map, e.g. to draw them at once or as a snapshot for collisions.
+    ///
+    /// Each cell gets the tile of the topmost layer that is not empty there.
+    /// Hidden layers and layers inside of hidden groups are skipped.
+    ///
+    /// Fails with [Error::LimitExceeded] if the map is too big
+    /// for a single layer, see [TileLayer::new].
+    pub fn flatten_tile_layers(
+        &self,
+        mut filter: impl FnMut(&TileLayer) -> bool,
+    ) -> Result<TileLayer> {
+        let bounds = Rect::new(ivec2::new(0, 0), self.size);
+        let mut flat = TileLayer::new("", self.size)?;
+        let mut visible = vec![true];
+        for (layer, pops) in self.iter_layers() {
+            visible.truncate(visible.len() - pops);
+            let shown = *visible.last().unwrap();
+            match layer {
+                Layer::Group(group) => visible.push(shown && group.visible),
+                Layer::Tile(layer) if shown && layer.visible && filter(layer) => {
+                    for (pos, gid) in layer.occupied_tiles_in(bounds) {
+                        if let Some(index) = flat.index_of(pos) {
+                            flat.set_index(index, Some(gid));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(flat)
+    }
+
+    /// Add the tilesets that are not part of this map yet,
+    /// returning how the GIDs of the given tilesets translate to this map.
+    fn merge_tilesets(&mut self, tilesets: &[TileSet]) -> Vec<GidRange> {
+        tilesets
+            .iter()
+            .enumerate()
+            .map(|(i, tileset)| {
+                let first = tileset.firstgid.to_id();
+                let end = tilesets
+                    .get(i + 1)
+                    .map_or(first + tileset.tilecount as u32, |next| {
+                        next.firstgid.to_id()
+                    });
+                let target = match self.tilesets.iter().find(|own| same_tileset(own, tileset)) {
+                    Some(own) => own.firstgid,
+                    None => self.add_tileset(tileset.clone()),
+                };
+                GidRange {
+                    first,
+                    end,
+                    target: target.to_id(),
+                }
+            })
+            .collect()
+    }
+
+    /// Copy a layer of another map including all layers inside of it into this map,
+    /// on top of the other layers of the parent.
+    fn copy_layer(
+        &mut self,
+        other: &Map,
+        id: LayerId,
+        parent: Option<LayerId>,
+        merge: &mut Merge,
+    ) -> Result<()> {
+        let mut layer = other[id].clone();
+        *layer.id_mut() = None;
+        if let Layer::Group(group) = &mut layer {
+            group.content.clear();
+        }
+        merge.apply(&mut layer);
+
+        let index = self.content_mut(parent)?.len();
+        let copy = self.insert_layer(parent, index, layer)?;
+        if let Layer::Group(group) = &other[id] {
+            for &child in &group.content {
+                self.copy_layer(other, child, Some(copy), merge)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The part of a map that stays in place when it is [resized](Map::resize).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far the contents of a map move when it is resized from `old` to `new`.
+    fn offset(self, old: ivec2, new: ivec2) -> ivec2 {
+        use Anchor::*;
+        let diff = new - old;
+        let x = match self {
+            TopLeft | Left | BottomLeft => 0,
+            Top | Center | Bottom => diff.x / 2,
+            TopRight | Right | BottomRight => diff.x,
+        };
+        let y = match self {
+            TopLeft | Top | TopRight => 0,
+            Left | Center | Right => diff.y / 2,
+            BottomLeft | Bottom | BottomRight => diff.y,
+        };
+        ivec2::new(x, y)
+    }
+}
+
+/// Check whether both tilesets are the same, so that merged maps can share them.
+fn same_tileset(a: &TileSet, b: &TileSet) -> bool {
+    match (&a.source, &b.source) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => {
+            a.name == b.name && a.image_path == b.image_path && a.tilecount == b.tilecount
+        }
+        _ => false,
+    }
+}
+
+/// The GIDs `first..end` of a merged map, which start at `target` in the combined map.
+struct GidRange {
+    first: u32,
+    end: u32,
+    target: u32,
+}
+
+/// How the layers of another map change when they are merged into a map.
+struct Merge {
+    offset: ivec2,
+    size: ivec2,
+    tile_size: ivec2,
+    gids: Vec<GidRange>,
+    next_object_id: usize,
+}
+
+impl Merge {
+    fn gid(&self, gid: GID) -> Option<GID> {
+        let id = gid.to_id();
+        let range = self
+            .gids
+            .iter()
+            .find(|range| (range.first..range.end).contains(&id))?;
+        Some(gid.with_id(id - range.first + range.target))
+    }
+
+    fn apply(&mut self, layer: &mut Layer) {
+        match layer {
+            Layer::Tile(layer) => layer.map_tiles(|gid| self.gid(gid)),
+            Layer::Object(layer) => {
+                for object in &mut layer.content {
+                    object.id = self.next_object_id;
+                    self.next_object_id += 1;
+                    object.tile_id = object.tile_id.and_then(|gid| self.gid(gid));
+                }
+            }
+            _ => {}
+        }
+        place_layer(layer, self.offset, self.size, self.tile_size);
+    }
+}
+
+/// Move the contents of a layer by the given number of tiles, in a map of the given size.
+/// Tiles that end up outside of the map are removed.
+/// Groups are not moved themselves, only the layers inside of them.
+pub(crate) fn place_layer(layer: &mut Layer, offset: ivec2, size: ivec2, tile_size: ivec2) {
+    let pixels = offset * tile_size;
+    match layer {
+        Layer::Tile(layer) => layer.reframe(offset, size),
+        Layer::Object(layer) => {
+            for object in &mut layer.content {
+                object.pos += fvec2::new(pixels.x as f32, pixels.y as f32);
+            }
+        }
+        Layer::Image(layer) => layer.offset += pixels,
+        Layer::Group(_) => {}
+    }
+}
+
+impl TileLayer {
+    /// Change the size of this layer, moving all tiles by the given offset.
+    /// Tiles that end up outside of the layer are removed.
+    pub(crate) fn reframe(&mut self, offset: ivec2, size: ivec2) {
+        if offset == ivec2::new(0, 0) && size == self.size {
+            return;
+        }
+        let mut tiles = vec![None; size.x.max(0) as usize * size.y.max(0) as usize];
+        for (pos, gid) in self.occupied_tiles() {
+            let pos = pos + offset;
+            if (0..size.x).contains(&pos.x) && (0..size.y).contains(&pos.y) {
+                tiles[pos.x as usize + pos.y as usize * size.x as usize] = Some(gid);
+            }
+        }
+        let compact = !matches!(self.tiles, TileStorage::Wide(_));
+        self.size = size;
+        self.occupancy = Occupancy::from_tiles(&tiles);
+        self.tiles = TileStorage::new(tiles, compact);
+    }
+}
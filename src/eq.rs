@@ -0,0 +1,84 @@
+//! Equality of maps and their contents, comparing what a map describes
+//! rather than how it is stored.
+
+use crate::{Layer, LayerId, Map, TileLayer, TileSet};
+
+/// Tile layers are equal if they contain the same tiles,
+/// regardless of how many bytes are used to store them.
+impl PartialEq for TileLayer {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.name == other.name
+            && self.size == other.size
+            && self.visible == other.visible
+            && self.opacity == other.opacity
+            && self.tintcolor == other.tintcolor
+            && self.properties == other.properties
+            && self.tiles().eq(other.tiles())
+            && self.unknown == other.unknown
+    }
+}
+
+/// Tilesets are compared without their loaded images, only the [TileSet::image_path] counts.
+impl PartialEq for TileSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.firstgid == other.firstgid
+            && self.name == other.name
+            && self.tile_size == other.tile_size
+            && self.spacing == other.spacing
+            && self.margin == other.margin
+            && self.tilecount == other.tilecount
+            && self.columns == other.columns
+            && self.image_path == other.image_path
+            && self.transparent == other.transparent
+            && self.image_size == other.image_size
+            && self.source == other.source
+            && self.properties == other.properties
+            && self.tile_data == other.tile_data
+            && self.unknown == other.unknown
+    }
+}
+
+/// Maps are equal if they have the same contents.
+/// The layers are compared as a tree, so [LayerIds](LayerId) may differ between equal maps.
+/// Where a map was loaded from is not compared.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.editor_version == other.editor_version
+            && self.orientation == other.orientation
+            && self.renderorder == other.renderorder
+            && self.size == other.size
+            && self.tile_size == other.tile_size
+            && self.stagger_axis == other.stagger_axis
+            && self.stagger_index == other.stagger_index
+            && self.hexsidelength == other.hexsidelength
+            && self.tilesets == other.tilesets
+            && self.backgroundcolor == other.backgroundcolor
+            && self.properties == other.properties
+            && self.unknown == other.unknown
+            && same_layers(self, &self.root_layers, other, &other.root_layers)
+    }
+}
+
+/// Compare layers of two maps, including the layers inside of groups.
+fn same_layers(a: &Map, a_ids: &[LayerId], b: &Map, b_ids: &[LayerId]) -> bool {
+    a_ids.len() == b_ids.len()
+        && a_ids
+            .iter()
+            .zip(b_ids)
+            .all(|(&a_id, &b_id)| match (&a[a_id], &b[b_id]) {
+                (Layer::Group(a_group), Layer::Group(b_group)) => {
+                    a_group.id == b_group.id
+                        && a_group.name == b_group.name
+                        && a_group.offset == b_group.offset
+                        && a_group.opacity == b_group.opacity
+                        && a_group.visible == b_group.visible
+                        && a_group.tintcolor == b_group.tintcolor
+                        && a_group.properties == b_group.properties
+                        && a_group.unknown == b_group.unknown
+                        && same_layers(a, &a_group.content, b, &b_group.content)
+                }
+                (a_layer, b_layer) => a_layer == b_layer,
+            })
+}
@@ -10,7 +10,7 @@ pub enum Error {
 
     /// An error that happened while parsing the map, e.g. the tmx file is not valid xml.
     #[error(transparent)]
-    ParseError(Box<dyn std::error::Error>),
+    ParseError(Box<dyn std::error::Error + Send + Sync>),
 
     /// A general IO error, e.g. opening a file failed
     #[error(transparent)]
@@ -22,14 +22,123 @@ pub enum Error {
 
     #[error("The expected property is a different type than expected")]
     PropertyTypeError,
+
+    /// Loading an external resource (e.g. an image or an external tileset) failed.
+    /// The resource description names the resource as well as the documents referencing it.
+    #[error("Failed to load {resource}: {source}")]
+    ResourceLoad {
+        resource: String,
+        source: Box<Error>,
+    },
+
+    /// Loading was aborted via the cancel flag of the
+    /// [ResourceManager](crate::ResourceManager::set_cancel_flag).
+    #[error("Loading was cancelled")]
+    Cancelled,
+
+    /// The map exceeds one of the [LoadLimits](crate::LoadLimits) of the
+    /// [ResourceManager](crate::ResourceManager).
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    /// An external document (e.g. a tileset or a template) references itself,
+    /// directly or via other documents.
+    /// The chain lists the documents in the order they were included,
+    /// the last one being the document that was included again.
+    #[error("Recursive include: {}", display_chain(.chain))]
+    RecursiveInclude { chain: Vec<std::path::PathBuf> },
+
+    /// A position is outside of the bounds of a layer, e.g. when setting a tile.
+    #[error("Position {pos:?} is outside of the layer with size {size:?}")]
+    OutOfBounds {
+        pos: crate::math::ivec2,
+        size: crate::math::ivec2,
+    },
+
+    /// A change to the layers of a map is not possible,
+    /// e.g. moving a group into itself or inserting a layer into a tile layer.
+    #[error("Invalid layer operation: {0}")]
+    InvalidLayer(String),
+
+    /// Data passed to [Map::from_cache](crate::Map::from_cache) is no valid map cache,
+    /// e.g. because it was written by a different version of tego.
+    #[error("Invalid map cache: {0}")]
+    InvalidCache(String),
+
+    /// The [ImageLoader](crate::ImageLoader) doesn't keep its contract, e.g.
+    /// [ImageLoader::load_batch](crate::ImageLoader::load_batch) returned fewer results
+    /// than it got sources.
+    #[error("Invalid image loader: {0}")]
+    InvalidImageLoader(String),
+
+    /// Drawing a map failed, e.g. because no tileset contains a tile of a layer
+    /// or the image of a tileset was not loaded.
+    #[error("Rendering failed: {0}")]
+    RenderError(String),
+
+    /// The map was saved with a newer version of Tiled than tego supports and
+    /// [MapLoadOptions::version_policy](crate::MapLoadOptions::version_policy) is
+    /// [VersionPolicy::Error](crate::VersionPolicy::Error).
+    #[error(transparent)]
+    UnsupportedVersion(#[from] UnsupportedVersion),
 }
 
+/// A map that is newer than [SUPPORTED_VERSION](crate::SUPPORTED_VERSION),
+/// see [VersionPolicy](crate::VersionPolicy).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Map version {} is newer than the supported version {}{}",
+    display_version(.version, .editor_version),
+    crate::SUPPORTED_VERSION,
+    display_constructs(.constructs)
+)]
+pub struct UnsupportedVersion {
+    /// Version of the map format.
+    pub version: crate::Version,
+
+    /// Version of Tiled that saved the map, if known.
+    pub editor_version: Option<crate::Version>,
+
+    /// The parts of the map that tego doesn't understand, see [Map::unsupported_constructs](crate::Map::unsupported_constructs).
+    pub constructs: Vec<String>,
+}
+
+fn display_version(version: &crate::Version, editor_version: &Option<crate::Version>) -> String {
+    match editor_version {
+        Some(editor_version) => format!("{} (Tiled {})", version, editor_version),
+        None => version.to_string(),
+    }
+}
+
+fn display_constructs(constructs: &[String]) -> String {
+    match constructs.is_empty() {
+        true => String::new(),
+        false => format!(", unsupported: {}", constructs.join(", ")),
+    }
+}
+
+fn display_chain(chain: &[std::path::PathBuf]) -> String {
+    chain
+        .iter()
+        .map(|path| format!("'{}'", path.display()))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(feature = "roxmltree")]
 impl From<roxmltree::Error> for Error {
     fn from(e: roxmltree::Error) -> Self {
         Error::ParseError(Box::new(e))
     }
 }
 
+#[cfg(any(feature = "quick-xml", feature = "streaming"))]
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        Error::ParseError(Box::new(e))
+    }
+}
+
 impl From<std::num::ParseIntError> for Error {
     fn from(e: std::num::ParseIntError) -> Self {
         Error::ParseError(Box::new(e))
@@ -0,0 +1,503 @@
+//! A C interface for loading maps with tego from other languages.
+//!
+//! Enabled by the `ffi` feature. `cargo build --release --features ffi` builds a shared
+//! and a static library in `target/release`, the matching declarations are in
+//! `include/tego.h`.
+//!
+//! All handles returned by these functions borrow from the map they belong to and are
+//! valid until the map is freed with [tego_map_free].
+//! Strings are copied into buffers of the caller, like `snprintf` does.
+//!
+//! Panics never unwind into the caller. They are reported like errors through
+//! [tego_last_error], and the function returns null, -1, 0 or false.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+
+use crate::{Layer, Map, Object, Property, PropertyContainer};
+
+pub const TEGO_LAYER_TILE: c_int = 0;
+pub const TEGO_LAYER_OBJECT: c_int = 1;
+pub const TEGO_LAYER_IMAGE: c_int = 2;
+pub const TEGO_LAYER_GROUP: c_int = 3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// Run `f`, returning `on_panic` if it panics instead of unwinding into C code,
+/// which is undefined behavior. The panic message becomes the last error.
+fn catch_panic<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        set_last_error(format!("Internal error: {}", message));
+        on_panic
+    })
+}
+
+/// Copy a string into the buffer of the caller, cut off at a character boundary if it
+/// doesn't fit. Returns the length of the whole string, without the terminating zero.
+unsafe fn copy_string(text: &str, buffer: *mut c_char, size: usize) -> usize {
+    if !buffer.is_null() && size > 0 {
+        let mut len = text.len().min(size - 1);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        std::ptr::copy_nonoverlapping(text.as_ptr().cast::<c_char>(), buffer, len);
+        *buffer.add(len) = 0;
+    }
+    text.len()
+}
+
+/// Load the map at the given path (UTF-8, zero terminated).
+/// Images are not decoded, only their paths are stored.
+///
+/// Returns null on failure, see [tego_last_error] for the reason.
+///
+/// # Safety
+/// `path` must be a valid zero terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_load(path: *const c_char) -> *mut Map {
+    catch_panic(std::ptr::null_mut(), || {
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(path) => path,
+            Err(e) => {
+                set_last_error(format!("Invalid path: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        match Map::from_file(Path::new(path)) {
+            Ok(map) => Box::into_raw(Box::new(map)),
+            Err(e) => {
+                set_last_error(e.to_string());
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Free a map returned by [tego_map_load]. Does nothing for null.
+///
+/// # Safety
+/// `map` must be null or a map returned by [tego_map_load] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_free(map: *mut Map) {
+    catch_panic((), || {
+        if !map.is_null() {
+            drop(Box::from_raw(map));
+        }
+    })
+}
+
+/// The message of the last error on this thread, or null if there was none.
+/// The string is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn tego_last_error() -> *const c_char {
+    catch_panic(std::ptr::null(), || {
+        LAST_ERROR.with(|error| {
+            error
+                .borrow()
+                .as_ref()
+                .map_or(std::ptr::null(), |message| message.as_ptr())
+        })
+    })
+}
+
+/// Size of the map in tiles.
+///
+/// # Safety
+/// `map` must be a valid map, `width` and `height` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_size(map: *const Map, width: *mut i32, height: *mut i32) {
+    catch_panic((), || {
+        *width = (*map).size.x;
+        *height = (*map).size.y;
+    })
+}
+
+/// Size of the tiles of the map in pixels.
+///
+/// # Safety
+/// `map` must be a valid map, `width` and `height` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_tile_size(map: *const Map, width: *mut i32, height: *mut i32) {
+    catch_panic((), || {
+        *width = (*map).tile_size.x;
+        *height = (*map).tile_size.y;
+    })
+}
+
+/// Number of top level layers of the map.
+///
+/// # Safety
+/// `map` must be a valid map.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_layer_count(map: *const Map) -> usize {
+    catch_panic(0, || (*map).layer_ids().len())
+}
+
+/// The top level layer with the given index, or null if the index is out of range.
+///
+/// # Safety
+/// `map` must be a valid map.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_layer(map: *const Map, index: usize) -> *const Layer {
+    catch_panic(std::ptr::null(), || {
+        let map = &*map;
+        map.layer_ids()
+            .get(index)
+            .map_or(std::ptr::null(), |&id| &map[id])
+    })
+}
+
+/// The properties of the map.
+///
+/// # Safety
+/// `map` must be a valid map.
+#[no_mangle]
+pub unsafe extern "C" fn tego_map_properties(map: *const Map) -> *const PropertyContainer {
+    catch_panic(std::ptr::null(), || &(*map).properties)
+}
+
+/// The kind of the layer, one of the `TEGO_LAYER_*` constants.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_layer_kind(layer: *const Layer) -> c_int {
+    catch_panic(-1, || match &*layer {
+        Layer::Tile(_) => TEGO_LAYER_TILE,
+        Layer::Object(_) => TEGO_LAYER_OBJECT,
+        Layer::Image(_) => TEGO_LAYER_IMAGE,
+        Layer::Group(_) => TEGO_LAYER_GROUP,
+    })
+}
+
+/// Copy the name of the layer into the buffer, returns the length of the name.
+///
+/// # Safety
+/// `layer` must be a valid layer, `buffer` null or valid for `size` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tego_layer_name(
+    layer: *const Layer,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    catch_panic(0, || copy_string((*layer).name().as_str(), buffer, size))
+}
+
+/// The properties of the layer.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_layer_properties(layer: *const Layer) -> *const PropertyContainer {
+    catch_panic(std::ptr::null(), || match &*layer {
+        Layer::Tile(layer) => &layer.properties,
+        Layer::Object(layer) => &layer.properties,
+        Layer::Image(layer) => &layer.properties,
+        Layer::Group(layer) => &layer.properties,
+    })
+}
+
+/// Number of layers inside of a group layer, 0 for other layers.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_group_layer_count(layer: *const Layer) -> usize {
+    catch_panic(0, || match &*layer {
+        Layer::Group(group) => group.content.len(),
+        _ => 0,
+    })
+}
+
+/// The layer with the given index inside of a group layer,
+/// or null if the index is out of range or the layer is no group.
+///
+/// # Safety
+/// `map` must be a valid map and `layer` a valid layer of it.
+#[no_mangle]
+pub unsafe extern "C" fn tego_group_layer(
+    map: *const Map,
+    layer: *const Layer,
+    index: usize,
+) -> *const Layer {
+    catch_panic(std::ptr::null(), || {
+        let map = &*map;
+        match &*layer {
+            Layer::Group(group) => group
+                .content
+                .get(index)
+                .map_or(std::ptr::null(), |&id| &map[id]),
+            _ => std::ptr::null(),
+        }
+    })
+}
+
+/// The raw GID of the tile at the given position of a tile layer, including the flip flags.
+/// Returns 0 for empty cells, positions outside of the layer and other layers.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_tile_layer_tile(layer: *const Layer, x: i32, y: i32) -> u32 {
+    catch_panic(0, || match &*layer {
+        Layer::Tile(tiles) => tiles
+            .tile(crate::math::ivec2::new(x, y))
+            .map_or(0, |gid| gid.as_raw()),
+        _ => 0,
+    })
+}
+
+/// Number of objects in an object layer, 0 for other layers.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_layer_object_count(layer: *const Layer) -> usize {
+    catch_panic(0, || match &*layer {
+        Layer::Object(objects) => objects.content.len(),
+        _ => 0,
+    })
+}
+
+/// The object with the given index of an object layer,
+/// or null if the index is out of range or the layer is no object layer.
+///
+/// # Safety
+/// `layer` must be a valid layer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_layer_object(
+    layer: *const Layer,
+    index: usize,
+) -> *const Object {
+    catch_panic(std::ptr::null(), || match &*layer {
+        Layer::Object(objects) => objects
+            .content
+            .get(index)
+            .map_or(std::ptr::null(), |object| object as *const Object),
+        _ => std::ptr::null(),
+    })
+}
+
+/// The unique id of the object.
+///
+/// # Safety
+/// `object` must be a valid object.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_id(object: *const Object) -> usize {
+    catch_panic(0, || (*object).id)
+}
+
+/// Copy the name of the object into the buffer, returns the length of the name.
+///
+/// # Safety
+/// `object` must be a valid object, `buffer` null or valid for `size` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_name(
+    object: *const Object,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    catch_panic(0, || copy_string((*object).name.as_str(), buffer, size))
+}
+
+/// Position and size of the object in pixels.
+///
+/// # Safety
+/// `object` must be a valid object, all other pointers must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_bounds(
+    object: *const Object,
+    x: *mut f32,
+    y: *mut f32,
+    width: *mut f32,
+    height: *mut f32,
+) {
+    catch_panic((), || {
+        let object = &*object;
+        *x = object.pos.x;
+        *y = object.pos.y;
+        *width = object.size.x;
+        *height = object.size.y;
+    })
+}
+
+/// The properties of the object.
+///
+/// # Safety
+/// `object` must be a valid object.
+#[no_mangle]
+pub unsafe extern "C" fn tego_object_properties(object: *const Object) -> *const PropertyContainer {
+    catch_panic(std::ptr::null(), || &(*object).properties)
+}
+
+/// Look up a property by its name (zero terminated UTF-8).
+unsafe fn property<'a>(
+    properties: *const PropertyContainer,
+    name: *const c_char,
+) -> Option<&'a Property> {
+    let name = CStr::from_ptr(name).to_str().ok()?;
+    (*properties).iter().find(|p| p.name.as_str() == name)
+}
+
+/// Read an int property into `value`.
+/// Returns false if there is no such property or it has another type.
+///
+/// # Safety
+/// `properties` must be valid, `name` a valid zero terminated string and `value` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_property_int(
+    properties: *const PropertyContainer,
+    name: *const c_char,
+    value: *mut i64,
+) -> bool {
+    catch_panic(false, || {
+        match property(properties, name).map(|p| p.as_i64()) {
+            Some(Ok(v)) => {
+                *value = v;
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Read a float property into `value`.
+/// Returns false if there is no such property or it has another type.
+///
+/// # Safety
+/// `properties` must be valid, `name` a valid zero terminated string and `value` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_property_float(
+    properties: *const PropertyContainer,
+    name: *const c_char,
+    value: *mut f64,
+) -> bool {
+    catch_panic(false, || {
+        match property(properties, name).map(|p| p.as_f64()) {
+            Some(Ok(v)) => {
+                *value = v;
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Read a bool property into `value`.
+/// Returns false if there is no such property or it has another type.
+///
+/// # Safety
+/// `properties` must be valid, `name` a valid zero terminated string and `value` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tego_property_bool(
+    properties: *const PropertyContainer,
+    name: *const c_char,
+    value: *mut bool,
+) -> bool {
+    catch_panic(false, || {
+        match property(properties, name).map(|p| p.as_bool()) {
+            Some(Ok(v)) => {
+                *value = v;
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Copy a string or file property into the buffer and return its length,
+/// or -1 if there is no such property or it has another type.
+///
+/// # Safety
+/// `properties` must be valid, `name` a valid zero terminated string
+/// and `buffer` null or valid for `size` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tego_property_string(
+    properties: *const PropertyContainer,
+    name: *const c_char,
+    buffer: *mut c_char,
+    size: usize,
+) -> isize {
+    catch_panic(-1, || {
+        let Some(value) = property(properties, name) else {
+            return -1;
+        };
+        match value.as_str().or_else(|_| value.as_file()) {
+            Ok(text) => copy_string(text, buffer, size) as isize,
+            Err(_) => -1,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_map_through_ffi() {
+        unsafe {
+            let path = CString::new("example-maps/default/objects.tmx").unwrap();
+            let map = tego_map_load(path.as_ptr());
+            assert!(!map.is_null());
+            assert_eq!(tego_map_layer_count(map), 2);
+
+            let group = tego_map_layer(map, 0);
+            assert_eq!(tego_layer_kind(group), TEGO_LAYER_GROUP);
+            let objects = tego_group_layer(map, group, 0);
+            assert_eq!(tego_layer_kind(objects), TEGO_LAYER_OBJECT);
+
+            let mut name = [0 as c_char; 8];
+            let len = tego_layer_name(objects, name.as_mut_ptr(), name.len());
+            assert_eq!(len, "Child Object Layer".len());
+            assert_eq!(CStr::from_ptr(name.as_ptr()).to_str(), Ok("Child O"));
+
+            let object = tego_object_layer_object(objects, 3);
+            let properties = tego_object_properties(object);
+            let mut answer = 0;
+            let key = CString::new("The Answer").unwrap();
+            assert!(tego_property_int(properties, key.as_ptr(), &mut answer));
+            assert_eq!(answer, 42);
+            assert_eq!(
+                tego_property_string(properties, key.as_ptr(), name.as_mut_ptr(), 8),
+                -1
+            );
+            tego_map_free(map);
+
+            let missing = CString::new("does/not/exist.tmx").unwrap();
+            assert!(tego_map_load(missing.as_ptr()).is_null());
+            assert!(!tego_last_error().is_null());
+        }
+    }
+
+    #[test]
+    fn test_cut_strings_at_char_boundary() {
+        let mut buffer = [0 as c_char; 4];
+        unsafe {
+            assert_eq!(copy_string("aäb", buffer.as_mut_ptr(), 3), 4);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str(), Ok("a"));
+            assert_eq!(copy_string("aäb", buffer.as_mut_ptr(), 4), 4);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str(), Ok("aä"));
+        }
+    }
+
+    #[test]
+    fn test_catch_panics() {
+        let result = catch_panic(-1, || panic!("broken map"));
+        assert_eq!(result, -1);
+        let message = unsafe { CStr::from_ptr(tego_last_error()) };
+        assert_eq!(message.to_str(), Ok("Internal error: broken map"));
+    }
+}
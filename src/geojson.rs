@@ -0,0 +1,174 @@
+//! Export of object layers as [GeoJSON](https://geojson.org), see [ObjectLayer::to_geojson].
+
+use std::fmt::Write;
+
+use crate::math::fvec2;
+use crate::writer::color_string;
+use crate::{Object, ObjectKind, ObjectLayer, PropertyContainer, PropertyValue};
+
+/// Number of corners of the polygons that approximate ellipses.
+const ELLIPSE_SEGMENTS: usize = 32;
+
+impl ObjectLayer {
+    /// Convert the objects of this layer into a GeoJSON FeatureCollection,
+    /// e.g. to process level geometry with GIS tools.
+    ///
+    /// Coordinates are the pixel coordinates of the map, with the y axis pointing down.
+    /// Rotations are applied to the coordinates.
+    /// Rectangles, tile objects and text boxes become polygons, ellipses are approximated
+    /// by polygons as well. Points, polygons and polylines are exported as they are.
+    ///
+    /// Each feature has the object id as its `id`. The name and type of the object
+    /// are stored as the properties `name` and `type`, followed by the custom properties.
+    pub fn to_geojson(&self) -> String {
+        let mut json = String::from(r#"{"type":"FeatureCollection","name":"#);
+        write_string(&mut json, &self.name);
+        json.push_str(r#","features":["#);
+        for (i, object) in self.content.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_feature(&mut json, object);
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+fn write_feature(json: &mut String, object: &Object) {
+    write!(json, r#"{{"type":"Feature","id":{},"geometry":"#, object.id).unwrap();
+    write_geometry(json, object);
+    json.push_str(r#","properties":{"name":"#);
+    write_string(json, &object.name);
+    json.push_str(r#","type":"#);
+    write_string(json, &object.type_);
+    write_properties(json, &object.properties);
+    json.push_str("}}");
+}
+
+fn write_geometry(json: &mut String, object: &Object) {
+    let rotate = |point: fvec2| {
+        let (sin, cos) = object.rotation.to_radians().sin_cos();
+        object.pos + fvec2::new(point.x * cos - point.y * sin, point.x * sin + point.y * cos)
+    };
+    let rectangle = |top: f32| {
+        let size = object.size;
+        vec![
+            fvec2::new(0., top),
+            fvec2::new(size.x, top),
+            fvec2::new(size.x, top + size.y),
+            fvec2::new(0., top + size.y),
+        ]
+    };
+    let (kind, points): (&str, Vec<fvec2>) = match &object.kind {
+        // tile objects are anchored at their bottom left corner
+        _ if object.tile_id.is_some() => ("Polygon", rectangle(-object.size.y)),
+        ObjectKind::Point => ("Point", vec![fvec2::new(0., 0.)]),
+        ObjectKind::Polyline { points } => ("LineString", points.to_vec()),
+        ObjectKind::Polygon { points } => ("Polygon", points.to_vec()),
+        ObjectKind::Ellipse => {
+            let radius = object.size * 0.5;
+            let points = (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    radius + fvec2::new(radius.x * angle.cos(), radius.y * angle.sin())
+                })
+                .collect();
+            ("Polygon", points)
+        }
+        ObjectKind::Rect | ObjectKind::Text { .. } => ("Polygon", rectangle(0.)),
+    };
+
+    write!(json, r#"{{"type":"{}","coordinates":"#, kind).unwrap();
+    let mut points: Vec<fvec2> = points.into_iter().map(rotate).collect();
+    match kind {
+        "Point" => write_point(json, points[0]),
+        "Polygon" => {
+            // rings of GeoJSON polygons end with their first point
+            points.extend(points.first().copied());
+            json.push('[');
+            write_points(json, &points);
+            json.push(']');
+        }
+        _ => write_points(json, &points),
+    }
+    json.push('}');
+}
+
+fn write_points(json: &mut String, points: &[fvec2]) {
+    json.push('[');
+    for (i, &point) in points.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_point(json, point);
+    }
+    json.push(']');
+}
+
+fn write_point(json: &mut String, point: fvec2) {
+    json.push('[');
+    write_number(json, point.x);
+    json.push(',');
+    write_number(json, point.y);
+    json.push(']');
+}
+
+/// Write the custom properties as members of the properties object, sorted by name.
+fn write_properties(json: &mut String, properties: &PropertyContainer) {
+    let mut properties: Vec<_> = properties
+        .iter()
+        .filter(|property| !matches!(property.name.as_str(), "name" | "type"))
+        .collect();
+    properties.sort_by(|a, b| a.name.cmp(&b.name));
+    for property in properties {
+        json.push(',');
+        write_string(json, &property.name);
+        json.push(':');
+        match &property.value {
+            PropertyValue::String(text) | PropertyValue::File(text) => write_string(json, text),
+            PropertyValue::Int(value) => write!(json, "{}", value).unwrap(),
+            PropertyValue::Float(value) => write_number(json, *value),
+            PropertyValue::Bool(value) => write!(json, "{}", value).unwrap(),
+            PropertyValue::Color(color) => write_string(json, &color_string(*color)),
+            PropertyValue::Object(object) => write!(json, "{}", object.id()).unwrap(),
+        }
+    }
+}
+
+/// Write a number, using null for values that JSON can't represent.
+fn write_number<T: Into<f64> + std::fmt::Display + Copy>(json: &mut String, value: T) {
+    if value.into().is_finite() {
+        write!(json, "{}", value).unwrap();
+    } else {
+        json.push_str("null");
+    }
+}
+
+pub(crate) fn write_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_string() {
+        let mut json = String::new();
+        write_string(&mut json, "a \"quoted\"\\path\n\u{1}");
+        assert_eq!(json, r#""a \"quoted\"\\path\n\u0001""#);
+    }
+}
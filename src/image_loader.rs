@@ -0,0 +1,73 @@
+//! Decoding images with the [image](https://crates.io/crates/image) crate, see [RgbaImageLoader].
+
+use std::any::Any;
+
+use image::RgbaImage;
+
+use crate::{Error, ImageLoader, ImageSource, Result};
+
+/// [ImageLoader] that decodes images into an [RgbaImage] of the image crate (version 0.23).
+///
+/// The color key of tilesets (the `trans` attribute in Tiled) is applied while loading,
+/// so pixels with that color are fully transparent.
+/// The loaded images can be retrieved by downcasting, e.g. via [Map::tile_image](crate::Map::tile_image).
+///
+/// Requires the `image` feature.
+///
+#[cfg_attr(feature = "fs", doc = "```")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// # use std::path::Path;
+/// use tego::{FileProvider, Map, ResourceManager, RgbaImageLoader};
+///
+/// let mut loader = ResourceManager::new(RgbaImageLoader::new(), FileProvider::new());
+/// let path = Path::new("example-maps/default/default_map.tmx");
+/// let map = Map::from_file_with_loader(path, &mut loader)?;
+/// let (image, rect) = map.tile_image("1".parse()?).unwrap();
+/// let image = image.downcast_ref::<image::RgbaImage>().unwrap();
+/// assert_eq!(image.dimensions(), (256, 256));
+/// assert_eq!(rect.size, map.tile_size);
+/// # Ok::<(),tego::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RgbaImageLoader {}
+
+impl RgbaImageLoader {
+    /// Create a loader that decodes images into RGBA images.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ImageLoader for RgbaImageLoader {
+    fn load(&mut self, mut source: ImageSource) -> Result<Box<dyn Any>> {
+        let mut image = image::load_from_memory(&source.read()?)
+            .map_err(|e| Error::ParseError(Box::new(e)))?
+            .to_rgba8();
+        if let Some(key) = source.context().transparent {
+            apply_color_key(&mut image, [key.red(), key.green(), key.blue()]);
+        }
+        Ok(Box::new(image))
+    }
+}
+
+/// Make all pixels with the given color transparent.
+fn apply_color_key(image: &mut RgbaImage, key: [u8; 3]) {
+    for pixel in image.pixels_mut() {
+        if pixel.0[..3] == key {
+            pixel.0[3] = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_color_key() {
+        let mut image =
+            RgbaImage::from_raw(2, 1, vec![255, 0, 255, 255, 255, 0, 254, 255]).unwrap();
+        apply_color_key(&mut image, [255, 0, 255]);
+        assert_eq!(image.into_raw(), [255, 0, 255, 0, 255, 0, 254, 255]);
+    }
+}
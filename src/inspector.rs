@@ -0,0 +1,282 @@
+//! A debug widget for [egui](https://crates.io/crates/egui), see [MapInspector].
+
+use egui::{CollapsingHeader, Grid, Response, Ui, Widget};
+
+use crate::writer::color_string;
+use crate::{Layer, LayerId, Map, Object, ObjectKind, PropertyContainer, PropertyValue, TileSet};
+
+/// An egui widget that shows the structure of a [Map], e.g. in an in-game debug overlay
+/// or for checking levels while testing.
+///
+/// The map, its tilesets and its layers are shown as collapsible tree, including the
+/// properties of the map, layers and objects. Requires the `egui` feature.
+///
+/// ```no_run
+/// # fn show(ctx: &egui::Context, map: &tego::Map) {
+/// egui::Window::new("Map").show(ctx, |ui| {
+///     ui.add(tego::MapInspector::new(map));
+/// });
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MapInspector<'a> {
+    map: &'a Map,
+    id_salt: &'a str,
+}
+
+impl<'a> MapInspector<'a> {
+    /// Create an inspector that shows the given map.
+    pub fn new(map: &'a Map) -> Self {
+        Self {
+            map,
+            id_salt: "tego_map_inspector",
+        }
+    }
+
+    /// Set the salt of the ids that egui uses to remember which parts of the tree are open.
+    /// Only needed when several inspectors are shown in the same [Ui].
+    pub fn id_salt(mut self, id_salt: &'a str) -> Self {
+        self.id_salt = id_salt;
+        self
+    }
+}
+
+impl Widget for MapInspector<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let map = self.map;
+        ui.push_id(self.id_salt, |ui| {
+            CollapsingHeader::new("Map")
+                .default_open(true)
+                .show(ui, |ui| {
+                    Grid::new("map_info").num_columns(2).show(ui, |ui| {
+                        row(ui, "Version", map.version);
+                        row(ui, "Orientation", map.orientation);
+                        row(ui, "Size", format!("{} x {} tiles", map.size.x, map.size.y));
+                        row(
+                            ui,
+                            "Tile size",
+                            format!("{} x {} px", map.tile_size.x, map.tile_size.y),
+                        );
+                        row(ui, "Background", color_string(map.backgroundcolor));
+                    });
+                    properties(ui, &map.properties);
+                });
+
+            CollapsingHeader::new(format!("Tilesets ({})", map.tilesets.len())).show(ui, |ui| {
+                for (index, tileset) in map.tilesets.iter().enumerate() {
+                    CollapsingHeader::new(tileset.name.as_ref())
+                        .id_salt(("tileset", index))
+                        .show(ui, |ui| tileset_info(ui, tileset));
+                }
+            });
+
+            CollapsingHeader::new("Layers")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for &id in map.layer_ids() {
+                        layer_tree(ui, map, id);
+                    }
+                });
+        })
+        .response
+    }
+}
+
+/// Add a row with a label and a value to a grid.
+fn row(ui: &mut Ui, label: &str, value: impl ToString) {
+    ui.label(label);
+    ui.label(value.to_string());
+    ui.end_row();
+}
+
+fn tileset_info(ui: &mut Ui, tileset: &TileSet) {
+    Grid::new("tileset_info").num_columns(2).show(ui, |ui| {
+        row(ui, "First GID", tileset.firstgid);
+        row(ui, "Tiles", tileset.tilecount);
+        row(ui, "Columns", tileset.columns);
+        row(
+            ui,
+            "Tile size",
+            format!("{} x {} px", tileset.tile_size.x, tileset.tile_size.y),
+        );
+        row(ui, "Spacing", tileset.spacing);
+        row(ui, "Margin", tileset.margin);
+        row(ui, "Image", tileset.image_path.display());
+        if let Some(source) = &tileset.source {
+            row(ui, "Source", source.display());
+        }
+    });
+    properties(ui, &tileset.properties);
+}
+
+/// Show a layer and, for groups, the layers inside of it.
+fn layer_tree(ui: &mut Ui, map: &Map, id: LayerId) {
+    let Some(layer) = map.layer(id) else {
+        return;
+    };
+    let kind = match layer {
+        Layer::Tile(_) => "tiles",
+        Layer::Group(_) => "group",
+        Layer::Object(_) => "objects",
+        Layer::Image(_) => "image",
+    };
+    CollapsingHeader::new(format!("{} ({})", layer.name(), kind))
+        .id_salt(id)
+        .show(ui, |ui| match layer {
+            Layer::Tile(layer) => {
+                Grid::new("layer_info").num_columns(2).show(ui, |ui| {
+                    row(
+                        ui,
+                        "Size",
+                        format!("{} x {} tiles", layer.size.x, layer.size.y),
+                    );
+                    row(ui, "Visible", layer.visible);
+                    row(ui, "Opacity", layer.opacity);
+                    row(ui, "Tint", color_string(layer.tintcolor));
+                });
+                properties(ui, &layer.properties);
+            }
+            Layer::Group(group) => {
+                Grid::new("layer_info").num_columns(2).show(ui, |ui| {
+                    row(
+                        ui,
+                        "Offset",
+                        format!("{}, {}", group.offset.x, group.offset.y),
+                    );
+                    row(ui, "Visible", group.visible);
+                    row(ui, "Opacity", group.opacity);
+                });
+                properties(ui, &group.properties);
+                for &child in &group.content {
+                    layer_tree(ui, map, child);
+                }
+            }
+            Layer::Object(layer) => {
+                Grid::new("layer_info").num_columns(2).show(ui, |ui| {
+                    row(
+                        ui,
+                        "Offset",
+                        format!("{}, {}", layer.offset.x, layer.offset.y),
+                    );
+                    row(ui, "Visible", layer.visible);
+                    row(ui, "Opacity", layer.opacity);
+                    row(ui, "Color", color_string(layer.color));
+                });
+                properties(ui, &layer.properties);
+                for object in &layer.content {
+                    object_info(ui, object);
+                }
+            }
+            Layer::Image(layer) => {
+                Grid::new("layer_info").num_columns(2).show(ui, |ui| {
+                    row(
+                        ui,
+                        "Offset",
+                        format!("{}, {}", layer.offset.x, layer.offset.y),
+                    );
+                    row(ui, "Visible", layer.visible);
+                    row(ui, "Opacity", layer.opacity);
+                });
+                properties(ui, &layer.properties);
+            }
+        });
+}
+
+fn object_info(ui: &mut Ui, object: &Object) {
+    let kind = match &object.kind {
+        ObjectKind::Rect if object.tile_id.is_some() => "tile",
+        ObjectKind::Rect => "rect",
+        ObjectKind::Ellipse => "ellipse",
+        ObjectKind::Point => "point",
+        ObjectKind::Polygon { .. } => "polygon",
+        ObjectKind::Polyline { .. } => "polyline",
+        ObjectKind::Text { .. } => "text",
+    };
+    CollapsingHeader::new(format!("#{} {} ({})", object.id, object.name, kind))
+        .id_salt(("object", object.id))
+        .show(ui, |ui| {
+            Grid::new("object_info").num_columns(2).show(ui, |ui| {
+                if !object.type_.is_empty() {
+                    row(ui, "Class", &object.type_);
+                }
+                row(
+                    ui,
+                    "Position",
+                    format!("{}, {}", object.pos.x, object.pos.y),
+                );
+                row(ui, "Size", format!("{} x {}", object.size.x, object.size.y));
+                row(ui, "Rotation", object.rotation);
+                row(ui, "Visible", object.visible);
+                if let Some(gid) = object.tile_id {
+                    row(ui, "GID", gid);
+                }
+                if let ObjectKind::Text { content, .. } = &object.kind {
+                    row(ui, "Text", content);
+                }
+            });
+            properties(ui, &object.properties);
+        });
+}
+
+/// Show custom properties sorted by name, if there are any.
+fn properties(ui: &mut Ui, properties: &PropertyContainer) {
+    let mut sorted: Vec<_> = properties.iter().collect();
+    if sorted.is_empty() {
+        return;
+    }
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    CollapsingHeader::new(format!("Properties ({})", sorted.len())).show(ui, |ui| {
+        Grid::new("properties")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for property in sorted {
+                    let value = match &property.value {
+                        PropertyValue::String(text) | PropertyValue::File(text) => text.clone(),
+                        PropertyValue::Int(value) => value.to_string(),
+                        PropertyValue::Float(value) => value.to_string(),
+                        PropertyValue::Bool(value) => value.to_string(),
+                        PropertyValue::Color(color) => color_string(*color),
+                        PropertyValue::Object(reference) => format!("object #{}", reference.id()),
+                    };
+                    row(ui, &property.name, value);
+                }
+            });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EmbeddedProvider, LazyLoader, ResourceManager};
+
+    #[test]
+    fn test_show_inspector() {
+        let map = Map::from_xml_str(
+            r#"
+            <map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8">
+                <properties><property name="music" value="cave.ogg"/></properties>
+                <group id="1" name="Level">
+                    <layer id="2" name="Ground" width="2" height="2">
+                        <data encoding="csv">0,0,0,0</data>
+                    </layer>
+                    <objectgroup id="3" name="Spawns">
+                        <object id="1" name="player" x="4" y="4"><point/></object>
+                    </objectgroup>
+                </group>
+            </map>"#,
+            &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+        )
+        .unwrap();
+
+        let ctx = egui::Context::default();
+        let mut response = None;
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                response = Some(ui.add(MapInspector::new(&map)));
+            });
+        });
+        assert!(response.unwrap().rect.height() > 0.);
+        assert!(!output.shapes.is_empty());
+    }
+}
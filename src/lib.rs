@@ -23,22 +23,114 @@
 
 use core::num::NonZeroU32;
 use std::any::Any;
+use std::collections::HashMap;
+use std::io::Read;
 use std::rc::Rc;
-use std::{fs::File, io::Read};
 
-use roxmltree::Document;
+use xml::{Document, Node, XmlNode};
 
 #[macro_use]
 extern crate impl_ops;
 
+mod ascii;
+mod atlas;
+#[cfg(feature = "cache")]
+mod cache;
+mod csv;
+mod dependencies;
+mod diff;
+mod edit;
+mod eq;
 mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod geojson;
+#[cfg(feature = "image")]
+mod image_loader;
+#[cfg(feature = "egui")]
+mod inspector;
+mod json;
+#[cfg(feature = "macroquad")]
+pub mod macroquad;
 pub mod math;
+mod mesh;
+mod minimap;
+mod name;
+mod navmesh;
+mod occupancy;
+#[cfg(feature = "parry2d")]
+mod parry;
+mod project;
 mod property;
+#[cfg(feature = "python")]
+pub mod python;
+mod registry;
+#[cfg(feature = "render")]
+pub mod render;
+mod renderer;
 mod resource_manager;
+#[cfg(feature = "sdl2")]
+pub mod sdl2;
+mod shape;
+mod spawn;
+#[cfg(feature = "streaming")]
+mod streaming;
+mod svg;
+mod texturepacker;
+mod tile;
+mod tile_storage;
+mod unknown;
+mod visitor;
+mod writer;
+mod xml;
+pub use atlas::{Atlas, AtlasTile};
+#[cfg(feature = "cache")]
+pub use cache::CACHE_VERSION;
+pub use dependencies::{Dependency, DependencyKind};
+pub use diff::{diff, Difference, PropertyOwner};
+pub use edit::Anchor;
 pub use errors::Error;
 pub use errors::Result;
+pub use errors::UnsupportedVersion;
+#[cfg(feature = "image")]
+pub use image_loader::RgbaImageLoader;
+#[cfg(feature = "egui")]
+pub use inspector::MapInspector;
+pub use mesh::{MeshBatch, TileMesh, TileVertex};
+pub use name::Name;
+pub use navmesh::NavMesh;
+use occupancy::Occupancy;
+#[cfg(feature = "parry2d")]
+pub use parry::Collider;
+pub use project::{ClassMember, EnumStorage, Project, PropertyType, PropertyTypeKind};
 pub use property::{Property, PropertyContainer, PropertyValue};
-pub use resource_manager::{FileProvider, ImageLoader, Provider, ResourceManager};
+pub use registry::ClassRegistry;
+pub use renderer::{RenderOptions, TileRenderer, TileTransform};
+#[cfg(feature = "fs")]
+pub use resource_manager::FileProvider;
+pub use resource_manager::{
+    EmbeddedProvider, ImageContext, ImageLoader, ImageSource, LazyLoader, LoadLimits, LoadStage,
+    MapLoadOptions, Provider, ResourceManager, VersionPolicy,
+};
+pub use shape::{CollisionShape, Shape};
+pub use spawn::Spawn;
+pub use svg::SvgOptions;
+use tile::TileData;
+pub use tile::{AnimationFrame, Tile};
+use tile_storage::TileStorage;
+use unknown::UnknownXml;
+pub use visitor::MapVisitor;
+
+/// Attributes shared by group, object and image layers.
+const LAYER_ATTRIBUTES: [&str; 7] = [
+    "id",
+    "name",
+    "offsetx",
+    "offsety",
+    "opacity",
+    "visible",
+    "tintcolor",
+];
 
 const GID_HORIZONTAL_FLIP_FLAG: u32 = 0x80000000;
 const GID_VERTICAL_FLIP_FLAG: u32 = 0x40000000;
@@ -47,8 +139,24 @@ const GID_DIAGONAL_FLIP_FLAG: u32 = 0x20000000;
 const GID_FLIP_MASK: u32 =
     GID_HORIZONTAL_FLIP_FLAG | GID_VERTICAL_FLIP_FLAG | GID_DIAGONAL_FLIP_FLAG;
 
+/// The newest version of the map format (and of Tiled) whose features tego knows about,
+/// see [VersionPolicy].
+pub const SUPPORTED_VERSION: Version = Version(1, 10, None);
+
 /// Version number consisting out of a MAJOR and MINOR version number, followed by an optional PATCH
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Versions are ordered by their numbers, a version without PATCH comes before all versions
+/// with the same MAJOR and MINOR number and a PATCH.
+///
+/// ```
+/// # use tego::Version;
+/// let version: Version = "1.7.2".parse()?;
+/// assert_eq!(version.to_string(), "1.7.2");
+/// assert!(version >= Version(1, 5, None));
+/// assert!(version < Version(1, 10, None));
+/// # Ok::<(),tego::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Version(
     /// Major version
     pub u32,
@@ -83,7 +191,19 @@ impl std::str::FromStr for Version {
     }
 }
 
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.0, self.1)?;
+        if let Some(patch) = self.2 {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Orientation {
+    #[default]
     Orthogonal,
     Isometric,
     Staggered,
@@ -107,7 +227,32 @@ impl std::str::FromStr for Orientation {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+impl Orientation {
+    /// The name of the orientation in TMX files, the inverse of [FromStr](std::str::FromStr).
+    ///
+    /// ```
+    /// # use tego::Orientation;
+    /// assert_eq!(Orientation::Isometric.as_str(), "isometric");
+    /// assert_eq!("isometric".parse::<Orientation>()?, Orientation::Isometric);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Orthogonal => "orthogonal",
+            Orientation::Isometric => "isometric",
+            Orientation::Staggered => "staggered",
+            Orientation::Hexagonal => "hexagonal",
+        }
+    }
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
 pub enum Renderorder {
     #[default]
     RightDown,
@@ -133,12 +278,78 @@ impl std::str::FromStr for Renderorder {
     }
 }
 
+impl Renderorder {
+    /// The name of the render order in TMX files, e.g. `right-down`,
+    /// the inverse of [FromStr](std::str::FromStr).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Renderorder::RightDown => "right-down",
+            Renderorder::RightUp => "right-up",
+            Renderorder::LeftDown => "left-down",
+            Renderorder::LeftUp => "left-up",
+        }
+    }
+}
+
+impl std::fmt::Display for Renderorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Which axis is shifted on staggered and hexagonal maps, see [Map::stagger_axis].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum StaggerAxis {
+    /// Every other column is shifted down.
+    X,
+    /// Every other row is shifted to the right.
+    #[default]
+    Y,
+}
+
+impl std::str::FromStr for StaggerAxis {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "x" => Ok(StaggerAxis::X),
+            "y" => Ok(StaggerAxis::Y),
+            _ => Err(Error::ParseError(
+                format!("Invalid stagger axis '{}'", s).into(),
+            )),
+        }
+    }
+}
+
+/// Whether the odd or the even rows (or columns) are shifted on staggered and hexagonal maps,
+/// see [Map::stagger_index].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum StaggerIndex {
+    #[default]
+    Odd,
+    Even,
+}
+
+impl std::str::FromStr for StaggerIndex {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "odd" => Ok(StaggerIndex::Odd),
+            "even" => Ok(StaggerIndex::Even),
+            _ => Err(Error::ParseError(
+                format!("Invalid stagger index '{}'", s).into(),
+            )),
+        }
+    }
+}
+
 /// An 8 bit RGB color with alpha value.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 pub struct Color(u32);
 
 impl Color {
-    pub fn from_argb(a: u8, r: u8, g: u8, b: u8) -> Self {
+    pub const fn from_argb(a: u8, r: u8, g: u8, b: u8) -> Self {
         Color((a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | (b as u32))
     }
 
@@ -158,6 +369,27 @@ impl Color {
     pub fn to_u32(&self) -> u32 {
         self.0
     }
+
+    /// Multiply the channels of both colors, as if they were in the interval [0-1],
+    /// e.g. to combine the [tint colors](TileLayer::tintcolor) of a layer and its groups.
+    /// Opaque white leaves the other color unchanged.
+    ///
+    /// ```
+    /// # use tego::Color;
+    /// let tint = Color::from_argb(255, 255, 128, 0);
+    /// let group = Color::from_argb(128, 128, 255, 255);
+    /// assert_eq!(tint.multiply(group), Color::from_argb(128, 128, 128, 0));
+    /// assert_eq!(tint.multiply(Color::from_argb(255, 255, 255, 255)), tint);
+    /// ```
+    pub fn multiply(&self, other: Color) -> Color {
+        let channel = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+        Color::from_argb(
+            channel(self.alpha(), other.alpha()),
+            channel(self.red(), other.red()),
+            channel(self.green(), other.green()),
+            channel(self.blue(), other.blue()),
+        )
+    }
 }
 
 impl std::str::FromStr for Color {
@@ -195,11 +427,32 @@ impl std::str::FromStr for Color {
 
 /// Global Tile ID
 /// A GID acts as an index into any tileset referenced in the map
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
 #[repr(transparent)]
 pub struct GID(NonZeroU32);
 
 impl GID {
+    /// The GID of the tile with the given id, without any flip flags.
+    ///
+    /// Returns None for 0, which stands for empty cells, and for ids that overlap
+    /// the bits of the flip flags.
+    ///
+    /// ```
+    /// # use tego::GID;
+    /// let gid = GID::new(42).unwrap();
+    /// assert_eq!(gid.to_id(), 42);
+    /// assert!(GID::new(0).is_none());
+    /// ```
+    pub const fn new(id: u32) -> Option<GID> {
+        if id & GID_FLIP_MASK != 0 {
+            return None;
+        }
+        match NonZeroU32::new(id) {
+            Some(raw) => Some(GID(raw)),
+            None => None,
+        }
+    }
+
     const fn as_raw(&self) -> u32 {
         self.0.get()
     }
@@ -229,6 +482,31 @@ impl GID {
     pub fn flip_diagonal(&self) -> bool {
         (self.as_raw() & GID_DIAGONAL_FLIP_FLAG) == GID_DIAGONAL_FLIP_FLAG
     }
+
+    /// The same tile with the given flip flags, replacing the flags of this GID.
+    ///
+    /// ```
+    /// # use tego::GID;
+    /// let gid = GID::new(42).unwrap().with_flips(true, false, true);
+    /// assert!(gid.flip_horizontal() && !gid.flip_vertical() && gid.flip_diagonal());
+    /// assert_eq!(gid.to_id(), 42);
+    /// assert_eq!(gid.to_string(), "42 (flipped horizontally, diagonally)");
+    /// ```
+    pub fn with_flips(self, horizontal: bool, vertical: bool, diagonal: bool) -> GID {
+        let flag = |set: bool, flag: u32| if set { flag } else { 0 };
+        let flags = flag(horizontal, GID_HORIZONTAL_FLIP_FLAG)
+            | flag(vertical, GID_VERTICAL_FLIP_FLAG)
+            | flag(diagonal, GID_DIAGONAL_FLIP_FLAG);
+        GID(NonZeroU32::new(self.to_id() | flags).unwrap())
+    }
+
+    /// A GID referencing another tile, with the same flip flags as this one.
+    ///
+    /// # Panics
+    /// If the id is 0.
+    pub(crate) fn with_id(self, id: u32) -> GID {
+        GID(NonZeroU32::new(id | (self.as_raw() & GID_FLIP_MASK)).unwrap())
+    }
 }
 
 impl std::str::FromStr for GID {
@@ -239,25 +517,44 @@ impl std::str::FromStr for GID {
     }
 }
 
-fn attribute<T>(node: &roxmltree::Node, name: &str) -> Result<T>
+/// Shows the tile id, followed by the flips, e.g. `42 (flipped horizontally, vertically)`.
+impl std::fmt::Display for GID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_id())?;
+        let flips: Vec<_> = [
+            (self.flip_horizontal(), "horizontally"),
+            (self.flip_vertical(), "vertically"),
+            (self.flip_diagonal(), "diagonally"),
+        ]
+        .into_iter()
+        .filter_map(|(flipped, name)| flipped.then_some(name))
+        .collect();
+        if !flips.is_empty() {
+            write!(f, " (flipped {})", flips.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn attribute<T>(node: &Node, name: &str) -> Result<T>
 where
     T: std::str::FromStr,
-    T::Err: std::error::Error + 'static,
+    T::Err: std::error::Error + Send + Sync + 'static,
     Error: From<<T as std::str::FromStr>::Err>,
 {
     Ok(node
         .attribute(name)
         .ok_or_else(|| Error::StructureError {
-            tag: node.tag_name().name().to_string(),
+            tag: node.tag().to_string(),
             msg: format!("Required attribute '{}' missing", name),
         })?
         .parse()?)
 }
 
-fn attribute_or<T>(node: &roxmltree::Node, name: &str, alternative: T) -> Result<T>
+fn attribute_or<T>(node: &Node, name: &str, alternative: T) -> Result<T>
 where
     T: Copy + std::str::FromStr,
-    T::Err: std::error::Error + 'static,
+    T::Err: std::error::Error + Send + Sync + 'static,
 {
     match node.attribute(name) {
         None => Ok(alternative),
@@ -267,10 +564,15 @@ where
     }
 }
 
-fn attribute_or_default<T>(node: &roxmltree::Node, name: &str) -> Result<T>
+/// Read the "visible" attribute of a layer, which Tiled stores as 0 or 1.
+fn visible_attribute(node: &Node) -> Result<bool> {
+    Ok(attribute_or::<u8>(node, "visible", 1)? != 0)
+}
+
+fn attribute_or_default<T>(node: &Node, name: &str) -> Result<T>
 where
     T: Default + std::str::FromStr,
-    T::Err: std::error::Error + 'static,
+    T::Err: std::error::Error + Send + Sync + 'static,
 {
     match node.attribute(name) {
         None => Ok(T::default()),
@@ -281,11 +583,7 @@ where
 }
 
 impl math::ivec2 {
-    pub(crate) fn from_tmx_or_default(
-        tmx: &roxmltree::Node,
-        x_attr: &str,
-        y_attr: &str,
-    ) -> Result<Self> {
+    pub(crate) fn from_tmx_or_default(tmx: &Node, x_attr: &str, y_attr: &str) -> Result<Self> {
         Ok(Self::new(
             attribute_or_default(tmx, x_attr)?,
             attribute_or_default(tmx, y_attr)?,
@@ -294,11 +592,7 @@ impl math::ivec2 {
 }
 
 impl math::fvec2 {
-    pub(crate) fn from_tmx_or_default(
-        tmx: &roxmltree::Node,
-        x_attr: &str,
-        y_attr: &str,
-    ) -> Result<Self> {
+    pub(crate) fn from_tmx_or_default(tmx: &Node, x_attr: &str, y_attr: &str) -> Result<Self> {
         Ok(Self::new(
             attribute_or_default(tmx, x_attr)?,
             attribute_or_default(tmx, y_attr)?,
@@ -316,118 +610,359 @@ pub struct Image {
     pub size: Option<math::ivec2>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum ImageStorage {
     SpriteSheet(Rc<dyn Any>),
+
+    /// The image was not loaded yet, see [TileSet::image_path] for where it is stored.
+    Pending,
 }
 
+impl ImageStorage {
+    /// Whether the image was not loaded yet, see [ResourceManager::preload].
+    pub fn is_pending(&self) -> bool {
+        matches!(self, ImageStorage::Pending)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct TileSet {
     pub firstgid: GID,
-    pub name: String,
+    pub name: Name,
     pub tile_size: math::ivec2,
     pub spacing: usize,
     pub margin: usize,
     pub tilecount: usize,
     pub columns: usize,
     pub image: ImageStorage,
+
+    /// Path of the sprite sheet, including the base path of the [ResourceManager].
+    pub image_path: std::path::PathBuf,
+
+    /// Color which is treated as transparent in the sprite sheet.
+    pub transparent: Option<Color>,
+
+    /// Size of the sprite sheet in pixels as stored in the tileset, if known.
+    pub image_size: Option<math::ivec2>,
+
+    /// Path of the external tileset file (`*.tsx`) this tileset was loaded from,
+    /// including the base path of the [ResourceManager].
+    /// None for tilesets that are embedded into the map.
+    pub source: Option<std::path::PathBuf>,
+
     pub properties: PropertyContainer,
+    tile_data: HashMap<u32, TileData>,
+
+    /// Parts of the tileset that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
+
+    /// Source rectangles of all tiles, see [TileSet::cache_tile_rects].
+    rect_cache: Option<Box<[math::Rect]>>,
 }
 
 impl TileSet {
-    pub fn from_xml(node: &roxmltree::Node, loader: &mut ResourceManager) -> Result<Self> {
-        let mut data_node = *node;
-
-        // Need those two for lifetime reasons related to data_node
-        #[allow(unused_assignments)]
-        let mut extern_document = None;
-        #[allow(unused_assignments)]
-        let mut extern_text = None;
+    /// Load a tileset from a TMX "tileset" node.
+    /// If the node references an external tileset, it is loaded with the given loader.
+    ///
+    /// The image is left [Pending](ImageStorage::Pending), so that the images of all
+    /// tilesets in a map can be loaded as one batch.
+    pub(crate) fn from_xml_without_image(
+        node: &Node,
+        loader: &mut ResourceManager,
+    ) -> Result<Self> {
+        let firstgid = attribute(node, "firstgid")?;
 
         if let Some(source) = node.attribute("source") {
-            extern_text = Some(loader.load_text(source)?);
-            extern_document = Some(roxmltree::Document::parse(extern_text.as_ref().unwrap())?);
-            data_node = extern_document.as_ref().unwrap().root_element();
+            let mut tileset = loader.load_tileset_without_image(source)?;
+            tileset.firstgid = firstgid;
+            return Ok(tileset);
         }
 
-        let image_storage;
-        use ImageStorage::*;
-        if let Some(image) = data_node
+        Self::from_tileset_xml(node, firstgid, loader)
+    }
+
+    /// Load a tileset from a node containing the actual tileset data.
+    /// Either an embedded tileset in a map or the root of an external tileset file.
+    /// The image of the tileset is not loaded yet.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "parse_tileset", level = "debug", skip_all, fields(name = data_node.attribute("name"))))]
+    pub(crate) fn from_tileset_xml(
+        data_node: &Node,
+        firstgid: GID,
+        loader: &mut ResourceManager,
+    ) -> Result<Self> {
+        let name = loader.intern(attribute::<String>(data_node, "name")?.as_str());
+        let tile_size = math::ivec2::new(
+            attribute(data_node, "tilewidth")?,
+            attribute(data_node, "tileheight")?,
+        );
+        let spacing = attribute_or_default(data_node, "spacing")?;
+        let margin = attribute_or_default(data_node, "margin")?;
+
+        let image = data_node
             .children()
-            .find(|n| n.tag_name().name() == "image")
-        {
-            image_storage =
-                SpriteSheet(loader.load_image(image.attribute("source").ok_or_else(|| {
-                    Error::StructureError {
-                        tag: image.tag_name().name().into(),
-                        msg: "Missing 'source' tag on image".into(),
-                    }
-                })?)?);
-        } else {
-            return Err(Error::UnsupportedFeature(
-                "Image collection tilesets are not implemented yet".into(),
-            ));
-        }
+            .find(|n| n.tag() == "image")
+            .ok_or_else(|| {
+                Error::UnsupportedFeature(
+                    "Image collection tilesets are not implemented yet".into(),
+                )
+            })?;
+        let source = image
+            .attribute("source")
+            .ok_or_else(|| Error::StructureError {
+                tag: image.tag().into(),
+                msg: "Missing 'source' tag on image".into(),
+            })?;
+        let image_info = ImageContext::from_xml(&image)?;
 
         Ok(Self {
-            firstgid: attribute(node, "firstgid")?,
-            name: attribute(&data_node, "name")?,
-            tile_size: math::ivec2::new(
-                attribute(&data_node, "tilewidth")?,
-                attribute(&data_node, "tileheight")?,
+            firstgid,
+            name,
+            tile_size,
+            spacing,
+            margin,
+            tilecount: attribute(data_node, "tilecount")?,
+            columns: attribute(data_node, "columns")?,
+            image: ImageStorage::Pending,
+            image_path: loader.resolve_path(source),
+            transparent: image_info.transparent,
+            image_size: image_info.size,
+            source: None,
+            properties: PropertyContainer::from_xml(data_node, loader)?,
+            rect_cache: None,
+            unknown: UnknownXml::from_xml(
+                data_node,
+                &[
+                    "firstgid",
+                    "source",
+                    "name",
+                    "tilewidth",
+                    "tileheight",
+                    "spacing",
+                    "margin",
+                    "tilecount",
+                    "columns",
+                ],
+                &["properties", "image", "tile"],
             ),
-            spacing: attribute_or_default(&data_node, "spacing")?,
-            margin: attribute_or_default(&data_node, "margin")?,
-            tilecount: attribute(&data_node, "tilecount")?,
-            columns: attribute(&data_node, "columns")?,
-            image: image_storage,
-            properties: PropertyContainer::from_xml(&data_node)?,
+            tile_data: data_node
+                .children()
+                .filter(|n| n.tag() == "tile")
+                .map(|n| TileData::from_xml(&n, loader))
+                .collect::<Result<_>>()?,
         })
     }
+
+    /// Get a handle to the tile with the given local id.
+    /// Returns None if the id is outside of this tileset.
+    pub fn tile(&self, local_id: u32) -> Option<Tile<'_>> {
+        if (local_id as usize) < self.tilecount {
+            Some(Tile::new(self, local_id))
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all tiles in this tileset, in order of their local id.
+    /// Yields the [GID] referencing each tile together with a handle to it.
+    pub fn tiles(&self) -> impl Iterator<Item = (GID, Tile<'_>)> {
+        (0..self.tilecount as u32).map(|id| (self.gid_of(id), Tile::new(self, id)))
+    }
+
+    /// The rectangle inside of the sprite sheet that contains the tile with the given local id,
+    /// taking [TileSet::margin] and [TileSet::spacing] into account.
+    ///
+    /// Uses the cached rectangles if [TileSet::cache_tile_rects] was called.
+    pub fn tile_rect(&self, local_id: u32) -> math::Rect {
+        match self
+            .rect_cache
+            .as_ref()
+            .and_then(|cache| cache.get(local_id as usize))
+        {
+            Some(rect) => *rect,
+            None => self.compute_tile_rect(local_id),
+        }
+    }
+
+    /// Compute the rectangles of all tiles up front, so that [TileSet::tile_rect]
+    /// (and thus [Map::tile_image]) only needs to do a lookup.
+    /// Useful when drawing many tiles every frame.
+    ///
+    /// The cache is not updated automatically,
+    /// call this again after changing the tile size, margin, spacing or columns.
+    pub fn cache_tile_rects(&mut self) {
+        self.rect_cache = Some(
+            (0..self.tilecount as u32)
+                .map(|id| self.compute_tile_rect(id))
+                .collect(),
+        );
+    }
+
+    fn compute_tile_rect(&self, local_id: u32) -> math::Rect {
+        use math::ivec2;
+        let size = self.tile_size;
+        let stride = size + ivec2::new(self.spacing as i32, self.spacing as i32);
+
+        let lid = local_id as i32;
+        let tile_id = ivec2::new(lid % self.columns as i32, lid / self.columns as i32);
+        let upper_left = ivec2::new(self.margin as i32, self.margin as i32) + tile_id * stride;
+        math::Rect::new(upper_left, size)
+    }
+
+    /// Information about the sprite sheet of this tileset, passed to the [ImageLoader].
+    pub(crate) fn image_context(&self) -> ImageContext<'_> {
+        ImageContext {
+            tileset: Some(self.name.as_str()),
+            tile_size: Some(self.tile_size),
+            spacing: self.spacing,
+            margin: self.margin,
+            transparent: self.transparent,
+            size: self.image_size,
+        }
+    }
+
+    /// Convert a local tile id into a [GID] referencing this tileset.
+    fn gid_of(&self, local_id: u32) -> GID {
+        GID(NonZeroU32::new(self.firstgid.to_id() + local_id).unwrap())
+    }
 }
 
-/// Helper function to read the binary data contained in a "data" tag
+/// Decode the data of a `<data>` tag into GIDs,
+/// failing if it decodes to more than `max_bytes` bytes.
+///
+/// The base64 text is streamed through the decompressor straight into the GID buffer,
+/// without keeping the intermediate data in memory.
+///
 /// # Panics
 /// The given node has no "encoding" attribute
-fn read_data_tag(data_node: &roxmltree::Node) -> Result<Vec<u8>> {
-    assert_eq!(data_node.tag_name().name(), "data");
+fn read_data_tag(data_node: &Node, max_bytes: usize) -> Result<Vec<Option<GID>>> {
+    assert_eq!(data_node.tag(), "data");
     assert!(data_node.attribute("encoding").is_some());
 
     match data_node.attribute("encoding").unwrap() {
-        "csv" => todo! {"Implement csv parsing"},
+        "csv" => csv::read_csv_gids(data_node.text().unwrap_or_default(), max_bytes),
         "base64" => {
-            // helper macro for decoding compressed data using libflate
-            macro_rules! decode_with {
-                ($input:ident $compression:ident) => {{
-                    use std::io::Read;
-                    let mut decoded = Vec::new();
-                    let mut decoder = libflate::$compression::Decoder::new(&$input[..])?;
-                    decoder.read_to_end(&mut decoded)?;
-                    decoded
-                }};
-            }
-
-            let raw_bytes = base64::decode(data_node.text().unwrap_or_default().trim())
-                .map_err(|e| Error::ParseError(Box::new(e)))?;
-            let raw_bytes = match data_node.attribute("compression") {
-                None => raw_bytes,
-                Some("zlib") => decode_with!(raw_bytes zlib),
-                Some("gzip") => decode_with!(raw_bytes gzip),
-                Some(compression) => Err(Error::StructureError {
-                    tag: data_node.tag_name().name().to_string(),
-                    msg: format!("Unsupported data compression '{}'", compression),
-                })?,
-            };
-            Ok(raw_bytes)
+            let text = data_node.text().unwrap_or_default().trim().as_bytes();
+            let expected_tiles = data_node
+                .parent()
+                .map_or(0, |layer| expected_tiles(&layer, max_bytes));
+            read_base64_gids(
+                text,
+                data_node.attribute("compression"),
+                expected_tiles,
+                max_bytes,
+            )
         }
         encoding => Err(Error::StructureError {
-            tag: data_node.tag_name().name().to_string(),
+            tag: data_node.tag().to_string(),
             msg: format!("Unsupported data encoding '{}'", encoding),
         }),
     }
 }
 
+/// Number of tiles in the given layer node, used as capacity hint while decoding.
+///
+/// The layer size is only a hint, so it is not trusted more than the size limit.
+fn expected_tiles(layer: &Node, max_bytes: usize) -> usize {
+    let tiles = || {
+        let width: usize = attribute(layer, "width").ok()?;
+        let height: usize = attribute(layer, "height").ok()?;
+        width.checked_mul(height)
+    };
+    tiles().unwrap_or_default().min(max_bytes / GID_SIZE)
+}
+
+/// Decode base64 text, decompress it with the given compression and read the GIDs from it.
+/// The text must not contain any whitespace.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "decode_tile_data", level = "debug", skip_all, fields(encoding = "base64", compression = compression.unwrap_or("none"))))]
+fn read_base64_gids(
+    mut text: impl Read,
+    compression: Option<&str>,
+    expected_tiles: usize,
+    max_bytes: usize,
+) -> Result<Vec<Option<GID>>> {
+    let base64 = base64::read::DecoderReader::new(&mut text, base64::STANDARD);
+    let decoded: Box<dyn Read> = match compression {
+        None => Box::new(base64),
+        Some("zlib") => Box::new(libflate::zlib::Decoder::new(base64)?),
+        Some("gzip") => Box::new(libflate::gzip::Decoder::new(base64)?),
+        Some(compression) => Err(Error::StructureError {
+            tag: "data".into(),
+            msg: format!("Unsupported data compression '{}'", compression),
+        })?,
+    };
+    read_gids(decoded, expected_tiles, max_bytes)
+}
+
+/// Number of bytes used to store a single GID in the tile data.
+const GID_SIZE: usize = std::mem::size_of::<u32>();
+
+// `Option<GID>` has the same layout as `u32`, see [gid_bytes]
+const _: () = assert!(std::mem::size_of::<Option<GID>>() == GID_SIZE);
+
+/// Read little endian GIDs from the reader until it is exhausted.
+///
+/// The data is read straight into the memory of the returned GIDs,
+/// so on little endian targets no conversion is needed at all.
+fn read_gids(
+    mut reader: impl Read,
+    expected_tiles: usize,
+    max_bytes: usize,
+) -> Result<Vec<Option<GID>>> {
+    // one more tile than needed, so that the end of the data is found without growing
+    let max_tiles = max_bytes / GID_SIZE + 1;
+    let mut gids = vec![None; expected_tiles.saturating_add(1).min(max_tiles)];
+    let mut total = 0;
+    loop {
+        if total == gids.len() * GID_SIZE {
+            let len = gids
+                .len()
+                .saturating_mul(2)
+                .clamp(1024, max_tiles.max(1024));
+            gids.resize(len, None);
+        }
+        let read = match reader.read(&mut gid_bytes(&mut gids)[total..]) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        total += read;
+        if total > max_bytes {
+            return Err(Error::LimitExceeded(format!(
+                "Layer data is bigger than {} bytes",
+                max_bytes
+            )));
+        }
+    }
+
+    if total % GID_SIZE != 0 {
+        return Err(Error::StructureError {
+            tag: "data".into(),
+            msg: format!("Tile data is not a multiple of {} bytes", GID_SIZE),
+        });
+    }
+    gids.truncate(total / GID_SIZE);
+    if cfg!(target_endian = "big") {
+        for gid in &mut gids {
+            *gid = NonZeroU32::new(u32::from_le(gid.map_or(0, |g| g.as_raw()))).map(GID);
+        }
+    }
+    Ok(gids)
+}
+
+/// View the given GIDs as the bytes of their raw values, in native byte order.
+fn gid_bytes(gids: &mut [Option<GID>]) -> &mut [u8] {
+    let len = std::mem::size_of_val(gids);
+    // SAFETY: GID is a transparent wrapper around NonZeroU32, so `Option<GID>` is
+    // guaranteed to have the layout of an u32 (with 0 being None).
+    // Every bit pattern is a valid value, so arbitrary bytes may be written.
+    unsafe { std::slice::from_raw_parts_mut(gids.as_mut_ptr().cast::<u8>(), len) }
+}
+
 /// This enum contains the different types of layers that can be found in a map
 #[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Layer {
     /// A layer containing a grid of tiles
     Tile(TileLayer),
@@ -449,21 +984,133 @@ pub enum Layer {
 }
 
 impl Layer {
-    pub fn try_from_xml(
-        node: &roxmltree::Node,
+    /// Check whether the node is one of the layer types handled by [Layer::try_from_xml].
+    pub(crate) fn is_layer_node(node: &Node) -> bool {
+        matches!(node.tag(), "layer" | "group" | "objectgroup" | "imagelayer")
+    }
+
+    /// The name of this layer, regardless of its kind.
+    pub fn name(&self) -> &Name {
+        match self {
+            Layer::Tile(layer) => &layer.name,
+            Layer::Group(layer) => &layer.name,
+            Layer::Object(layer) => &layer.name,
+            Layer::Image(layer) => &layer.name,
+        }
+    }
+
+    /// The unique id of this layer in the TMX file, regardless of its kind.
+    pub fn id(&self) -> Option<usize> {
+        match self {
+            Layer::Tile(layer) => layer.id,
+            Layer::Group(layer) => layer.id,
+            Layer::Object(layer) => layer.id,
+            Layer::Image(layer) => layer.id,
+        }
+    }
+
+    fn id_mut(&mut self) -> &mut Option<usize> {
+        match self {
+            Layer::Tile(layer) => &mut layer.id,
+            Layer::Group(layer) => &mut layer.id,
+            Layer::Object(layer) => &mut layer.id,
+            Layer::Image(layer) => &mut layer.id,
+        }
+    }
+
+    /// Parse a layer node and add it to the given layers, including the layers inside of groups.
+    /// Returns None if the node is not a layer or the layer is skipped by the load options.
+    pub(crate) fn try_from_xml(
+        node: &Node,
         loader: &mut ResourceManager,
-    ) -> Option<Result<Self>> {
+        layers: &mut Vec<Option<Layer>>,
+    ) -> Option<Result<LayerId>> {
         use Layer::*;
-        match node.tag_name().name() {
-            "layer" => Some(TileLayer::from_xml(node).map(Tile)),
-            "group" => Some(GroupLayer::from_xml(node, loader).map(Group)),
-            "objectgroup" => Some(ObjectLayer::from_xml(node, loader).map(Object)),
-            "imagelayer" => Some(ImageLayer::from_xml(node, loader).map(Image)),
-            _ => None,
+        let layer = match node.tag() {
+            "layer" => TileLayer::from_xml(node, loader).map(Tile),
+            "group" => GroupLayer::from_xml(node, loader, layers).map(Group),
+            "objectgroup" if loader.load_options().skip_object_layers => return None,
+            "objectgroup" => ObjectLayer::from_xml(node, loader).map(Object),
+            "imagelayer" => ImageLayer::from_xml(node, loader).map(Image),
+            _ => return None,
+        };
+        Some(layer.map(|layer| LayerId::push(layers, layer)))
+    }
+}
+
+/// Handle to a layer of a [Map], e.g. to look up a layer with [Map::layer].
+///
+/// Handles stay valid as long as the map exists and the layer is not
+/// [removed](Map::remove_layer), but are meaningless for other maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(u32);
+
+impl LayerId {
+    /// Add a layer to the storage of a map, returning its handle.
+    pub(crate) fn push(layers: &mut Vec<Option<Layer>>, layer: Layer) -> Self {
+        layers.push(Some(layer));
+        Self((layers.len() - 1) as u32)
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Move this layer including all of its sublayers into another storage,
+    /// returning the handle of the layer in the new storage.
+    fn move_layer(self, from: &mut [Option<Layer>], to: &mut Vec<Option<Layer>>) -> Self {
+        let mut layer = from[self.index()].take().unwrap();
+        if let Layer::Group(group) = &mut layer {
+            for child in &mut group.content {
+                *child = child.move_layer(from, to);
+            }
         }
+        Self::push(to, layer)
     }
 }
 
+/// Top level layers that [Map::reparse] takes over from the previous version of a map.
+#[derive(Default)]
+struct ReusedLayers {
+    /// Number of reusable layers by the hash of their xml.
+    available: HashMap<u64, usize>,
+
+    /// Position in the top level layers and hash of each reused layer.
+    reused: Vec<(usize, u64)>,
+}
+
+impl ReusedLayers {
+    /// Reuse layers with the given hashes, each hash is given with the number of layers.
+    fn new(hashes: impl Iterator<Item = (u64, usize)>) -> Self {
+        Self {
+            available: hashes.collect(),
+            reused: Vec::new(),
+        }
+    }
+
+    /// Reuse a layer with the given hash, if one is available.
+    /// `parsed` is the number of top level layers that were parsed so far.
+    fn take(&mut self, hash: u64, parsed: usize) -> bool {
+        match self.available.get_mut(&hash) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                self.reused.push((parsed + self.reused.len(), hash));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Hash of the xml text of the given node.
+fn xml_hash(tmx: &str, node: &Node) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    tmx[node.range()].hash(&mut hasher);
+    hasher.finish()
+}
+
 pub struct TileIterator<'map, 'layer> {
     map: &'map Map,
     layer: &'layer TileLayer,
@@ -497,110 +1144,247 @@ impl<'a, 'b> Iterator for TileIterator<'a, 'b> {
             return None;
         }
 
-        let idx = self.pos.x + self.layer.size.x * self.pos.y;
-        let element = Some((self.pos, self.layer.tiles[idx as usize]));
+        let idx = self.pos.x as usize + self.layer.size.x as usize * self.pos.y as usize;
+        let element = Some((self.pos, self.layer.tiles.get(idx)));
         self.pos.x += 1;
         element
     }
 }
 
 /// A layer to group multiple sub-layers
+///
+/// Cloning or comparing a group uses the handles of its layers, not the layers themselves.
+/// Compare the whole [Map] to compare the layers inside of groups.
 #[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GroupLayer {
     pub id: Option<usize>,
-    pub name: String,
+    pub name: Name,
     pub offset: math::ivec2,
     pub opacity: f32,
     pub visible: bool,
     pub tintcolor: Color,
-    pub content: Vec<Layer>,
+
+    /// The layers inside of this group, see [GroupLayer::layers].
+    pub content: Vec<LayerId>,
     pub properties: PropertyContainer,
+
+    /// Parts of the layer that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl GroupLayer {
-    /// Load a group layer from a TMX "group" node
-    pub fn from_xml(node: &roxmltree::Node, loader: &mut ResourceManager) -> Result<Self> {
-        assert_eq!(node.tag_name().name(), "group");
+    /// Create an empty group, e.g. to add it with [Map::insert_layer].
+    pub fn new(name: impl Into<Name>) -> Self {
+        Self {
+            id: None,
+            name: name.into(),
+            offset: math::ivec2::new(0, 0),
+            opacity: 1.,
+            visible: true,
+            tintcolor: Color::from_argb(255, 255, 255, 255),
+            content: Vec::new(),
+            properties: PropertyContainer::new(),
+            unknown: UnknownXml::default(),
+        }
+    }
+
+    /// Load a group layer from a TMX "group" node.
+    /// The layers inside of the group are added to the given layers.
+    pub(crate) fn from_xml(
+        node: &Node,
+        loader: &mut ResourceManager,
+        layers: &mut Vec<Option<Layer>>,
+    ) -> Result<Self> {
+        assert_eq!(node.tag(), "group");
 
         let content = node
             .children()
-            .filter_map(|c| Layer::try_from_xml(&c, loader))
+            .filter_map(|c| Layer::try_from_xml(&c, loader, layers))
             .collect::<Result<Vec<_>>>();
 
         Ok(Self {
             id: node.attribute("id").map(|t| t.parse()).transpose()?,
-            name: node.attribute("name").unwrap_or_default().to_string(),
+            name: loader.intern(node.attribute("name").unwrap_or_default()),
             offset: math::ivec2::from_tmx_or_default(node, "offsetx", "offsety")?,
             opacity: attribute_or(node, "opacity", 1.)?,
-            visible: attribute_or(node, "opacity", true)?,
+            visible: visible_attribute(node)?,
             tintcolor: attribute_or(node, "tintcolor", Color::from_argb(255, 255, 255, 255))?,
             content: content?,
-            properties: PropertyContainer::from_xml(node)?,
+            properties: PropertyContainer::from_xml(node, loader)?,
+            unknown: UnknownXml::from_xml(
+                node,
+                &LAYER_ATTRIBUTES,
+                &["properties", "layer", "group", "objectgroup", "imagelayer"],
+            ),
         })
     }
+
+    /// Iterate over the layers inside of this group. The group must be part of the given map.
+    pub fn layers<'a>(&'a self, map: &'a Map) -> impl ExactSizeIterator<Item = &'a Layer> {
+        self.content.iter().map(move |&id| &map[id])
+    }
 }
 
 #[non_exhaustive]
+#[derive(Debug, Clone)]
 pub struct TileLayer {
     pub id: Option<usize>,
-    pub name: String,
+    pub name: Name,
     pub size: math::ivec2,
+    pub visible: bool,
+    pub opacity: f32,
 
     /// Color that is multiplied with the colors of the tiles in this layer.
     /// Defaults to opaque white, which acts as a no-op when multiplied.
     ///
     /// *Note:* Multiplication with the raw values of the [Color] struct would
     /// lead to the wrong result! The colors must first be converted to the
-    /// invervall [0-1] (division by 255).
+    /// invervall [0-1] (division by 255), which [Color::multiply] does.
     pub tintcolor: Color,
-    pub tiles: Vec<Option<GID>>,
 
     pub properties: PropertyContainer,
+
+    /// The tiles of this layer, stored row by row.
+    tiles: TileStorage,
+
+    /// Tracks which entries of `tiles` are not empty.
+    occupancy: Occupancy,
+
+    /// Parts of the layer that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl TileLayer {
-    fn parse_data(data_node: &roxmltree::Node) -> Result<Vec<Option<GID>>> {
-        assert_eq!(data_node.tag_name().name(), "data");
+    /// Create a tile layer of the given size without any tiles.
+    ///
+    /// Fails with [Error::LimitExceeded] if the layer has more than `i32::MAX` tiles.
+    pub fn new(name: impl Into<Name>, size: math::ivec2) -> Result<Self> {
+        let tiles = vec![None; Self::tile_count(size)?];
+        Ok(Self {
+            id: None,
+            name: name.into(),
+            size,
+            visible: true,
+            opacity: 1.,
+            tintcolor: Color::from_argb(255, 255, 255, 255),
+            properties: PropertyContainer::new(),
+            occupancy: Occupancy::from_tiles(&tiles),
+            tiles: TileStorage::new(tiles, false),
+            unknown: UnknownXml::default(),
+        })
+    }
+
+    /// Number of tiles of a layer with the given size, negative sizes count as 0.
+    /// Fails if there are more than `i32::MAX` tiles.
+    fn tile_count(size: math::ivec2) -> Result<usize> {
+        match size.x.max(0).checked_mul(size.y.max(0)) {
+            Some(count) => Ok(count as usize),
+            None => Err(Error::LimitExceeded(format!(
+                "Layer size {}x{} has more than {} tiles",
+                size.x,
+                size.y,
+                i32::MAX
+            ))),
+        }
+    }
+
+    fn parse_data(data_node: &Node, max_bytes: usize) -> Result<Vec<Option<GID>>> {
+        assert_eq!(data_node.tag(), "data");
 
         match data_node.attribute("encoding") {
-            None => todo! {"Tag based tile data loading not yet implemented"},
-            Some(_) => {
-                let raw_bytes = read_data_tag(data_node)?;
-
-                const BYTE_SIZE: usize = std::mem::size_of::<u32>();
-                assert!(raw_bytes.len() % BYTE_SIZE == 0);
-
-                // convert chunk of bytes into GIDS (via u32)
-                Ok(raw_bytes
-                    .chunks_exact(BYTE_SIZE)
-                    .map(|c| {
-                        Some(GID(NonZeroU32::new(u32::from_le_bytes(
-                            c.try_into().unwrap(),
-                        ))?))
-                    })
-                    .collect())
-            }
+            None => Err(Error::UnsupportedFeature(
+                "Tile data stored as <tile> elements is not implemented yet".into(),
+            )),
+            Some(_) => read_data_tag(data_node, max_bytes),
         }
     }
 
-    pub fn from_xml(tmx: &roxmltree::Node) -> Result<Self> {
+    /// Decode the tile data of all tile layers in the given layer nodes (including the
+    /// layers inside of groups) in parallel.
+    /// Returns the decoded data by the id of the `<data>` node.
+    #[cfg(feature = "rayon")]
+    fn decode_parallel(
+        layer_nodes: &[Node],
+        limits: &LoadLimits,
+    ) -> Result<HashMap<u32, Vec<Option<GID>>>> {
+        use rayon::prelude::*;
+
+        let data_nodes: Vec<_> = layer_nodes
+            .iter()
+            .flat_map(|node| node.descendants())
+            .filter(|node| node.tag() == "layer")
+            .filter_map(|node| node.children().find(|n| n.tag() == "data"))
+            .collect();
+        data_nodes
+            .par_iter()
+            .map(|node| {
+                Ok((
+                    node.index(),
+                    Self::parse_data(node, limits.max_layer_bytes)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Load a tile layer from a TMX "layer" node.
+    /// The [LoadLimits] of the loader are enforced while decoding the tile data.
+    pub(crate) fn from_xml(tmx: &Node, loader: &mut ResourceManager) -> Result<Self> {
         let map_attr = |name: &str| {
             tmx.attribute(name).ok_or_else(|| Error::StructureError {
-                tag: tmx.tag_name().name().to_string(),
+                tag: tmx.tag().to_string(),
                 msg: format!("Required attribute '{}' missing", name),
             })
         };
+        let size = math::ivec2::new(map_attr("width")?.parse()?, map_attr("height")?.parse()?);
+        loader.limits().check_size("Layer", size)?;
+        let data_node =
+            tmx.children()
+                .find(|n| n.tag() == "data")
+                .ok_or_else(|| Error::StructureError {
+                    tag: tmx.tag().to_string(),
+                    msg: "Tile data missing".into(),
+                })?;
+        let tiles = match loader.take_decoded_tile_data(&data_node) {
+            Some(tiles) => tiles,
+            None => Self::parse_data(&data_node, loader.limits().max_layer_bytes)?,
+        };
+        let expected = Self::tile_count(size)?;
+        if tiles.len() != expected {
+            return Err(Error::StructureError {
+                tag: data_node.tag().to_string(),
+                msg: format!(
+                    "Expected {} tiles for a {}x{} layer, found {}",
+                    expected,
+                    size.x,
+                    size.y,
+                    tiles.len()
+                ),
+            });
+        }
         Ok(Self {
             id: tmx.attribute("id").map(|t| t.parse()).transpose()?,
-            name: tmx.attribute("name").unwrap_or_default().to_string(),
-            size: math::ivec2::new(map_attr("width")?.parse()?, map_attr("height")?.parse()?),
+            name: loader.intern(tmx.attribute("name").unwrap_or_default()),
+            size,
+            visible: visible_attribute(tmx)?,
+            opacity: attribute_or(tmx, "opacity", 1.)?,
             tintcolor: attribute_or(tmx, "tintcolor", Color::from_argb(255, 255, 255, 255))?,
-            tiles: Self::parse_data(
-                &tmx.children()
-                    .find(|n| n.tag_name().name() == "data")
-                    .unwrap(),
-            )?,
-            properties: PropertyContainer::from_xml(tmx)?,
+            occupancy: Occupancy::from_tiles(&tiles),
+            tiles: TileStorage::new(tiles, loader.load_options().compact_tiles),
+            properties: PropertyContainer::from_xml(tmx, loader)?,
+            unknown: UnknownXml::from_xml(
+                tmx,
+                &[
+                    "id",
+                    "name",
+                    "width",
+                    "height",
+                    "visible",
+                    "opacity",
+                    "tintcolor",
+                ],
+                &["properties", "data"],
+            ),
         })
     }
 
@@ -615,18 +1399,149 @@ impl TileLayer {
     pub fn tiles_in_renderorder<'a, 'b>(&'b self, map: &'a Map) -> TileIterator<'a, 'b> {
         TileIterator::new(map, self)
     }
-}
 
-/// An ObjectLayer is a container of Objects.
-/// Objects are not aligned to the tile grid,
-/// and can be used to include extra information in a map.
-///
+    /// Get the tile at the given position.
+    /// Returns None if the cell is empty or outside of the layer.
+    pub fn tile(&self, pos: math::ivec2) -> Option<GID> {
+        self.index_of(pos).and_then(|index| self.tiles.get(index))
+    }
+
+    /// Iterate over all cells of this layer row by row, yielding None for empty cells.
+    pub fn tiles(&self) -> impl ExactSizeIterator<Item = Option<GID>> + '_ {
+        self.tiles.iter()
+    }
+
+    /// All cells of this layer row by row, as they are stored in memory.
+    ///
+    /// Returns None if the layer was stored with fewer bits per tile,
+    /// see [MapLoadOptions::compact_tiles]. Use [TileLayer::tiles] in that case.
+    pub fn as_slice(&self) -> Option<&[Option<GID>]> {
+        self.tiles.as_slice()
+    }
+
+    /// Number of bytes used to store each tile of this layer.
+    ///
+    /// This is 4 unless the layer was loaded with [MapLoadOptions::compact_tiles],
+    /// in which case it may be 2 or 1, depending on the largest GID in the layer.
+    pub fn bytes_per_tile(&self) -> usize {
+        self.tiles.bytes_per_tile()
+    }
+
+    /// Check whether there is a tile at the given position.
+    pub fn is_occupied(&self, pos: math::ivec2) -> bool {
+        self.index_of(pos)
+            .is_some_and(|index| self.occupancy.get(index))
+    }
+
+    /// Iterate over the positions and GIDs of all non-empty cells, row by row.
+    ///
+    /// Empty regions are skipped quickly with a bitset instead of checking every cell,
+    /// which makes this much faster than [TileLayer::tiles] for sparse layers.
+    pub fn occupied_tiles(&self) -> impl Iterator<Item = (math::ivec2, GID)> + '_ {
+        self.occupied_tiles_in(math::Rect::new(math::ivec2::new(0, 0), self.size))
+    }
+
+    /// Like [TileLayer::occupied_tiles], but only yields the tiles inside of the given
+    /// rectangle (in tile coordinates), e.g. the visible part of the layer or the area
+    /// around a collision query.
+    pub fn occupied_tiles_in(
+        &self,
+        rect: math::Rect,
+    ) -> impl Iterator<Item = (math::ivec2, GID)> + '_ {
+        let (start, end) = self.clamp_rect(rect);
+        let width = self.size.x as usize;
+        (start.y..end.y).flat_map(move |y| {
+            let row = y as usize * width;
+            let cells = self
+                .occupancy
+                .occupied(row + start.x as usize, row + end.x as usize);
+            cells.map(move |index| {
+                let pos = math::ivec2::new((index - row) as i32, y);
+                (pos, self.tiles.get(index).unwrap())
+            })
+        })
+    }
+
+    /// Set the tile at the given position, None removes the tile.
+    ///
+    /// Returns [Error::OutOfBounds] if the position is outside of the layer.
+    ///
+    /// ```
+    /// # let mut map = tego::Map::from_file(std::path::Path::new("example-maps/default/groups.tmx"))?;
+    /// # let id = map.layer_ids()[0];
+    /// # let tego::Layer::Tile(layer) = &mut map[id] else { unreachable!() };
+    /// let pos = tego::math::ivec2::new(2, 3);
+    /// layer.set_tile(pos, None)?;
+    /// assert_eq!(layer.tile(pos), None);
+    /// assert!(layer.set_tile(tego::math::ivec2::new(-1, 0), None).is_err());
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn set_tile(&mut self, pos: math::ivec2, tile: Option<GID>) -> Result<()> {
+        let index = self.index_of(pos).ok_or(Error::OutOfBounds {
+            pos,
+            size: self.size,
+        })?;
+        self.set_index(index, tile);
+        Ok(())
+    }
+
+    /// Set all cells inside of the given rectangle (in tile coordinates) to the same tile.
+    /// The parts of the rectangle that are outside of the layer are ignored.
+    pub fn fill_rect(&mut self, rect: math::Rect, tile: Option<GID>) {
+        let (start, end) = self.clamp_rect(rect);
+        for y in start.y..end.y {
+            for x in start.x..end.x {
+                let index = x as usize + y as usize * self.size.x as usize;
+                self.set_index(index, tile);
+            }
+        }
+    }
+
+    /// Remove all tiles from this layer.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.occupancy.clear();
+    }
+
+    /// Replace the GIDs of all non-empty cells, cells for which `f` returns None are cleared.
+    pub(crate) fn map_tiles(&mut self, mut f: impl FnMut(GID) -> Option<GID>) {
+        let occupied: Vec<_> = self.occupancy.occupied(0, self.tiles.len()).collect();
+        for index in occupied {
+            let tile = self.tiles.get(index).and_then(&mut f);
+            self.set_index(index, tile);
+        }
+    }
+
+    fn set_index(&mut self, index: usize, tile: Option<GID>) {
+        self.tiles.set(index, tile);
+        self.occupancy.set(index, tile.is_some());
+    }
+
+    /// The part of the rectangle that is inside of this layer, as start and end position.
+    /// Both are equal if the rectangle is completely outside.
+    fn clamp_rect(&self, rect: math::Rect) -> (math::ivec2, math::ivec2) {
+        let rect = rect.clamped_to(math::Rect::new(math::ivec2::new(0, 0), self.size));
+        (rect.upper_left, rect.upper_left + rect.size)
+    }
+
+    /// Index of the given position in the tile vector, None if the position is outside.
+    fn index_of(&self, pos: math::ivec2) -> Option<usize> {
+        let inside = (0..self.size.x).contains(&pos.x) && (0..self.size.y).contains(&pos.y);
+        inside.then(|| pos.x as usize + pos.y as usize * self.size.x as usize)
+    }
+}
+
+/// An ObjectLayer is a container of Objects.
+/// Objects are not aligned to the tile grid,
+/// and can be used to include extra information in a map.
+///
 /// Check the [Tiled Documentation](https://doc.mapeditor.org/en/stable/manual/objects/)
 /// for more information on objects.
 #[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ObjectLayer {
     pub id: Option<usize>,
-    pub name: String,
+    pub name: Name,
 
     /// Color that is used to render [Objects](Object) in this layer.
     pub color: Color,
@@ -643,28 +1558,52 @@ pub struct ObjectLayer {
     pub content: Vec<Object>,
 
     pub properties: PropertyContainer,
+
+    /// Parts of the layer that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl ObjectLayer {
-    pub fn from_xml(tmx: &roxmltree::Node, loader: &mut ResourceManager) -> Result<Self> {
-        assert_eq!(tmx.tag_name().name(), "objectgroup");
+    /// Create an object layer without any objects.
+    pub fn new(name: impl Into<Name>) -> Self {
+        Self {
+            id: None,
+            name: name.into(),
+            color: Color::from_argb(255, 160, 160, 164),
+            opacity: 1.,
+            visible: true,
+            tintcolor: Color::from_argb(255, 255, 255, 255),
+            offset: math::ivec2::new(0, 0),
+            content: Vec::new(),
+            properties: PropertyContainer::new(),
+            unknown: UnknownXml::default(),
+        }
+    }
+
+    pub(crate) fn from_xml(tmx: &Node, loader: &mut ResourceManager) -> Result<Self> {
+        assert_eq!(tmx.tag(), "objectgroup");
 
         let content = tmx
             .children()
-            .filter(|t| t.tag_name().name() == "object")
+            .filter(|t| t.tag() == "object")
             .map(|t| Object::from_xml(&t, loader))
             .collect::<Result<_>>()?;
 
         Ok(Self {
             id: tmx.attribute("id").map(|t| t.parse()).transpose()?,
-            name: tmx.attribute("name").unwrap_or_default().to_string(),
+            name: loader.intern(tmx.attribute("name").unwrap_or_default()),
             color: attribute_or(tmx, "color", Color::from_argb(255, 160, 160, 164))?,
             opacity: attribute_or(tmx, "opacity", 1.)?,
-            visible: attribute_or(tmx, "opacity", true)?,
+            visible: visible_attribute(tmx)?,
             tintcolor: attribute_or(tmx, "tintcolor", Color::from_argb(255, 255, 255, 255))?,
             offset: math::ivec2::from_tmx_or_default(tmx, "offsetx", "offsety")?,
             content,
-            properties: PropertyContainer::from_xml(tmx)?,
+            properties: PropertyContainer::from_xml(tmx, loader)?,
+            unknown: UnknownXml::from_xml(
+                tmx,
+                &[&LAYER_ATTRIBUTES[..], &["color"]].concat(),
+                &["properties", "object"],
+            ),
         })
     }
 }
@@ -675,11 +1614,13 @@ impl ObjectLayer {
 /// (e.g. rect, ellipse, text).
 /// See [ObjectKind] for more info.
 #[non_exhaustive]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Object {
     pub id: usize,
-    pub name: String,
-    pub type_: String,
+    pub name: Name,
+
+    /// The class of the object, stored as `class` since Tiled 1.9 and as `type` before.
+    pub type_: Name,
     pub pos: math::fvec2,
     pub size: math::fvec2,
     pub rotation: f32,
@@ -687,14 +1628,17 @@ pub struct Object {
     pub visible: bool,
     pub kind: ObjectKind,
     pub properties: PropertyContainer,
+
+    /// Parts of the object that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl Object {
     fn new(id: usize) -> Self {
         Object {
             id,
-            name: String::default(),
-            type_: String::default(),
+            name: Name::default(),
+            type_: Name::default(),
             pos: math::fvec2::default(),
             size: math::fvec2::default(),
             rotation: f32::default(),
@@ -702,21 +1646,24 @@ impl Object {
             visible: true,
             kind: ObjectKind::Rect,
             properties: PropertyContainer::new(),
+            unknown: UnknownXml::default(),
         }
     }
 
     /// Take an existing Object and update it with the contents of an xml node.
-    fn fill_from_xml(&mut self, tmx: &roxmltree::Node) -> Result<()> {
-        assert_eq!(tmx.tag_name().name(), "object");
+    /// Names are interned with the given loader.
+    fn fill_from_xml(&mut self, tmx: &Node, loader: &ResourceManager) -> Result<()> {
+        assert_eq!(tmx.tag(), "object");
 
         if let Some(id) = tmx.attribute("id") {
             self.id = id.parse()?;
         }
         if let Some(name) = tmx.attribute("name") {
-            self.name = name.parse()?;
+            self.name = loader.intern(name);
         }
-        if let Some(type_) = tmx.attribute("type_") {
-            self.type_ = type_.parse()?;
+        // Tiled 1.9 renamed the "type" attribute to "class"
+        if let Some(type_) = tmx.attribute("class").or_else(|| tmx.attribute("type")) {
+            self.type_ = loader.intern(type_);
         }
         if tmx.attribute("x").is_some() || tmx.attribute("y").is_some() {
             self.pos = math::fvec2::from_tmx_or_default(tmx, "x", "y")?;
@@ -727,7 +1674,7 @@ impl Object {
         if let Some(rotation) = tmx.attribute("rotation") {
             self.rotation = rotation.parse()?;
         }
-        if let Some(tile_id) = tmx.attribute("tile_id") {
+        if let Some(tile_id) = tmx.attribute("gid") {
             self.tile_id = Some(tile_id.parse()?);
         }
         if let Some(visible) = tmx.attribute("visible") {
@@ -741,16 +1688,31 @@ impl Object {
             self.kind = kind;
         }
 
-        self.properties.update_from_xml(tmx)?;
+        self.properties.update_from_xml(tmx, loader)?;
+        self.unknown.update_from_xml(
+            tmx,
+            &[
+                "id", "template", "name", "type", "class", "gid", "x", "y", "width", "height",
+                "rotation", "visible",
+            ],
+            &[
+                "properties",
+                "ellipse",
+                "point",
+                "polygon",
+                "polyline",
+                "text",
+            ],
+        );
         Ok(())
     }
 
-    fn from_xml(tmx: &roxmltree::Node, loader: &mut ResourceManager) -> Result<Self> {
-        assert_eq!(tmx.tag_name().name(), "object");
+    fn from_xml(tmx: &Node, loader: &mut ResourceManager) -> Result<Self> {
+        assert_eq!(tmx.tag(), "object");
 
         let map_attr = |name: &str| {
             tmx.attribute(name).ok_or_else(|| Error::StructureError {
-                tag: tmx.tag_name().name().to_string(),
+                tag: tmx.tag().to_string(),
                 msg: format!("Required attribute '{}' missing", name),
             })
         };
@@ -765,23 +1727,23 @@ impl Object {
             Self::new(id)
         };
 
-        obj.fill_from_xml(tmx)?;
+        obj.fill_from_xml(tmx, loader)?;
 
         Ok(obj)
     }
 }
 
 #[non_exhaustive]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ObjectKind {
     Rect,
     Ellipse,
     Point,
     Polygon {
-        points: Vec<math::fvec2>,
+        points: math::PointList,
     },
     Polyline {
-        points: Vec<math::fvec2>,
+        points: math::PointList,
     },
     Text {
         content: String,
@@ -798,12 +1760,12 @@ pub enum ObjectKind {
 }
 
 trait AsPointListExt {
-    fn as_point_list(&self) -> Result<Vec<math::fvec2>>;
+    fn as_point_list(&self) -> Result<math::PointList>;
 }
 
 impl AsPointListExt for &str {
-    fn as_point_list(&self) -> Result<Vec<math::fvec2>> {
-        let mut points = vec![];
+    fn as_point_list(&self) -> Result<math::PointList> {
+        let mut points = math::PointList::new();
         for point in self.split_ascii_whitespace() {
             let mut coords = point.split(',');
             if let (Some(x), Some(y), None) = (coords.next(), coords.next(), coords.next()) {
@@ -819,18 +1781,18 @@ impl AsPointListExt for &str {
 }
 
 impl ObjectKind {
-    fn from_xml(tmx: &roxmltree::Node) -> Result<Self> {
+    fn from_xml(tmx: &Node) -> Result<Self> {
         use Error::StructureError;
         use ObjectKind::*;
         for child in tmx.children() {
-            match child.tag_name().name() {
+            match child.tag() {
                 "ellipse" => return Ok(Ellipse),
                 "point" => return Ok(Point),
                 poly @ ("polygon" | "polyline") => {
                     let points = child
                         .attribute("points")
                         .ok_or(StructureError {
-                            tag: child.tag_name().name().into(),
+                            tag: child.tag().into(),
                             msg: "Missing attribute points".into(),
                         })?
                         .as_point_list()?;
@@ -841,16 +1803,20 @@ impl ObjectKind {
                     });
                 }
                 "text" => {
+                    // Tiled stores the flags as 0 or 1
+                    let flag = |name: &str, default: bool| -> Result<bool> {
+                        Ok(attribute_or::<u8>(&child, name, default as u8)? != 0)
+                    };
                     return Ok(Text {
                         content: child.text().unwrap_or_default().into(),
-                        pixelsize: attribute_or(tmx, "pixelsize", 16)?,
-                        wrap: attribute_or_default(tmx, "wrap")?,
-                        color: attribute_or_default(tmx, "color")?,
-                        bold: attribute_or_default(tmx, "bold")?,
-                        italic: attribute_or_default(tmx, "italic")?,
-                        underline: attribute_or_default(tmx, "underline")?,
-                        strikeout: attribute_or_default(tmx, "strikeout")?,
-                        kerning: attribute_or_default(tmx, "kerning")?,
+                        pixelsize: attribute_or(&child, "pixelsize", 16)?,
+                        wrap: flag("wrap", false)?,
+                        color: attribute_or(&child, "color", Color::from_argb(255, 0, 0, 0))?,
+                        bold: flag("bold", false)?,
+                        italic: flag("italic", false)?,
+                        underline: flag("underline", false)?,
+                        strikeout: flag("strikeout", false)?,
+                        kerning: flag("kerning", true)?,
                     });
                 }
                 _ => continue,
@@ -861,27 +1827,46 @@ impl ObjectKind {
 }
 
 #[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ImageLayer {
     pub id: Option<usize>,
-    pub name: String,
+    pub name: Name,
     pub offset: math::ivec2,
     pub opacity: f32,
     pub visible: bool,
     pub tintcolor: Color,
     // todo: actual image data
     pub properties: PropertyContainer,
+
+    /// Parts of the layer that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl ImageLayer {
-    fn from_xml(tmx: &roxmltree::Node, _loader: &mut ResourceManager) -> Result<Self> {
+    /// Create an image layer without an image.
+    pub fn new(name: impl Into<Name>) -> Self {
+        Self {
+            id: None,
+            name: name.into(),
+            offset: math::ivec2::new(0, 0),
+            opacity: 1.,
+            visible: true,
+            tintcolor: Color::from_argb(255, 255, 255, 255),
+            properties: PropertyContainer::new(),
+            unknown: UnknownXml::default(),
+        }
+    }
+
+    fn from_xml(tmx: &Node, loader: &mut ResourceManager) -> Result<Self> {
         Ok(ImageLayer {
             id: tmx.attribute("id").map(|t| t.parse()).transpose()?,
-            name: tmx.attribute("name").unwrap_or_default().to_string(),
+            name: loader.intern(tmx.attribute("name").unwrap_or_default()),
             offset: math::ivec2::from_tmx_or_default(tmx, "offsetx", "offsety")?,
             opacity: attribute_or(tmx, "opacity", 1.)?,
-            visible: attribute_or(tmx, "opacity", true)?,
+            visible: visible_attribute(tmx)?,
             tintcolor: attribute_or(tmx, "tintcolor", Color::from_argb(255, 255, 255, 255))?,
-            properties: PropertyContainer::from_xml(tmx)?,
+            properties: PropertyContainer::from_xml(tmx, loader)?,
+            unknown: UnknownXml::from_xml(tmx, &LAYER_ATTRIBUTES, &["properties"]),
         })
     }
 }
@@ -891,6 +1876,7 @@ impl ImageLayer {
 /// Stacking the layers in iteration order creates the final map image.
 /// Each layer contains indices ([GIDs](GID)) referencing a specific tile in a tile sets.
 #[non_exhaustive]
+#[derive(Debug)]
 pub struct Map {
     pub version: Version,
     pub editor_version: Option<Version>,
@@ -898,116 +1884,571 @@ pub struct Map {
     pub renderorder: Renderorder,
     pub size: math::ivec2,
     pub tile_size: math::ivec2,
+
+    /// Whether rows or columns are shifted, only used by staggered and hexagonal maps.
+    pub stagger_axis: StaggerAxis,
+
+    /// Whether the odd or even rows (or columns) are shifted,
+    /// only used by staggered and hexagonal maps.
+    pub stagger_index: StaggerIndex,
+
+    /// Length of the straight edge of the hexagons in pixels, only used by hexagonal maps.
+    pub hexsidelength: i32,
+
     pub tilesets: Vec<TileSet>,
 
     /// Background color of this map.
     /// By default fully transparent.
     pub backgroundcolor: Color,
 
-    /// The Layers that make up this map.
-    /// The final map image is rendered by stacking the layers in iteration order.
-    pub layers: Vec<Layer>,
+    /// All layers of this map, including the layers inside of groups, indexed by [LayerId].
+    /// Removed layers leave an empty slot, so that the handles of other layers stay valid.
+    layers: Vec<Option<Layer>>,
+
+    /// The top level layers, see [Map::layer_ids].
+    root_layers: Vec<LayerId>,
+
+    /// Hashes of the xml of the top level layers as they were loaded, used by [Map::reparse].
+    /// Empty if the map was not loaded from a complete document.
+    layer_hashes: HashMap<LayerId, u64>,
 
     /// Custom properties contained in this map.
     pub properties: PropertyContainer,
+
+    /// Path and modification time of the file this map was loaded from, used for reloading.
+    source: Option<(std::path::PathBuf, Option<std::time::SystemTime>)>,
+
+    /// Parts of the map that tego doesn't parse, written back when saving.
+    unknown: UnknownXml,
 }
 
 impl Map {
+    /// Load a map from the file system, with the default [ResourceManager].
+    ///
+    /// Requires the `fs` feature (enabled by default),
+    /// use [Map::from_file_with_loader] or [Map::from_xml_str] to load maps from elsewhere.
+    #[cfg(feature = "fs")]
     pub fn from_file(path: &std::path::Path) -> Result<Self> {
         Self::from_file_with_loader(path, &mut ResourceManager::default())
     }
 
+    /// Load a map with the given loader.
+    /// The map file itself is read through the [Providers](Provider) of the loader,
+    /// like all other resources.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "load_map", skip_all, fields(path = %path.display()), err))]
     pub fn from_file_with_loader(
         path: &std::path::Path,
         resource_manager: &mut ResourceManager,
     ) -> Result<Self> {
-        let mut file = File::open(path)?;
+        let file_xml = resource_manager.read_text(path)?;
+        let modified = resource_manager.modified(path);
+
+        resource_manager.set_base_path(path.parent().unwrap_or(path));
+        let mut map = resource_manager.with_document_path(path.to_owned(), |loader| {
+            Self::from_xml_str(&file_xml, loader)
+        })?;
+        map.source = Some((path.to_owned(), modified));
+        Ok(map)
+    }
 
-        // TODO(texel, 2021-11-10): Change to use resource manager
-        let mut file_xml = String::new();
-        file.read_to_string(&mut file_xml)?;
+    /// The path of the file this map was loaded from, if it was loaded from a file.
+    pub fn source_path(&self) -> Option<&std::path::Path> {
+        self.source.as_ref().map(|(path, _)| path.as_path())
+    }
 
-        resource_manager.set_base_path(path.parent().unwrap_or(path).to_string_lossy().to_string());
-        Self::from_xml_str(&file_xml, resource_manager)
+    /// Check if the map file or any of the resources cached by the loader changed
+    /// since the map was loaded.
+    /// See [ResourceManager::changed_resources] for how changes are detected.
+    pub fn needs_reload(&self, resource_manager: &ResourceManager) -> bool {
+        let map_changed = match &self.source {
+            Some((path, modified @ Some(_))) => resource_manager.modified(path) != *modified,
+            _ => false,
+        };
+        map_changed || !resource_manager.changed_resources().is_empty()
     }
 
-    /// Parse a map from xml data
+    /// Load this map again from the file it was originally loaded from.
+    ///
+    /// Resources that changed since they were loaded are removed from the caches of the
+    /// loader first (see [ResourceManager::invalidate_changed]),
+    /// all other resources (e.g. images) are reused.
+    /// On error, the map is left unchanged.
+    pub fn reload(&mut self, resource_manager: &mut ResourceManager) -> Result<()> {
+        let path = match &self.source {
+            Some((path, _)) => path.clone(),
+            None => {
+                return Err(Error::UnsupportedFeature(
+                    "Reloading a map that was not loaded from a file".into(),
+                ))
+            }
+        };
+
+        resource_manager.invalidate_changed();
+        *self = Self::from_file_with_loader(&path, resource_manager)?;
+        Ok(())
+    }
+
+    /// List all external files that the map at the given path references,
+    /// e.g. tilesets, templates and images.
+    ///
+    /// External tilesets and templates are scanned recursively.
+    /// This does neither load any images nor decode the tile data,
+    /// which makes it suitable for asset pipelines that need to know what to pack.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn list_dependencies(path: &std::path::Path) -> Result<Vec<Dependency>> {
+        Self::list_dependencies_with_loader(path, &mut ResourceManager::default())
+    }
+
+    /// Like [Map::list_dependencies], but reads the referenced files with the given loader.
+    pub fn list_dependencies_with_loader(
+        path: &std::path::Path,
+        resource_manager: &mut ResourceManager,
+    ) -> Result<Vec<Dependency>> {
+        let file_xml = resource_manager.read_text(path)?;
+        resource_manager.set_base_path(path.parent().unwrap_or(path));
+        let document = Document::parse(&file_xml)?;
+
+        let mut dependencies = Vec::new();
+        dependencies::collect(
+            &document.root_element(),
+            resource_manager,
+            &mut dependencies,
+        )?;
+        Ok(dependencies)
+    }
+
+    /// Parse a map from xml data.
+    ///
+    /// Which parts of the map are loaded can be configured with the [MapLoadOptions]
+    /// of the resource manager, see [ResourceManager::set_load_options].
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "parse_map", skip_all, fields(bytes = tmx.len()), err))]
     pub fn from_xml_str(tmx: &str, resource_manager: &mut ResourceManager) -> Result<Self> {
         let document = Document::parse(tmx)?;
+        #[cfg(feature = "rayon")]
+        let decoded = Self::decode_parallel(
+            &document,
+            resource_manager.load_options(),
+            &resource_manager.limits(),
+        )?;
+        #[cfg(not(feature = "rayon"))]
+        let decoded = HashMap::new();
+        Self::from_document(
+            tmx,
+            &document,
+            decoded,
+            resource_manager,
+            &mut ReusedLayers::default(),
+        )
+    }
 
-        let map_node = document.root_element();
+    /// Update this map from a changed version of its xml data,
+    /// e.g. after the map was saved in an editor.
+    ///
+    /// Only the top level layers whose xml changed are parsed again,
+    /// together with all of their sublayers.
+    /// All other layers are taken over from this map as they are,
+    /// which avoids decoding their tile data again.
+    /// Layers that may have been changed since they were parsed, e.g. through
+    /// [Map::layer_mut], are parsed again as well.
+    /// Everything else (e.g. the tilesets and properties) is parsed again,
+    /// images are reused from the caches of the resource manager.
+    ///
+    /// The [LayerIds](LayerId) of this map are invalid afterwards.
+    /// On error, the map is left unchanged.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "reparse_map", skip_all, fields(bytes = tmx.len()), err))]
+    pub fn reparse(&mut self, tmx: &str, resource_manager: &mut ResourceManager) -> Result<()> {
+        let document = Document::parse(tmx)?;
+        let mut previous: HashMap<u64, Vec<LayerId>> = HashMap::new();
+        for &id in &self.root_layers {
+            if let Some(&hash) = self.layer_hashes.get(&id) {
+                previous.entry(hash).or_default().push(id);
+            }
+        }
+        let mut reuse = ReusedLayers::new(previous.iter().map(|(&h, ids)| (h, ids.len())));
+        let mut map =
+            Self::from_document(tmx, &document, HashMap::new(), resource_manager, &mut reuse)?;
+
+        for (position, hash) in reuse.reused {
+            let id = previous.get_mut(&hash).and_then(Vec::pop).unwrap();
+            let id = id.move_layer(&mut self.layers, &mut map.layers);
+            map.root_layers.insert(position, id);
+            map.layer_hashes.insert(id, hash);
+        }
+        map.source = self.source.take();
+        *self = map;
+        Ok(())
+    }
 
-        if map_node.tag_name().name() != "map" {
-            return Err(Error::StructureError {
-                tag: map_node.tag_name().name().to_string(),
-                msg: format!(
-                    "Expected tag 'map' at root level, got '{}'.",
-                    map_node.tag_name().name()
-                ),
-            });
+    /// Load several maps at once, e.g. all maps of a world.
+    ///
+    /// All maps are loaded with the given resource manager,
+    /// so tilesets, templates and images that are used by several maps are only loaded once.
+    /// With the `rayon` feature, the map files are parsed and their tile data is decoded
+    /// in parallel, only the parts that use the caches of the resource manager
+    /// are loaded one map after another.
+    ///
+    /// Returns the maps in the order of the given paths, or the first error that occurred.
+    pub fn load_many<P: AsRef<std::path::Path>>(
+        paths: impl IntoIterator<Item = P>,
+        resource_manager: &mut ResourceManager,
+    ) -> Result<Vec<Self>> {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let text = resource_manager.read_text(path)?;
+                Ok((path.to_owned(), text, resource_manager.modified(path)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        #[cfg(feature = "rayon")]
+        let documents: Vec<_> = {
+            use rayon::prelude::*;
+            let options = resource_manager.load_options().clone();
+            let limits = resource_manager.limits();
+            files
+                .par_iter()
+                .map(|(_, text, _)| {
+                    let document = Document::parse(text)?;
+                    let decoded = Self::decode_parallel(&document, &options, &limits)?;
+                    Ok((document, decoded))
+                })
+                .collect::<Result<_>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let documents = files
+            .iter()
+            .map(|(_, text, _)| Ok((Document::parse(text)?, HashMap::new())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut maps = Vec::with_capacity(files.len());
+        for ((path, text, modified), (document, decoded)) in files.iter().zip(documents) {
+            resource_manager.set_base_path(path.parent().unwrap_or(path));
+            let mut map = resource_manager.with_document_path(path.clone(), |loader| {
+                Self::from_document(
+                    text,
+                    &document,
+                    decoded,
+                    loader,
+                    &mut ReusedLayers::default(),
+                )
+            })?;
+            map.source = Some((path.clone(), *modified));
+            maps.push(map);
         }
+        Ok(maps)
+    }
+
+    /// Decode the tile data of all layers in the given map document in parallel,
+    /// see [ResourceManager::with_decoded_tile_data].
+    #[cfg(feature = "rayon")]
+    fn decode_parallel(
+        document: &Document,
+        options: &MapLoadOptions,
+        limits: &LoadLimits,
+    ) -> Result<HashMap<u32, Vec<Option<GID>>>> {
+        let layer_nodes: Vec<_> = map_root(document)?
+            .children()
+            .filter(|n| !options.header_only && options.includes_layer(n))
+            .collect();
+        TileLayer::decode_parallel(&layer_nodes, limits)
+    }
+
+    /// Build a map from the parsed document of the given text,
+    /// using the given tile data that was decoded ahead of time.
+    /// Top level layers that can be reused are skipped and recorded in `reuse`.
+    fn from_document(
+        tmx: &str,
+        document: &Document,
+        decoded: HashMap<u32, Vec<Option<GID>>>,
+        resource_manager: &mut ResourceManager,
+        reuse: &mut ReusedLayers,
+    ) -> Result<Self> {
+        let map_node = map_root(document)?;
 
         let map_attr = |name: &str| {
             map_node
                 .attribute(name)
                 .ok_or_else(|| Error::StructureError {
-                    tag: map_node.tag_name().name().to_string(),
+                    tag: map_node.tag().to_string(),
                     msg: format!("Required attribute '{}' missing", name),
                 })
         };
 
-        let tilesets = map_node
+        let size = math::ivec2::new(map_attr("width")?.parse()?, map_attr("height")?.parse()?);
+        let limits = resource_manager.limits();
+        limits.check_size("Map", size)?;
+
+        let options = resource_manager.load_options().clone();
+
+        let tileset_nodes: Vec<_> = map_node
             .children()
-            .filter(|n| n.tag_name().name() == "tileset")
-            .map(|n| TileSet::from_xml(&n, resource_manager))
-            .collect::<Result<Vec<_>>>()?;
+            .filter(|n| !options.header_only && n.tag() == "tileset")
+            .collect();
+        if tileset_nodes.len() > limits.max_tilesets {
+            return Err(Error::LimitExceeded(format!(
+                "Map has more than {} tilesets",
+                limits.max_tilesets
+            )));
+        }
+        let mut tilesets = Vec::with_capacity(tileset_nodes.len());
+        for node in &tileset_nodes {
+            tilesets.push(TileSet::from_xml_without_image(node, resource_manager)?);
+            resource_manager.report_progress(
+                LoadStage::TileSets,
+                tilesets.len(),
+                tileset_nodes.len(),
+            )?;
+        }
+        if !options.skip_images {
+            resource_manager.load_tileset_images(&mut tilesets)?;
+        }
+
+        let layer_nodes: Vec<_> = map_node
+            .children()
+            .filter(|n| !options.header_only && options.includes_layer(n))
+            .collect();
+
+        let mut layers = Vec::with_capacity(layer_nodes.len());
+        let mut root_layers = Vec::with_capacity(layer_nodes.len());
+        let mut layer_hashes = HashMap::with_capacity(layer_nodes.len());
+        resource_manager.with_decoded_tile_data(decoded, |resource_manager| {
+            for node in &layer_nodes {
+                let hash = xml_hash(tmx, node);
+                if reuse.take(hash, root_layers.len()) {
+                    continue;
+                }
+                if let Some(layer) = Layer::try_from_xml(node, resource_manager, &mut layers) {
+                    let layer = layer?;
+                    root_layers.push(layer);
+                    layer_hashes.insert(layer, hash);
+                    resource_manager.report_progress(
+                        LoadStage::Layers,
+                        root_layers.len(),
+                        layer_nodes.len() - reuse.reused.len(),
+                    )?;
+                }
+            }
+            Ok(())
+        })?;
 
         let mut map = Map {
             version: map_attr("version")?.parse()?,
             editor_version: None,
             orientation: map_attr("orientation")?.parse()?,
             renderorder: attribute_or_default(&map_node, "renderorder")?,
-            size: math::ivec2::new(map_attr("width")?.parse()?, map_attr("height")?.parse()?),
+            size,
             tile_size: math::ivec2::new(
                 map_attr("tilewidth")?.parse()?,
                 map_attr("tileheight")?.parse()?,
             ),
+            stagger_axis: attribute_or_default(&map_node, "staggeraxis")?,
+            stagger_index: attribute_or_default(&map_node, "staggerindex")?,
+            hexsidelength: attribute_or_default(&map_node, "hexsidelength")?,
             tilesets,
             backgroundcolor: attribute_or_default(&map_node, "backgroundcolor")?,
-            layers: map_node
-                .children()
-                .filter_map(|c| Layer::try_from_xml(&c, resource_manager))
-                .collect::<Result<Vec<_>>>()?,
-            properties: PropertyContainer::from_xml(&map_node)?,
+            layers,
+            root_layers,
+            layer_hashes,
+            properties: PropertyContainer::from_xml(&map_node, resource_manager)?,
+            source: None,
+            unknown: UnknownXml::from_xml(
+                &map_node,
+                &[
+                    "version",
+                    "tiledversion",
+                    "orientation",
+                    "renderorder",
+                    "width",
+                    "height",
+                    "tilewidth",
+                    "tileheight",
+                    "staggeraxis",
+                    "staggerindex",
+                    "hexsidelength",
+                    "infinite",
+                    "backgroundcolor",
+                    "nextlayerid",
+                    "nextobjectid",
+                ],
+                &[
+                    "properties",
+                    "tileset",
+                    "layer",
+                    "group",
+                    "objectgroup",
+                    "imagelayer",
+                ],
+            ),
         };
         if map_node.attribute("tiledversion").is_some() {
             map.editor_version = Some(map_attr("tiledversion")?.parse()?);
         }
+        resource_manager.check_version(&map)?;
         Ok(map)
     }
 
+    /// Get a handle to the tile that a GID references, e.g. to look up its class.
+    /// The flip flags of the GID are ignored.
+    ///
+    /// Returns None if no tileset of the map contains the tile.
+    pub fn tile(&self, id: GID) -> Option<Tile<'_>> {
+        let tileset = self
+            .tilesets
+            .iter()
+            .rfind(|t| t.firstgid.to_id() <= id.to_id())?;
+        tileset.tile(id.to_id() - tileset.firstgid.to_id())
+    }
+
     /// Fetch the image that belongs to a given GID.
     /// Returns the image and the pixel coordinates where the tile image is inside of that image.
     ///
     /// Important: To correctly draw the tile,
     ///     inspect the [GID] passed to this function to lookup information if/how the tile should
     ///     be flipped.
+    ///
+    /// Returns None if no tileset contains the GID, or if the image of the tileset is still
+    /// [Pending](ImageStorage::Pending). Use [Map::tile] to tell these cases apart:
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// # let map = tego::Map::from_file(Path::new("example-maps/default/default_map.tmx"))?;
+    /// # let gid = tego::GID::new(1).unwrap();
+    /// if map.tile_image(gid).is_none() {
+    ///     match map.tile(gid) {
+    ///         Some(tile) if tile.tileset().image.is_pending() => println!("load images first"),
+    ///         _ => println!("no tileset contains {}", gid),
+    ///     }
+    /// }
+    /// # Ok::<(),tego::Error>(())
+    /// ```
     pub fn tile_image(&self, id: GID) -> Option<(&dyn std::any::Any, math::Rect)> {
-        use math::ivec2;
-        let tileset = self.tilesets.iter().rfind(|t| t.firstgid <= id)?;
+        let tileset = self
+            .tilesets
+            .iter()
+            .rfind(|t| t.firstgid.to_id() <= id.to_id())?;
+        let local_id = id.to_id() - tileset.firstgid.to_id();
+        if local_id as usize >= tileset.tilecount {
+            return None;
+        }
+        let rect = tileset.tile_rect(local_id);
 
-        let size = ivec2::new(tileset.tile_size.x, tileset.tile_size.y);
-        let stride = tileset.spacing as i32;
-        let stride = size + ivec2::new(stride, stride);
+        match &tileset.image {
+            ImageStorage::SpriteSheet(spritesheet) => Some((&**spritesheet, rect)),
+            ImageStorage::Pending => None,
+        }
+    }
 
-        let lid = (id.to_id() - tileset.firstgid.to_id()) as i32;
-        let tile_id = ivec2::new(lid % tileset.columns as i32, lid / tileset.columns as i32);
-        let upper_left =
-            ivec2::new(tileset.margin as i32, tileset.margin as i32) + tile_id * stride;
+    /// Add a tileset after all other tilesets of this map.
+    ///
+    /// The [firstgid](TileSet::firstgid) of the tileset is set to the first GID that is not
+    /// used by the other tilesets and returned. The tiles of the map keep their GIDs.
+    pub fn add_tileset(&mut self, mut tileset: TileSet) -> GID {
+        let next = self
+            .tilesets
+            .last()
+            .map_or(1, |last| last.firstgid.to_id() + last.tilecount as u32);
+        tileset.firstgid = GID(NonZeroU32::new(next).unwrap());
+        self.tilesets.push(tileset);
+        GID(NonZeroU32::new(next).unwrap())
+    }
 
-        match &tileset.image {
-            ImageStorage::SpriteSheet(spritesheet) => {
-                Some((&**spritesheet, math::Rect::new(upper_left, size)))
+    /// Remove the tileset at the given index.
+    ///
+    /// Tiles and tile objects using the tileset are removed from all layers.
+    /// The following tilesets move down to fill the gap and the GIDs of their tiles
+    /// are rewritten to match.
+    ///
+    /// # Panics
+    /// If the index is out of range.
+    pub fn remove_tileset(&mut self, index: usize) -> TileSet {
+        let tileset = self.tilesets.remove(index);
+        let start = tileset.firstgid.to_id();
+        let (end, removed) = match self.tilesets.get(index) {
+            Some(next) => (next.firstgid.to_id(), next.firstgid.to_id() - start),
+            None => (u32::MAX, tileset.tilecount as u32),
+        };
+        for next in &mut self.tilesets[index..] {
+            next.firstgid = next.firstgid.with_id(next.firstgid.to_id() - removed);
+        }
+        self.map_gids(|gid| match gid.to_id() {
+            id if id < start => Some(gid),
+            id if id < end => None,
+            id => Some(gid.with_id(id - removed)),
+        });
+        tileset
+    }
+
+    /// Replace GIDs in all tile layers and tile objects of this map, e.g. after the tiles
+    /// of a tileset were rearranged.
+    ///
+    /// The flip flags of the GIDs are ignored when looking them up in the mapping,
+    /// and the replaced GIDs keep the flags of the original ones.
+    /// GIDs that are not part of the mapping stay unchanged.
+    pub fn remap_gids(&mut self, mapping: &HashMap<GID, GID>) {
+        let mapping: HashMap<u32, u32> = mapping
+            .iter()
+            .map(|(from, to)| (from.to_id(), to.to_id()))
+            .collect();
+        self.map_gids(|gid| match mapping.get(&gid.to_id()) {
+            Some(&id) => Some(gid.with_id(id)),
+            None => Some(gid),
+        });
+    }
+
+    /// The local ids of the tiles that are used by this map, for each tileset in the order
+    /// of [Map::tilesets], e.g. to find tiles that could be removed from a sprite sheet.
+    ///
+    /// Tiles count as used if they are placed in a tile layer or used by a tile object,
+    /// or if they are a frame of the animation of a used tile.
+    pub fn tileset_usage(&self) -> Vec<std::collections::BTreeSet<u32>> {
+        let mut usage = vec![std::collections::BTreeSet::new(); self.tilesets.len()];
+        let mut add = |gid: GID| {
+            let Some(index) = self
+                .tilesets
+                .iter()
+                .rposition(|t| t.firstgid.to_id() <= gid.to_id())
+            else {
+                return;
+            };
+            let tileset = &self.tilesets[index];
+            let local_id = gid.to_id() - tileset.firstgid.to_id();
+            if (local_id as usize) < tileset.tilecount && usage[index].insert(local_id) {
+                if let Some(data) = tileset.tile_data.get(&local_id) {
+                    usage[index].extend(data.animation.iter().map(|frame| frame.tile_id));
+                }
+            }
+        };
+        for (layer, _) in self.iter_layers() {
+            match layer {
+                Layer::Tile(layer) => layer.occupied_tiles().for_each(|(_, gid)| add(gid)),
+                Layer::Object(layer) => layer
+                    .content
+                    .iter()
+                    .filter_map(|o| o.tile_id)
+                    .for_each(&mut add),
+                _ => {}
+            }
+        }
+        usage
+    }
+
+    /// Replace the GIDs of all tiles and tile objects of this map.
+    /// Tiles for which `f` returns None are removed.
+    fn map_gids(&mut self, mut f: impl FnMut(GID) -> Option<GID>) {
+        for layer in self.layers_mut() {
+            match layer {
+                Layer::Tile(layer) => layer.map_tiles(&mut f),
+                Layer::Object(layer) => layer.content.retain_mut(|object| match object.tile_id {
+                    Some(gid) => {
+                        object.tile_id = f(gid);
+                        object.tile_id.is_some()
+                    }
+                    None => true,
+                }),
+                _ => {}
             }
         }
     }
@@ -1037,33 +2478,256 @@ impl Map {
     /// }
     /// ```
     pub fn iter_layers(&self) -> impl Iterator<Item = (&Layer, usize)> {
-        LayerIterator::new(&self.layers)
+        self.iter_layer_ids().map(|(id, pops)| (&self[id], pops))
+    }
+
+    /// Like [Map::iter_layers], but yields the [LayerIds](LayerId) of the layers.
+    pub fn iter_layer_ids(&self) -> impl Iterator<Item = (LayerId, usize)> + '_ {
+        LayerIterator::new(&self.layers, &self.root_layers)
+    }
+
+    /// The top level layers of this map.
+    /// The final map image is rendered by stacking the layers in iteration order.
+    /// Use [GroupLayer::layers] to get the layers inside of a group.
+    pub fn layers(&self) -> impl ExactSizeIterator<Item = &Layer> {
+        self.root_layers.iter().map(|&id| &self[id])
+    }
+
+    /// Handles of the top level layers of this map, in the same order as [Map::layers].
+    pub fn layer_ids(&self) -> &[LayerId] {
+        &self.root_layers
+    }
+
+    /// Get a layer of this map by its handle, including layers inside of groups.
+    /// Returns None if the handle belongs to another map or the layer was removed.
+    /// Maps can also be indexed with handles directly, e.g. `&map[id]`.
+    pub fn layer(&self, id: LayerId) -> Option<&Layer> {
+        self.layers.get(id.index())?.as_ref()
+    }
+
+    /// Mutable version of [Map::layer].
+    pub fn layer_mut(&mut self, id: LayerId) -> Option<&mut Layer> {
+        // the layer may be changed, so Map::reparse can't take over its top level layer anymore
+        let root = self
+            .root_layers
+            .iter()
+            .copied()
+            .find(|&root| root == id || self.contains_layer(root, id));
+        if let Some(root) = root {
+            self.layer_hashes.remove(&root);
+        }
+        self.layers.get_mut(id.index())?.as_mut()
+    }
+
+    /// All layers of this map including the ones inside of groups, in no particular order.
+    /// [Map::reparse] parses all layers again afterwards, as any of them may be changed.
+    pub(crate) fn layers_mut(&mut self) -> impl Iterator<Item = &mut Layer> {
+        self.layer_hashes.clear();
+        self.layers.iter_mut().flatten()
+    }
+
+    /// Find the first layer with the given name, in the order of [Map::iter_layers].
+    pub fn find_layer(&self, name: &str) -> Option<LayerId> {
+        self.iter_layer_ids()
+            .map(|(id, _)| id)
+            .find(|&id| self[id].name() == name)
+    }
+
+    /// Add a layer on top of all other top level layers, see [Map::insert_layer].
+    pub fn push_layer(&mut self, layer: Layer) -> Result<LayerId> {
+        self.insert_layer(None, self.root_layers.len(), layer)
+    }
+
+    /// Insert a layer at the given position into a group, or into the top level layers
+    /// if `parent` is None. Returns the handle of the new layer.
+    ///
+    /// Layers without an [id](Layer::id) get the next free id of this map.
+    /// Groups must be empty when they are inserted, their layers are added afterwards.
+    pub fn insert_layer(
+        &mut self,
+        parent: Option<LayerId>,
+        index: usize,
+        mut layer: Layer,
+    ) -> Result<LayerId> {
+        if matches!(&layer, Layer::Group(group) if !group.content.is_empty()) {
+            return Err(Error::InvalidLayer(
+                "Groups must be empty when they are inserted".into(),
+            ));
+        }
+        let len = self.content_mut(parent)?.len();
+        if index > len {
+            return Err(Error::InvalidLayer(format!(
+                "Index {} is out of range for {} layers",
+                index, len
+            )));
+        }
+
+        if layer.id().is_none() {
+            *layer.id_mut() = Some(self.next_layer_id());
+        }
+        let id = LayerId::push(&mut self.layers, layer);
+        self.content_mut(parent)?.insert(index, id);
+        Ok(id)
+    }
+
+    /// Remove a layer from this map, including all layers inside of it if it is a group.
+    /// Returns the removed layer, or None if there is no layer with this handle.
+    ///
+    /// Handles of other layers stay valid. The content of a removed group is cleared.
+    pub fn remove_layer(&mut self, id: LayerId) -> Option<Layer> {
+        self.layer(id)?;
+        self.detach(id);
+        let mut layer = self.discard(id);
+        if let Layer::Group(group) = &mut layer {
+            group.content.clear();
+        }
+        Some(layer)
+    }
+
+    /// Move a layer to the given position in a group, or in the top level layers if
+    /// `parent` is None. The index is the position after the layer was taken out of its
+    /// current place, so the layer ends up at `index` in its new parent.
+    ///
+    /// The map is unchanged if the layer can't be moved,
+    /// e.g. because a group would be moved into itself.
+    pub fn move_layer(&mut self, id: LayerId, parent: Option<LayerId>, index: usize) -> Result<()> {
+        if self.layer(id).is_none() {
+            return Err(Error::InvalidLayer(format!("There is no layer {:?}", id)));
+        }
+        if let Some(parent) = parent {
+            if parent == id || self.contains_layer(id, parent) {
+                return Err(Error::InvalidLayer(
+                    "A group can't be moved into itself".into(),
+                ));
+            }
+        }
+        let content = self.content_mut(parent)?;
+        let len = content.len() - content.contains(&id) as usize;
+        if index > len {
+            return Err(Error::InvalidLayer(format!(
+                "Index {} is out of range for {} layers",
+                index, len
+            )));
+        }
+
+        self.detach(id);
+        self.content_mut(parent)?.insert(index, id);
+        Ok(())
+    }
+
+    /// The smallest layer id that is not used in this map yet.
+    pub(crate) fn next_layer_id(&self) -> usize {
+        self.iter_layers()
+            .filter_map(|(layer, _)| layer.id())
+            .max()
+            .map_or(1, |id| id + 1)
+    }
+
+    /// The smallest object id that is not used in this map yet.
+    pub(crate) fn next_object_id(&self) -> usize {
+        self.iter_layers()
+            .filter_map(|(layer, _)| match layer {
+                Layer::Object(layer) => layer.content.iter().map(|o| o.id).max(),
+                _ => None,
+            })
+            .max()
+            .map_or(1, |id| id + 1)
+    }
+
+    /// The handles of the layers inside of the given group,
+    /// or the top level layers if `parent` is None.
+    fn content_mut(&mut self, parent: Option<LayerId>) -> Result<&mut Vec<LayerId>> {
+        let Some(parent) = parent else {
+            return Ok(&mut self.root_layers);
+        };
+        match self.layer_mut(parent) {
+            Some(Layer::Group(group)) => Ok(&mut group.content),
+            Some(_) => Err(Error::InvalidLayer(format!(
+                "Layer {:?} is not a group",
+                parent
+            ))),
+            None => Err(Error::InvalidLayer(format!(
+                "There is no layer {:?}",
+                parent
+            ))),
+        }
+    }
+
+    /// Check whether the given layer is somewhere inside of the group.
+    fn contains_layer(&self, group: LayerId, id: LayerId) -> bool {
+        match self.layer(group) {
+            Some(Layer::Group(group)) => group
+                .content
+                .iter()
+                .any(|&child| child == id || self.contains_layer(child, id)),
+            _ => false,
+        }
+    }
+
+    /// Take a layer out of the group or top level layers it is part of.
+    fn detach(&mut self, id: LayerId) {
+        self.root_layers.retain(|&layer| layer != id);
+        for layer in self.layers_mut() {
+            if let Layer::Group(group) = layer {
+                group.content.retain(|&layer| layer != id);
+            }
+        }
+    }
+
+    /// Remove a layer and everything inside of it from the storage of this map.
+    fn discard(&mut self, id: LayerId) -> Layer {
+        self.layer_hashes.remove(&id);
+        let layer = self.layers[id.index()].take().unwrap();
+        if let Layer::Group(group) = &layer {
+            for &child in &group.content {
+                self.discard(child);
+            }
+        }
+        layer
+    }
+}
+
+impl std::ops::Index<LayerId> for Map {
+    type Output = Layer;
+
+    /// # Panics
+    /// If the layer was removed or the handle belongs to another map.
+    fn index(&self, id: LayerId) -> &Layer {
+        self.layer(id).expect("no layer with this id")
+    }
+}
+
+impl std::ops::IndexMut<LayerId> for Map {
+    fn index_mut(&mut self, id: LayerId) -> &mut Layer {
+        self.layer_mut(id).expect("no layer with this id")
     }
 }
 
 struct LayerIterator<'a> {
-    iter_stack: Vec<std::slice::Iter<'a, Layer>>,
+    layers: &'a [Option<Layer>],
+    iter_stack: Vec<std::slice::Iter<'a, LayerId>>,
 }
 
 impl<'a> LayerIterator<'a> {
-    fn new(layers: &'a [Layer]) -> Self {
+    fn new(layers: &'a [Option<Layer>], root_layers: &'a [LayerId]) -> Self {
         Self {
-            iter_stack: vec![layers.iter()],
+            layers,
+            iter_stack: vec![root_layers.iter()],
         }
     }
 }
 
 impl<'a> Iterator for LayerIterator<'a> {
-    type Item = (&'a Layer, usize);
+    type Item = (LayerId, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut pops = 0;
         while let Some(iter) = self.iter_stack.last_mut() {
-            if let Some(layer) = iter.next() {
-                if let Layer::Group(group) = layer {
+            if let Some(&id) = iter.next() {
+                if let Some(Layer::Group(group)) = &self.layers[id.index()] {
                     self.iter_stack.push(group.content.iter());
                 }
-                return Some((layer, pops));
+                return Some((id, pops));
             } else {
                 pops += 1;
                 self.iter_stack.pop();
@@ -1073,6 +2737,95 @@ impl<'a> Iterator for LayerIterator<'a> {
     }
 }
 
+/// Get the root node of a TMX document, failing if it is not a map.
+fn map_root<'a, 'input>(document: &'a Document<'input>) -> Result<Node<'a, 'input>> {
+    let map_node = document.root_element();
+    if map_node.tag() != "map" {
+        return Err(Error::StructureError {
+            tag: map_node.tag().to_string(),
+            msg: format!(
+                "Expected tag 'map' at root level, got '{}'.",
+                map_node.tag()
+            ),
+        });
+    }
+    Ok(map_node)
+}
+
+/// Basic information about a map, which can be read a lot faster than the whole [Map].
+/// See [MapInfo::peek].
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct MapInfo {
+    pub version: Version,
+    pub editor_version: Option<Version>,
+    pub orientation: Orientation,
+    pub size: math::ivec2,
+    pub tile_size: math::ivec2,
+
+    /// Names of all layers in the map, including the layers inside of groups.
+    /// The layers are listed in the same order as visited by [Map::iter_layers].
+    pub layer_names: Vec<String>,
+}
+
+impl MapInfo {
+    /// Read the basic information about the map at the given path.
+    ///
+    /// This neither decodes any tile data nor touches the tilesets or images of the map,
+    /// which makes it suitable for map browsers or dedicated servers.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn peek(path: &std::path::Path) -> Result<Self> {
+        Self::peek_with(path, &ResourceManager::default())
+    }
+
+    /// Like [MapInfo::peek], but the map is read through the [Providers](Provider) of the
+    /// given loader, like [Map::from_file_with_loader] does.
+    pub fn peek_with(path: &std::path::Path, resource_manager: &ResourceManager) -> Result<Self> {
+        Self::from_xml_str(&resource_manager.read_text(path)?)
+    }
+
+    /// Read the basic information about a map from xml data. See [MapInfo::peek].
+    pub fn from_xml_str(tmx: &str) -> Result<Self> {
+        let document = Document::parse(tmx)?;
+        let map_node = map_root(&document)?;
+
+        fn collect_names(node: Node, names: &mut Vec<String>) {
+            for child in node.children().filter(Layer::is_layer_node) {
+                names.push(child.attribute("name").unwrap_or_default().to_string());
+                if child.tag() == "group" {
+                    collect_names(child, names);
+                }
+            }
+        }
+        let mut layer_names = Vec::new();
+        collect_names(map_node, &mut layer_names);
+
+        Ok(Self {
+            version: attribute(&map_node, "version")?,
+            editor_version: map_node
+                .attribute("tiledversion")
+                .map(str::parse)
+                .transpose()?,
+            orientation: attribute(&map_node, "orientation")?,
+            size: math::ivec2::new(
+                attribute(&map_node, "width")?,
+                attribute(&map_node, "height")?,
+            ),
+            tile_size: math::ivec2::new(
+                attribute(&map_node, "tilewidth")?,
+                attribute(&map_node, "tileheight")?,
+            ),
+            layer_names,
+        })
+    }
+}
+
+#[doc(hidden)]
+#[doc = include_str!("../README.md")]
+fn __test_readme() {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1084,6 +2837,46 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_gid_construction() {
+        assert!(GID::new(GID_HORIZONTAL_FLIP_FLAG | 1).is_none());
+        let gid = GID::new(7).unwrap();
+        assert_eq!(gid.to_string(), "7");
+
+        let flipped = gid.with_flips(true, true, true);
+        assert_eq!(flipped.as_raw(), GID_FLIP_MASK | 7);
+        assert_eq!(
+            flipped.to_string(),
+            "7 (flipped horizontally, vertically, diagonally)"
+        );
+        assert_eq!(flipped.with_flips(false, false, false), gid);
+        assert_eq!(flipped.with_id(3).to_id(), 3);
+    }
+
+    #[test]
+    fn test_enum_names_round_trip() -> Result<()> {
+        use Orientation::*;
+        for orientation in [Orthogonal, Isometric, Staggered, Hexagonal] {
+            assert_eq!(orientation.to_string().parse::<Orientation>()?, orientation);
+        }
+        use Renderorder::*;
+        for renderorder in [RightDown, RightUp, LeftDown, LeftUp] {
+            assert_eq!(renderorder.as_str().parse::<Renderorder>()?, renderorder);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_display_and_order() -> Result<()> {
+        for text in ["1.0", "1.7.2", "1.10.0"] {
+            assert_eq!(text.parse::<Version>()?.to_string(), text);
+        }
+        assert!(Version(1, 2, None) < Version(1, 2, Some(0)));
+        assert!(Version(1, 2, Some(9)) < Version(1, 10, None));
+        assert!(Version(2, 0, None) > Version(1, 10, Some(3)));
+        Ok(())
+    }
+
     #[test]
     fn test_default_render_order() -> Result<()> {
         // explicitly no renderorder
@@ -1112,32 +2905,45 @@ mod test {
     #[test]
     fn test_layer_iterator() {
         use Layer::*;
+        let mut layers = Vec::new();
         macro_rules! layer {
             (tile) => {
-                Tile(TileLayer {
-                    id: Some(0),
-                    name: "".into(),
-                    size: math::ivec2::new(0, 0),
-                    tintcolor: Color::default(),
-                    tiles: vec![],
-                    properties: PropertyContainer::new(),
-                })
-            };
-            (group $layers:expr) => {
-                Group(GroupLayer {
-                    id: Some(0),
-                    name: "".into(),
-                    offset: math::ivec2::new(0, 0),
-                    opacity: 0.,
-                    tintcolor: Color::default(),
-                    visible: false,
-                    content: $layers,
-                    properties: PropertyContainer::new(),
-                })
+                LayerId::push(
+                    &mut layers,
+                    Tile(TileLayer {
+                        id: Some(0),
+                        name: "".into(),
+                        size: math::ivec2::new(0, 0),
+                        visible: true,
+                        opacity: 1.,
+                        tintcolor: Color::default(),
+                        tiles: Default::default(),
+                        occupancy: Default::default(),
+                        properties: PropertyContainer::new(),
+                        unknown: UnknownXml::default(),
+                    }),
+                )
             };
+            (group $layers:expr) => {{
+                let content = $layers;
+                LayerId::push(
+                    &mut layers,
+                    Group(GroupLayer {
+                        id: Some(0),
+                        name: "".into(),
+                        offset: math::ivec2::new(0, 0),
+                        opacity: 0.,
+                        tintcolor: Color::default(),
+                        visible: false,
+                        content,
+                        properties: PropertyContainer::new(),
+                        unknown: UnknownXml::default(),
+                    }),
+                )
+            }};
         }
 
-        let layers = vec![
+        let root_layers = vec![
             layer!(tile),
             layer!(group vec![]),
             layer!(tile),
@@ -1149,11 +2955,11 @@ mod test {
             ]),
         ];
 
-        let result: Vec<_> = LayerIterator::new(&layers).collect();
+        let result: Vec<_> = LayerIterator::new(&layers, &root_layers).collect();
         assert_eq!(result.len(), 7);
 
         // check that we get the group
-        assert!(std::ptr::eq(result[2].0, &layers[2]));
+        assert_eq!(result[2].0, root_layers[2]);
 
         // no pops on a top level tile layer
         assert_eq!(result[0].1, 0);
@@ -1162,6 +2968,31 @@ mod test {
         assert_eq!(result[2].1, 1);
     }
 
+    #[test]
+    fn test_point_list_parsing() -> Result<()> {
+        use math::fvec2;
+        let triangle = "0,0 16,0 8,-4.5".as_point_list()?;
+        assert_eq!(
+            &triangle[..],
+            [
+                fvec2::new(0., 0.),
+                fvec2::new(16., 0.),
+                fvec2::new(8., -4.5)
+            ]
+        );
+
+        let many = (0..10)
+            .map(|i| format!("{},{}", i, i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let many = many.as_str().as_point_list()?;
+        assert_eq!(many.len(), 10);
+        assert_eq!(many[9], fvec2::new(9., 9.));
+
+        assert!("1,2,3".as_point_list().is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_color_parsing() {
         assert_eq!(
@@ -1185,8 +3016,32 @@ mod test {
         // invalid hex
         assert!("#FQ00FF".parse::<Color>().is_err());
     }
-}
 
-#[doc(hidden)]
-#[doc = include_str!("../README.md")]
-fn __test_readme() {}
+    #[test]
+    fn test_read_gids_across_partial_reads() {
+        /// Reader that returns at most 3 bytes per read, splitting GIDs between reads
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(3).min(self.0.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let data = [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0x80];
+        let gids = read_gids(Trickle(&data), 3, usize::MAX).unwrap();
+        assert_eq!(gids.len(), 3);
+        assert_eq!(read_gids(Trickle(&data), 0, usize::MAX).unwrap(), gids);
+        assert_eq!(gids[0].map(|g| g.to_id()), Some(1));
+        assert!(gids[1].is_none());
+        assert!(gids[2].unwrap().flip_horizontal());
+
+        assert!(matches!(
+            read_gids(Trickle(&data), 3, 11),
+            Err(Error::LimitExceeded(_))
+        ));
+        assert!(read_gids(Trickle(&data[..10]), 3, usize::MAX).is_err());
+    }
+}
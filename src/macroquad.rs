@@ -0,0 +1,219 @@
+//! Drawing maps with [macroquad](https://crates.io/crates/macroquad), see [draw_layer].
+//!
+//! Requires the `macroquad` feature. The sprite sheets of the map have to be loaded as
+//! [Texture2D], i.e. with the [TextureLoader]. Since textures can only be created once
+//! the window is open, maps have to be loaded inside of the macroquad main function.
+//!
+//! ```no_run
+//! use macroquad::prelude::*;
+//! use tego::{FileProvider, Layer, Map, ResourceManager};
+//!
+//! #[macroquad::main("Map")]
+//! async fn main() {
+//!     let mut loader = ResourceManager::new(tego::macroquad::TextureLoader::new(), FileProvider::new());
+//!     let path = std::path::Path::new("example-maps/default/groups.tmx");
+//!     let map = Map::from_file_with_loader(path, &mut loader).unwrap();
+//!     let mut camera = vec2(0., 0.);
+//!     loop {
+//!         camera.x += get_frame_time() * 10.;
+//!         clear_background(BLACK);
+//!         for (layer, _) in map.iter_layers() {
+//!             if let Layer::Tile(layer) = layer {
+//!                 tego::macroquad::draw_layer(&map, layer, camera).unwrap();
+//!             }
+//!         }
+//!         next_frame().await
+//!     }
+//! }
+//! ```
+
+use std::any::Any;
+
+use ::macroquad::color::Color as QuadColor;
+use ::macroquad::math::{vec2, Rect as QuadRect, Vec2};
+use ::macroquad::texture::{draw_texture_ex, DrawTextureParams, FilterMode, Image, Texture2D};
+use ::macroquad::window::{screen_height, screen_width};
+
+use crate::math::{ivec2, Rect};
+use crate::{
+    Error, ImageLoader, ImageSource, Map, RenderOptions, Result, TileLayer, TileRenderer,
+    TileTransform,
+};
+
+/// [ImageLoader] that uploads images as [Texture2D] of macroquad (version 0.4).
+///
+/// The textures use nearest filtering, so that pixel art stays sharp when it is scaled.
+/// The color key of tilesets (the `trans` attribute in Tiled) is applied while loading,
+/// so pixels with that color are fully transparent.
+///
+/// Requires the `macroquad` feature and an open macroquad window.
+#[derive(Debug, Default, Clone)]
+pub struct TextureLoader {}
+
+impl TextureLoader {
+    /// Create a loader that uploads images as macroquad textures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ImageLoader for TextureLoader {
+    fn load(&mut self, mut source: ImageSource) -> Result<Box<dyn Any>> {
+        let mut image = Image::from_file_with_format(&source.read()?, None)
+            .map_err(|e| Error::ParseError(Box::new(e)))?;
+        if let Some(key) = source.context().transparent {
+            apply_color_key(&mut image, [key.red(), key.green(), key.blue()]);
+        }
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        Ok(Box::new(texture))
+    }
+}
+
+/// Make all pixels with the given color transparent.
+fn apply_color_key(image: &mut Image, key: [u8; 3]) {
+    for pixel in image.bytes.chunks_exact_mut(4) {
+        if pixel[..3] == key {
+            pixel[3] = 0;
+        }
+    }
+}
+
+/// Draw the tiles of a layer with macroquad, multiplied with the tint color and opacity
+/// of the layer. See [Map::render_tile_layer_with] for what is drawn.
+///
+/// `camera` is the pixel of the map that is drawn to the upper left corner of the screen,
+/// only the tiles on the screen are drawn. The tiles are drawn with the current macroquad
+/// camera, so it has to be the default one that maps coordinates to pixels of the screen.
+///
+/// Fails if the sprite sheet of a used tileset was not loaded as [Texture2D].
+pub fn draw_layer(map: &Map, layer: &TileLayer, camera: Vec2) -> Result<()> {
+    let tint = layer.tintcolor;
+    let mut renderer = QuadRenderer {
+        camera,
+        color: QuadColor::from_rgba(
+            tint.red(),
+            tint.green(),
+            tint.blue(),
+            (tint.alpha() as f32 * layer.opacity.clamp(0., 1.)).round() as u8,
+        ),
+        error: None,
+    };
+    let view = Rect::new(
+        ivec2::new(camera.x.floor() as i32, camera.y.floor() as i32),
+        ivec2::new(
+            screen_width().ceil() as i32 + 1,
+            screen_height().ceil() as i32 + 1,
+        ),
+    );
+    let options = RenderOptions {
+        region: Some(view),
+        ..RenderOptions::default()
+    };
+    map.render_tile_layer_with(layer, &mut renderer, &options)?;
+    match renderer.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// [TileRenderer] that draws tiles with macroquad.
+struct QuadRenderer {
+    /// The pixel of the map that is drawn to the upper left corner of the screen.
+    camera: Vec2,
+
+    /// Color the tiles are multiplied with.
+    color: QuadColor,
+
+    /// The first error that happened while drawing.
+    error: Option<Error>,
+}
+
+impl TileRenderer for QuadRenderer {
+    fn draw_tile(&mut self, image: &dyn Any, source: Rect, target: Rect, transform: TileTransform) {
+        let Some(texture) = image.downcast_ref::<Texture2D>() else {
+            self.error.get_or_insert_with(|| {
+                Error::UnsupportedFeature(
+                    "drawing with macroquad needs the sprite sheets as Texture2D, \
+                    load the map with tego::macroquad::TextureLoader"
+                        .to_owned(),
+                )
+            });
+            return;
+        };
+        let (pos, params) = draw_params(source, target, transform);
+        draw_texture_ex(
+            texture,
+            pos.x - self.camera.x,
+            pos.y - self.camera.y,
+            self.color,
+            params,
+        );
+    }
+}
+
+/// The position and parameters for `draw_texture_ex` that draw the `source` rectangle of a
+/// texture mirrored by `transform` into `target`.
+fn draw_params(source: Rect, target: Rect, transform: TileTransform) -> (Vec2, DrawTextureParams) {
+    let (flip_x, flip_y, rotation) = transform.to_flip_and_rotation();
+    let upper_left = vec2(target.upper_left.x as f32, target.upper_left.y as f32);
+    let target_size = vec2(target.size.x as f32, target.size.y as f32);
+    // macroquad rotates around the center of the unrotated sprite
+    let size = match transform.flip_diagonal {
+        true => vec2(target_size.y, target_size.x),
+        false => target_size,
+    };
+    let center = upper_left + target_size / 2.;
+    let params = DrawTextureParams {
+        dest_size: Some(size),
+        source: Some(QuadRect::new(
+            source.upper_left.x as f32,
+            source.upper_left.y as f32,
+            source.size.x as f32,
+            source.size.y as f32,
+        )),
+        rotation: rotation.to_radians(),
+        flip_x,
+        flip_y,
+        pivot: None,
+    };
+    (center - size / 2., params)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_draw_params() {
+        let source = Rect::new(ivec2::new(16, 0), ivec2::new(16, 32));
+        let target = Rect::new(ivec2::new(100, 50), ivec2::new(32, 16));
+        let transform = TileTransform {
+            flip_horizontal: true,
+            flip_diagonal: true,
+            ..Default::default()
+        };
+        let (pos, params) = draw_params(source, target, transform);
+        // the unrotated sprite has the size of the source and the same center as the target
+        assert_eq!(params.dest_size, Some(vec2(16., 32.)));
+        assert_eq!(pos, vec2(108., 42.));
+        assert_eq!(params.source, Some(QuadRect::new(16., 0., 16., 32.)));
+        assert_eq!(params.rotation, 90f32.to_radians());
+        assert!(!params.flip_x && !params.flip_y);
+
+        let (pos, params) = draw_params(source, target, TileTransform::default());
+        assert_eq!(pos, vec2(100., 50.));
+        assert_eq!(params.rotation, 0.);
+    }
+
+    #[test]
+    fn test_apply_color_key() {
+        let mut image = Image {
+            bytes: vec![255, 0, 255, 255, 255, 0, 254, 255],
+            width: 2,
+            height: 1,
+        };
+        apply_color_key(&mut image, [255, 0, 255]);
+        assert_eq!(image.bytes, [255, 0, 255, 0, 255, 0, 254, 255]);
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::ops;
 
+use smallvec::SmallVec;
+
 macro_rules! define_vector {
     ($name:ident $T:ty) => {
         #[derive(Debug, PartialEq, Copy, Clone, Default)]
@@ -21,20 +23,160 @@ macro_rules! define_vector {
         impl_op_ex! {- |a: &$name, b: &$name| -> $name { $name::new(a.x - b.x, a.y - b.y )}}
         impl_op_ex! {* |a: &$name, b: &$name| -> $name { $name::new(a.x * b.x, a.y * b.y )}}
         impl_op_ex! {/ |a: &$name, b: &$name| -> $name { $name::new(a.x / b.x, a.y / b.y )}}
+        impl_op_ex! {+= |a: &mut $name, b: &$name| { a.x += b.x; a.y += b.y; }}
+        impl_op_ex! {-= |a: &mut $name, b: &$name| { a.x -= b.x; a.y -= b.y; }}
+        impl_op_ex! {*= |a: &mut $name, b: &$name| { a.x *= b.x; a.y *= b.y; }}
+        impl_op_ex! {/= |a: &mut $name, b: &$name| { a.x /= b.x; a.y /= b.y; }}
+
+        // scaling by a number
+        impl_op_ex_commutative! {* |a: &$name, f: &$T| -> $name { $name::new(a.x * f, a.y * f )}}
+        impl_op_ex! {/ |a: &$name, f: &$T| -> $name { $name::new(a.x / f, a.y / f )}}
+        impl_op_ex! {*= |a: &mut $name, f: &$T| { a.x *= f; a.y *= f; }}
+        impl_op_ex! {/= |a: &mut $name, f: &$T| { a.x /= f; a.y /= f; }}
+
+        // Arrays and tuples are understood by most math libraries, the types of glam, mint and
+        // nalgebra are converted directly with the features of the same name, see below.
+        impl From<[$T; 2]> for $name {
+            fn from([x, y]: [$T; 2]) -> Self {
+                Self::new(x, y)
+            }
+        }
+
+        impl From<$name> for [$T; 2] {
+            fn from(v: $name) -> Self {
+                [v.x, v.y]
+            }
+        }
+
+        impl From<($T, $T)> for $name {
+            fn from((x, y): ($T, $T)) -> Self {
+                Self::new(x, y)
+            }
+        }
+
+        impl From<$name> for ($T, $T) {
+            fn from(v: $name) -> Self {
+                (v.x, v.y)
+            }
+        }
     };
 }
 
 // Define vector of integers
 define_vector! {ivec2 i32}
-impl_op_ex_commutative! {* |a: &ivec2, f: &i32| -> ivec2 { ivec2::new(a.x * f, a.y * f )}}
+impl_op_ex! {- |a: &ivec2| -> ivec2 { ivec2::new(-a.x, -a.y)}}
 impl Eq for ivec2 {}
 
+// Define vector of unsigned integers, e.g. for indexing into images
+define_vector! {uvec2 u32}
+impl Eq for uvec2 {}
+
 // Define vector of floats
 define_vector! {fvec2 f32}
-impl_op_ex_commutative! {* |a: &fvec2, f: &f32| -> fvec2 { fvec2::new(a.x * f, a.y * f )}}
+impl_op_ex! {- |a: &fvec2| -> fvec2 { fvec2::new(-a.x, -a.y)}}
+
+impl ivec2 {
+    /// Convert to an unsigned vector, None if a coordinate is negative.
+    ///
+    /// ```
+    /// # use tego::math::{ivec2, uvec2};
+    /// assert_eq!(ivec2::new(3, 4).try_into_uvec2(), Some(uvec2::new(3, 4)));
+    /// assert_eq!(ivec2::new(3, -4).try_into_uvec2(), None);
+    /// ```
+    pub fn try_into_uvec2(self) -> Option<uvec2> {
+        uvec2::try_from(self).ok()
+    }
+}
+
+impl uvec2 {
+    /// Convert to a signed vector, None if a coordinate doesn't fit into an i32.
+    pub fn try_into_ivec2(self) -> Option<ivec2> {
+        ivec2::try_from(self).ok()
+    }
+}
+
+impl TryFrom<ivec2> for uvec2 {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(v: ivec2) -> Result<Self, Self::Error> {
+        Ok(Self::new(v.x.try_into()?, v.y.try_into()?))
+    }
+}
+
+impl TryFrom<uvec2> for ivec2 {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(v: uvec2) -> Result<Self, Self::Error> {
+        Ok(Self::new(v.x.try_into()?, v.y.try_into()?))
+    }
+}
+
+// Conversions into the vectors and points of math libraries, all of them convert from and into
+// arrays as well. Rect has no counterpart in these libraries, it still converts through arrays.
+#[allow(unused_macros)]
+macro_rules! convert_vector {
+    ($name:ident $T:ty => $($other:ty),+) => {
+        $(
+            impl From<$other> for $name {
+                fn from(v: $other) -> Self {
+                    <[$T; 2]>::from(v).into()
+                }
+            }
+
+            impl From<$name> for $other {
+                fn from(v: $name) -> Self {
+                    <[$T; 2]>::from(v).into()
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "glam")]
+convert_vector! {ivec2 i32 => glam::IVec2}
+#[cfg(feature = "glam")]
+convert_vector! {uvec2 u32 => glam::UVec2}
+#[cfg(feature = "glam")]
+convert_vector! {fvec2 f32 => glam::Vec2}
+
+#[cfg(feature = "mint")]
+convert_vector! {ivec2 i32 => mint::Vector2<i32>, mint::Point2<i32>}
+#[cfg(feature = "mint")]
+convert_vector! {uvec2 u32 => mint::Vector2<u32>, mint::Point2<u32>}
+#[cfg(feature = "mint")]
+convert_vector! {fvec2 f32 => mint::Vector2<f32>, mint::Point2<f32>}
+
+#[cfg(feature = "nalgebra")]
+convert_vector! {ivec2 i32 => nalgebra::Vector2<i32>, nalgebra::Point2<i32>}
+#[cfg(feature = "nalgebra")]
+convert_vector! {uvec2 u32 => nalgebra::Vector2<u32>, nalgebra::Point2<u32>}
+#[cfg(feature = "nalgebra")]
+convert_vector! {fvec2 f32 => nalgebra::Vector2<f32>, nalgebra::Point2<f32>}
+
+// Integer vectors scaled by a float, e.g. tile positions by a zoom factor, give float vectors.
+impl_op_ex_commutative! {* |a: &ivec2, f: &f32| -> fvec2 { fvec2::new(a.x as f32 * f, a.y as f32 * f)}}
+impl_op_ex! {/ |a: &ivec2, f: &f32| -> fvec2 { fvec2::new(a.x as f32 / f, a.y as f32 / f)}}
+
+impl fvec2 {
+    /// Whether both coordinates differ by at most `epsilon`,
+    /// to compare results of calculations that might have rounding errors.
+    ///
+    /// ```
+    /// # use tego::math::fvec2;
+    /// // rotate (1, 0) by 90 degrees
+    /// let (sin, cos) = std::f32::consts::FRAC_PI_2.sin_cos();
+    /// let a = fvec2::new(cos, sin);
+    /// assert_ne!(a, fvec2::new(0., 1.));
+    /// assert!(a.approx_eq(fvec2::new(0., 1.), 1e-6));
+    /// assert!(!a.approx_eq(fvec2::new(0.1, 1.), 1e-6));
+    /// ```
+    pub fn approx_eq(&self, other: fvec2, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon
+    }
+}
 
 /// Struct that defines a rectangle given by its upper left corner and extends.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct Rect {
     pub upper_left: ivec2,
     pub size: ivec2,
@@ -44,4 +186,244 @@ impl Rect {
     pub fn new(upper_left: ivec2, size: ivec2) -> Self {
         Self { upper_left, size }
     }
+
+    /// The rectangle from the corner `min` up to, but not including, the corner `max`.
+    ///
+    /// ```
+    /// # use tego::math::{ivec2, Rect};
+    /// let rect = Rect::from_min_max(ivec2::new(1, 2), ivec2::new(4, 4));
+    /// assert_eq!(rect, Rect::new(ivec2::new(1, 2), ivec2::new(3, 2)));
+    /// ```
+    pub fn from_min_max(min: ivec2, max: ivec2) -> Self {
+        Self::new(min, max - min)
+    }
+
+    /// The corner after the lower right corner, i.e. the first position outside of the
+    /// rectangle in both directions.
+    fn end(&self) -> ivec2 {
+        self.upper_left + self.size
+    }
+
+    /// All integer positions inside of the rectangle, row by row,
+    /// e.g. the tiles of a layer that are in view.
+    ///
+    /// ```
+    /// # use tego::math::{ivec2, Rect};
+    /// let rect = Rect::new(ivec2::new(1, 2), ivec2::new(2, 2));
+    /// let points: Vec<_> = rect.iter_points().map(<(i32, i32)>::from).collect();
+    /// assert_eq!(points, [(1, 2), (2, 2), (1, 3), (2, 3)]);
+    /// ```
+    pub fn iter_points(&self) -> impl Iterator<Item = ivec2> {
+        let (start, end) = (self.upper_left, self.end());
+        (start.y..end.y).flat_map(move |y| (start.x..end.x).map(move |x| ivec2::new(x, y)))
+    }
+
+    /// The part of this rectangle that is inside of `other`.
+    /// If they don't overlap, the size of the result is 0 in at least one direction.
+    ///
+    /// ```
+    /// # use tego::math::{ivec2, Rect};
+    /// let layer = Rect::new(ivec2::new(0, 0), ivec2::new(10, 10));
+    /// let view = Rect::new(ivec2::new(-2, 8), ivec2::new(5, 5));
+    /// assert_eq!(view.clamped_to(layer), Rect::new(ivec2::new(0, 8), ivec2::new(3, 2)));
+    /// ```
+    pub fn clamped_to(&self, other: Rect) -> Rect {
+        let start = ivec2::new(
+            self.upper_left.x.max(other.upper_left.x),
+            self.upper_left.y.max(other.upper_left.y),
+        );
+        let (end, other_end) = (self.end(), other.end());
+        let end = ivec2::new(end.x.min(other_end.x), end.y.min(other_end.y));
+        Self::from_min_max(start, ivec2::new(end.x.max(start.x), end.y.max(start.y)))
+    }
+}
+
+// Like the vectors, rectangles convert from and into arrays and tuples
+// in the order `x, y, width, height`.
+impl From<[i32; 4]> for Rect {
+    fn from([x, y, width, height]: [i32; 4]) -> Self {
+        Self::new(ivec2::new(x, y), ivec2::new(width, height))
+    }
+}
+
+impl From<Rect> for [i32; 4] {
+    fn from(r: Rect) -> Self {
+        [r.upper_left.x, r.upper_left.y, r.size.x, r.size.y]
+    }
+}
+
+impl From<(i32, i32, i32, i32)> for Rect {
+    fn from((x, y, width, height): (i32, i32, i32, i32)) -> Self {
+        Self::new(ivec2::new(x, y), ivec2::new(width, height))
+    }
+}
+
+impl From<Rect> for (i32, i32, i32, i32) {
+    fn from(r: Rect) -> Self {
+        (r.upper_left.x, r.upper_left.y, r.size.x, r.size.y)
+    }
+}
+
+/// Number of points that a [PointList] stores without allocating.
+const INLINE_POINTS: usize = 4;
+
+/// A list of points, e.g. the corners of a polygon.
+///
+/// Most polygons only have a handful of corners, so short lists are stored inline
+/// instead of allocating for each of them.
+/// Dereferences to a slice of points.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PointList(SmallVec<[fvec2; INLINE_POINTS]>);
+
+impl PointList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, point: fvec2) {
+        self.0.push(point);
+    }
+}
+
+impl ops::Deref for PointList {
+    type Target = [fvec2];
+
+    fn deref(&self) -> &[fvec2] {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for PointList {
+    fn deref_mut(&mut self) -> &mut [fvec2] {
+        &mut self.0
+    }
+}
+
+impl FromIterator<fvec2> for PointList {
+    fn from_iter<I: IntoIterator<Item = fvec2>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl From<Vec<fvec2>> for PointList {
+    fn from(points: Vec<fvec2>) -> Self {
+        Self(SmallVec::from_vec(points))
+    }
+}
+
+impl<'a> IntoIterator for &'a PointList {
+    type Item = &'a fvec2;
+    type IntoIter = std::slice::Iter<'a, fvec2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_assignment_and_scalar_ops() {
+        let mut camera = fvec2::new(10., 20.);
+        camera += fvec2::new(1., 2.);
+        camera -= fvec2::new(3., 4.);
+        camera *= 2.;
+        camera /= fvec2::new(4., 2.);
+        assert_eq!(camera, fvec2::new(4., 18.));
+        assert_eq!(-camera / 2., fvec2::new(-2., -9.));
+
+        let mut tile = ivec2::new(7, 9);
+        tile *= 2;
+        tile /= 3;
+        assert_eq!(tile, ivec2::new(4, 6));
+        assert_eq!(2 * tile, ivec2::new(8, 12));
+
+        assert_eq!(tile * 0.5, fvec2::new(2., 3.));
+        assert_eq!(1.5 * tile, fvec2::new(6., 9.));
+        assert_eq!(tile / 4., fvec2::new(1., 1.5));
+    }
+
+    #[test]
+    fn test_array_and_tuple_conversions() {
+        assert_eq!(ivec2::from([1, -2]), ivec2::new(1, -2));
+        assert_eq!(<(f32, f32)>::from(fvec2::new(0.5, 2.)), (0.5, 2.));
+        assert_eq!(<[u32; 2]>::from(uvec2::from((3, 4))), [3, 4]);
+
+        let rect = Rect::new(ivec2::new(1, 2), ivec2::new(3, 4));
+        assert_eq!(<[i32; 4]>::from(rect), [1, 2, 3, 4]);
+        assert_eq!(Rect::from([1, 2, 3, 4]), rect);
+        assert_eq!(<(i32, i32, i32, i32)>::from(rect), (1, 2, 3, 4));
+        assert_eq!(Rect::from((1, 2, 3, 4)), rect);
+    }
+
+    #[test]
+    fn test_unsigned_conversions() {
+        assert_eq!(
+            ivec2::new(0, i32::MAX).try_into_uvec2(),
+            Some(uvec2::new(0, i32::MAX as u32))
+        );
+        assert_eq!(ivec2::new(-1, 0).try_into_uvec2(), None);
+        assert_eq!(uvec2::new(1, 2).try_into_ivec2(), Some(ivec2::new(1, 2)));
+        assert_eq!(uvec2::new(u32::MAX, 2).try_into_ivec2(), None);
+        assert_eq!(uvec2::from((640, 480)) / 2, uvec2::new(320, 240));
+    }
+
+    #[test]
+    fn test_rect_helpers() {
+        let rect = Rect::new(ivec2::new(-1, -1), ivec2::new(3, 2));
+        assert_eq!(rect.iter_points().count(), 6);
+        assert_eq!(rect.iter_points().last(), Some(ivec2::new(1, 0)));
+        assert_eq!(
+            Rect::new(ivec2::new(0, 0), ivec2::new(0, 5))
+                .iter_points()
+                .count(),
+            0
+        );
+
+        // disjoint rectangles give an empty one
+        let far = Rect::new(ivec2::new(10, -1), ivec2::new(2, 2));
+        assert_eq!(rect.clamped_to(far).size, ivec2::new(0, 2));
+        assert_eq!(rect.clamped_to(rect), rect);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam_conversions() {
+        assert_eq!(
+            glam::IVec2::from(ivec2::new(1, -2)),
+            glam::IVec2::new(1, -2)
+        );
+        assert_eq!(uvec2::from(glam::UVec2::new(3, 4)), uvec2::new(3, 4));
+        let v: glam::Vec2 = fvec2::new(0.5, 1.5).into();
+        assert_eq!(fvec2::from(v * 2.), fvec2::new(1., 3.));
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn test_mint_conversions() {
+        let v: mint::Vector2<i32> = ivec2::new(1, -2).into();
+        assert_eq!((v.x, v.y), (1, -2));
+        assert_eq!(
+            fvec2::from(mint::Point2 { x: 0.5, y: 1.5 }),
+            fvec2::new(0.5, 1.5)
+        );
+        assert_eq!(uvec2::from(mint::Vector2 { x: 3, y: 4 }), uvec2::new(3, 4));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_nalgebra_conversions() {
+        assert_eq!(
+            nalgebra::Vector2::from(ivec2::new(1, -2)),
+            nalgebra::Vector2::new(1, -2)
+        );
+        let p: nalgebra::Point2<f32> = fvec2::new(0.5, 1.5).into();
+        assert_eq!(
+            fvec2::from(p + nalgebra::Vector2::new(1., 1.)),
+            fvec2::new(1.5, 2.5)
+        );
+        assert_eq!(uvec2::from(nalgebra::Point2::new(3, 4)), uvec2::new(3, 4));
+    }
 }
@@ -0,0 +1,224 @@
+//! Vertex and index buffers for drawing tile layers on the GPU, see [TileLayer::build_mesh]
+//! and [TileSet::uv_table].
+
+use std::ops::Range;
+
+use crate::math::fvec2;
+use crate::{Map, TileLayer, TileSet, GID};
+
+/// A corner of a tile quad in a [TileMesh].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TileVertex {
+    /// Position in pixels, relative to the upper left corner of the map.
+    pub position: [f32; 2],
+
+    /// Texture coordinates inside of the sprite sheet, between 0 and 1.
+    pub uv: [f32; 2],
+}
+
+/// Geometry of a tile layer, created by [TileLayer::build_mesh].
+///
+/// Every tile is a quad of four vertices (upper left, upper right, lower right, lower left)
+/// and six indices for the triangles `0 1 2` and `0 2 3` of the quad.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileMesh {
+    pub vertices: Vec<TileVertex>,
+    pub indices: Vec<u32>,
+
+    /// Ranges of [TileMesh::indices] that use the same sprite sheet, one draw call each.
+    /// Draw them in order to keep the render order of the tiles: a new batch starts
+    /// whenever the tileset changes, so a tileset can have several batches.
+    /// Layers that only use a single tileset have a single batch.
+    pub batches: Vec<MeshBatch>,
+}
+
+/// Part of a [TileMesh] that is drawn with the sprite sheet of one tileset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshBatch {
+    /// Index of the tileset in [Map::tilesets].
+    pub tileset: usize,
+
+    /// The indices to draw, as range of [TileMesh::indices].
+    pub indices: Range<u32>,
+}
+
+impl TileLayer {
+    /// Build the vertex and index buffers to draw this layer with any graphics API,
+    /// so the whole layer can be uploaded once and drawn with one call per [MeshBatch].
+    ///
+    /// The texture coordinates already include the flip flags of the tiles.
+    /// The tiles are placed with [Map::cell_to_pixel] and ordered like in [Map::render_with].
+    /// Tiles that are bigger than the grid of the map are aligned to the lower left
+    /// corner of their cell, like Tiled does. Tiles without a tileset are skipped.
+    /// The tint color and visibility of the layer are left to the caller.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/default_map.tmx"))?;
+    /// for (layer, _) in map.iter_layers() {
+    ///     if let tego::Layer::Tile(layer) = layer {
+    ///         let mesh = layer.build_mesh(&map);
+    ///         assert_eq!(mesh.indices.len(), mesh.vertices.len() / 4 * 6);
+    ///         // upload the buffers and draw each batch with the sprite sheet of its tileset
+    ///     }
+    /// }
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn build_mesh(&self, map: &Map) -> TileMesh {
+        let quads = map
+            .cells_in_renderorder(self)
+            .filter_map(|(pos, gid)| Some((tileset_index(map, gid)?, pos, gid)));
+
+        let mut mesh = TileMesh::default();
+        for (index, pos, gid) in quads {
+            let tileset = &map.tilesets[index];
+            let first = mesh.indices.len() as u32;
+            match mesh.batches.last_mut() {
+                Some(batch) if batch.tileset == index => batch.indices.end = first + 6,
+                _ => mesh.batches.push(MeshBatch {
+                    tileset: index,
+                    indices: first..first + 6,
+                }),
+            }
+
+            let mut size = tileset.tile_size;
+            if gid.flip_diagonal() {
+                std::mem::swap(&mut size.x, &mut size.y);
+            }
+            let cell = map.cell_to_pixel(pos);
+            let x = cell.x as f32;
+            let y = (cell.y + map.tile_size.y - size.y) as f32;
+            let (uv_min, uv_max) = uv_rect(tileset, gid.to_id() - tileset.firstgid.to_id());
+
+            let base = mesh.vertices.len() as u32;
+            for corner in [(0., 0.), (1., 0.), (1., 1.), (0., 1.)] {
+                let (u, v) = flipped_corner(gid, corner);
+                mesh.vertices.push(TileVertex {
+                    position: [x + corner.0 * size.x as f32, y + corner.1 * size.y as f32],
+                    uv: [
+                        uv_min.x + u * (uv_max.x - uv_min.x),
+                        uv_min.y + v * (uv_max.y - uv_min.y),
+                    ],
+                });
+            }
+            mesh.indices
+                .extend([0, 1, 2, 0, 2, 3].into_iter().map(|offset| base + offset));
+        }
+        mesh
+    }
+}
+
+impl TileSet {
+    /// The texture coordinates of all tiles as `[left, top, right, bottom]`, between 0 and 1,
+    /// indexed by local id. Taking [TileSet::margin] and [TileSet::spacing] into account.
+    ///
+    /// Upload the table once (e.g. as uniform or storage buffer), so shaders can look up
+    /// tiles by their id instead of computing their rectangles every frame.
+    ///
+    /// If [TileSet::image_size] is not known, the size of the sprite sheet is computed
+    /// from the tiles.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/default_map.tmx"))?;
+    /// let table = map.tilesets[0].uv_table();
+    /// assert_eq!(table.len(), map.tilesets[0].tilecount);
+    /// assert_eq!(table[1], [1. / 16., 0., 2. / 16., 1. / 16.]);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn uv_table(&self) -> Vec<[f32; 4]> {
+        (0..self.tilecount as u32)
+            .map(|id| {
+                let (min, max) = uv_rect(self, id);
+                [min.x, min.y, max.x, max.y]
+            })
+            .collect()
+    }
+}
+
+/// Index of the tileset that contains the tile, if any.
+fn tileset_index(map: &Map, gid: GID) -> Option<usize> {
+    map.tilesets
+        .iter()
+        .rposition(|t| t.firstgid.to_id() <= gid.to_id())
+        .filter(|&index| {
+            let tileset = &map.tilesets[index];
+            tileset
+                .tile(gid.to_id() - tileset.firstgid.to_id())
+                .is_some()
+        })
+}
+
+/// Upper left and lower right texture coordinates of a tile, between 0 and 1.
+///
+/// If the size of the sprite sheet isn't stored in the tileset,
+/// it is computed from the number of tiles.
+fn uv_rect(tileset: &TileSet, local_id: u32) -> (fvec2, fvec2) {
+    let image_size = tileset.image_size.unwrap_or_else(|| {
+        let columns = tileset.columns.max(1);
+        let rows = tileset.tilecount.div_ceil(columns).max(1);
+        let stride = |tile: i32, count: usize| {
+            2 * tileset.margin as i32
+                + count as i32 * tile
+                + (count as i32 - 1) * tileset.spacing as i32
+        };
+        crate::math::ivec2::new(
+            stride(tileset.tile_size.x, columns),
+            stride(tileset.tile_size.y, rows),
+        )
+    });
+    let rect = tileset.tile_rect(local_id);
+    let scale = |value: i32, size: i32| value as f32 / size.max(1) as f32;
+    (
+        fvec2::new(
+            scale(rect.upper_left.x, image_size.x),
+            scale(rect.upper_left.y, image_size.y),
+        ),
+        fvec2::new(
+            scale(rect.upper_left.x + rect.size.x, image_size.x),
+            scale(rect.upper_left.y + rect.size.y, image_size.y),
+        ),
+    )
+}
+
+/// The position inside of the source tile (between 0 and 1) that ends up at the given
+/// corner of the drawn tile, after applying the flip flags.
+///
+/// Tiled flips diagonally first, then horizontally and vertically, so this undoes
+/// the flips in reverse order.
+fn flipped_corner(gid: GID, (mut x, mut y): (f32, f32)) -> (f32, f32) {
+    if gid.flip_vertical() {
+        y = 1. - y;
+    }
+    if gid.flip_horizontal() {
+        x = 1. - x;
+    }
+    if gid.flip_diagonal() {
+        std::mem::swap(&mut x, &mut y);
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flipped_corner() {
+        let gid = |flags: u32| GID(std::num::NonZeroU32::new(1 | flags).unwrap());
+        let upper_left = (0., 0.);
+        assert_eq!(flipped_corner(gid(0), upper_left), (0., 0.));
+        assert_eq!(
+            flipped_corner(gid(crate::GID_HORIZONTAL_FLIP_FLAG), upper_left),
+            (1., 0.)
+        );
+        assert_eq!(
+            flipped_corner(gid(crate::GID_VERTICAL_FLIP_FLAG), upper_left),
+            (0., 1.)
+        );
+        // rotated by 90° clockwise, the lower left corner of the tile ends up in the upper left
+        let rotated = gid(crate::GID_DIAGONAL_FLIP_FLAG | crate::GID_HORIZONTAL_FLIP_FLAG);
+        assert_eq!(flipped_corner(rotated, upper_left), (0., 1.));
+    }
+}
@@ -0,0 +1,90 @@
+//! Reducing a map to one pixel per tile, see [Map::minimap].
+
+use crate::{Color, Layer, LayerId, Map, Tile};
+
+impl Map {
+    /// Draw a minimap of this map with one pixel per tile, as raw RGBA data
+    /// (4 bytes per pixel, row by row, [Map::size] pixels).
+    ///
+    /// tego doesn't look at image data, so `color` picks the color of each tile,
+    /// e.g. by its class or by the average color of its tileset that the caller
+    /// computed from the sprite sheet.
+    /// The visible tile layers are blended on top of each other and on top of the
+    /// [background color](Map::backgroundcolor), taking the opacity of groups into account.
+    /// Layer offsets and tint colors are ignored.
+    ///
+    /// ```no_run
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/island/island.tmx"))?;
+    /// let water = tego::Color::from_argb(255, 40, 80, 200);
+    /// let ground = tego::Color::from_argb(255, 200, 180, 120);
+    /// let pixels = map.minimap(|tile| match tile.class().as_str() {
+    ///     "water" => water,
+    ///     _ => ground,
+    /// });
+    /// assert_eq!(pixels.len(), (map.size.x * map.size.y * 4) as usize);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn minimap(&self, mut color: impl FnMut(&Tile) -> Color) -> Vec<u8> {
+        let background = self.backgroundcolor;
+        let mut pixels: Vec<[f32; 4]> =
+            vec![premultiply(background, 1.); (self.size.x.max(0) * self.size.y.max(0)) as usize];
+        for &id in self.layer_ids() {
+            self.draw_minimap_layer(&mut pixels, id, 1., &mut color);
+        }
+        pixels
+            .into_iter()
+            .flat_map(|[r, g, b, a]| {
+                let channel = |value: f32| {
+                    let value = if a > 0. { value / a } else { 0. };
+                    (value * 255.).round() as u8
+                };
+                [channel(r), channel(g), channel(b), (a * 255.).round() as u8]
+            })
+            .collect()
+    }
+
+    fn draw_minimap_layer(
+        &self,
+        pixels: &mut [[f32; 4]],
+        id: LayerId,
+        opacity: f32,
+        color: &mut impl FnMut(&Tile) -> Color,
+    ) {
+        match &self[id] {
+            Layer::Group(group) if group.visible => {
+                for &child in &group.content {
+                    self.draw_minimap_layer(pixels, child, opacity * group.opacity, color);
+                }
+            }
+            Layer::Tile(layer) if layer.visible => {
+                for (pos, gid) in layer.occupied_tiles() {
+                    if pos.x >= self.size.x || pos.y >= self.size.y {
+                        continue;
+                    }
+                    let Some(tile) = self.tile(gid) else {
+                        continue;
+                    };
+                    let pixel = &mut pixels[(pos.y * self.size.x + pos.x) as usize];
+                    let source = premultiply(color(&tile), opacity * layer.opacity);
+                    for (target, value) in pixel.iter_mut().zip(source) {
+                        *target = value + *target * (1. - source[3]);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The color as RGBA with values between 0 and 1, multiplied by its alpha.
+fn premultiply(color: Color, opacity: f32) -> [f32; 4] {
+    let alpha = color.alpha() as f32 / 255. * opacity;
+    let channel = |value: u8| value as f32 / 255. * alpha;
+    [
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue()),
+        alpha,
+    ]
+}
@@ -0,0 +1,138 @@
+//! This module provides [Name], a cheaply clonable string type used for
+//! identifiers that repeat a lot inside of maps, e.g. layer names or property keys.
+
+use std::{borrow::Borrow, collections::HashSet, fmt, ops::Deref, sync::Arc};
+
+/// An immutable, atomically reference counted string.
+///
+/// Names are interned while a map is loaded,
+/// so that objects sharing the same name or class also share the same allocation.
+/// Cloning a name only increases a reference count.
+///
+/// Names dereference to [str] and can be compared with string slices directly:
+///
+/// ```
+/// let name = tego::Name::from("Ground");
+/// assert_eq!(name, "Ground");
+/// assert!(name.starts_with("Gr"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Name(Arc<str>);
+
+impl Name {
+    /// Get the name as string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check whether both names share the same allocation.
+    ///
+    /// For names from the same pool (see [ResourceManager::intern](crate::ResourceManager::intern))
+    /// this is equivalent to comparing the strings, but only compares a pointer.
+    pub fn ptr_eq(a: &Name, b: &Name) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl Default for Name {
+    fn default() -> Self {
+        Self::from("")
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Self(name.into())
+    }
+}
+
+impl From<String> for Name {
+    fn from(name: String) -> Self {
+        Self(name.into())
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Name {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+/// Pool of names, so that equal strings share the same allocation.
+#[derive(Default)]
+pub(crate) struct Interner {
+    names: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Get the name for the given string, reusing a previous allocation if possible.
+    pub(crate) fn intern(&mut self, name: &str) -> Name {
+        if let Some(existing) = self.names.get(name) {
+            return Name(existing.clone());
+        }
+        let name: Arc<str> = name.into();
+        self.names.insert(name.clone());
+        Name(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interned_names_share_allocation() {
+        let mut interner = Interner::default();
+        let a = interner.intern("Enemy");
+        let b = interner.intern("Enemy");
+        let c = interner.intern("Player");
+
+        assert!(Name::ptr_eq(&a, &b));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,226 @@
+//! Navigation meshes for path finding, built from the walkable areas of a map,
+//! see [Map::build_navmesh].
+
+use std::collections::HashMap;
+
+use crate::math::fvec2;
+use crate::{Layer, Map, Object};
+
+/// Vertices closer than this (in pixels) are merged, so that polygons which share
+/// an edge are connected.
+const MERGE_DISTANCE: f32 = 1. / 64.;
+
+/// A triangulation of the walkable areas of a map, in world coordinates
+/// (pixels, relative to the upper left corner of the map).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NavMesh {
+    pub vertices: Vec<fvec2>,
+
+    /// Indices into [vertices](NavMesh::vertices). All triangles have the same winding,
+    /// clockwise on screen.
+    pub triangles: Vec<[u32; 3]>,
+
+    /// For each triangle, the triangles on the other side of its three edges,
+    /// None where the edge is on the border of the walkable area.
+    /// Edge `i` goes from corner `i` to corner `(i + 1) % 3`.
+    pub neighbors: Vec<[Option<u32>; 3]>,
+}
+
+impl NavMesh {
+    /// Index of the triangle that contains the given point, if any.
+    pub fn triangle_at(&self, point: fvec2) -> Option<usize> {
+        self.triangles.iter().position(|triangle| {
+            let [a, b, c] = triangle.map(|i| self.vertices[i as usize]);
+            contains(a, b, c, point)
+        })
+    }
+
+    /// Center of gravity of a triangle, e.g. to use as node of a path finding graph.
+    ///
+    /// # Panics
+    /// If there is no triangle with the given index.
+    pub fn center(&self, triangle: usize) -> fvec2 {
+        let [a, b, c] = self.triangles[triangle].map(|i| self.vertices[i as usize]);
+        (a + b + c) * (1. / 3.)
+    }
+}
+
+impl Map {
+    /// Triangulate the walkable areas of this map into a [NavMesh].
+    ///
+    /// The areas are the [collision shapes](Map::collision_shapes) of the objects accepted by
+    /// `filter`, e.g. all objects of a layer with a certain name or of a certain class.
+    /// Rectangles, ellipses and polygons are used, polylines, points and text are ignored.
+    /// Areas are connected where they share an edge with the same corners. Overlapping areas
+    /// and holes are not supported.
+    ///
+    /// ```
+    /// # use tego::{EmbeddedProvider, LazyLoader, Layer, Map, ResourceManager};
+    /// let map = Map::from_xml_str(r#"
+    ///     <map version="1.10" orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8">
+    ///         <objectgroup id="1" name="Walkable">
+    ///             <object id="1" x="0" y="0" width="16" height="8"/>
+    ///             <object id="2" x="16" y="0" width="8" height="8"/>
+    ///         </objectgroup>
+    ///     </map>"#,
+    ///     &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    /// )?;
+    /// let navmesh = map.build_navmesh(|layer, _object| layer.name() == "Walkable");
+    /// assert_eq!(navmesh.triangles.len(), 4);
+    /// assert_eq!(navmesh.vertices.len(), 6);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn build_navmesh(&self, filter: impl FnMut(&Layer, &Object) -> bool) -> NavMesh {
+        let mut mesh = NavMesh::default();
+        let mut vertex_ids = HashMap::new();
+        for shape in self.collision_shapes(filter) {
+            let Some(outline) = shape.shape.outline() else {
+                continue;
+            };
+            let corners: Vec<u32> = outline
+                .iter()
+                .map(|&point| {
+                    let key = (
+                        (point.x / MERGE_DISTANCE).round() as i64,
+                        (point.y / MERGE_DISTANCE).round() as i64,
+                    );
+                    *vertex_ids.entry(key).or_insert_with(|| {
+                        mesh.vertices.push(point);
+                        mesh.vertices.len() as u32 - 1
+                    })
+                })
+                .collect();
+            mesh.triangles.extend(triangulate(&mesh.vertices, &corners));
+        }
+        mesh.neighbors = neighbors(&mesh.triangles);
+        mesh
+    }
+}
+
+/// Split a simple polygon into triangles by cutting off ears.
+pub(crate) fn triangulate(vertices: &[fvec2], polygon: &[u32]) -> Vec<[u32; 3]> {
+    let mut remaining = polygon.to_vec();
+    remaining.dedup();
+    if remaining.len() > 1 && remaining.first() == remaining.last() {
+        remaining.pop();
+    }
+    let point = |i: u32| vertices[i as usize];
+    let area: f32 = (0..remaining.len())
+        .map(|i| {
+            let (a, b) = (
+                point(remaining[i]),
+                point(remaining[(i + 1) % remaining.len()]),
+            );
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    if area < 0. {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::new();
+    while remaining.len() >= 3 {
+        let len = remaining.len();
+        let corners = |i: usize| {
+            [
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            ]
+        };
+        let ear = (0..len).find(|&i| {
+            let [a, b, c] = corners(i);
+            cross(point(b) - point(a), point(c) - point(b)) > 0.
+                && !remaining.iter().any(|&other| {
+                    ![a, b, c].contains(&other)
+                        && contains(point(a), point(b), point(c), point(other))
+                })
+        });
+        // only collinear or self intersecting corners are left
+        let Some(ear) = ear else {
+            break;
+        };
+        triangles.push(corners(ear));
+        remaining.remove(ear);
+    }
+    triangles
+}
+
+/// Connect the triangles that share an edge.
+fn neighbors(triangles: &[[u32; 3]]) -> Vec<[Option<u32>; 3]> {
+    let mut neighbors = vec![[None; 3]; triangles.len()];
+    let mut open_edges: HashMap<(u32, u32), (usize, usize)> = HashMap::new();
+    for (index, triangle) in triangles.iter().enumerate() {
+        for edge in 0..3 {
+            let (a, b) = (triangle[edge], triangle[(edge + 1) % 3]);
+            // the other triangle has the same winding, so it goes along the edge the other way
+            match open_edges.remove(&(b, a)) {
+                Some((other, other_edge)) => {
+                    neighbors[index][edge] = Some(other as u32);
+                    neighbors[other][other_edge] = Some(index as u32);
+                }
+                None => {
+                    open_edges.insert((a, b), (index, edge));
+                }
+            }
+        }
+    }
+    neighbors
+}
+
+fn cross(a: fvec2, b: fvec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Whether the point is inside of the triangle or on its border.
+/// The corners have to be in clockwise order on screen.
+fn contains(a: fvec2, b: fvec2, c: fvec2, point: fvec2) -> bool {
+    cross(b - a, point - a) >= 0. && cross(c - b, point - b) >= 0. && cross(a - c, point - c) >= 0.
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn triangulate_concave_polygon() {
+        // an L shape
+        let vertices: Vec<_> = [(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)]
+            .into_iter()
+            .map(fvec2::from)
+            .collect();
+        let polygon = [0, 1, 2, 3, 4, 5];
+        let triangles = triangulate(&vertices, &polygon);
+        assert_eq!(triangles.len(), 4);
+
+        let area: f32 = triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|i| vertices[i as usize]);
+                cross(b - a, c - a) / 2.
+            })
+            .sum();
+        assert_eq!(area, 3.);
+
+        // polygons in the other direction give triangles with the same winding
+        let mut reversed = polygon;
+        reversed.reverse();
+        assert!(triangulate(&vertices, &reversed).iter().all(|t| {
+            let [a, b, c] = t.map(|i| vertices[i as usize]);
+            cross(b - a, c - a) > 0.
+        }));
+    }
+
+    #[test]
+    fn connect_triangles() {
+        let neighbors = neighbors(&[[0, 1, 2], [2, 1, 3], [4, 5, 6]]);
+        assert_eq!(
+            neighbors,
+            [
+                [None, Some(1), None],
+                [Some(0), None, None],
+                [None, None, None]
+            ]
+        );
+    }
+}
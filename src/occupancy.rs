@@ -0,0 +1,99 @@
+//! Bitset that tracks which cells of a tile layer contain a tile,
+//! so that large empty regions can be skipped quickly.
+
+use crate::GID;
+
+const BITS: usize = u64::BITS as usize;
+
+/// One bit per cell of a layer, stored row by row.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Occupancy {
+    words: Vec<u64>,
+}
+
+impl Occupancy {
+    pub(crate) fn from_tiles(tiles: &[Option<GID>]) -> Self {
+        let mut words = vec![0; tiles.len().div_ceil(BITS)];
+        for (index, tile) in tiles.iter().enumerate() {
+            if tile.is_some() {
+                words[index / BITS] |= 1 << (index % BITS);
+            }
+        }
+        Self { words }
+    }
+
+    pub(crate) fn set(&mut self, index: usize, occupied: bool) {
+        let bit = 1 << (index % BITS);
+        match occupied {
+            true => self.words[index / BITS] |= bit,
+            false => self.words[index / BITS] &= !bit,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub(crate) fn get(&self, index: usize) -> bool {
+        self.words
+            .get(index / BITS)
+            .is_some_and(|word| word & (1 << (index % BITS)) != 0)
+    }
+
+    /// Iterate over the indices of all occupied cells in the range `start..end`,
+    /// skipping 64 empty cells at once.
+    pub(crate) fn occupied(&self, start: usize, end: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut word_index = start / BITS;
+        // mask out the bits before the start of the range
+        let mut current = self.words.get(word_index).copied().unwrap_or(0) & (!0 << (start % BITS));
+        std::iter::from_fn(move || loop {
+            if current != 0 {
+                let index = word_index * BITS + current.trailing_zeros() as usize;
+                current &= current - 1;
+                return (index < end).then_some(index);
+            }
+            word_index += 1;
+            if word_index * BITS >= end {
+                return None;
+            }
+            current = self.words.get(word_index).copied()?;
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iterate_occupied_cells() {
+        let gid: GID = "1".parse().unwrap();
+        let mut tiles = vec![None; 200];
+        for index in [0, 5, 63, 64, 130, 199] {
+            tiles[index] = Some(gid);
+        }
+        let occupancy = Occupancy::from_tiles(&tiles);
+
+        assert_eq!(
+            occupancy.occupied(0, 200).collect::<Vec<_>>(),
+            [0, 5, 63, 64, 130, 199]
+        );
+        assert_eq!(
+            occupancy.occupied(6, 131).collect::<Vec<_>>(),
+            [63, 64, 130]
+        );
+        assert_eq!(occupancy.occupied(65, 130).count(), 0);
+        assert!(occupancy.get(64));
+        assert!(!occupancy.get(65));
+
+        let mut occupancy = occupancy;
+        occupancy.set(64, false);
+        occupancy.set(65, true);
+        assert_eq!(
+            occupancy.occupied(6, 131).collect::<Vec<_>>(),
+            [63, 65, 130]
+        );
+        occupancy.clear();
+        assert_eq!(occupancy.occupied(0, 200).count(), 0);
+    }
+}
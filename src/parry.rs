@@ -0,0 +1,206 @@
+//! Colliders for [parry2d](https://crates.io/crates/parry2d), see [Map::colliders].
+
+use parry2d::math::{Isometry, Point, Real, Vector};
+use parry2d::shape::SharedShape;
+
+use crate::math::{fvec2, ivec2};
+use crate::navmesh::triangulate;
+use crate::{Layer, Map, Object, Shape};
+
+/// A parry2d shape together with its position and the object it was created from,
+/// see [Map::colliders].
+#[derive(Clone)]
+pub struct Collider<'a> {
+    /// Position and rotation of the shape in world coordinates.
+    pub position: Isometry<Real>,
+    pub shape: SharedShape,
+
+    /// The object of an object layer, or of the collision data of a tile.
+    pub object: &'a Object,
+
+    /// Position of the tile in its layer, if the collider belongs to the collision data of a tile.
+    pub tile: Option<ivec2>,
+}
+
+impl std::fmt::Debug for Collider<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Collider")
+            .field("position", &self.position)
+            .field("shape", &self.shape.shape_type())
+            .field("object", &self.object.id)
+            .field("tile", &self.tile)
+            .finish()
+    }
+}
+
+impl Shape {
+    /// Convert the shape into a parry2d shape and its position.
+    ///
+    /// Rectangles become cuboids, circles balls, convex polygons convex polygons and
+    /// polylines polylines. Concave polygons are split into triangles, which are combined
+    /// into a compound shape. Returns None for points, which have no area, and for
+    /// polygons and polylines without enough points.
+    ///
+    /// The coordinates are kept as they are, i.e. in pixels with the y axis pointing down.
+    /// Requires the `parry2d` feature.
+    pub fn to_parry(&self) -> Option<(Isometry<Real>, SharedShape)> {
+        match self {
+            Shape::Rect {
+                center,
+                half_size,
+                rotation,
+            } => Some((
+                Isometry::new(Vector::new(center.x, center.y), *rotation),
+                SharedShape::cuboid(half_size.x, half_size.y),
+            )),
+            Shape::Circle { center, radius } => Some((
+                Isometry::translation(center.x, center.y),
+                SharedShape::ball(*radius),
+            )),
+            Shape::Polygon { points } => {
+                polygon_shape(points).map(|shape| (Isometry::identity(), shape))
+            }
+            Shape::Polyline { points } if points.len() >= 2 => Some((
+                Isometry::identity(),
+                SharedShape::polyline(points.iter().map(|&p| point(p)).collect(), None),
+            )),
+            Shape::Polyline { .. } | Shape::Point(_) => None,
+        }
+    }
+}
+
+impl Map {
+    /// Collect the [collision shapes](Map::collision_shapes) of this map as parry2d shapes,
+    /// e.g. to create the colliders of rapier2d. See [Shape::to_parry] for how shapes are
+    /// converted, `filter` decides which objects are included.
+    ///
+    /// Requires the `parry2d` feature.
+    ///
+    /// ```
+    /// # use tego::{EmbeddedProvider, LazyLoader, Map, ResourceManager};
+    /// let map = Map::from_xml_str(r#"
+    ///     <map version="1.10" orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8">
+    ///         <objectgroup id="1" name="Walls" offsetx="8">
+    ///             <object id="1" x="0" y="0" width="16" height="8"/>
+    ///             <object id="2" x="16" y="16" width="8" height="8"><ellipse/></object>
+    ///         </objectgroup>
+    ///     </map>"#,
+    ///     &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    /// )?;
+    /// let colliders = map.colliders(|layer, _object| layer.name() == "Walls");
+    /// assert_eq!(colliders.len(), 2);
+    /// assert_eq!(colliders[0].position.translation.vector.x, 16.);
+    /// assert!(colliders[1].shape.as_ball().is_some());
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn colliders(&self, filter: impl FnMut(&Layer, &Object) -> bool) -> Vec<Collider<'_>> {
+        self.collision_shapes(filter)
+            .into_iter()
+            .filter_map(|shape| {
+                let (position, collider) = shape.shape.to_parry()?;
+                Some(Collider {
+                    position,
+                    shape: collider,
+                    object: shape.object,
+                    tile: shape.tile,
+                })
+            })
+            .collect()
+    }
+}
+
+fn point(p: fvec2) -> Point<Real> {
+    Point::new(p.x, p.y)
+}
+
+/// A convex polygon, or a compound of triangles for concave polygons.
+fn polygon_shape(points: &[fvec2]) -> Option<SharedShape> {
+    if points.len() < 3 {
+        return None;
+    }
+    // parry expects the corners in counter-clockwise order, i.e. with a positive area
+    let mut corners: Vec<_> = points.iter().map(|&p| point(p)).collect();
+    let edge = |i: usize| corners[(i + 1) % corners.len()] - corners[i];
+    let area: f32 = (0..corners.len())
+        .map(|i| corners[i].coords.perp(&edge(i)))
+        .sum();
+    if area < 0. {
+        corners.reverse();
+    }
+    let edge = |i: usize| corners[(i + 1) % corners.len()] - corners[i];
+    let convex = (0..corners.len()).all(|i| edge(i).perp(&edge((i + 1) % corners.len())) >= 0.);
+    if convex {
+        return SharedShape::convex_polyline(corners);
+    }
+
+    let indices: Vec<u32> = (0..points.len() as u32).collect();
+    let triangles: Vec<_> = triangulate(points, &indices)
+        .into_iter()
+        .map(|[a, b, c]| {
+            let corner = |i: u32| point(points[i as usize]);
+            (
+                Isometry::identity(),
+                SharedShape::triangle(corner(a), corner(b), corner(c)),
+            )
+        })
+        .collect();
+    match triangles.is_empty() {
+        true => None,
+        false => Some(SharedShape::compound(triangles)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::math::PointList;
+
+    #[test]
+    fn test_rect_to_cuboid() {
+        let rect = Shape::Rect {
+            center: fvec2::new(10., 20.),
+            half_size: fvec2::new(4., 2.),
+            rotation: std::f32::consts::FRAC_PI_2,
+        };
+        let (position, shape) = rect.to_parry().unwrap();
+        assert_eq!(position.translation.vector, Vector::new(10., 20.));
+        assert!((position.rotation.angle() - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        assert_eq!(shape.as_cuboid().unwrap().half_extents, Vector::new(4., 2.));
+        assert!(Shape::Point(fvec2::new(1., 2.)).to_parry().is_none());
+    }
+
+    #[test]
+    fn test_polygons() {
+        // counter-clockwise on screen, which is clockwise for parry and has to be reversed
+        let square = Shape::Polygon {
+            points: PointList::from(vec![
+                fvec2::new(0., 0.),
+                fvec2::new(0., 4.),
+                fvec2::new(4., 4.),
+                fvec2::new(4., 0.),
+            ]),
+        };
+        let (_, shape) = square.to_parry().unwrap();
+        let polygon = shape.as_convex_polygon().unwrap();
+        assert_eq!(polygon.points().len(), 4);
+        assert!(shape.contains_local_point(&Point::new(1., 3.)));
+
+        // an L shape
+        let concave = Shape::Polygon {
+            points: PointList::from(vec![
+                fvec2::new(0., 0.),
+                fvec2::new(4., 0.),
+                fvec2::new(4., 2.),
+                fvec2::new(2., 2.),
+                fvec2::new(2., 4.),
+                fvec2::new(0., 4.),
+            ]),
+        };
+        let (_, shape) = concave.to_parry().unwrap();
+        let compound = shape.as_compound().unwrap();
+        assert_eq!(compound.shapes().len(), 4);
+        assert!(shape.contains_local_point(&Point::new(1., 3.)));
+        assert!(shape.contains_local_point(&Point::new(3., 1.)));
+        assert!(!shape.contains_local_point(&Point::new(3., 3.)));
+    }
+}
@@ -0,0 +1,313 @@
+//! Tiled project files (`*.tiled-project`), see [Project].
+
+use std::path::{Path, PathBuf};
+
+use crate::json::Json;
+use crate::property::ObjectReference;
+use crate::{Color, Error, Name, Property, PropertyContainer, PropertyValue, Result};
+
+/// A Tiled project: the folders that contain its maps and the custom property types
+/// shared by them.
+///
+/// ```
+/// # use std::path::Path;
+/// let project = tego::Project::from_file(Path::new("example-maps/example.tiled-project"))?;
+/// assert!(project.property_type("Enemy").is_some());
+/// for map in project.maps()? {
+///     let map = tego::Map::from_file(&map)?;
+/// }
+/// # Ok::<(),tego::Error>(())
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    /// The folders that contain the files of the project.
+    /// Paths are relative to the current directory, like the path the project was loaded from.
+    pub folders: Vec<PathBuf>,
+
+    /// The folder with the scripted extensions of the project.
+    pub extensions_path: PathBuf,
+
+    /// The rules file that is used for automapping, if one is set.
+    pub automapping_rules_file: Option<PathBuf>,
+
+    /// The oldest Tiled version that the files saved in the project have to be compatible
+    /// with, e.g. 1100 for Tiled 1.10. 0 if it is not limited.
+    pub compatibility_version: u32,
+
+    /// The custom classes and enums defined in the project.
+    pub property_types: Vec<PropertyType>,
+
+    /// Custom properties of the project.
+    /// Properties of class type are not supported yet and skipped.
+    pub properties: PropertyContainer,
+}
+
+/// A custom class or enum of a [Project], which properties and objects can use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyType {
+    /// Unique id of the type within the project.
+    pub id: u32,
+
+    /// Name of the type, which properties refer to in their `propertytype`.
+    pub name: Name,
+
+    /// Whether this is a class or an enum, with the definition of it.
+    pub kind: PropertyTypeKind,
+}
+
+/// The definition of a custom [PropertyType].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyTypeKind {
+    /// A class, i.e. a set of named members.
+    Class {
+        /// The members of the class, in the order of the project file.
+        members: Vec<ClassMember>,
+
+        /// The things that can use this class, e.g. "property", "map", "layer", "object",
+        /// "tile", "tileset" or "wangcolor".
+        use_as: Vec<String>,
+
+        /// Color of objects of this class in the editor.
+        color: Color,
+    },
+    /// An enum, i.e. a choice between named values.
+    Enum {
+        /// The names of the values, in the order of their indices.
+        values: Vec<String>,
+
+        /// Whether the enum is stored as its value or as index into the values.
+        storage: EnumStorage,
+
+        /// Whether a property can have several values of the enum, stored as comma separated
+        /// list of the values or as bit flags of the indices.
+        values_as_flags: bool,
+    },
+}
+
+/// How values of an enum [PropertyType] are stored in properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumStorage {
+    /// Stored as the name of the value.
+    String,
+
+    /// Stored as the index of the value.
+    Int,
+}
+
+/// A member of a custom class, with its default value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassMember {
+    /// Name of the member, which is the name of the property in instances of the class.
+    pub name: Name,
+
+    /// The custom type of the member, for members of class or enum type.
+    pub property_type: Option<Name>,
+
+    /// Default value of the member. None for members of class type,
+    /// since nested classes are not supported yet.
+    pub value: Option<PropertyValue>,
+}
+
+impl Project {
+    /// Load a project from a `*.tiled-project` file.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json_str(&json, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parse the content of a project file, with paths relative to the given directory.
+    pub fn from_json_str(json: &str, directory: &Path) -> Result<Self> {
+        let json = Json::parse(json)?;
+        let path = |name: &str| {
+            json.get(name)
+                .and_then(Json::as_str)
+                .filter(|path| !path.is_empty())
+                .map(|path| directory.join(path))
+        };
+        Ok(Self {
+            folders: json
+                .get("folders")
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .filter_map(Json::as_str)
+                .map(|folder| directory.join(folder))
+                .collect(),
+            extensions_path: path("extensionsPath").unwrap_or_else(|| directory.join("extensions")),
+            automapping_rules_file: path("automappingRulesFile"),
+            compatibility_version: json
+                .get("compatibilityVersion")
+                .and_then(Json::as_f64)
+                .unwrap_or_default() as u32,
+            property_types: json
+                .get("propertyTypes")
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .map(PropertyType::from_json)
+                .collect::<Result<_>>()?,
+            properties: properties_from_json(json.get("properties"))?,
+        })
+    }
+
+    /// Look up a custom class or enum by its name.
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types.iter().find(|t| t.name == name)
+    }
+
+    /// The paths of all maps (`*.tmx`) in the folders of the project and their subfolders,
+    /// sorted by path.
+    ///
+    /// Every folder is searched only once, even if symbolic links lead to it several times,
+    /// so links that point to one of their parent folders don't cause an endless search.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn maps(&self) -> Result<Vec<PathBuf>> {
+        fn collect(
+            directory: &Path,
+            visited: &mut std::collections::HashSet<PathBuf>,
+            maps: &mut Vec<PathBuf>,
+        ) -> Result<()> {
+            if !visited.insert(std::fs::canonicalize(directory)?) {
+                return Ok(());
+            }
+            for entry in std::fs::read_dir(directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    collect(&path, visited, maps)?;
+                } else if path.extension().is_some_and(|e| e == "tmx") {
+                    maps.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut visited = std::collections::HashSet::new();
+        let mut maps = Vec::new();
+        for folder in &self.folders {
+            collect(folder, &mut visited, &mut maps)?;
+        }
+        maps.sort();
+        maps.dedup();
+        Ok(maps)
+    }
+}
+
+impl PropertyType {
+    fn from_json(json: &Json) -> Result<Self> {
+        let string = |name: &str| json.get(name).and_then(Json::as_str).unwrap_or_default();
+        let strings = |name: &str| {
+            json.get(name)
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .filter_map(Json::as_str)
+                .map(str::to_owned)
+                .collect()
+        };
+        let kind = match string("type") {
+            "class" => PropertyTypeKind::Class {
+                members: json
+                    .get("members")
+                    .map_or(&[][..], Json::elements)
+                    .iter()
+                    .map(ClassMember::from_json)
+                    .collect::<Result<_>>()?,
+                use_as: strings("useAs"),
+                color: match json.get("color").and_then(Json::as_str) {
+                    Some(color) => color.parse()?,
+                    None => Color::default(),
+                },
+            },
+            "enum" => PropertyTypeKind::Enum {
+                values: strings("values"),
+                storage: match string("storageType") {
+                    "int" => EnumStorage::Int,
+                    _ => EnumStorage::String,
+                },
+                values_as_flags: json
+                    .get("valuesAsFlags")
+                    .and_then(Json::as_bool)
+                    .unwrap_or_default(),
+            },
+            other => {
+                return Err(Error::StructureError {
+                    tag: "propertyTypes".into(),
+                    msg: format!(
+                        "Unknown kind of property type '{}' for '{}'",
+                        other,
+                        string("name")
+                    ),
+                })
+            }
+        };
+        Ok(Self {
+            id: json.get("id").and_then(Json::as_f64).unwrap_or_default() as u32,
+            name: string("name").into(),
+            kind,
+        })
+    }
+}
+
+impl ClassMember {
+    fn from_json(json: &Json) -> Result<Self> {
+        let name = json.get("name").and_then(Json::as_str).unwrap_or_default();
+        Ok(Self {
+            name: name.into(),
+            property_type: json
+                .get("propertyType")
+                .and_then(Json::as_str)
+                .map(Name::from),
+            value: value_from_json(json, name)?,
+        })
+    }
+}
+
+/// Parse a list of properties in the JSON format of Tiled.
+fn properties_from_json(json: Option<&Json>) -> Result<PropertyContainer> {
+    let mut properties = PropertyContainer::new();
+    for property in json.map_or(&[][..], Json::elements) {
+        let name = property
+            .get("name")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+        if let Some(value) = value_from_json(property, name)? {
+            properties.insert(Property {
+                name: name.into(),
+                value,
+            });
+        }
+    }
+    Ok(properties)
+}
+
+/// The value of a property or class member. None for values of class type.
+fn value_from_json(json: &Json, name: &str) -> Result<Option<PropertyValue>> {
+    let value = json.get("value");
+    let text = || value.and_then(Json::as_str).unwrap_or_default().to_owned();
+    let number = || value.and_then(Json::as_f64).unwrap_or_default();
+    Ok(Some(
+        match json.get("type").and_then(Json::as_str).unwrap_or("string") {
+            "string" => PropertyValue::String(text()),
+            "int" => PropertyValue::Int(number() as i64),
+            "float" => PropertyValue::Float(number()),
+            "bool" => PropertyValue::Bool(value.and_then(Json::as_bool).unwrap_or_default()),
+            "color" => PropertyValue::Color(match text().as_str() {
+                "" => Color::default(),
+                color => color.parse()?,
+            }),
+            "file" => PropertyValue::File(text()),
+            "object" => PropertyValue::Object(ObjectReference(number() as i64)),
+            "class" => return Ok(None),
+            other => {
+                return Err(Error::StructureError {
+                    tag: "properties".into(),
+                    msg: format!("Unknown property type '{}' for property '{}'", other, name),
+                })
+            }
+        },
+    ))
+}
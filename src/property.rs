@@ -3,13 +3,21 @@
 
 use std::collections::HashMap;
 
-use crate::{Color, Error, Result};
+use crate::xml::{Node, XmlNode};
+use crate::{Color, Error, Name, ResourceManager, Result};
 
 /// Reference type to an object stored in this map.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct ObjectReference(i64);
+pub struct ObjectReference(pub(crate) i64);
 
-fn parse_string_value<'a>(tmx: &'a roxmltree::Node) -> &'a str {
+impl ObjectReference {
+    /// The id of the referenced object.
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+fn parse_string_value<'a>(tmx: &'a Node) -> &'a str {
     match tmx.attribute("value") {
         Some(text) => text,
         None => tmx.text().unwrap_or_default(),
@@ -30,7 +38,7 @@ pub enum PropertyValue {
 
 impl PropertyValue {
     /// Parse a single property value from an `<property>` xml node
-    fn from_xml(tmx: &roxmltree::Node) -> Result<Self> {
+    fn from_xml(tmx: &Node) -> Result<Self> {
         use PropertyValue::*;
 
         // Helper to parse a value if the attribute "value" exists or return the default if not
@@ -52,7 +60,7 @@ impl PropertyValue {
             "file" => Ok(File(tmx.attribute("value").unwrap_or_default().into())),
             "object" => Ok(Object(ObjectReference(parse!()))),
             other => Err(Error::StructureError {
-                tag: tmx.tag_name().name().into(),
+                tag: tmx.tag().into(),
                 msg: format!(
                     "Unknown property type '{}' for property '{}'",
                     other,
@@ -65,7 +73,7 @@ impl PropertyValue {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Property {
-    pub name: String,
+    pub name: Name,
     pub value: PropertyValue,
 }
 
@@ -142,21 +150,23 @@ impl Property {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PropertyContainer {
-    properties: HashMap<String, Property>,
+    /// Most map elements have no custom properties,
+    /// so the map is only allocated when there are any.
+    /// Boxing keeps empty containers at the size of a pointer.
+    #[allow(clippy::box_collection)]
+    properties: Option<Box<HashMap<Name, Property>>>,
 }
 
 impl PropertyContainer {
     pub(crate) fn new() -> Self {
-        Self {
-            properties: HashMap::new(),
-        }
+        Self { properties: None }
     }
 
-    pub(crate) fn from_xml(tmx: &roxmltree::Node) -> Result<Self> {
+    pub(crate) fn from_xml(tmx: &Node, loader: &ResourceManager) -> Result<Self> {
         let mut this = Self::new();
-        this.update_from_xml(tmx)?;
+        this.update_from_xml(tmx, loader)?;
         Ok(this)
     }
 
@@ -166,43 +176,52 @@ impl PropertyContainer {
     ///
     /// Properties from the xml node will overwrite properties with the same name
     /// in self.
-    pub(crate) fn update_from_xml(&mut self, tmx: &roxmltree::Node) -> Result<()> {
-        let properties = tmx.children().find(|c| c.tag_name().name() == "properties");
+    /// Property names are interned with the given loader.
+    pub(crate) fn update_from_xml(&mut self, tmx: &Node, loader: &ResourceManager) -> Result<()> {
+        let properties = tmx.children().find(|c| c.tag() == "properties");
 
         if properties.is_none() {
             return Ok(());
         }
         let properties = properties.unwrap();
 
-        for property in properties
-            .children()
-            .filter(|c| c.tag_name().name() == "property")
-        {
+        for property in properties.children().filter(|c| c.tag() == "property") {
             let name = match property.attribute("name") {
                 Some(name) => name,
                 None => {
                     return Err(Error::StructureError {
-                        tag: property.tag_name().name().into(),
+                        tag: property.tag().into(),
                         msg: "Property is missing a name!".to_owned(),
                     })
                 }
             };
 
-            self.properties.insert(
-                name.to_string(),
-                Property {
-                    name: name.into(),
-                    value: PropertyValue::from_xml(&property)?,
-                },
-            );
+            self.insert(Property {
+                name: loader.intern(name),
+                value: PropertyValue::from_xml(&property)?,
+            });
         }
 
         Ok(())
     }
 
+    /// Add a property, replacing the property with the same name.
+    pub(crate) fn insert(&mut self, property: Property) {
+        self.properties
+            .get_or_insert_with(Default::default)
+            .insert(property.name.clone(), property);
+    }
+
+    /// Get the value of the property with the given name, if it exists.
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        Some(&self.properties.as_ref()?.get(name)?.value)
+    }
+
     /// Iterate over all the properties stored in this container.
     pub fn iter(&self) -> impl Iterator<Item = &Property> {
-        self.properties.values()
+        self.properties
+            .iter()
+            .flat_map(|properties| properties.values())
     }
 }
 
@@ -214,13 +233,17 @@ impl std::ops::Index<&str> for PropertyContainer {
     /// # Panics
     /// If the given property does not exist, this function will panic.
     fn index(&self, index: &str) -> &Self::Output {
-        &self.properties[index].value
+        match &self.properties {
+            Some(properties) => &properties[index].value,
+            None => panic!("There is no property named '{}'", index),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::xml::Document;
 
     #[test]
     #[ignore] // TODO(texel, 2021-11-22): fix testcase, failing because of property order
@@ -236,9 +259,10 @@ mod test {
             </map>
         "##;
 
-        let tmx = roxmltree::Document::parse(tmx).unwrap();
+        let tmx = Document::parse(tmx).unwrap();
 
-        let properties = PropertyContainer::from_xml(&tmx.root_element()).unwrap();
+        let properties =
+            PropertyContainer::from_xml(&tmx.root_element(), &ResourceManager::default()).unwrap();
         let properties: Vec<_> = properties.iter().collect();
 
         assert_eq!(properties.len(), 4);
@@ -279,9 +303,29 @@ mod test {
             </map>
         "##;
 
-        let tmx = roxmltree::Document::parse(tmx).unwrap();
+        let tmx = Document::parse(tmx).unwrap();
 
-        let properties = PropertyContainer::from_xml(&tmx.root_element()).unwrap();
+        let properties =
+            PropertyContainer::from_xml(&tmx.root_element(), &ResourceManager::default()).unwrap();
         assert_eq!(properties["all_defaults"], PropertyValue::String("".into()));
+        assert_eq!(
+            properties.get("string_value"),
+            Some(&PropertyValue::String("Hello".into()))
+        );
+        assert_eq!(properties.get("missing"), None);
+    }
+
+    #[test]
+    fn test_empty_container_is_small() {
+        let tmx = Document::parse("<map/>").unwrap();
+        let properties =
+            PropertyContainer::from_xml(&tmx.root_element(), &ResourceManager::default()).unwrap();
+
+        assert_eq!(properties.iter().count(), 0);
+        assert!(properties.properties.is_none());
+        assert_eq!(
+            std::mem::size_of::<PropertyContainer>(),
+            std::mem::size_of::<usize>()
+        );
     }
 }
@@ -0,0 +1,479 @@
+//! Python bindings for reading maps with tego, e.g. in scripts of a level pipeline.
+//!
+//! Enabled by the `python` feature. The extension module is built with
+//! [maturin](https://www.maturin.rs), e.g. `maturin develop --features python`,
+//! and is imported as `tego`:
+//!
+//! ```python
+//! import tego
+//!
+//! map = tego.load("example-maps/default/objects.tmx")
+//! for layer in map.layers:
+//!     print(layer.name, layer.kind)
+//!     for obj in layer.objects:
+//!         print(obj.id, obj.name, obj.pos, {name: p.value for name, p in obj.properties.items()})
+//! ```
+//!
+//! Maps are read only. Images are not decoded, loading a map only reads the paths of its
+//! images. Layers and objects keep the map they belong to alive.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+use crate::{Layer, LayerId, Map, Object, ObjectKind, PropertyContainer, PropertyValue};
+
+create_exception!(tego, TegoError, PyException, "A map could not be loaded.");
+
+fn to_py_err(error: crate::Error) -> PyErr {
+    TegoError::new_err(error.to_string())
+}
+
+/// Load the map at the given path.
+#[pyfunction]
+fn load(path: PathBuf) -> PyResult<PyMap> {
+    let map = Map::from_file(&path).map_err(to_py_err)?;
+    Ok(PyMap { map: Rc::new(map) })
+}
+
+/// A Tiled map.
+#[pyclass(name = "Map", unsendable)]
+struct PyMap {
+    map: Rc<Map>,
+}
+
+#[pymethods]
+impl PyMap {
+    /// The version of the TMX format, e.g. `"1.10"`.
+    #[getter]
+    fn version(&self) -> String {
+        self.map.version.to_string()
+    }
+
+    /// `"orthogonal"`, `"isometric"`, `"staggered"` or `"hexagonal"`.
+    #[getter]
+    fn orientation(&self) -> String {
+        self.map.orientation.to_string()
+    }
+
+    /// Number of tiles in x and y direction.
+    #[getter]
+    fn size(&self) -> (i32, i32) {
+        self.map.size.into()
+    }
+
+    /// Size of a tile in pixels.
+    #[getter]
+    fn tile_size(&self) -> (i32, i32) {
+        self.map.tile_size.into()
+    }
+
+    /// Names of the tilesets.
+    #[getter]
+    fn tilesets(&self) -> Vec<String> {
+        self.map
+            .tilesets
+            .iter()
+            .map(|t| t.name.to_string())
+            .collect()
+    }
+
+    /// The top level layers, use `Layer.layers` for the layers inside of groups.
+    #[getter]
+    fn layers(&self) -> Vec<PyLayer> {
+        layers(&self.map, self.map.layer_ids())
+    }
+
+    #[getter]
+    fn properties(&self) -> HashMap<String, PyProperty> {
+        properties(&self.map.properties)
+    }
+
+    /// The first layer with the given name, including the layers inside of groups.
+    fn find_layer(&self, name: &str) -> Option<PyLayer> {
+        self.map.find_layer(name).map(|id| PyLayer {
+            map: self.map.clone(),
+            id,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<tego.Map {}x{} {}>",
+            self.map.size.x, self.map.size.y, self.map.orientation
+        )
+    }
+}
+
+/// A layer of a map.
+#[pyclass(name = "Layer", unsendable)]
+struct PyLayer {
+    map: Rc<Map>,
+    id: LayerId,
+}
+
+impl PyLayer {
+    fn layer(&self) -> &Layer {
+        &self.map[self.id]
+    }
+}
+
+#[pymethods]
+impl PyLayer {
+    /// The id of the layer in Tiled, if it has one.
+    #[getter]
+    fn id(&self) -> Option<usize> {
+        match self.layer() {
+            Layer::Tile(layer) => layer.id,
+            Layer::Object(layer) => layer.id,
+            Layer::Image(layer) => layer.id,
+            Layer::Group(layer) => layer.id,
+        }
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.layer().name().to_string()
+    }
+
+    /// `"tile"`, `"object"`, `"image"` or `"group"`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.layer() {
+            Layer::Tile(_) => "tile",
+            Layer::Object(_) => "object",
+            Layer::Image(_) => "image",
+            Layer::Group(_) => "group",
+        }
+    }
+
+    #[getter]
+    fn visible(&self) -> bool {
+        match self.layer() {
+            Layer::Tile(layer) => layer.visible,
+            Layer::Object(layer) => layer.visible,
+            Layer::Image(layer) => layer.visible,
+            Layer::Group(layer) => layer.visible,
+        }
+    }
+
+    #[getter]
+    fn opacity(&self) -> f32 {
+        match self.layer() {
+            Layer::Tile(layer) => layer.opacity,
+            Layer::Object(layer) => layer.opacity,
+            Layer::Image(layer) => layer.opacity,
+            Layer::Group(layer) => layer.opacity,
+        }
+    }
+
+    #[getter]
+    fn properties(&self) -> HashMap<String, PyProperty> {
+        properties(match self.layer() {
+            Layer::Tile(layer) => &layer.properties,
+            Layer::Object(layer) => &layer.properties,
+            Layer::Image(layer) => &layer.properties,
+            Layer::Group(layer) => &layer.properties,
+        })
+    }
+
+    /// The layers inside of a group, empty for other layers.
+    #[getter]
+    fn layers(&self) -> Vec<PyLayer> {
+        match self.layer() {
+            Layer::Group(group) => layers(&self.map, &group.content),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The objects of an object layer, empty for other layers.
+    #[getter]
+    fn objects(&self) -> Vec<PyMapObject> {
+        match self.layer() {
+            Layer::Object(layer) => (0..layer.content.len())
+                .map(|index| PyMapObject {
+                    map: self.map.clone(),
+                    layer: self.id,
+                    index,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Number of tiles of a tile layer in x and y direction, None for other layers.
+    #[getter]
+    fn size(&self) -> Option<(i32, i32)> {
+        match self.layer() {
+            Layer::Tile(layer) => Some(layer.size.into()),
+            _ => None,
+        }
+    }
+
+    /// The raw GID of the tile at the given position of a tile layer, including the flip
+    /// flags. None for empty cells, positions outside of the layer and other layers.
+    fn tile(&self, x: i32, y: i32) -> Option<u32> {
+        match self.layer() {
+            Layer::Tile(layer) => layer
+                .tile(crate::math::ivec2::new(x, y))
+                .map(|gid| gid.as_raw()),
+            _ => None,
+        }
+    }
+
+    /// The non-empty cells of a tile layer as `(x, y, gid)`, with the raw GIDs like `tile`.
+    fn tiles(&self) -> Vec<(i32, i32, u32)> {
+        match self.layer() {
+            Layer::Tile(layer) => layer
+                .occupied_tiles()
+                .map(|(pos, gid)| (pos.x, pos.y, gid.as_raw()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<tego.Layer {} '{}'>", self.kind(), self.layer().name())
+    }
+}
+
+fn layers(map: &Rc<Map>, ids: &[LayerId]) -> Vec<PyLayer> {
+    ids.iter()
+        .map(|&id| PyLayer {
+            map: map.clone(),
+            id,
+        })
+        .collect()
+}
+
+/// An object of an object layer.
+#[pyclass(name = "Object", unsendable)]
+struct PyMapObject {
+    map: Rc<Map>,
+    layer: LayerId,
+    index: usize,
+}
+
+impl PyMapObject {
+    fn object(&self) -> &Object {
+        match &self.map[self.layer] {
+            Layer::Object(layer) => &layer.content[self.index],
+            _ => unreachable!("objects are only created for object layers"),
+        }
+    }
+}
+
+#[pymethods]
+impl PyMapObject {
+    #[getter]
+    fn id(&self) -> usize {
+        self.object().id
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.object().name.to_string()
+    }
+
+    /// The class of the object, named `class_` since `class` is a keyword.
+    #[getter]
+    fn class_(&self) -> String {
+        self.object().type_.to_string()
+    }
+
+    /// `"rect"`, `"ellipse"`, `"point"`, `"polygon"`, `"polyline"` or `"text"`.
+    /// Tile objects are rects with a `gid`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.object().kind {
+            ObjectKind::Rect => "rect",
+            ObjectKind::Ellipse => "ellipse",
+            ObjectKind::Point => "point",
+            ObjectKind::Polygon { .. } => "polygon",
+            ObjectKind::Polyline { .. } => "polyline",
+            ObjectKind::Text { .. } => "text",
+        }
+    }
+
+    /// Position in pixels, relative to the layer.
+    #[getter]
+    fn pos(&self) -> (f32, f32) {
+        self.object().pos.into()
+    }
+
+    #[getter]
+    fn size(&self) -> (f32, f32) {
+        self.object().size.into()
+    }
+
+    /// Clockwise rotation in degrees.
+    #[getter]
+    fn rotation(&self) -> f32 {
+        self.object().rotation
+    }
+
+    #[getter]
+    fn visible(&self) -> bool {
+        self.object().visible
+    }
+
+    /// The raw GID of tile objects, including the flip flags.
+    #[getter]
+    fn gid(&self) -> Option<u32> {
+        self.object().tile_id.map(|gid| gid.as_raw())
+    }
+
+    /// The points of polygons and polylines relative to `pos`, empty for other objects.
+    #[getter]
+    fn points(&self) -> Vec<(f32, f32)> {
+        match &self.object().kind {
+            ObjectKind::Polygon { points } | ObjectKind::Polyline { points } => {
+                points.iter().map(|&p| p.into()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The content of text objects.
+    #[getter]
+    fn text(&self) -> Option<String> {
+        match &self.object().kind {
+            ObjectKind::Text { content, .. } => Some(content.clone()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn properties(&self) -> HashMap<String, PyProperty> {
+        properties(&self.object().properties)
+    }
+
+    fn __repr__(&self) -> String {
+        let object = self.object();
+        format!("<tego.Object {} '{}'>", object.id, object.name)
+    }
+}
+
+/// A custom property.
+#[pyclass(name = "Property", frozen)]
+struct PyProperty {
+    name: String,
+    value: PropertyValue,
+}
+
+#[pymethods]
+impl PyProperty {
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The type of the property in Tiled: `"string"`, `"int"`, `"float"`, `"bool"`,
+    /// `"color"`, `"file"` or `"object"`.
+    #[getter]
+    fn r#type(&self) -> &'static str {
+        match self.value {
+            PropertyValue::String(_) => "string",
+            PropertyValue::Int(_) => "int",
+            PropertyValue::Float(_) => "float",
+            PropertyValue::Bool(_) => "bool",
+            PropertyValue::Color(_) => "color",
+            PropertyValue::File(_) => "file",
+            PropertyValue::Object(_) => "object",
+        }
+    }
+
+    /// The value as Python object. Colors are strings like `"#ffa0a0a4"`,
+    /// files are paths as strings and object references are the ids of the objects.
+    #[getter]
+    fn value<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(match &self.value {
+            PropertyValue::String(text) | PropertyValue::File(text) => {
+                text.into_pyobject(py)?.into_any()
+            }
+            PropertyValue::Int(value) => value.into_pyobject(py)?.into_any(),
+            PropertyValue::Float(value) => value.into_pyobject(py)?.into_any(),
+            PropertyValue::Bool(value) => value.into_pyobject(py)?.to_owned().into_any(),
+            PropertyValue::Color(color) => crate::writer::color_string(*color)
+                .into_pyobject(py)?
+                .into_any(),
+            PropertyValue::Object(reference) => reference.id().into_pyobject(py)?.into_any(),
+        })
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "<tego.Property {}: {} = {}>",
+            self.name,
+            self.r#type(),
+            self.value(py)?.repr()?
+        ))
+    }
+}
+
+fn properties(container: &PropertyContainer) -> HashMap<String, PyProperty> {
+    container
+        .iter()
+        .map(|property| {
+            let name = property.name.to_string();
+            let value = property.value.clone();
+            (name.clone(), PyProperty { name, value })
+        })
+        .collect()
+}
+
+/// The `tego` Python module.
+#[pymodule]
+#[pyo3(name = "tego")]
+fn tego_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_class::<PyMap>()?;
+    m.add_class::<PyLayer>()?;
+    m.add_class::<PyMapObject>()?;
+    m.add_class::<PyProperty>()?;
+    m.add("TegoError", m.py().get_type::<TegoError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_read_map_from_python() {
+        Python::initialize();
+        Python::attach(|py| {
+            let tego = PyModule::new(py, "tego").unwrap();
+            tego_module(&tego).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("tego", tego).unwrap();
+            py.run(
+                cr#"
+map = tego.load("example-maps/default/objects.tmx")
+assert map.orientation == "orthogonal"
+group = map.layers[0]
+assert group.kind == "group"
+objects = group.layers[0]
+assert objects.name == "Child Object Layer"
+assert objects.tiles() == []
+answer = objects.objects[3].properties["The Answer"]
+assert (answer.type, answer.value) == ("int", 42)
+assert map.find_layer("Child Object Layer").name == objects.name
+try:
+    tego.load("does/not/exist.tmx")
+    assert False
+except tego.TegoError:
+    pass
+"#,
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+        });
+    }
+}
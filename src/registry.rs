@@ -0,0 +1,98 @@
+//! Creating game entities from objects by their class, see [ClassRegistry].
+
+use std::collections::HashMap;
+
+use crate::{Map, Name, Object, PropertyContainer};
+
+type Constructor<'a, T> = Box<dyn Fn(&Object, &PropertyContainer) -> T + 'a>;
+
+/// Constructors for the objects of a map, registered by the class of the objects.
+/// Use it with [Map::instantiate].
+///
+/// ```
+/// # use std::path::Path;
+/// use tego::{ClassRegistry, Map, PropertyValue};
+///
+/// enum Entity {
+///     Enemy { health: i64 },
+///     Door,
+/// }
+///
+/// let mut registry = ClassRegistry::new();
+/// registry
+///     .on("Enemy", |_object, properties| Entity::Enemy {
+///         health: match properties.get("health") {
+///             Some(PropertyValue::Int(health)) => *health,
+///             _ => 10,
+///         },
+///     })
+///     .on("Door", |_, _| Entity::Door);
+///
+/// let map = Map::from_file(Path::new("example-maps/default/objects.tmx"))?;
+/// let entities: Vec<Entity> = map.instantiate(&registry);
+/// # Ok::<(),tego::Error>(())
+/// ```
+pub struct ClassRegistry<'a, T> {
+    constructors: HashMap<Name, Constructor<'a, T>>,
+}
+
+impl<'a, T> ClassRegistry<'a, T> {
+    /// Create a registry without any constructors.
+    pub fn new() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Register the constructor for objects of the given class, replacing the one that was
+    /// registered for it before.
+    ///
+    /// The constructor gets the object and its properties, which include the properties of
+    /// its tile for tile objects, see [Spawn::properties](crate::Spawn::properties).
+    pub fn on(
+        &mut self,
+        class: impl Into<Name>,
+        constructor: impl Fn(&Object, &PropertyContainer) -> T + 'a,
+    ) -> &mut Self {
+        self.constructors
+            .insert(class.into(), Box::new(constructor));
+        self
+    }
+
+    /// Whether a constructor is registered for the class.
+    pub fn contains(&self, class: &str) -> bool {
+        self.constructors.contains_key(class)
+    }
+
+    /// Call the constructor of the object's class, None if there is none.
+    fn construct(&self, class: &str, object: &Object, properties: &PropertyContainer) -> Option<T> {
+        self.constructors
+            .get(class)
+            .map(|constructor| constructor(object, properties))
+    }
+}
+
+impl<T> Default for ClassRegistry<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Map {
+    /// Create an entity for every object of the map whose class has a constructor in the
+    /// registry. Objects of other classes are skipped.
+    ///
+    /// The class of a tile object without a class of its own is the class of its tile.
+    /// Objects are visited like [Map::spawn_with] does, so the entities are returned in the
+    /// order of the layers from bottom to top, including hidden layers and objects.
+    pub fn instantiate<T>(&self, registry: &ClassRegistry<T>) -> Vec<T> {
+        let mut entities = Vec::new();
+        self.spawn_with(|spawn| {
+            let Some(object) = spawn.object else {
+                return;
+            };
+            entities.extend(registry.construct(spawn.class, object, &spawn.properties));
+        });
+        entities
+    }
+}
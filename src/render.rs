@@ -0,0 +1,678 @@
+//! Drawing maps into images with the [image](https://crates.io/crates/image) crate.
+//!
+//! Requires the `render` feature. The sprite sheets of the map have to be loaded as
+//! [RgbaImage], i.e. with the [RgbaImageLoader](crate::RgbaImageLoader).
+
+use std::any::Any;
+use std::time::Duration;
+
+use image::{Rgba, RgbaImage};
+
+use crate::math::{fvec2, ivec2, uvec2, Rect};
+use crate::{
+    Color, Error, Layer, Map, Object, ObjectKind, ObjectLayer, RenderOptions, Result, TileRenderer,
+    TileTransform, GID,
+};
+
+/// Tint color that leaves colors unchanged.
+const WHITE: Color = Color::from_argb(255, 255, 255, 255);
+
+/// Number of corners of the polygons that approximate ellipses.
+const ELLIPSE_SEGMENTS: usize = 32;
+
+/// Opacity of the area of rectangles, ellipses and polygons, relative to their outline.
+const FILL_OPACITY: f32 = 0.25;
+
+/// Radius of the circles that mark point objects, in pixels.
+const POINT_RADIUS: f32 = 3.;
+
+/// Draw the visible layers of a map into a new image of [Map::pixel_size].
+/// See [Map::render_with] for what is drawn.
+///
+/// The image is filled with the [background color](Map::backgroundcolor) first.
+/// The tiles of each layer are multiplied with the tint colors of the layer and the groups
+/// it is in and blended with their combined opacity, like Tiled does.
+///
+/// Objects are drawn like Tiled shows them in the editor: rectangles, ellipses and polygons
+/// as outlines in the [color of their layer](ObjectLayer::color) with a translucent area,
+/// polylines as lines and points as small circles. Tile objects show their tile stretched
+/// to the size of the object, without rotation. Text objects are drawn in their color with
+/// a small built-in pixel font that only covers ASCII letters, digits and common punctuation.
+///
+/// Fails if the sprite sheet of a used tileset was not loaded as [RgbaImage].
+///
+/// ```no_run
+/// # use std::path::Path;
+/// use tego::{FileProvider, Map, ResourceManager, RgbaImageLoader};
+///
+/// let mut loader = ResourceManager::new(RgbaImageLoader::new(), FileProvider::new());
+/// let map = Map::from_file_with_loader(Path::new("example-maps/default/groups.tmx"), &mut loader)?;
+/// tego::render::render_to_image(&map)?.save("groups.png").unwrap();
+/// # Ok::<(),tego::Error>(())
+/// ```
+pub fn render_to_image(map: &Map) -> Result<RgbaImage> {
+    render_image_with_options(map, &RenderOptions::default())
+}
+
+/// Like [render_to_image], but animated tiles show the frame of their animation after
+/// `elapsed` time, see [Map::render_animated_with].
+pub fn render_frame_to_image(map: &Map, elapsed: Duration) -> Result<RgbaImage> {
+    let options = RenderOptions {
+        elapsed: Some(elapsed),
+        ..RenderOptions::default()
+    };
+    render_image_with_options(map, &options)
+}
+
+/// Draw the part of the map inside of `region` (in pixels, relative to the upper left corner
+/// of the map) into `target`, e.g. the view of a camera. The upper left corner of the region
+/// is drawn to the upper left corner of the target.
+///
+/// Only the tiles and objects that overlap the region are drawn, on orthogonal maps only
+/// the tiles around the region are visited at all (see [RenderOptions::region]).
+/// The target is filled with the background color of the map first, see [render_to_image].
+pub fn render_region(map: &Map, region: Rect, target: &mut RgbaImage) -> Result<()> {
+    let mut canvas = Canvas::new(map, target, region.upper_left);
+    let options = RenderOptions {
+        region: Some(region),
+        ..RenderOptions::default()
+    };
+    map.render_with_options(&mut canvas, &options)?;
+    canvas.finish()
+}
+
+fn render_image_with_options(map: &Map, options: &RenderOptions) -> Result<RgbaImage> {
+    let size = map.pixel_size();
+    let size = size.try_into_uvec2().unwrap_or_default();
+    let mut image = RgbaImage::new(size.x, size.y);
+    let mut canvas = Canvas::new(map, &mut image, ivec2::new(0, 0));
+    map.render_with_options(&mut canvas, options)?;
+    canvas.finish()?;
+    Ok(image)
+}
+
+/// [TileRenderer] that draws into an [RgbaImage].
+struct Canvas<'a> {
+    map: &'a Map,
+    target: &'a mut RgbaImage,
+
+    /// The pixel of the map that is drawn to the upper left corner of the target.
+    origin: ivec2,
+
+    /// The appearance of the layers that are being drawn, the innermost one last.
+    layers: Vec<Appearance>,
+
+    /// The first error that happened while drawing.
+    error: Option<Error>,
+}
+
+impl<'a> Canvas<'a> {
+    /// Fill the target with the background color of the map and start drawing.
+    fn new(map: &'a Map, target: &'a mut RgbaImage, origin: ivec2) -> Self {
+        let background = rgba(map.backgroundcolor);
+        target.pixels_mut().for_each(|pixel| *pixel = background);
+        Self {
+            map,
+            target,
+            origin,
+            layers: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Whether drawing succeeded.
+    fn finish(self) -> Result<()> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn appearance(&self) -> Appearance {
+        self.layers.last().copied().unwrap_or_default()
+    }
+
+    fn unsupported_image(&mut self) {
+        self.error.get_or_insert_with(|| {
+            Error::UnsupportedFeature(
+                "rendering needs the sprite sheets as image::RgbaImage, \
+                load the map with RgbaImageLoader"
+                    .to_owned(),
+            )
+        });
+    }
+
+    /// Blend the color into the pixel of the map at the given position, if it is on the target.
+    fn plot(&mut self, pos: ivec2, color: Rgba<u8>) {
+        if let Some(pos) = pixel_in(self.target, pos - self.origin) {
+            let pixel = self.target.get_pixel_mut(pos.x, pos.y);
+            *pixel = blend(*pixel, color);
+        }
+    }
+
+    /// Draw lines through the points (in pixels of the map), back to the first one if `closed`.
+    /// Every pixel is drawn only once, so that translucent lines have the same color everywhere.
+    fn draw_lines(&mut self, points: &[fvec2], closed: bool, color: Rgba<u8>) {
+        let ends = match closed {
+            true => points.len(),
+            false => points.len().saturating_sub(1),
+        };
+        // only the parts of the lines on the target are visited, lines can be much longer
+        let size = uvec2::from(self.target.dimensions());
+        let min = self.origin - ivec2::new(1, 1);
+        let max = self.origin + ivec2::new(size.x as i32, size.y as i32) + ivec2::new(1, 1);
+        let bounds = (
+            fvec2::new(min.x as f32, min.y as f32),
+            fvec2::new(max.x as f32, max.y as f32),
+        );
+        let mut pixels = Vec::new();
+        for i in 0..ends {
+            let Some((a, b)) = clip_line(points[i], points[(i + 1) % points.len()], bounds) else {
+                continue;
+            };
+            let steps = (b.x - a.x).abs().max((b.y - a.y).abs()).ceil().max(1.);
+            for step in 0..=steps as i32 {
+                let point = a + (b - a) * (step as f32 / steps);
+                pixels.push((point.x.floor() as i32, point.y.floor() as i32));
+            }
+        }
+        if let [point] = points {
+            pixels.push((point.x.floor() as i32, point.y.floor() as i32));
+        }
+        pixels.sort_unstable();
+        pixels.dedup();
+        for (x, y) in pixels {
+            self.plot(ivec2::new(x, y), color);
+        }
+    }
+
+    /// Fill the pixels whose centers are inside of the polygon (in pixels of the map),
+    /// using the even-odd rule.
+    fn fill_polygon(&mut self, points: &[fvec2], color: Rgba<u8>) {
+        if points.len() < 3 {
+            return;
+        }
+        let (top, bottom) = points
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(top, bottom), p| {
+                (top.min(p.y), bottom.max(p.y))
+            });
+        // only the rows on the target are of interest
+        let first_row = (top.floor() as i32).max(self.origin.y);
+        let last_row = (bottom.ceil() as i32).min(self.origin.y + self.target.height() as i32);
+        let mut crossings = Vec::new();
+        for y in first_row..last_row {
+            let center = y as f32 + 0.5;
+            crossings.clear();
+            for i in 0..points.len() {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                if (a.y <= center) != (b.y <= center) {
+                    crossings.push(a.x + (center - a.y) / (b.y - a.y) * (b.x - a.x));
+                }
+            }
+            crossings.sort_unstable_by(f32::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil() as i32;
+                let end = (span[1] - 0.5).ceil() as i32;
+                let start = start.max(self.origin.x);
+                let end = end.min(self.origin.x + self.target.width() as i32);
+                for x in start..end {
+                    self.plot(ivec2::new(x, y), color);
+                }
+            }
+        }
+    }
+
+    fn draw_tile_object(&mut self, object: &Object, gid: GID, offset: ivec2) {
+        let Some((image, source)) = self.map.tile_image(gid) else {
+            let map = self.map;
+            self.error
+                .get_or_insert_with(|| map.missing_tile_image(gid));
+            return;
+        };
+        let Some(sheet) = image.downcast_ref::<RgbaImage>() else {
+            self.unsupported_image();
+            return;
+        };
+        // tile objects are aligned to their lower left corner
+        let pos = object.pos + fvec2::new(0., -object.size.y);
+        let upper_left = offset + ivec2::new(pos.x.round() as i32, pos.y.round() as i32);
+        let size = ivec2::new(object.size.x.round() as i32, object.size.y.round() as i32);
+        let transform = TileTransform::from(gid);
+        draw_tile(
+            self.target,
+            sheet,
+            source,
+            Rect::new(upper_left - self.origin, size),
+            transform,
+            self.appearance(),
+        );
+    }
+
+    /// Draw the text with the built-in font, line by line from the upper left corner of
+    /// the object.
+    fn draw_text(&mut self, object: &Object, place: impl Fn(fvec2) -> fvec2, color: Rgba<u8>) {
+        let ObjectKind::Text {
+            content,
+            pixelsize,
+            wrap,
+            ..
+        } = &object.kind
+        else {
+            return;
+        };
+        // the glyphs are 5 units high and about as tall as capital letters of the pixel size
+        let unit = (*pixelsize as f32 / 7.).max(1.);
+        let columns = match wrap {
+            true => ((object.size.x / (GLYPH_ADVANCE as f32 * unit)) as usize).max(1),
+            false => usize::MAX,
+        };
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let chars: Vec<char> = line.chars().collect();
+            lines.extend(chars.chunks(columns).map(<[char]>::to_vec));
+            if chars.is_empty() {
+                lines.push(Vec::new());
+            }
+        }
+        for (row, line) in lines.iter().enumerate() {
+            for (column, &c) in line.iter().enumerate() {
+                let glyph = glyph(c);
+                for (y, bits) in glyph.iter().enumerate() {
+                    for x in (0..3).filter(|x| bits & (0b100 >> x) != 0) {
+                        let corner = fvec2::new(
+                            (column * GLYPH_ADVANCE + x) as f32,
+                            (row * LINE_HEIGHT + 1 + y) as f32,
+                        ) * unit;
+                        let square = [
+                            corner,
+                            corner + fvec2::new(unit, 0.),
+                            corner + fvec2::new(unit, unit),
+                            corner + fvec2::new(0., unit),
+                        ]
+                        .map(&place);
+                        self.fill_polygon(&square, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TileRenderer for Canvas<'_> {
+    fn begin_layer(&mut self, layer: &Layer) {
+        let parent = self.layers.last().copied().unwrap_or_default();
+        self.layers.push(parent.inside(layer));
+    }
+
+    fn end_layer(&mut self, _layer: &Layer) {
+        self.layers.pop();
+    }
+
+    fn draw_tile(&mut self, image: &dyn Any, source: Rect, target: Rect, transform: TileTransform) {
+        let appearance = self.appearance();
+        match image.downcast_ref::<RgbaImage>() {
+            Some(sheet) => draw_tile(
+                self.target,
+                sheet,
+                source,
+                Rect::new(target.upper_left - self.origin, target.size),
+                transform,
+                appearance,
+            ),
+            None => self.unsupported_image(),
+        }
+    }
+
+    fn draw_object(&mut self, layer: &ObjectLayer, object: &Object, offset: ivec2) {
+        if let Some(gid) = object.tile_id {
+            self.draw_tile_object(object, gid, offset);
+            return;
+        }
+        // only images are tinted, shapes just fade with the opacity of the layers
+        let appearance = Appearance {
+            tint: WHITE,
+            ..self.appearance()
+        };
+        let line = appearance.apply(rgba(layer.color));
+        let area = Appearance {
+            opacity: appearance.opacity * FILL_OPACITY,
+            ..appearance
+        }
+        .apply(rgba(layer.color));
+
+        let (sin, cos) = object.rotation.to_radians().sin_cos();
+        let origin = object.pos + fvec2::new(offset.x as f32, offset.y as f32);
+        let place = |p: fvec2| origin + fvec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos);
+        let size = object.size;
+        let ellipse = |center: fvec2, radius: fvec2| -> Vec<fvec2> {
+            (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    center + fvec2::new(angle.cos() * radius.x, angle.sin() * radius.y)
+                })
+                .collect()
+        };
+        let (outline, closed) = match &object.kind {
+            ObjectKind::Rect => (
+                vec![
+                    fvec2::default(),
+                    fvec2::new(size.x, 0.),
+                    size,
+                    fvec2::new(0., size.y),
+                ],
+                true,
+            ),
+            ObjectKind::Ellipse => (ellipse(size * 0.5, size * 0.5), true),
+            ObjectKind::Point => (
+                ellipse(fvec2::default(), fvec2::new(POINT_RADIUS, POINT_RADIUS)),
+                true,
+            ),
+            ObjectKind::Polygon { points } => (points.to_vec(), true),
+            ObjectKind::Polyline { points } => (points.to_vec(), false),
+            ObjectKind::Text { color, .. } => {
+                let color = appearance.apply(rgba(*color));
+                self.draw_text(object, place, color);
+                return;
+            }
+        };
+        let outline: Vec<fvec2> = outline.into_iter().map(place).collect();
+        if closed {
+            self.fill_polygon(&outline, area);
+        }
+        self.draw_lines(&outline, closed, line);
+    }
+}
+
+fn rgba(color: Color) -> Rgba<u8> {
+    Rgba([color.red(), color.green(), color.blue(), color.alpha()])
+}
+
+/// Horizontal distance between the glyphs of the built-in font, in units of the font.
+const GLYPH_ADVANCE: usize = 4;
+
+/// Distance between the lines of text, in units of the font.
+const LINE_HEIGHT: usize = 7;
+
+/// The pixels of a character in the built-in 3x5 font, one row after the other with the
+/// leftmost pixel in the highest bit. Lower case letters look like upper case ones,
+/// unknown characters are drawn as a box.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        _ => [0b111, 0b111, 0b111, 0b111, 0b111],
+    }
+}
+
+/// How the tiles of a layer are drawn, combined from the layer and the groups it is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Appearance {
+    opacity: f32,
+    tint: Color,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            opacity: 1.,
+            tint: WHITE,
+        }
+    }
+}
+
+impl Appearance {
+    /// The appearance of a layer inside of a group with this appearance.
+    fn inside(&self, layer: &Layer) -> Self {
+        let (opacity, tint) = match layer {
+            Layer::Tile(layer) => (layer.opacity, layer.tintcolor),
+            Layer::Object(layer) => (layer.opacity, layer.tintcolor),
+            Layer::Image(layer) => (layer.opacity, layer.tintcolor),
+            Layer::Group(layer) => (layer.opacity, layer.tintcolor),
+        };
+        Self {
+            opacity: self.opacity * opacity,
+            tint: self.tint.multiply(tint),
+        }
+    }
+
+    fn apply(&self, pixel: Rgba<u8>) -> Rgba<u8> {
+        let tint = rgba(self.tint).0;
+        let factor = |i: usize| match i {
+            3 => tint[3] as f32 / 255. * self.opacity,
+            _ => tint[i] as f32 / 255.,
+        };
+        Rgba(std::array::from_fn(|i| {
+            (pixel.0[i] as f32 * factor(i)).round() as u8
+        }))
+    }
+}
+
+/// Blend the `source` rectangle of the sprite sheet into the `target` rectangle,
+/// applying the flips and the appearance of the layer.
+/// The tile is stretched if the rectangles have different sizes.
+fn draw_tile(
+    target: &mut RgbaImage,
+    sheet: &RgbaImage,
+    source: Rect,
+    destination: Rect,
+    transform: TileTransform,
+    appearance: Appearance,
+) {
+    let size = destination.size;
+    let origin = destination.upper_left;
+    // size of the destination before the diagonal flip, i.e. in the orientation of the source
+    let unflipped = match transform.flip_diagonal {
+        true => ivec2::new(size.y, size.x),
+        false => size,
+    };
+    // only visit the pixels inside of the image, tiles can be much bigger than the view
+    let image_size = uvec2::from(target.dimensions())
+        .try_into_ivec2()
+        .unwrap_or(ivec2::new(i32::MAX, i32::MAX));
+    let visible = destination.clamped_to(Rect::new(ivec2::new(0, 0), image_size));
+    for pos in visible.iter_points() {
+        let Some(target_pos) = pixel_in(target, pos) else {
+            continue;
+        };
+        // undo the flips in reverse order: Tiled flips diagonally first
+        let ivec2 { x: mut u, y: mut v } = pos - origin;
+        if transform.flip_vertical {
+            v = size.y - 1 - v;
+        }
+        if transform.flip_horizontal {
+            u = size.x - 1 - u;
+        }
+        if transform.flip_diagonal {
+            std::mem::swap(&mut u, &mut v);
+        }
+        let source_pos = source.upper_left + ivec2::new(u, v) * source.size / unflipped;
+        let Some(source_pos) = pixel_in(sheet, source_pos) else {
+            continue;
+        };
+        let pixel = target.get_pixel_mut(target_pos.x, target_pos.y);
+        let color = appearance.apply(*sheet.get_pixel(source_pos.x, source_pos.y));
+        *pixel = blend(*pixel, color);
+    }
+}
+
+/// The part of the line from `a` to `b` inside of the rectangle from `bounds.0` to `bounds.1`,
+/// None if the line misses the rectangle.
+fn clip_line(a: fvec2, b: fvec2, bounds: (fvec2, fvec2)) -> Option<(fvec2, fvec2)> {
+    let (min, max) = bounds;
+    let delta = b - a;
+    // Liang-Barsky: narrow down the part of the line (0 = a, 1 = b) edge by edge
+    let (mut start, mut end) = (0f32, 1f32);
+    for (direction, distance) in [
+        (-delta.x, a.x - min.x),
+        (delta.x, max.x - a.x),
+        (-delta.y, a.y - min.y),
+        (delta.y, max.y - a.y),
+    ] {
+        if direction == 0. {
+            if distance < 0. {
+                return None;
+            }
+        } else if direction < 0. {
+            start = start.max(distance / direction);
+        } else {
+            end = end.min(distance / direction);
+        }
+    }
+    let point = |t: f32| if t == 1. { b } else { a + delta * t };
+    (start <= end).then(|| (point(start), point(end)))
+}
+
+/// The position as coordinates of the image, None if it is outside of the image.
+fn pixel_in(image: &RgbaImage, pos: ivec2) -> Option<uvec2> {
+    let size = uvec2::from(image.dimensions());
+    pos.try_into_uvec2()
+        .filter(|pos| pos.x < size.x && pos.y < size.y)
+}
+
+/// Draw `source` over `target`.
+fn blend(target: Rgba<u8>, source: Rgba<u8>) -> Rgba<u8> {
+    let alpha = source.0[3] as f32 / 255.;
+    let target_alpha = target.0[3] as f32 / 255. * (1. - alpha);
+    let out_alpha = alpha + target_alpha;
+    if out_alpha <= 0. {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |i: usize| {
+        ((source.0[i] as f32 * alpha + target.0[i] as f32 * target_alpha) / out_alpha).round() as u8
+    };
+    Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (out_alpha * 255.).round() as u8,
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blend() {
+        let red = Rgba([255, 0, 0, 255]);
+        let half_blue = Rgba([0, 0, 255, 128]);
+        assert_eq!(blend(Rgba([0, 0, 0, 0]), half_blue), half_blue);
+        assert_eq!(blend(red, half_blue), Rgba([127, 0, 128, 255]));
+        assert_eq!(blend(half_blue, red), red);
+    }
+
+    #[test]
+    fn test_clip_huge_tiles() {
+        let red = Rgba([255, 0, 0, 255]);
+        let sheet = RgbaImage::from_pixel(2, 2, red);
+        let mut target = RgbaImage::new(4, 4);
+        // stretched over far more pixels than could be visited in a test
+        let destination = Rect::new(ivec2::new(-1_000_000, 2), ivec2::new(2_000_000, 1_000_000));
+        draw_tile(
+            &mut target,
+            &sheet,
+            Rect::new(ivec2::new(0, 0), ivec2::new(2, 2)),
+            destination,
+            TileTransform::default(),
+            Appearance::default(),
+        );
+        assert_eq!(*target.get_pixel(0, 1), Rgba([0, 0, 0, 0]));
+        assert!((0..4).all(|x| *target.get_pixel(x, 2) == red && *target.get_pixel(x, 3) == red));
+    }
+
+    #[test]
+    fn test_clip_line() {
+        let bounds = (fvec2::new(0., 0.), fvec2::new(10., 10.));
+        let inside = (fvec2::new(1., 2.), fvec2::new(9., 3.));
+        assert_eq!(clip_line(inside.0, inside.1, bounds), Some(inside));
+        assert_eq!(
+            clip_line(fvec2::new(-10., 5.), fvec2::new(1e9, 5.), bounds),
+            Some((fvec2::new(0., 5.), fvec2::new(10., 5.)))
+        );
+        assert_eq!(
+            clip_line(fvec2::new(-1., -1.), fvec2::new(-1., 1e9), bounds),
+            None
+        );
+        assert_eq!(
+            clip_line(fvec2::new(-5., 0.), fvec2::new(0., -5.), bounds),
+            None
+        );
+    }
+
+    #[test]
+    fn test_appearance() {
+        let mut group = crate::GroupLayer::new("group");
+        group.opacity = 0.5;
+        group.tintcolor = crate::Color::from_argb(255, 255, 0, 128);
+        let mut tiles = crate::TileLayer::new("tiles", crate::math::ivec2::new(1, 1)).unwrap();
+        tiles.opacity = 0.5;
+        tiles.tintcolor = crate::Color::from_argb(255, 255, 255, 128);
+
+        let appearance = Appearance::default()
+            .inside(&Layer::Group(group))
+            .inside(&Layer::Tile(tiles));
+        assert_eq!(appearance.opacity, 0.25);
+        assert_eq!(
+            appearance.apply(Rgba([200, 200, 200, 200])),
+            Rgba([200, 0, 50, 50])
+        );
+    }
+}
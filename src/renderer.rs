@@ -0,0 +1,645 @@
+//! Drawing maps with any graphics backend, see [TileRenderer] and [Map::render_with].
+
+use std::any::Any;
+use std::time::Duration;
+
+use crate::math::{fvec2, ivec2, Rect};
+use crate::{
+    Error, Layer, LayerId, Map, Object, ObjectKind, ObjectLayer, Orientation, Renderorder, Result,
+};
+use crate::{StaggerAxis, StaggerIndex, TileLayer, GID};
+
+/// How a tile has to be mirrored when it is drawn, see [TileRenderer::draw_tile].
+///
+/// Tiled applies the diagonal flip first, then the horizontal and vertical ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TileTransform {
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+
+    /// Swap the x and y axis of the tile, i.e. mirror it at the diagonal
+    /// from the upper left to the lower right corner.
+    pub flip_diagonal: bool,
+}
+
+impl TileTransform {
+    /// Express the transform as mirroring followed by a clockwise rotation by 0 or 90 degrees,
+    /// for backends that draw rotated sprites, like `draw_texture_ex` of macroquad or
+    /// `copy_ex` of SDL2. Returns the horizontal and vertical flip and the rotation in degrees.
+    ///
+    /// The sprite has to be drawn with its unrotated size (the size of the `target` of
+    /// [TileRenderer::draw_tile] with swapped axes for diagonal flips) around the center
+    /// of the target.
+    ///
+    /// ```
+    /// # use tego::TileTransform;
+    /// let transform = TileTransform { flip_diagonal: true, ..Default::default() };
+    /// assert_eq!(transform.to_flip_and_rotation(), (false, true, 90.));
+    /// ```
+    pub fn to_flip_and_rotation(self) -> (bool, bool, f32) {
+        if self.flip_diagonal {
+            // mirroring at the diagonal is a vertical flip followed by a clockwise rotation,
+            // rotating first swaps the horizontal and vertical flips
+            (self.flip_vertical, !self.flip_horizontal, 90.)
+        } else {
+            (self.flip_horizontal, self.flip_vertical, 0.)
+        }
+    }
+}
+
+impl From<GID> for TileTransform {
+    fn from(gid: GID) -> Self {
+        Self {
+            flip_horizontal: gid.flip_horizontal(),
+            flip_vertical: gid.flip_vertical(),
+            flip_diagonal: gid.flip_diagonal(),
+        }
+    }
+}
+
+/// A graphics backend that can draw maps, driven by [Map::render_with].
+///
+/// tego takes care of walking the layers and tiles in the right order and of placing
+/// the tiles, the renderer only has to draw sprites.
+///
+/// ```
+/// # use std::{any::Any, path::Path};
+/// use tego::math::Rect;
+/// use tego::{TileRenderer, TileTransform};
+///
+/// #[derive(Default)]
+/// struct CountingRenderer(usize);
+///
+/// impl TileRenderer for CountingRenderer {
+///     fn draw_tile(&mut self, _image: &dyn Any, _source: Rect, _target: Rect, _transform: TileTransform) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let map = tego::Map::from_file(Path::new("example-maps/default/mirrored_tiles.tmx"))?;
+/// let mut renderer = CountingRenderer::default();
+/// map.render_with(&mut renderer)?;
+/// assert_eq!(renderer.0, 16);
+/// # Ok::<(),tego::Error>(())
+/// ```
+pub trait TileRenderer {
+    /// Called for every visible layer, including groups, before its content is drawn.
+    fn begin_layer(&mut self, _layer: &Layer) {}
+
+    /// Called after the content of a layer was drawn, for groups after all layers inside of it.
+    fn end_layer(&mut self, _layer: &Layer) {}
+
+    /// Draw the `source` rectangle of a sprite sheet, mirrored by `transform`,
+    /// into the `target` rectangle (in pixels, relative to the upper left corner of the map).
+    ///
+    /// The sprite sheet is the image returned by the [ImageLoader](crate::ImageLoader).
+    /// The size of `target` already accounts for diagonal flips.
+    fn draw_tile(&mut self, image: &dyn Any, source: Rect, target: Rect, transform: TileTransform);
+
+    /// Draw an object of a visible object layer. `offset` is the sum of the offsets of the layer
+    /// and the groups it is in. Does nothing by default.
+    fn draw_object(&mut self, _layer: &ObjectLayer, _object: &Object, _offset: ivec2) {}
+}
+
+impl Map {
+    /// Size of the whole map in pixels, taking the [orientation](Map::orientation) into account.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/groups.tmx"))?;
+    /// assert_eq!(map.pixel_size(), map.size * map.tile_size);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn pixel_size(&self) -> ivec2 {
+        match self.orientation {
+            Orientation::Isometric => {
+                let diagonal = self.size.x + self.size.y;
+                ivec2::new(
+                    diagonal * self.tile_size.x / 2,
+                    diagonal * self.tile_size.y / 2,
+                )
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let g = self.stagger_geometry();
+                match self.stagger_axis {
+                    StaggerAxis::X => ivec2::new(
+                        self.size.x * g.column_width + g.side_offset.x,
+                        self.size.y * (g.tile.y + g.side_length.y)
+                            + if self.size.x > 1 { g.row_height } else { 0 },
+                    ),
+                    StaggerAxis::Y => ivec2::new(
+                        self.size.x * (g.tile.x + g.side_length.x)
+                            + if self.size.y > 1 { g.column_width } else { 0 },
+                        self.size.y * g.row_height + g.side_offset.y,
+                    ),
+                }
+            }
+            _ => self.size * self.tile_size,
+        }
+    }
+
+    /// The upper left corner of the bounding box of a cell in pixels,
+    /// taking the [orientation](Map::orientation) into account.
+    ///
+    /// For isometric and staggered maps this is the bounding box of the diamond of the cell,
+    /// for hexagonal maps the bounding box of the hexagon.
+    /// Tiles are aligned to the lower left corner of the bounding box, like Tiled does.
+    ///
+    /// ```
+    /// # use tego::math::ivec2;
+    /// # use tego::{EmbeddedProvider, LazyLoader, Map, ResourceManager};
+    /// let map = Map::from_xml_str(r#"
+    ///     <map version="1.10" orientation="hexagonal" width="4" height="4" tilewidth="32"
+    ///          tileheight="32" hexsidelength="16" staggeraxis="y" staggerindex="odd">
+    ///     </map>"#,
+    ///     &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    /// )?;
+    /// assert_eq!(map.cell_to_pixel(ivec2::new(1, 0)), ivec2::new(32, 0));
+    /// // odd rows are shifted half a hexagon to the right
+    /// assert_eq!(map.cell_to_pixel(ivec2::new(1, 1)), ivec2::new(48, 24));
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn cell_to_pixel(&self, pos: ivec2) -> ivec2 {
+        let tile = self.tile_size;
+        match self.orientation {
+            Orientation::Isometric => ivec2::new(
+                (pos.x - pos.y + self.size.y - 1) * tile.x / 2,
+                (pos.x + pos.y) * tile.y / 2,
+            ),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let g = self.stagger_geometry();
+                match self.stagger_axis {
+                    StaggerAxis::X => ivec2::new(
+                        pos.x * g.column_width,
+                        pos.y * (g.tile.y + g.side_length.y)
+                            + if self.is_staggered(pos.x) {
+                                g.row_height
+                            } else {
+                                0
+                            },
+                    ),
+                    StaggerAxis::Y => ivec2::new(
+                        pos.x * (g.tile.x + g.side_length.x)
+                            + if self.is_staggered(pos.y) {
+                                g.column_width
+                            } else {
+                                0
+                            },
+                        pos.y * g.row_height,
+                    ),
+                }
+            }
+            _ => pos * tile,
+        }
+    }
+
+    /// The cells that share an edge with the given cell, taking the
+    /// [orientation](Map::orientation) into account.
+    ///
+    /// Orthogonal and isometric cells have four neighbors, the diamonds of staggered maps
+    /// touch four cells in the adjacent rows (or columns) and hexagons have six neighbors.
+    /// The neighbors may lie outside of the map.
+    ///
+    /// ```
+    /// # use tego::math::ivec2;
+    /// # use tego::{EmbeddedProvider, LazyLoader, Map, ResourceManager};
+    /// let map = Map::from_xml_str(r#"
+    ///     <map version="1.10" orientation="hexagonal" width="4" height="4" tilewidth="32"
+    ///          tileheight="32" hexsidelength="16" staggeraxis="y" staggerindex="odd">
+    ///     </map>"#,
+    ///     &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    /// )?;
+    /// let neighbors = map.neighbors(ivec2::new(1, 1));
+    /// assert_eq!(neighbors.len(), 6);
+    /// assert!(neighbors.contains(&ivec2::new(2, 0)));
+    /// assert!(!neighbors.contains(&ivec2::new(0, 0)));
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn neighbors(&self, pos: ivec2) -> Vec<ivec2> {
+        let hexagonal = match self.orientation {
+            Orientation::Staggered => false,
+            Orientation::Hexagonal => true,
+            _ => {
+                return vec![
+                    ivec2::new(pos.x, pos.y - 1),
+                    ivec2::new(pos.x + 1, pos.y),
+                    ivec2::new(pos.x, pos.y + 1),
+                    ivec2::new(pos.x - 1, pos.y),
+                ]
+            }
+        };
+
+        // `along` runs along the rows (or columns for StaggerAxis::X),
+        // `across` is the index of the row that may be shifted
+        let (along, across, to_pos): (_, _, fn(i32, i32) -> ivec2) = match self.stagger_axis {
+            StaggerAxis::X => (pos.y, pos.x, |along, across| ivec2::new(across, along)),
+            StaggerAxis::Y => (pos.x, pos.y, ivec2::new),
+        };
+        // a shifted cell touches the cell with the same index in the adjacent rows and the
+        // next one, an unshifted cell the previous one
+        let first = if self.is_staggered(across) {
+            along
+        } else {
+            along - 1
+        };
+        let mut neighbors = vec![
+            to_pos(first, across - 1),
+            to_pos(first + 1, across - 1),
+            to_pos(first + 1, across + 1),
+            to_pos(first, across + 1),
+        ];
+        if hexagonal {
+            neighbors.insert(2, to_pos(along + 1, across));
+            neighbors.push(to_pos(along - 1, across));
+        }
+        neighbors
+    }
+
+    /// Whether the row (or column for [StaggerAxis::X]) with the given index is shifted
+    /// on staggered and hexagonal maps.
+    fn is_staggered(&self, index: i32) -> bool {
+        (index & 1 == 1) == (self.stagger_index == StaggerIndex::Odd)
+    }
+
+    /// Measurements of the cells of staggered and hexagonal maps,
+    /// staggered maps are treated as hexagonal maps with a side length of zero.
+    fn stagger_geometry(&self) -> StaggerGeometry {
+        let tile = ivec2::new(self.tile_size.x & !1, self.tile_size.y & !1);
+        let side = match self.orientation {
+            Orientation::Hexagonal => self.hexsidelength,
+            _ => 0,
+        };
+        let side_length = match self.stagger_axis {
+            StaggerAxis::X => ivec2::new(side, 0),
+            StaggerAxis::Y => ivec2::new(0, side),
+        };
+        let side_offset = ivec2::new((tile.x - side_length.x) / 2, (tile.y - side_length.y) / 2);
+        StaggerGeometry {
+            tile,
+            side_length,
+            side_offset,
+            column_width: side_offset.x + side_length.x,
+            row_height: side_offset.y + side_length.y,
+        }
+    }
+
+    /// Draw this map with the given renderer.
+    ///
+    /// The visible layers are drawn from bottom to top. The tiles of each layer are drawn
+    /// in the [render order](Map::renderorder) of the map, for the other orientations from
+    /// back to front. Image layers are skipped, since tego doesn't load their images yet.
+    ///
+    /// Fails with [Error::RenderError] if a tile of a visible layer can't be drawn, because
+    /// no tileset contains it or the sprite sheet of its tileset is still
+    /// [Pending](crate::ImageStorage::Pending).
+    ///
+    /// Animated tiles are drawn as they are, use [Map::render_animated_with] to draw
+    /// the current frame of their animation instead.
+    pub fn render_with(&self, renderer: &mut impl TileRenderer) -> Result<()> {
+        self.render_with_options(renderer, &RenderOptions::default())
+    }
+
+    /// Like [Map::render_with], but animated tiles are replaced by the frame their animation
+    /// shows after `elapsed` time, see [Map::animation_frame].
+    pub fn render_animated_with(
+        &self,
+        renderer: &mut impl TileRenderer,
+        elapsed: Duration,
+    ) -> Result<()> {
+        let options = RenderOptions {
+            elapsed: Some(elapsed),
+            ..RenderOptions::default()
+        };
+        self.render_with_options(renderer, &options)
+    }
+
+    /// Like [Map::render_with], with the [RenderOptions] to only draw a part of the map
+    /// or to animate tiles.
+    ///
+    /// ```
+    /// # use std::{any::Any, path::Path};
+    /// use tego::math::{ivec2, Rect};
+    /// use tego::{RenderOptions, TileRenderer, TileTransform};
+    ///
+    /// #[derive(Default)]
+    /// struct CountingRenderer(usize);
+    ///
+    /// impl TileRenderer for CountingRenderer {
+    ///     fn draw_tile(&mut self, _image: &dyn Any, _source: Rect, _target: Rect, _transform: TileTransform) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/mirrored_tiles.tmx"))?;
+    /// let mut renderer = CountingRenderer::default();
+    /// let camera = Rect::new(ivec2::new(8, 8), ivec2::new(16, 16));
+    /// map.render_with_options(&mut renderer, &RenderOptions { region: Some(camera), ..Default::default() })?;
+    /// assert_eq!(renderer.0, 4);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn render_with_options(
+        &self,
+        renderer: &mut impl TileRenderer,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        for &id in self.layer_ids() {
+            self.render_layer(id, ivec2::new(0, 0), options, renderer)?;
+        }
+        Ok(())
+    }
+
+    /// Draw only the tiles of a single tile layer, e.g. to draw the entities of a game
+    /// between the layers. The layer is drawn even if it is not visible and
+    /// [begin_layer](TileRenderer::begin_layer) and [end_layer](TileRenderer::end_layer)
+    /// are not called. The offsets of the groups the layer is in are not applied.
+    ///
+    /// Fails like [Map::render_with].
+    ///
+    /// ```
+    /// # use std::{any::Any, path::Path};
+    /// # use tego::math::Rect;
+    /// # use tego::{TileRenderer, TileTransform};
+    /// # #[derive(Default)]
+    /// # struct CountingRenderer(usize);
+    /// # impl TileRenderer for CountingRenderer {
+    /// #     fn draw_tile(&mut self, _image: &dyn Any, _source: Rect, _target: Rect, _transform: TileTransform) {
+    /// #         self.0 += 1;
+    /// #     }
+    /// # }
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/groups.tmx"))?;
+    /// let layer = map.iter_layers().find_map(|(layer, _)| match layer {
+    ///     tego::Layer::Tile(layer) => Some(layer),
+    ///     _ => None,
+    /// }).unwrap();
+    /// let mut renderer = CountingRenderer::default();
+    /// map.render_tile_layer_with(layer, &mut renderer, &Default::default())?;
+    /// assert_eq!(renderer.0, layer.occupied_tiles().count());
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn render_tile_layer_with(
+        &self,
+        layer: &TileLayer,
+        renderer: &mut impl TileRenderer,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        self.render_tiles(layer, ivec2::new(0, 0), options, renderer)
+    }
+
+    /// The GID of the tile that the animation of a tile shows after `elapsed` time,
+    /// with the flip flags of the given GID. All animations start at the same time, like in Tiled.
+    ///
+    /// Returns the GID unchanged if the tile is not animated.
+    ///
+    /// ```
+    /// # use std::{path::Path, time::Duration};
+    /// let map = tego::Map::from_file(Path::new("example-maps/island/island.tmx"))?;
+    /// let water = map.tilesets[0].tile(37).unwrap().gid();
+    /// assert_eq!(map.animation_frame(water, Duration::ZERO), water);
+    /// assert_eq!(map.animation_frame(water, Duration::from_millis(1500)).to_id(), 47);
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn animation_frame(&self, gid: GID, elapsed: Duration) -> GID {
+        match self.tile(gid) {
+            Some(tile) => gid.with_id(tile.frame_at(elapsed).gid().to_id()),
+            None => gid,
+        }
+    }
+
+    fn render_layer(
+        &self,
+        id: LayerId,
+        offset: ivec2,
+        options: &RenderOptions,
+        renderer: &mut impl TileRenderer,
+    ) -> Result<()> {
+        let layer = &self[id];
+        let visible = match layer {
+            Layer::Tile(layer) => layer.visible,
+            Layer::Object(layer) => layer.visible,
+            Layer::Image(layer) => layer.visible,
+            Layer::Group(layer) => layer.visible,
+        };
+        if !visible {
+            return Ok(());
+        }
+
+        renderer.begin_layer(layer);
+        match layer {
+            Layer::Group(group) => {
+                for &child in &group.content {
+                    self.render_layer(child, offset + group.offset, options, renderer)?;
+                }
+            }
+            Layer::Tile(tiles) => self.render_tiles(tiles, offset, options, renderer)?,
+            Layer::Object(objects) => {
+                let offset = offset + objects.offset;
+                for object in objects.content.iter().filter(|o| o.visible) {
+                    let visible = match options.region {
+                        Some(region) => intersects(region, object_bounds(object, offset)),
+                        None => true,
+                    };
+                    if visible {
+                        renderer.draw_object(objects, object, offset);
+                    }
+                }
+            }
+            Layer::Image(_) => {}
+        }
+        renderer.end_layer(layer);
+        Ok(())
+    }
+
+    fn render_tiles(
+        &self,
+        tiles: &TileLayer,
+        offset: ivec2,
+        options: &RenderOptions,
+        renderer: &mut impl TileRenderer,
+    ) -> Result<()> {
+        let cells = match options.region {
+            Some(region) => self.cells_around(Rect::new(region.upper_left - offset, region.size)),
+            None => Rect::new(ivec2::new(0, 0), tiles.size),
+        };
+        for (pos, gid) in self.cells_in_renderorder_in(tiles, cells) {
+            let gid = match options.elapsed {
+                Some(elapsed) => self.animation_frame(gid, elapsed),
+                None => gid,
+            };
+            self.render_tile(pos, gid, offset, options.region, renderer)?;
+        }
+        Ok(())
+    }
+
+    fn render_tile(
+        &self,
+        pos: ivec2,
+        gid: GID,
+        offset: ivec2,
+        region: Option<Rect>,
+        renderer: &mut impl TileRenderer,
+    ) -> Result<()> {
+        let Some((image, source)) = self.tile_image(gid) else {
+            return Err(self.missing_tile_image(gid));
+        };
+        let transform = TileTransform::from(gid);
+        let mut size = source.size;
+        if transform.flip_diagonal {
+            std::mem::swap(&mut size.x, &mut size.y);
+        }
+        // tiles are aligned to the lower left corner of their cell
+        let cell = self.cell_to_pixel(pos);
+        let upper_left = offset + ivec2::new(cell.x, cell.y + self.tile_size.y - size.y);
+        let target = Rect::new(upper_left, size);
+        if region.is_none_or(|region| intersects(region, target)) {
+            renderer.draw_tile(image, source, target, transform);
+        }
+        Ok(())
+    }
+
+    /// The error for a tile whose image can't be found by [Map::tile_image].
+    pub(crate) fn missing_tile_image(&self, gid: GID) -> Error {
+        Error::RenderError(match self.tile(gid) {
+            Some(tile) => format!(
+                "The image of tileset '{}' is not loaded, see ResourceManager::preload",
+                tile.tileset().name
+            ),
+            None => format!("No tileset contains tile {}", gid),
+        })
+    }
+
+    /// The cells whose tiles may overlap the given rectangle (in pixels, relative to the
+    /// layer), taking into account that tiles may be larger than the cells.
+    fn cells_around(&self, region: Rect) -> Rect {
+        if self.orientation != Orientation::Orthogonal
+            || self.tile_size.x <= 0
+            || self.tile_size.y <= 0
+        {
+            return Rect::new(ivec2::new(0, 0), self.size);
+        }
+        // tiles are aligned to the lower left corner, so they stick out to the right and up
+        let overhang = self.tilesets.iter().fold(ivec2::new(0, 0), |max, tileset| {
+            let size = tileset.tile_size.x.max(tileset.tile_size.y);
+            ivec2::new(max.x.max(size), max.y.max(size))
+        });
+        let tile = self.tile_size;
+        let first = ivec2::new(
+            (region.upper_left.x - overhang.x).div_euclid(tile.x),
+            region.upper_left.y.div_euclid(tile.y),
+        );
+        let end = region.upper_left + region.size + ivec2::new(0, overhang.y);
+        let last = ivec2::new(end.x.div_euclid(tile.x), end.y.div_euclid(tile.y));
+        Rect::from_min_max(first, last + ivec2::new(1, 1))
+    }
+
+    /// The non-empty cells of the layer, in the order in which they are drawn.
+    pub(crate) fn cells_in_renderorder<'a>(
+        &self,
+        layer: &'a TileLayer,
+    ) -> impl Iterator<Item = (ivec2, GID)> + 'a {
+        self.cells_in_renderorder_in(layer, Rect::new(ivec2::new(0, 0), layer.size))
+    }
+
+    /// Like [Map::cells_in_renderorder], limited to a rectangle of cells.
+    fn cells_in_renderorder_in<'a>(
+        &self,
+        layer: &'a TileLayer,
+        rect: Rect,
+    ) -> impl Iterator<Item = (ivec2, GID)> + 'a {
+        let mut cells: Vec<_> = layer.occupied_tiles_in(rect).collect();
+        let (right, down) = match self.renderorder {
+            Renderorder::RightDown => (true, true),
+            Renderorder::RightUp => (true, false),
+            Renderorder::LeftDown => (false, true),
+            Renderorder::LeftUp => (false, false),
+        };
+        match self.orientation {
+            // cells further in the back have a smaller sum of their coordinates
+            Orientation::Isometric => cells.sort_by_key(|(pos, _)| (pos.x + pos.y, pos.x)),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                cells.sort_by_key(|(pos, _)| (self.cell_to_pixel(*pos).y, pos.x))
+            }
+            Orientation::Orthogonal => cells.sort_by_key(|(pos, _)| {
+                (
+                    if down { pos.y } else { -pos.y },
+                    if right { pos.x } else { -pos.x },
+                )
+            }),
+        }
+        cells.into_iter()
+    }
+}
+
+/// Options for [Map::render_with_options].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    /// Replace animated tiles by the frame their animation shows after this time,
+    /// see [Map::animation_frame]. Without it, animated tiles are drawn as they are.
+    pub elapsed: Option<Duration>,
+
+    /// Only draw the tiles and objects that overlap this rectangle (in pixels, relative to
+    /// the upper left corner of the map), e.g. the part of the map that a camera sees.
+    ///
+    /// On orthogonal maps, only the tiles around the rectangle are visited,
+    /// so that huge maps can be drawn quickly.
+    pub region: Option<Rect>,
+}
+
+/// Whether the rectangles overlap.
+fn intersects(a: Rect, b: Rect) -> bool {
+    let overlap = a.clamped_to(b).size;
+    overlap.x > 0 && overlap.y > 0
+}
+
+/// A rectangle that contains the object (in pixels), including its rotation.
+fn object_bounds(object: &Object, offset: ivec2) -> Rect {
+    let size = object.size;
+    let corners: Vec<fvec2> = match &object.kind {
+        ObjectKind::Polygon { points } | ObjectKind::Polyline { points } => points.to_vec(),
+        // tile objects are aligned to their lower left corner
+        _ if object.tile_id.is_some() => vec![fvec2::new(0., -size.y), fvec2::new(size.x, 0.)],
+        _ => vec![fvec2::default(), size],
+    };
+    let (min, max) = bounds(&corners);
+    let corners = [min, fvec2::new(max.x, min.y), max, fvec2::new(min.x, max.y)];
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    let rotated: Vec<_> = corners
+        .iter()
+        .map(|p| object.pos + fvec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos))
+        .collect();
+    let (min, max) = bounds(&rotated);
+    let upper_left = offset + ivec2::new(min.x.floor() as i32, min.y.floor() as i32);
+    let lower_right = offset + ivec2::new(max.x.ceil() as i32, max.y.ceil() as i32);
+    // points and lines have no area, but should still be drawn
+    Rect::from_min_max(upper_left, lower_right + ivec2::new(1, 1))
+}
+
+/// The smallest and largest coordinates of the points.
+fn bounds(points: &[fvec2]) -> (fvec2, fvec2) {
+    points.iter().fold(
+        (
+            fvec2::new(f32::MAX, f32::MAX),
+            fvec2::new(f32::MIN, f32::MIN),
+        ),
+        |(min, max), p| {
+            (
+                fvec2::new(min.x.min(p.x), min.y.min(p.y)),
+                fvec2::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        },
+    )
+}
+
+/// See [Map::stagger_geometry], the names follow Tiled's hexagonal renderer.
+struct StaggerGeometry {
+    /// The tile size rounded down to even numbers.
+    tile: ivec2,
+
+    /// Length of the straight edges of the hexagons along the stagger axis.
+    side_length: ivec2,
+
+    /// Distance from the edge of the bounding box to the start of the straight edge.
+    side_offset: ivec2,
+
+    column_width: i32,
+    row_height: i32,
+}
@@ -1,13 +1,192 @@
-use std::{any::Any, collections::HashMap, io::Read, path::Path, rc::Rc};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
 
-use crate::{Error, Object, Result};
+use crate::errors::UnsupportedVersion;
+use crate::xml::{Document, Node, XmlNode};
+use crate::{
+    math, name::Interner, Color, Error, ImageStorage, Layer, Map, Name, Object, Result, TileSet,
+    GID,
+};
 
+/// The ResourceManager is responsible for loading external resources referenced by a map,
+/// e.g. images, external tilesets or object templates.
+///
+/// Cloning a ResourceManager is cheap.
+/// All clones share the same [ImageLoader], [Provider] and caches,
+/// so maps loaded through any of them reuse the already loaded resources.
+/// Only the base path is independent for each clone.
+#[derive(Clone)]
 pub struct ResourceManager {
-    base_path: String,
-    image_loader: Box<dyn ImageLoader>,
-    file_provider: Box<dyn Provider>,
-    image_cache: HashMap<String, Rc<dyn Any>>,
-    template_cache: HashMap<String, Object>,
+    base_path: PathBuf,
+    limits: LoadLimits,
+
+    /// Tile data that was decoded ahead of time, by the id of the `<data>` node.
+    decoded_tile_data: HashMap<u32, Vec<Option<GID>>>,
+    load_options: MapLoadOptions,
+
+    /// Chain of documents that are currently being loaded, starting with the map.
+    documents: Vec<PathBuf>,
+
+    shared: Rc<Shared>,
+}
+
+/// State that is shared between all clones of a [ResourceManager]
+struct Shared {
+    image_loader: RefCell<Box<dyn ImageLoader>>,
+    file_provider: RefCell<ProviderStack>,
+    image_cache: RefCell<HashMap<PathBuf, Rc<dyn Any>>>,
+    template_cache: RefCell<HashMap<PathBuf, Rc<Object>>>,
+    tileset_cache: RefCell<HashMap<PathBuf, TileSet>>,
+
+    /// Modification times of the cached resources, used to detect changes.
+    timestamps: RefCell<HashMap<PathBuf, SystemTime>>,
+
+    progress_callback: RefCell<Option<Box<ProgressCallback>>>,
+    cancel_flag: RefCell<Option<Arc<AtomicBool>>>,
+    version_warning_callback: RefCell<Option<Box<VersionWarningCallback>>>,
+
+    /// Pool of all names in the loaded maps, see [Name].
+    names: RefCell<Interner>,
+}
+
+type ProgressCallback = dyn FnMut(LoadStage, usize, usize);
+type VersionWarningCallback = dyn FnMut(&UnsupportedVersion);
+
+/// Limits that are enforced while loading a map,
+/// to protect against malicious maps, e.g. on servers that accept maps uploaded by users.
+/// Exceeding a limit fails loading with [Error::LimitExceeded].
+///
+/// By default, nothing is limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadLimits {
+    /// Maximum width and height of the map and its layers in tiles.
+    pub max_map_size: math::ivec2,
+
+    /// Maximum number of bytes the tile data of a single layer may decode to,
+    /// after decompression.
+    pub max_layer_bytes: usize,
+
+    /// Maximum number of tilesets in a map.
+    pub max_tilesets: usize,
+}
+
+impl Default for LoadLimits {
+    fn default() -> Self {
+        Self {
+            max_map_size: math::ivec2::new(i32::MAX, i32::MAX),
+            max_layer_bytes: usize::MAX,
+            max_tilesets: usize::MAX,
+        }
+    }
+}
+
+impl LoadLimits {
+    /// Check the size of the map or of a layer against [LoadLimits::max_map_size].
+    pub(crate) fn check_size(&self, what: &str, size: math::ivec2) -> Result<()> {
+        if size.x > self.max_map_size.x || size.y > self.max_map_size.y {
+            return Err(Error::LimitExceeded(format!(
+                "{} size {}x{} is bigger than {}x{}",
+                what, size.x, size.y, self.max_map_size.x, self.max_map_size.y
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Options to load only parts of a map, e.g. on headless servers that don't need any images.
+///
+/// By default, the whole map is loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapLoadOptions {
+    /// Don't load the images of the tilesets, leaving them [Pending](ImageStorage::Pending).
+    /// They can be loaded later with [ResourceManager::preload].
+    pub skip_images: bool,
+
+    /// Don't load any object layers, including object layers inside of group layers.
+    pub skip_object_layers: bool,
+
+    /// Only load the top level layers with the given names.
+    /// Group layers are loaded including all of their children.
+    /// Loads all layers if None.
+    pub layer_names: Option<Vec<String>>,
+
+    /// Stop after the header of the map, i.e. only load the attributes and properties
+    /// of the map without any tilesets or layers.
+    pub header_only: bool,
+
+    /// Store the tiles of tile layers with 16 or 8 bits per tile instead of 32 bits,
+    /// if all GIDs of the layer (including the flip flags) fit.
+    /// Layers that use flipped tiles are always stored with 32 bits.
+    /// Reduces the memory of big maps, at a small cost when accessing tiles.
+    pub compact_tiles: bool,
+
+    /// What to do with maps that are newer than [SUPPORTED_VERSION](crate::SUPPORTED_VERSION).
+    pub version_policy: VersionPolicy,
+}
+
+/// How to handle maps that were saved with a newer version of Tiled than tego supports,
+/// see [MapLoadOptions::version_policy].
+///
+/// Newer versions of Tiled can add features that tego doesn't know about. tego keeps them
+/// when saving the map, but otherwise ignores them, which might change the meaning of a map.
+/// A map is considered newer if the major and minor number of its format version or of the
+/// Tiled version that saved it are above [SUPPORTED_VERSION](crate::SUPPORTED_VERSION).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Load newer maps like all others.
+    #[default]
+    Ignore,
+
+    /// Load newer maps, but report them to the callback set with
+    /// [ResourceManager::set_version_warning_callback].
+    Warn,
+
+    /// Fail loading newer maps with [Error::UnsupportedVersion].
+    Error,
+}
+
+impl MapLoadOptions {
+    /// Check whether the given top level node is a layer that should be loaded.
+    pub(crate) fn includes_layer(&self, node: &Node) -> bool {
+        if !Layer::is_layer_node(node) {
+            return false;
+        }
+        if self.skip_object_layers && node.tag() == "objectgroup" {
+            return false;
+        }
+        match &self.layer_names {
+            Some(names) => names
+                .iter()
+                .any(|name| node.attribute("name").unwrap_or_default() == name),
+            None => true,
+        }
+    }
+}
+
+/// The parts of loading a map, as reported to the progress callback of a [ResourceManager].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    /// Parsing the tilesets of the map, including external tilesets.
+    TileSets,
+
+    /// Loading the images of all tilesets, see [ImageLoader::load_batch].
+    Images,
+
+    /// Parsing the layers of the map and decoding their tile data.
+    /// Progress is reported for each top level layer.
+    Layers,
 }
 
 impl ResourceManager {
@@ -19,90 +198,806 @@ impl ResourceManager {
     ) -> Self {
         Self {
             base_path: ".".into(),
-            image_loader: Box::new(image_loader),
-            file_provider: Box::new(file_provider),
-            image_cache: HashMap::new(),
-            template_cache: HashMap::new(),
-        }
-    }
-
-    pub fn load_image(&mut self, path: &str) -> Result<Rc<dyn Any>> {
-        // TODO(texel, 2021-11-10): Use file provider
-        let path = format!("{}/{}", &self.base_path, path);
-        let entry = self.image_cache.entry(path.clone());
-        use std::collections::hash_map::Entry::*;
-        Ok(match entry {
-            Occupied(slot) => slot.get().clone(),
-            Vacant(slot) => {
-                let data = self.image_loader.load(&path)?.into();
-                slot.insert(data).clone()
+            limits: LoadLimits::default(),
+            decoded_tile_data: HashMap::new(),
+            load_options: MapLoadOptions::default(),
+            documents: Vec::new(),
+            shared: Rc::new(Shared {
+                image_loader: RefCell::new(Box::new(image_loader)),
+                file_provider: RefCell::new(ProviderStack::new(file_provider)),
+                image_cache: RefCell::new(HashMap::new()),
+                template_cache: RefCell::new(HashMap::new()),
+                tileset_cache: RefCell::new(HashMap::new()),
+                timestamps: RefCell::new(HashMap::new()),
+                progress_callback: RefCell::new(None),
+                cancel_flag: RefCell::new(None),
+                version_warning_callback: RefCell::new(None),
+                names: RefCell::new(Interner::default()),
+            }),
+        }
+    }
+
+    pub fn load_image(&mut self, relpath: &str) -> Result<Rc<dyn Any>> {
+        self.load_image_with_context(relpath, ImageContext::default())
+    }
+
+    /// Load an image, passing extra information about how the image is used to the
+    /// [ImageLoader].
+    ///
+    /// Images are cached by their path,
+    /// so loading an image again returns the cached data regardless of the context.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "load_image", level = "debug", skip_all, fields(path = relpath)))]
+    pub fn load_image_with_context(
+        &mut self,
+        relpath: &str,
+        context: ImageContext,
+    ) -> Result<Rc<dyn Any>> {
+        let path = self.resolve_path(relpath);
+        if let Some(image) = self.shared.image_cache.borrow().get(&path) {
+            return Ok(image.clone());
+        }
+
+        let tileset = context.tileset;
+        let source = ImageSource {
+            path: &path,
+            context,
+            provider: &self.shared.file_provider,
+        };
+        let data = self.shared.image_loader.borrow_mut().load(source);
+        let data = data.map_err(|e| self.image_error(&path, tileset, e))?;
+        Ok(self.cache_image(path, data))
+    }
+
+    /// Load the images of all tilesets whose image is still [ImageStorage::Pending].
+    ///
+    /// Images that are not cached yet are handed to the [ImageLoader] as a single batch,
+    /// see [ImageLoader::load_batch].
+    pub fn load_tileset_images(&mut self, tilesets: &mut [TileSet]) -> Result<()> {
+        let mut missing: Vec<&TileSet> = Vec::new();
+        for tileset in tilesets.iter() {
+            let pending = matches!(tileset.image, ImageStorage::Pending);
+            let cached = self
+                .shared
+                .image_cache
+                .borrow()
+                .contains_key(&tileset.image_path);
+            if pending && !cached && !missing.iter().any(|t| t.image_path == tileset.image_path) {
+                missing.push(tileset);
             }
-        })
+        }
+
+        if !missing.is_empty() {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("load_images", count = missing.len()).entered();
+            self.report_progress(LoadStage::Images, 0, missing.len())?;
+            let sources = missing
+                .iter()
+                .map(|tileset| ImageSource {
+                    path: &tileset.image_path,
+                    context: tileset.image_context(),
+                    provider: &self.shared.file_provider,
+                })
+                .collect();
+            let results = self.shared.image_loader.borrow_mut().load_batch(sources);
+            if results.len() != missing.len() {
+                return Err(Error::InvalidImageLoader(format!(
+                    "load_batch returned {} results for {} images",
+                    results.len(),
+                    missing.len()
+                )));
+            }
+
+            for (tileset, data) in missing.iter().zip(results) {
+                let data = match data {
+                    Ok(data) => data,
+                    Err(e) => {
+                        // images of external tilesets are referenced by the tileset file
+                        let depth = self.documents.len();
+                        self.documents.extend(tileset.source.clone());
+                        let error =
+                            self.image_error(&tileset.image_path, Some(tileset.name.as_str()), e);
+                        self.documents.truncate(depth);
+                        return Err(error);
+                    }
+                };
+                self.cache_image(tileset.image_path.clone(), data);
+            }
+            self.report_progress(LoadStage::Images, missing.len(), missing.len())?;
+        }
+
+        let cache = self.shared.image_cache.borrow();
+        for tileset in tilesets {
+            if let ImageStorage::Pending = tileset.image {
+                tileset.image = ImageStorage::SpriteSheet(cache[&tileset.image_path].clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Load all images of the map that were not loaded yet, e.g. because the map was loaded
+    /// with [MapLoadOptions::skip_images].
+    ///
+    /// This allows parsing maps first and loading the images afterwards
+    /// during a loading screen, reporting [LoadStage::Images] to the progress callback.
+    pub fn preload(&mut self, map: &mut Map) -> Result<()> {
+        self.load_tileset_images(&mut map.tilesets)
+    }
+
+    fn cache_image(&self, path: PathBuf, data: Box<dyn Any>) -> Rc<dyn Any> {
+        let data: Rc<dyn Any> = data.into();
+        self.record_timestamp(&path);
+        self.shared
+            .image_cache
+            .borrow_mut()
+            .insert(path, data.clone());
+        data
+    }
+
+    fn image_error(&self, path: &Path, tileset: Option<&str>, error: Error) -> Error {
+        let resource = match tileset {
+            Some(tileset) => format!("image '{}' of tileset '{}'", path.display(), tileset),
+            None => format!("image '{}'", path.display()),
+        };
+        self.resource_error(resource, error)
+    }
+
+    /// Load an object template (`*.tx` file).
+    /// Templates are cached, loading the same file again returns the cached data.
+    ///
+    /// The template is returned as an [Object] with an id of 0,
+    /// including the custom properties stored in the template.
+    /// To instantiate the template at runtime, clone the object and adjust it as needed:
+    ///
+    /// ```
+    /// # fn main() -> tego::Result<()> {
+    /// let mut loader = tego::ResourceManager::default();
+    /// loader.set_base_path("example-maps/default");
+    ///
+    /// let template = loader.template("Polygon.tx")?;
+    /// let mut object = (*template).clone();
+    /// object.id = 42;
+    /// object.pos = tego::math::fvec2::new(16., 32.);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn template(&mut self, relpath: &str) -> Result<Rc<Object>> {
+        let path = self.resolve_path(relpath);
+        if let Some(template) = self.shared.template_cache.borrow().get(&path) {
+            return Ok(template.clone());
+        }
+
+        let result: Rc<Object> = self
+            .read_object_template(relpath)
+            .map_err(|e| self.resource_error(format!("template '{}'", path.display()), e))?
+            .into();
+        self.record_timestamp(&path);
+        self.shared
+            .template_cache
+            .borrow_mut()
+            .insert(path, result.clone());
+        Ok(result)
     }
 
+    /// Load an object template, returning a copy that can be modified.
+    /// See [ResourceManager::template].
     pub fn load_object_template(&mut self, relpath: &str) -> Result<Object> {
-        let path = format!("{}/{}", &self.base_path, relpath);
-        let entry = self.template_cache.entry(path);
-        use std::collections::hash_map::Entry::*;
-        Ok(match entry {
-            Occupied(slot) => slot.get().clone(),
-            Vacant(slot) => {
-                // inlined self.load_text to make the borrow checker happy
-                let template_text = {
-                    let data = self.file_provider.read(&self.base_path, relpath)?;
-                    Ok::<_, Error>(
-                        String::from_utf8(data).map_err(|e| Error::ParseError(Box::new(e)))?,
-                    )
-                }?;
-
-                // parse xml and grab first object node
-                let tmx = roxmltree::Document::parse(&template_text)?;
-                let root = tmx.root_element();
-                let object_node = root
-                    .children()
-                    .find(|c| c.tag_name().name() == "object")
-                    .ok_or(Error::StructureError {
-                        tag: root.tag_name().name().into(),
-                        msg: "Expected an 'object' node in template, but none was found".into(),
-                    })?;
-
-                let mut result = Object::new(0);
-                result.fill_from_xml(&object_node)?;
-                slot.insert(result).clone()
-            }
+        Ok((*self.template(relpath)?).clone())
+    }
+
+    /// Load an external tileset (`*.tsx` file).
+    /// Tilesets are cached, loading the same file again returns the cached data.
+    ///
+    /// External tilesets have no first GID on their own,
+    /// the returned tileset uses a [TileSet::firstgid] of 1.
+    pub fn load_tileset(&mut self, relpath: &str) -> Result<TileSet> {
+        let mut tileset = self.load_tileset_without_image(relpath)?;
+        self.load_tileset_images(std::slice::from_mut(&mut tileset))?;
+        Ok(tileset)
+    }
+
+    /// Like [ResourceManager::load_tileset], but leaves the image of the tileset
+    /// [Pending](ImageStorage::Pending).
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "load_tileset", level = "debug", skip_all, fields(path = relpath)))]
+    pub(crate) fn load_tileset_without_image(&mut self, relpath: &str) -> Result<TileSet> {
+        let path = self.resolve_path(relpath);
+        if let Some(tileset) = self.shared.tileset_cache.borrow().get(&path) {
+            return Ok(tileset.clone());
+        }
+
+        let result = self
+            .read_tileset(relpath)
+            .map_err(|e| self.resource_error(format!("tileset '{}'", path.display()), e))?;
+        self.record_timestamp(&path);
+        self.shared
+            .tileset_cache
+            .borrow_mut()
+            .insert(path, result.clone());
+        Ok(result)
+    }
+
+    fn read_object_template(&mut self, relpath: &str) -> Result<Object> {
+        let template_text = self.load_text(relpath)?;
+
+        // parse xml and grab first object node
+        let tmx = Document::parse(&template_text)?;
+        let root = tmx.root_element();
+        let object_node =
+            root.children()
+                .find(|c| c.tag() == "object")
+                .ok_or(Error::StructureError {
+                    tag: root.tag().into(),
+                    msg: "Expected an 'object' node in template, but none was found".into(),
+                })?;
+
+        self.with_document(relpath, |loader| {
+            let mut result = Object::new(0);
+            result.fill_from_xml(&object_node, loader)?;
+            Ok(result)
         })
     }
 
-    pub fn load_text(&mut self, path: &str) -> Result<String> {
-        let data = self.file_provider.read(&self.base_path, path)?;
+    fn read_tileset(&mut self, relpath: &str) -> Result<TileSet> {
+        let tileset_text = self.load_text(relpath)?;
+        let tsx = Document::parse(&tileset_text)?;
+        let root = tsx.root_element();
+        if root.tag() != "tileset" {
+            return Err(Error::StructureError {
+                tag: root.tag().into(),
+                msg: "Expected tag 'tileset' at root level".into(),
+            });
+        }
+
+        let firstgid = GID(std::num::NonZeroU32::new(1).unwrap());
+        let mut tileset = self.with_document(relpath, |loader| {
+            TileSet::from_tileset_xml(&root, firstgid, loader)
+        })?;
+        tileset.source = Some(self.resolve_path(relpath));
+        Ok(tileset)
+    }
+
+    /// Wrap an error that happened while loading a resource,
+    /// adding information about the documents that reference the resource.
+    /// Errors that already carry this information are returned unchanged.
+    fn resource_error(&self, mut resource: String, error: Error) -> Error {
+        if matches!(
+            error,
+            Error::ResourceLoad { .. } | Error::RecursiveInclude { .. }
+        ) {
+            return error;
+        }
+
+        for (i, document) in self.documents.iter().rev().enumerate() {
+            let separator = if i == 0 { " referenced by" } else { " <-" };
+            resource += &format!("{} '{}'", separator, document.display());
+        }
+        Error::ResourceLoad {
+            resource,
+            source: Box::new(error),
+        }
+    }
+
+    pub fn load_text(&mut self, relpath: &str) -> Result<String> {
+        self.read_text(&self.resolve_path(relpath))
+    }
+
+    /// Read a text file through the providers, ignoring the base path.
+    /// The text reuses the buffer returned by the provider.
+    pub(crate) fn read_text(&self, path: &Path) -> Result<String> {
+        let data = self.shared.file_provider.borrow_mut().read(path)?;
         String::from_utf8(data).map_err(|e| Error::ParseError(Box::new(e)))
     }
 
+    /// Modification time of the given file as reported by the providers,
+    /// ignoring the base path.
+    pub(crate) fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.shared.file_provider.borrow_mut().modified(path)
+    }
+
+    /// Remove the resource with the given path from all caches,
+    /// so that it is loaded again the next time it is requested.
+    ///
+    /// The path must include the base path, like the paths returned by
+    /// [Map::list_dependencies](crate::Map::list_dependencies).
+    /// Use this to react to change notifications, e.g. from a file watcher.
+    pub fn invalidate(&mut self, path: &Path) {
+        self.shared.image_cache.borrow_mut().remove(path);
+        self.shared.template_cache.borrow_mut().remove(path);
+        self.shared.tileset_cache.borrow_mut().remove(path);
+        self.shared.timestamps.borrow_mut().remove(path);
+    }
+
+    /// Return the paths of all cached resources that have been modified since they were loaded.
+    /// Changes are detected with [Provider::modified],
+    /// resources for which the provider does not report a modification time are never
+    /// considered to be changed.
+    pub fn changed_resources(&self) -> Vec<PathBuf> {
+        let mut provider = self.shared.file_provider.borrow_mut();
+        self.shared
+            .timestamps
+            .borrow()
+            .iter()
+            .filter(|(path, modified)| provider.modified(path) != Some(**modified))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Remove all resources from the caches that have been modified since they were loaded.
+    /// Returns the paths of the removed resources.
+    /// See [ResourceManager::changed_resources].
+    pub fn invalidate_changed(&mut self) -> Vec<PathBuf> {
+        let changed = self.changed_resources();
+        for path in &changed {
+            self.invalidate(path);
+        }
+        changed
+    }
+
+    fn record_timestamp(&self, path: &Path) {
+        if let Some(modified) = self.modified(path) {
+            self.shared
+                .timestamps
+                .borrow_mut()
+                .insert(path.to_owned(), modified);
+        }
+    }
+
+    /// Combine the base path with a path relative to it.
+    /// The result is normalized, see [normalize_path].
+    pub(crate) fn resolve_path(&self, relpath: &str) -> PathBuf {
+        normalize_path(&self.base_path.join(relpath))
+    }
+
+    /// Run the given function with the base path set to the directory of the document at relpath.
+    /// Used while loading external documents, so that paths inside of them are resolved
+    /// relative to the document instead of the map.
+    pub(crate) fn with_document<T>(
+        &mut self,
+        relpath: &str,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let path = self.resolve_path(relpath);
+        self.with_document_path(path, f)
+    }
+
+    /// Like [ResourceManager::with_document], but takes the already resolved path
+    /// of the document.
+    ///
+    /// Fails with [Error::RecursiveInclude] if the document is already being loaded,
+    /// i.e. if it (indirectly) references itself.
+    pub(crate) fn with_document_path<T>(
+        &mut self,
+        path: PathBuf,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        if let Some(start) = self.documents.iter().position(|d| *d == path) {
+            let mut chain = self.documents[start..].to_vec();
+            chain.push(path);
+            return Err(Error::RecursiveInclude { chain });
+        }
+
+        let document_base = path.parent().map(Path::to_owned).unwrap_or_default();
+
+        let previous_base = std::mem::replace(&mut self.base_path, document_base);
+        self.documents.push(path);
+        let result = f(self);
+        self.documents.pop();
+        self.base_path = previous_base;
+        result
+    }
+
+    /// Set a function that is called with the current [LoadStage], the number of finished
+    /// steps and the total number of steps in that stage while a map is loaded.
+    /// Use this to drive a loading screen for big maps.
+    ///
+    /// The callback is shared between all clones of this resource manager.
+    pub fn set_progress_callback(
+        &mut self,
+        callback: impl FnMut(LoadStage, usize, usize) + 'static,
+    ) {
+        *self.shared.progress_callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Set a flag that aborts loading when it is set to true, e.g. from another thread
+    /// when the player leaves the loading screen.
+    ///
+    /// The flag is checked after each step reported to the progress callback
+    /// (see [ResourceManager::set_progress_callback]),
+    /// loading then fails with [Error::Cancelled].
+    /// Resources that were completely loaded before stay cached.
+    ///
+    /// The flag is shared between all clones of this resource manager.
+    pub fn set_cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        *self.shared.cancel_flag.borrow_mut() = Some(flag);
+    }
+
+    /// Set a function that is called for every map that is newer than tego supports,
+    /// if [MapLoadOptions::version_policy] is [VersionPolicy::Warn].
+    ///
+    /// The callback is shared between all clones of this resource manager.
+    ///
+    /// ```
+    /// use tego::{MapLoadOptions, ResourceManager, VersionPolicy};
+    ///
+    /// let mut loader = ResourceManager::default();
+    /// loader.set_load_options(MapLoadOptions {
+    ///     version_policy: VersionPolicy::Warn,
+    ///     ..MapLoadOptions::default()
+    /// });
+    /// loader.set_version_warning_callback(|warning| eprintln!("warning: {}", warning));
+    /// ```
+    pub fn set_version_warning_callback(
+        &mut self,
+        callback: impl FnMut(&UnsupportedVersion) + 'static,
+    ) {
+        *self.shared.version_warning_callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Apply the [VersionPolicy] of the load options to a map that was just loaded.
+    pub(crate) fn check_version(&self, map: &Map) -> Result<()> {
+        let policy = self.load_options.version_policy;
+        if policy == VersionPolicy::Ignore || !map.is_newer_than_supported() {
+            return Ok(());
+        }
+        let unsupported = UnsupportedVersion {
+            version: map.version,
+            editor_version: map.editor_version,
+            constructs: map.unsupported_constructs(),
+        };
+        match policy {
+            VersionPolicy::Error => Err(unsupported.into()),
+            _ => {
+                if let Some(callback) = self.shared.version_warning_callback.borrow_mut().as_mut() {
+                    callback(&unsupported);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Report progress to the progress callback,
+    /// then fail if loading was cancelled in the meantime.
+    pub(crate) fn report_progress(
+        &self,
+        stage: LoadStage,
+        done: usize,
+        total: usize,
+    ) -> Result<()> {
+        if let Some(callback) = self.shared.progress_callback.borrow_mut().as_mut() {
+            callback(stage, done, total);
+        }
+
+        match self.shared.cancel_flag.borrow().as_ref() {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// The limits that are enforced while loading maps with this resource manager.
+    pub fn limits(&self) -> LoadLimits {
+        self.limits
+    }
+
+    /// Set the limits that are enforced while loading maps with this resource manager.
+    /// Clones of this resource manager start with the same limits,
+    /// but can change them independently.
+    pub fn set_limits(&mut self, limits: LoadLimits) {
+        self.limits = limits;
+    }
+
+    /// The options that define which parts of a map are loaded.
+    pub fn load_options(&self) -> &MapLoadOptions {
+        &self.load_options
+    }
+
+    /// Set the options that define which parts of a map are loaded.
+    /// Clones of this resource manager start with the same options,
+    /// but can change them independently.
+    pub fn set_load_options(&mut self, options: MapLoadOptions) {
+        self.load_options = options;
+    }
+
+    /// Add another [Provider] on top of the existing ones.
+    ///
+    /// Files are searched in all providers, starting with the one that was added last,
+    /// so that e.g. game mods can override single tilesets or images
+    /// without replacing the whole map package.
+    /// Only files that a provider doesn't have are searched in the providers below it,
+    /// other errors (e.g. a denied permission) fail the loading.
+    ///
+    /// Resources that are already cached are not affected,
+    /// use [ResourceManager::invalidate] to load them again.
+    /// The providers are shared between all clones of this resource manager.
+    pub fn push_provider(&mut self, provider: impl Provider + 'static) {
+        self.shared.file_provider.borrow_mut().push(provider);
+    }
+
+    /// Run the given function with tile data that was decoded ahead of time,
+    /// e.g. in parallel. The data is discarded afterwards, even if the function failed.
+    pub(crate) fn with_decoded_tile_data<T>(
+        &mut self,
+        decoded: HashMap<u32, Vec<Option<GID>>>,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let previous = std::mem::replace(&mut self.decoded_tile_data, decoded);
+        let result = f(self);
+        self.decoded_tile_data = previous;
+        result
+    }
+
+    /// Take the tile data of the given `<data>` node, if it was decoded ahead of time.
+    pub(crate) fn take_decoded_tile_data(&mut self, data_node: &Node) -> Option<Vec<Option<GID>>> {
+        self.decoded_tile_data.remove(&data_node.index())
+    }
+
+    /// Get the [Name] for the given string from the pool of names of this resource manager.
+    ///
+    /// All names in maps loaded with this resource manager come from the same pool,
+    /// e.g. tileset names, classes and property names.
+    /// Interning a string up front allows comparing it against those names cheaply,
+    /// see [Name::ptr_eq].
+    pub fn intern(&self, name: &str) -> Name {
+        self.shared.names.borrow_mut().intern(name)
+    }
+
     /// Get a reference to the resource manager's base path.
-    pub fn base_path(&self) -> &str {
-        self.base_path.as_ref()
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
     }
 
     /// Set the resource manager's base path.
-    pub fn set_base_path(&mut self, base_path: String) {
-        self.base_path = base_path;
+    /// All relative paths inside of a map are resolved relative to the base path.
+    pub fn set_base_path(&mut self, base_path: impl Into<PathBuf>) {
+        self.base_path = base_path.into();
+    }
+
+    /// Check whether two resource managers share the same loader, provider and caches,
+    /// i.e. if one is a clone of the other.
+    pub fn shares_caches_with(&self, other: &ResourceManager) -> bool {
+        Rc::ptr_eq(&self.shared, &other.shared)
     }
 }
 
+/// Uses the [LazyLoader] and reads files from the file system with a [FileProvider].
+/// Without the `fs` feature, there are no files at all until a [Provider] is
+/// [pushed](ResourceManager::push_provider).
 impl Default for ResourceManager {
     fn default() -> Self {
-        ResourceManager::new(LazyLoader {}, FileProvider {})
+        #[cfg(feature = "fs")]
+        let provider = FileProvider::new();
+        #[cfg(not(feature = "fs"))]
+        let provider = EmbeddedProvider::new();
+        ResourceManager::new(LazyLoader {}, provider)
+    }
+}
+
+/// An image that should be loaded by an [ImageLoader].
+///
+/// The image data can be fetched via [ImageSource::read],
+/// which uses the [Provider] of the [ResourceManager].
+pub struct ImageSource<'a> {
+    path: &'a Path,
+    context: ImageContext<'a>,
+    provider: &'a RefCell<ProviderStack>,
+}
+
+/// Extra information about an image, passed to an [ImageLoader] via [ImageSource::context].
+///
+/// Loaders can use this e.g. to apply the color key,
+/// or to slice a sprite sheet into single tiles right away.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageContext<'a> {
+    /// Name of the tileset that uses this image, if any.
+    pub tileset: Option<&'a str>,
+
+    /// Size of the tiles inside of the image, if it is used as sprite sheet.
+    pub tile_size: Option<math::ivec2>,
+
+    /// Spacing in pixels between the tiles inside of the image.
+    pub spacing: usize,
+
+    /// Margin in pixels around the tiles inside of the image.
+    pub margin: usize,
+
+    /// Color which should be treated as transparent.
+    pub transparent: Option<Color>,
+
+    /// Size of the image as stored in the map, if known.
+    pub size: Option<math::ivec2>,
+}
+
+impl ImageContext<'_> {
+    /// Read the information stored in an `<image>` node.
+    pub(crate) fn from_xml(tmx: &Node) -> Result<Self> {
+        let size = match (tmx.attribute("width"), tmx.attribute("height")) {
+            (Some(width), Some(height)) => Some(math::ivec2::new(width.parse()?, height.parse()?)),
+            _ => None,
+        };
+        Ok(Self {
+            // the color key is stored without leading '#'
+            transparent: tmx
+                .attribute("trans")
+                .map(|trans| format!("#{}", trans.trim_start_matches('#')).parse())
+                .transpose()?,
+            size,
+            ..Self::default()
+        })
+    }
+}
+
+impl<'a> ImageSource<'a> {
+    /// Extra information about the image, e.g. the tileset it belongs to.
+    pub fn context(&self) -> &ImageContext<'a> {
+        &self.context
+    }
+
+    /// The path of the image, including the base path of the [ResourceManager].
+    /// Also used as key for caching the loaded image.
+    pub fn path(&self) -> &'a Path {
+        self.path
+    }
+
+    /// Read the raw (still encoded) image data using the [Provider].
+    pub fn read(&mut self) -> Result<Vec<u8>> {
+        self.provider.borrow_mut().read(self.path)
+    }
+
+    /// Open the raw (still encoded) image data as a stream using the [Provider].
+    /// Prefer this over [ImageSource::read] for big images,
+    /// if the image decoder supports streaming.
+    pub fn open(&mut self) -> Result<Box<dyn Read>> {
+        self.provider.borrow_mut().open(self.path)
     }
 }
 
 pub trait ImageLoader {
-    fn load(&mut self, path: &str) -> Result<Box<dyn Any>>;
+    /// Load the given image.
+    /// The type of the returned object is up to the loader,
+    /// the user can later downcast it to the concrete type.
+    fn load(&mut self, source: ImageSource) -> Result<Box<dyn Any>>;
+
+    /// Load several images at once, returning one result per source in the same order.
+    ///
+    /// When loading a map, the images of all tilesets are collected first and passed
+    /// to this function together.
+    /// Override it to e.g. decode the images in parallel or on a worker thread.
+    /// The default implementation calls [ImageLoader::load] for each source.
+    fn load_batch(&mut self, sources: Vec<ImageSource>) -> Vec<Result<Box<dyn Any>>> {
+        sources
+            .into_iter()
+            .map(|source| self.load(source))
+            .collect()
+    }
+}
+
+/// Closures that take the path of an image can be used directly as [ImageLoader],
+/// which is handy for quick scripts and tests.
+///
+/// ```
+/// # use std::{any::Any, path::Path};
+/// let loader = |path: &Path| -> tego::Result<Box<dyn Any>> {
+///     Ok(Box::new(path.to_owned()))
+/// };
+/// let resource_manager = tego::ResourceManager::new(loader, tego::FileProvider::new());
+/// ```
+impl<F> ImageLoader for F
+where
+    F: FnMut(&Path) -> Result<Box<dyn Any>>,
+{
+    fn load(&mut self, source: ImageSource) -> Result<Box<dyn Any>> {
+        self(source.path())
+    }
 }
 
 /// Trait to provide external data.
+///
+/// The paths passed to a provider are already joined with the base path of the
+/// [ResourceManager] and normalized (see [ResourceManager::set_base_path]).
 pub trait Provider {
-    /// Open a file that is located at base_path/path and return its contents.
-    fn read(&mut self, base_path: &str, path: &str) -> Result<Vec<u8>>;
+    /// Open the file at the given path and return its contents.
+    ///
+    /// Fail with an [Error::IO] of kind [NotFound](std::io::ErrorKind::NotFound) if there is
+    /// no such file, so that the providers below this one are asked for it, see
+    /// [ResourceManager::push_provider]. Other errors are returned to the caller directly.
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>>;
+
+    /// Open the file at the given path and return a reader for its contents.
+    ///
+    /// The default implementation reads the whole file using [Provider::read].
+    /// Implementors should override this if the data can be streamed.
+    fn open(&mut self, path: &Path) -> Result<Box<dyn Read>> {
+        Ok(Box::new(std::io::Cursor::new(self.read(path)?)))
+    }
+
+    /// Return the time the file at the given path was last modified, if known.
+    /// Used to detect changed resources for reloading maps.
+    ///
+    /// The default implementation returns None, which disables change detection.
+    fn modified(&mut self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Ordered stack of providers, searched from top to bottom.
+struct ProviderStack {
+    providers: Vec<Box<dyn Provider>>,
+
+    /// Index of the provider that last served each path, used for change detection.
+    origins: HashMap<PathBuf, usize>,
+}
+
+impl ProviderStack {
+    fn new(base: impl Provider + 'static) -> Self {
+        Self {
+            providers: vec![Box::new(base)],
+            origins: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, provider: impl Provider + 'static) {
+        self.providers.push(Box::new(provider));
+    }
+
+    /// Try the operation with each provider, starting with the topmost.
+    /// Returns the first success or the first error other than a missing file,
+    /// or the error of the bottommost provider if no provider has the file.
+    fn first_success<T>(
+        &mut self,
+        path: &Path,
+        mut operation: impl FnMut(&mut dyn Provider) -> Result<T>,
+    ) -> Result<T> {
+        let mut error = None;
+        for (index, provider) in self.providers.iter_mut().enumerate().rev() {
+            match operation(provider.as_mut()) {
+                Ok(result) => {
+                    self.origins.insert(path.to_owned(), index);
+                    return Ok(result);
+                }
+                Err(Error::IO(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    error = Some(Error::IO(e))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(error.expect("there is always a base provider"))
+    }
+}
+
+impl Provider for ProviderStack {
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+        self.first_success(path, |provider| provider.read(path))
+    }
+
+    fn open(&mut self, path: &Path) -> Result<Box<dyn Read>> {
+        self.first_success(path, |provider| provider.open(path))
+    }
+
+    fn modified(&mut self, path: &Path) -> Option<SystemTime> {
+        match self.origins.get(path) {
+            Some(&index) => self.providers[index].modified(path),
+            None => self
+                .providers
+                .iter_mut()
+                .rev()
+                .find_map(|provider| provider.modified(path)),
+        }
+    }
+}
+
+/// Closures that return the contents of the file at a path can be used directly as [Provider].
+///
+/// ```
+/// # use std::path::Path;
+/// let provider = |path: &Path| -> tego::Result<Vec<u8>> { Ok(std::fs::read(path)?) };
+/// let resource_manager = tego::ResourceManager::new(tego::LazyLoader {}, provider);
+/// ```
+impl<F> Provider for F
+where
+    F: FnMut(&Path) -> Result<Vec<u8>>,
+{
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+        self(path)
+    }
 }
 
 /// Trivial Image loader implementation that only stores paths for manual loading later.
@@ -110,21 +1005,201 @@ pub trait Provider {
 pub struct LazyLoader {}
 
 impl ImageLoader for LazyLoader {
-    fn load(&mut self, path: &str) -> Result<Box<dyn Any>> {
-        Ok(Box::new(path.to_string()))
+    fn load(&mut self, source: ImageSource) -> Result<Box<dyn Any>> {
+        Ok(Box::new(source.path().to_string_lossy().into_owned()))
     }
 }
 
 /// [Provider] that reads the data from files on the file system.
-pub struct FileProvider {}
+///
+/// Requires the `fs` feature (enabled by default).
+#[cfg(feature = "fs")]
+#[derive(Debug, Default, Clone)]
+pub struct FileProvider {
+    case_insensitive_fallback: bool,
+}
 
+#[cfg(feature = "fs")]
+impl FileProvider {
+    /// Create a provider that reads files from the file system as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable the case-insensitive fallback (disabled by default).
+    ///
+    /// Maps authored on case-insensitive file systems (e.g. Windows) may reference
+    /// `Tiles.PNG` while the file is actually called `tiles.png`.
+    /// With the fallback enabled, files that can't be found are searched again,
+    /// ignoring the case of every path component.
+    /// If several files match, any of them may be used.
+    pub fn set_case_insensitive_fallback(&mut self, enabled: bool) {
+        self.case_insensitive_fallback = enabled;
+    }
+
+    /// Apply the case-insensitive fallback to a path, if enabled and needed.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if !self.case_insensitive_fallback || path.exists() {
+            return path.to_owned();
+        }
+        find_case_insensitive(path).unwrap_or_else(|| path.to_owned())
+    }
+}
+
+/// Search for a file that matches the given path when ignoring the case.
+#[cfg(feature = "fs")]
+fn find_case_insensitive(path: &Path) -> Option<PathBuf> {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        let name = match component {
+            Component::Normal(name) => name,
+            other => {
+                result.push(other);
+                continue;
+            }
+        };
+
+        if result.join(name).exists() {
+            result.push(name);
+            continue;
+        }
+
+        let directory = match result.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => &result,
+        };
+        let wanted = name.to_string_lossy().to_lowercase();
+        let entry = std::fs::read_dir(directory)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_string_lossy().to_lowercase() == wanted)?;
+        result.push(entry.file_name());
+    }
+    Some(result)
+}
+
+#[cfg(feature = "fs")]
 impl Provider for FileProvider {
-    fn read(&mut self, base_path: &str, path: &str) -> Result<Vec<u8>> {
-        let path = Path::new(base_path).join(Path::new(path));
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+        Ok(std::fs::read(self.resolve(path))?)
+    }
+
+    fn open(&mut self, path: &Path) -> Result<Box<dyn Read>> {
+        let file = std::fs::File::open(self.resolve(path))?;
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+
+    fn modified(&mut self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(self.resolve(path))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+}
+
+/// [Provider] that serves files which are embedded into the binary,
+/// e.g. via [include_bytes].
+/// This allows loading maps without any access to a file system.
+///
+/// Paths are normalized (see [ResourceManager::set_base_path]) before they are stored,
+/// so `maps/../tiles.png` and `tiles.png` refer to the same file.
+///
+/// ```
+/// let mut provider = tego::EmbeddedProvider::new();
+/// provider.add("maps/tiles.tsx", b"<tileset/>");
+/// ```
+#[derive(Default, Clone)]
+pub struct EmbeddedProvider {
+    files: HashMap<PathBuf, &'static [u8]>,
+}
+
+impl EmbeddedProvider {
+    /// Create a provider without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the data available under the given path.
+    /// Replaces any previous data with the same path.
+    pub fn add(&mut self, path: impl AsRef<Path>, data: &'static [u8]) {
+        self.files.insert(normalize_path(path.as_ref()), data);
+    }
+
+    fn lookup(&self, path: &Path) -> Result<&'static [u8]> {
+        self.files.get(path).copied().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No embedded file '{}'", path.display()),
+            )
+            .into()
+        })
+    }
+}
+
+impl Provider for EmbeddedProvider {
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+        Ok(self.lookup(path)?.to_vec())
+    }
+
+    fn open(&mut self, path: &Path) -> Result<Box<dyn Read>> {
+        Ok(Box::new(self.lookup(path)?))
+    }
+}
+
+/// Bring a path into a canonical form,
+/// by removing `.` components and resolving `..` components where possible.
+/// This does not access the file system, so symbolic links are not resolved.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match result.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    result.pop();
+                }
+                // there is nothing above the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => result.push(".."),
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_path_normalization() {
+        assert_eq!(normalize_path(Path::new("./a/b")), Path::new("a/b"));
+        assert_eq!(normalize_path(Path::new("a/../b/./c")), Path::new("b/c"));
+        assert_eq!(
+            normalize_path(Path::new("../a/../../b")),
+            Path::new("../../b")
+        );
+        assert_eq!(normalize_path(Path::new("/../a")), Path::new("/a"));
+    }
+
+    #[test]
+    fn test_detect_recursive_include() {
+        let mut loader = ResourceManager::default();
+        let result = loader.with_document("a.tsx", |loader| {
+            loader.with_document("b.tx", |loader| loader.with_document("a.tsx", |_| Ok(())))
+        });
+
+        match result {
+            Err(Error::RecursiveInclude { chain }) => {
+                assert_eq!(
+                    chain,
+                    [Path::new("a.tsx"), Path::new("b.tx"), Path::new("a.tsx")]
+                )
+            }
+            _ => panic!("recursive include was not detected"),
+        }
 
-        let mut file = std::fs::File::open(path)?;
-        let mut content = Vec::new();
-        file.read_to_end(&mut content)?;
-        Ok(content)
+        // the chain is unwound after a failed load
+        assert!(loader.with_document("a.tsx", |_| Ok(())).is_ok());
     }
 }
@@ -0,0 +1,215 @@
+//! Drawing maps with the renderer of [SDL2](https://crates.io/crates/sdl2), see [draw_layer].
+//!
+//! Requires the `sdl2` feature, which links against the SDL2 library. The sprite sheets of
+//! the map have to be loaded as [RgbaImage], i.e. with the [RgbaImageLoader](crate::RgbaImageLoader).
+//! They are turned into SDL textures the first time they are drawn and kept in a [TextureCache].
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! use tego::{FileProvider, Layer, Map, ResourceManager, RgbaImageLoader};
+//!
+//! let sdl = sdl2::init().unwrap();
+//! let window = sdl.video().unwrap().window("Map", 800, 600).build().unwrap();
+//! let mut canvas = window.into_canvas().build().unwrap();
+//! let creator = canvas.texture_creator();
+//! let mut textures = tego::sdl2::TextureCache::new(&creator);
+//!
+//! let mut loader = ResourceManager::new(RgbaImageLoader::new(), FileProvider::new());
+//! let map = Map::from_file_with_loader(Path::new("example-maps/default/groups.tmx"), &mut loader)?;
+//! for (layer, _) in map.iter_layers() {
+//!     if let Layer::Tile(layer) = layer {
+//!         tego::sdl2::draw_layer(&mut canvas, &mut textures, &map, layer, tego::math::ivec2::new(0, 0))?;
+//!     }
+//! }
+//! canvas.present();
+//! # Ok::<(),tego::Error>(())
+//! ```
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use ::sdl2::pixels::PixelFormatEnum;
+use ::sdl2::rect::Rect as SdlRect;
+use ::sdl2::render::{BlendMode, Canvas, RenderTarget, Texture, TextureCreator};
+use image::RgbaImage;
+
+use crate::math::{ivec2, Rect};
+use crate::{Color, Error, Map, RenderOptions, Result, TileLayer, TileRenderer, TileTransform};
+
+/// The SDL textures of the sprite sheets of maps, created when they are drawn the first time.
+///
+/// The textures are found by the address of the sprite sheets, so the cache has to be
+/// [cleared](TextureCache::clear) when a map is dropped or reloaded.
+pub struct TextureCache<'a, T> {
+    creator: &'a TextureCreator<T>,
+    textures: HashMap<*const RgbaImage, Texture<'a>>,
+}
+
+impl<'a, T> TextureCache<'a, T> {
+    /// Create an empty cache that creates textures with the given texture creator,
+    /// which has to belong to the canvas the layers are drawn to.
+    pub fn new(creator: &'a TextureCreator<T>) -> Self {
+        Self {
+            creator,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Remove all textures.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    /// The texture of a sprite sheet, created if it is drawn for the first time.
+    fn texture(&mut self, image: &RgbaImage) -> Result<&mut Texture<'a>> {
+        let key: *const RgbaImage = image;
+        if !self.textures.contains_key(&key) {
+            let (width, height) = image.dimensions();
+            let mut texture = self
+                .creator
+                .create_texture_static(PixelFormatEnum::RGBA32, width, height)
+                .map_err(|e| Error::RenderError(e.to_string()))?;
+            texture
+                .update(None, image.as_raw(), width as usize * 4)
+                .map_err(|e| Error::RenderError(e.to_string()))?;
+            texture.set_blend_mode(BlendMode::Blend);
+            self.textures.insert(key, texture);
+        }
+        Ok(self.textures.get_mut(&key).unwrap())
+    }
+}
+
+/// Draw the tiles of a layer to an SDL canvas, multiplied with the tint color and opacity
+/// of the layer. See [Map::render_tile_layer_with] for what is drawn.
+///
+/// `camera` is the pixel of the map that is drawn to the upper left corner of the viewport
+/// of the canvas, only the tiles inside of the viewport are drawn.
+///
+/// Fails if the sprite sheet of a used tileset was not loaded as [RgbaImage]
+/// or if SDL fails to create a texture or to draw.
+pub fn draw_layer<C: RenderTarget, T>(
+    canvas: &mut Canvas<C>,
+    textures: &mut TextureCache<'_, T>,
+    map: &Map,
+    layer: &TileLayer,
+    camera: ivec2,
+) -> Result<()> {
+    let viewport = canvas.viewport();
+    let options = RenderOptions {
+        region: Some(Rect::new(
+            camera,
+            ivec2::new(viewport.width() as i32, viewport.height() as i32),
+        )),
+        ..RenderOptions::default()
+    };
+    let mut renderer = SdlRenderer {
+        canvas,
+        textures,
+        camera,
+        tint: layer.tintcolor,
+        alpha: (layer.tintcolor.alpha() as f32 * layer.opacity.clamp(0., 1.)).round() as u8,
+        error: None,
+    };
+    map.render_tile_layer_with(layer, &mut renderer, &options)?;
+    match renderer.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// [TileRenderer] that copies tiles to an SDL canvas.
+struct SdlRenderer<'c, 't, 'a, C: RenderTarget, T> {
+    canvas: &'c mut Canvas<C>,
+    textures: &'t mut TextureCache<'a, T>,
+
+    /// The pixel of the map that is drawn to the upper left corner of the viewport.
+    camera: ivec2,
+
+    /// Color and opacity the tiles are multiplied with.
+    tint: Color,
+    alpha: u8,
+
+    /// The first error that happened while drawing.
+    error: Option<Error>,
+}
+
+impl<C: RenderTarget, T> SdlRenderer<'_, '_, '_, C, T> {
+    fn copy_tile(
+        &mut self,
+        image: &dyn Any,
+        source: Rect,
+        target: Rect,
+        transform: TileTransform,
+    ) -> Result<()> {
+        let Some(image) = image.downcast_ref::<RgbaImage>() else {
+            return Err(Error::UnsupportedFeature(
+                "drawing with SDL2 needs the sprite sheets as image::RgbaImage, \
+                load the map with RgbaImageLoader"
+                    .to_owned(),
+            ));
+        };
+        let texture = self.textures.texture(image)?;
+        texture.set_color_mod(self.tint.red(), self.tint.green(), self.tint.blue());
+        texture.set_alpha_mod(self.alpha);
+
+        let (flip_horizontal, flip_vertical, angle) = transform.to_flip_and_rotation();
+        let dest = unrotated_target(target, transform);
+        self.canvas
+            .copy_ex(
+                texture,
+                sdl_rect(source),
+                sdl_rect(Rect::new(dest.upper_left - self.camera, dest.size)),
+                angle as f64,
+                None,
+                flip_horizontal,
+                flip_vertical,
+            )
+            .map_err(Error::RenderError)
+    }
+}
+
+impl<C: RenderTarget, T> TileRenderer for SdlRenderer<'_, '_, '_, C, T> {
+    fn draw_tile(&mut self, image: &dyn Any, source: Rect, target: Rect, transform: TileTransform) {
+        if self.error.is_none() {
+            self.error = self.copy_tile(image, source, target, transform).err();
+        }
+    }
+}
+
+/// The rectangle that has to be rotated around its center to cover `target`,
+/// SDL rotates the destination rectangle of `copy_ex` around its center.
+fn unrotated_target(target: Rect, transform: TileTransform) -> Rect {
+    if !transform.flip_diagonal {
+        return target;
+    }
+    let size = ivec2::new(target.size.y, target.size.x);
+    Rect::new(target.upper_left + (target.size - size) / 2, size)
+}
+
+fn sdl_rect(rect: Rect) -> SdlRect {
+    SdlRect::new(
+        rect.upper_left.x,
+        rect.upper_left.y,
+        rect.size.x.max(0) as u32,
+        rect.size.y.max(0) as u32,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unrotated_target() {
+        let target = Rect::new(ivec2::new(100, 50), ivec2::new(32, 16));
+        let diagonal = TileTransform {
+            flip_diagonal: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            unrotated_target(target, diagonal),
+            Rect::new(ivec2::new(108, 42), ivec2::new(16, 32))
+        );
+        assert_eq!(unrotated_target(target, TileTransform::default()), target);
+    }
+}
@@ -0,0 +1,380 @@
+//! Collision geometry of a map in world coordinates, see [Map::collision_shapes].
+
+use crate::math::{fvec2, ivec2, PointList};
+use crate::{Layer, LayerId, Map, Object, ObjectKind, GID};
+
+/// Number of corners of the polygons that approximate ellipses which are not circles.
+const ELLIPSE_SEGMENTS: usize = 16;
+
+/// Geometry of an object in world coordinates (pixels, relative to the upper left corner
+/// of the map), independent of any physics engine.
+///
+/// Angles are in radians, clockwise like in Tiled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A rectangle, rotated by `rotation` around its center.
+    Rect {
+        center: fvec2,
+        half_size: fvec2,
+        rotation: f32,
+    },
+    Circle {
+        center: fvec2,
+        radius: f32,
+    },
+
+    /// A closed polygon. Ellipses that are no circles are approximated by polygons.
+    Polygon {
+        points: PointList,
+    },
+    Polyline {
+        points: PointList,
+    },
+    Point(fvec2),
+}
+
+impl Shape {
+    /// The corners of the area that this shape covers, e.g. to triangulate it.
+    /// Circles are approximated by polygons. Returns None for polylines and points.
+    pub fn outline(&self) -> Option<PointList> {
+        match self {
+            Shape::Rect {
+                center,
+                half_size,
+                rotation,
+            } => {
+                let (sin, cos) = rotation.sin_cos();
+                let axis = fvec2::new(cos, sin) * half_size.x;
+                let normal = fvec2::new(-sin, cos) * half_size.y;
+                Some(PointList::from(vec![
+                    center - axis - normal,
+                    center + axis - normal,
+                    center + axis + normal,
+                    center - axis + normal,
+                ]))
+            }
+            Shape::Circle { center, radius } => Some(
+                (0..ELLIPSE_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                        center + fvec2::new(angle.cos(), angle.sin()) * *radius
+                    })
+                    .collect(),
+            ),
+            Shape::Polygon { points } => Some(points.clone()),
+            Shape::Polyline { .. } | Shape::Point(_) => None,
+        }
+    }
+}
+
+/// A [Shape] together with the object it was created from, see [Map::collision_shapes].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionShape<'a> {
+    pub shape: Shape,
+
+    /// The object of an object layer, or of the collision data of a tile.
+    pub object: &'a Object,
+
+    /// Position of the tile in its layer, if the shape belongs to the collision data of a tile.
+    pub tile: Option<ivec2>,
+}
+
+impl Map {
+    /// Collect the collision shapes of this map in world coordinates, from the objects of
+    /// all object layers and from the collision data of the tiles in all tile layers
+    /// (see [Tile::collision](crate::Tile::collision)).
+    ///
+    /// `filter` is called with the layer and the object (for tiles the object of the collision
+    /// data) and decides whether the object is included, e.g. to only use layers with a
+    /// certain name or objects of a certain class.
+    ///
+    /// Offsets of layers and groups, object rotations and the flip flags of tiles are
+    /// applied. Tile objects are treated as rectangles and text objects are skipped.
+    /// Only orthogonal maps are supported.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// use tego::{Layer, Shape};
+    ///
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/objects.tmx"))?;
+    /// let shapes = map.collision_shapes(|layer, _object| match layer {
+    ///     Layer::Object(layer) => layer.name == "Child Object Layer",
+    ///     _ => false,
+    /// });
+    /// assert!(shapes.iter().any(|s| matches!(s.shape, Shape::Polygon { .. })));
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn collision_shapes(
+        &self,
+        mut filter: impl FnMut(&Layer, &Object) -> bool,
+    ) -> Vec<CollisionShape<'_>> {
+        let mut shapes = Vec::new();
+        for &id in self.layer_ids() {
+            self.collect_shapes(id, fvec2::default(), &mut filter, &mut shapes);
+        }
+        shapes
+    }
+
+    fn collect_shapes<'a>(
+        &'a self,
+        id: LayerId,
+        offset: fvec2,
+        filter: &mut impl FnMut(&Layer, &Object) -> bool,
+        shapes: &mut Vec<CollisionShape<'a>>,
+    ) {
+        let layer = &self[id];
+        match layer {
+            Layer::Group(group) => {
+                let offset = offset + to_fvec2(group.offset);
+                for &child in &group.content {
+                    self.collect_shapes(child, offset, filter, shapes);
+                }
+            }
+            Layer::Object(objects) => {
+                let offset = Transform::translation(offset + to_fvec2(objects.offset));
+                for object in objects.content.iter().filter(|o| filter(layer, o)) {
+                    if let Some(shape) = object_shape(object, &offset) {
+                        shapes.push(CollisionShape {
+                            shape,
+                            object,
+                            tile: None,
+                        });
+                    }
+                }
+            }
+            Layer::Tile(tiles) => {
+                for (pos, gid) in tiles.occupied_tiles() {
+                    let Some(tile) = self.tile(gid) else {
+                        continue;
+                    };
+                    let Some(collision) = tile.collision() else {
+                        continue;
+                    };
+                    let cell = self.tile_transform(pos, gid, tile.tileset().tile_size);
+                    let cell = Transform::translation(offset).then(&cell);
+                    let objects = collision.content.iter().filter(|o| filter(layer, o));
+                    for object in objects {
+                        if let Some(shape) = object_shape(object, &cell) {
+                            shapes.push(CollisionShape {
+                                shape,
+                                object,
+                                tile: Some(pos),
+                            });
+                        }
+                    }
+                }
+            }
+            Layer::Image(_) => {}
+        }
+    }
+
+    /// Transformation from the coordinates inside of a tile to world coordinates,
+    /// for a tile of the given size at the given cell.
+    fn tile_transform(&self, pos: ivec2, gid: GID, size: ivec2) -> Transform {
+        let mut size = to_fvec2(size);
+        let mut flip = Transform::default();
+        if gid.flip_diagonal() {
+            flip = Transform {
+                matrix: [[0., 1.], [1., 0.]],
+                translation: fvec2::default(),
+            };
+            std::mem::swap(&mut size.x, &mut size.y);
+        }
+        if gid.flip_horizontal() {
+            flip = Transform {
+                matrix: [[-1., 0.], [0., 1.]],
+                translation: fvec2::new(size.x, 0.),
+            }
+            .then(&flip);
+        }
+        if gid.flip_vertical() {
+            flip = Transform {
+                matrix: [[1., 0.], [0., -1.]],
+                translation: fvec2::new(0., size.y),
+            }
+            .then(&flip);
+        }
+        // tiles are aligned to the lower left corner of their cell
+        let corner = fvec2::new(
+            (pos.x * self.tile_size.x) as f32,
+            ((pos.y + 1) * self.tile_size.y) as f32 - size.y,
+        );
+        Transform::translation(corner).then(&flip)
+    }
+}
+
+/// The shape of an object, transformed into world coordinates.
+/// Returns None for objects without a shape, i.e. text.
+fn object_shape(object: &Object, parent: &Transform) -> Option<Shape> {
+    let size = object.size;
+    let local = match &object.kind {
+        // tile objects are aligned to their lower left corner
+        ObjectKind::Rect if object.tile_id.is_some() => Shape::Rect {
+            center: fvec2::new(size.x / 2., -size.y / 2.),
+            half_size: size * 0.5,
+            rotation: 0.,
+        },
+        ObjectKind::Rect => Shape::Rect {
+            center: size * 0.5,
+            half_size: size * 0.5,
+            rotation: 0.,
+        },
+        ObjectKind::Ellipse if size.x == size.y => Shape::Circle {
+            center: size * 0.5,
+            radius: size.x / 2.,
+        },
+        ObjectKind::Ellipse => Shape::Polygon {
+            points: (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    fvec2::new(
+                        (1. + angle.cos()) * size.x / 2.,
+                        (1. + angle.sin()) * size.y / 2.,
+                    )
+                })
+                .collect(),
+        },
+        ObjectKind::Point => Shape::Point(fvec2::default()),
+        ObjectKind::Polygon { points } => Shape::Polygon {
+            points: points.clone(),
+        },
+        ObjectKind::Polyline { points } => Shape::Polyline {
+            points: points.clone(),
+        },
+        ObjectKind::Text { .. } => return None,
+    };
+    let placement = Transform::translation(object.pos).then(&Transform::rotation(object.rotation));
+    Some(parent.then(&placement).apply_to(local))
+}
+
+pub(crate) fn to_fvec2(v: ivec2) -> fvec2 {
+    fvec2::new(v.x as f32, v.y as f32)
+}
+
+/// An affine transformation without scaling: rotation, mirroring and translation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+    /// Row major 2x2 matrix.
+    matrix: [[f32; 2]; 2],
+    translation: fvec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            matrix: [[1., 0.], [0., 1.]],
+            translation: fvec2::default(),
+        }
+    }
+}
+
+impl Transform {
+    fn translation(translation: fvec2) -> Self {
+        Self {
+            translation,
+            ..Self::default()
+        }
+    }
+
+    /// Clockwise rotation by the given angle in degrees (in a y-down coordinate system).
+    fn rotation(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self {
+            matrix: [[cos, -sin], [sin, cos]],
+            translation: fvec2::default(),
+        }
+    }
+
+    /// The transformation that applies `inner` first, then `self`.
+    fn then(&self, inner: &Transform) -> Transform {
+        let [[a, b], [c, d]] = self.matrix;
+        let [[e, f], [g, h]] = inner.matrix;
+        Transform {
+            matrix: [
+                [a * e + b * g, a * f + b * h],
+                [c * e + d * g, c * f + d * h],
+            ],
+            translation: self.point(inner.translation),
+        }
+    }
+
+    fn vector(&self, v: fvec2) -> fvec2 {
+        let [[a, b], [c, d]] = self.matrix;
+        fvec2::new(a * v.x + b * v.y, c * v.x + d * v.y)
+    }
+
+    fn point(&self, p: fvec2) -> fvec2 {
+        self.vector(p) + self.translation
+    }
+
+    fn apply_to(&self, shape: Shape) -> Shape {
+        let points = |points: PointList| points.iter().map(|&p| self.point(p)).collect();
+        match shape {
+            Shape::Rect {
+                center,
+                half_size,
+                rotation,
+            } => {
+                let axis = self.vector(fvec2::new(rotation.cos(), rotation.sin()));
+                Shape::Rect {
+                    center: self.point(center),
+                    half_size,
+                    rotation: axis.y.atan2(axis.x),
+                }
+            }
+            Shape::Circle { center, radius } => Shape::Circle {
+                center: self.point(center),
+                radius,
+            },
+            Shape::Polygon { points: p } => Shape::Polygon { points: points(p) },
+            Shape::Polyline { points: p } => Shape::Polyline { points: points(p) },
+            Shape::Point(p) => Shape::Point(self.point(p)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_near(a: fvec2, b: fvec2) {
+        assert!(a.approx_eq(b, 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_rotated_object() {
+        let mut object = Object::new(1);
+        object.pos = fvec2::new(10., 20.);
+        object.size = fvec2::new(4., 2.);
+        object.rotation = 90.;
+        let Some(Shape::Rect {
+            center,
+            half_size,
+            rotation,
+        }) = object_shape(&object, &Transform::default())
+        else {
+            panic!("rect object has no rect shape");
+        };
+        // rotating clockwise around the upper left corner moves the rect to the left
+        assert_near(center, fvec2::new(9., 22.));
+        assert_eq!(half_size, fvec2::new(2., 1.));
+        assert!((rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_flipped_tile_transform() {
+        let flip = Transform {
+            matrix: [[-1., 0.], [0., 1.]],
+            translation: fvec2::new(16., 0.),
+        };
+        let shape = flip.apply_to(Shape::Polyline {
+            points: vec![fvec2::new(0., 0.), fvec2::new(4., 8.)].into(),
+        });
+        assert_eq!(
+            shape,
+            Shape::Polyline {
+                points: vec![fvec2::new(16., 0.), fvec2::new(12., 8.)].into()
+            }
+        );
+    }
+}
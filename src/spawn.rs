@@ -0,0 +1,148 @@
+//! Turning tiles and objects into game entities, see [Map::spawn_with].
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::math::{fvec2, ivec2, Rect};
+use crate::shape::to_fvec2;
+use crate::{Layer, LayerId, Map, Name, Object, PropertyContainer, Tile, GID};
+
+/// A tile or object of a map, with everything that is needed to create an entity for it,
+/// see [Map::spawn_with].
+#[derive(Clone)]
+pub struct Spawn<'a> {
+    /// The layer that contains the tile or object.
+    pub layer: &'a Layer,
+
+    /// The object, None for the tiles of tile layers.
+    pub object: Option<&'a Object>,
+
+    /// The cell of a tile in a tile layer, None for objects.
+    pub cell: Option<ivec2>,
+
+    /// Position in pixels, relative to the upper left corner of the map, including the
+    /// offsets of the layer and its groups.
+    ///
+    /// For tiles this is the upper left corner of the cell (see [Map::cell_to_pixel]),
+    /// for objects their [position](Object::pos).
+    pub position: fvec2,
+
+    /// Size in pixels, the tile size of the map for tiles.
+    pub size: fvec2,
+
+    /// The tile, including its flip flags. None for objects that are no tile objects.
+    pub gid: Option<GID>,
+
+    /// The tile that [gid](Spawn::gid) references, None if no tileset contains it.
+    pub tile: Option<Tile<'a>>,
+
+    /// The sprite sheet and the source rectangle of the tile, see [Map::tile_image].
+    pub image: Option<(&'a dyn Any, Rect)>,
+
+    /// The class of the object, or of the tile if the object has none.
+    pub class: &'a Name,
+
+    /// The properties of the tile, overridden by the properties of the object.
+    pub properties: Cow<'a, PropertyContainer>,
+}
+
+impl Map {
+    /// Call `spawn` for every tile of the tile layers and every object of the object layers,
+    /// e.g. to create the entities of an entity component system.
+    ///
+    /// Layers are visited from bottom to top, including hidden layers and the layers inside
+    /// of groups. The tiles of a layer are visited in the same order as [Map::render_with]
+    /// draws them, objects in the order of the layer.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/objects.tmx"))?;
+    /// let mut names = Vec::new();
+    /// map.spawn_with(|spawn| {
+    ///     if let Some(object) = spawn.object {
+    ///         names.push(object.name.to_string());
+    ///     }
+    /// });
+    /// assert!(!names.is_empty());
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn spawn_with<'a>(&'a self, mut spawn: impl FnMut(Spawn<'a>)) {
+        for &id in self.layer_ids() {
+            self.spawn_layer(id, ivec2::new(0, 0), &mut spawn);
+        }
+    }
+
+    fn spawn_layer<'a>(&'a self, id: LayerId, offset: ivec2, spawn: &mut impl FnMut(Spawn<'a>)) {
+        let layer = &self[id];
+        match layer {
+            Layer::Group(group) => {
+                for &child in &group.content {
+                    self.spawn_layer(child, offset + group.offset, spawn);
+                }
+            }
+            Layer::Tile(tiles) => {
+                for (pos, gid) in self.cells_in_renderorder(tiles) {
+                    let tile = self.tile(gid);
+                    spawn(Spawn {
+                        layer,
+                        object: None,
+                        cell: Some(pos),
+                        position: to_fvec2(offset + self.cell_to_pixel(pos)),
+                        size: to_fvec2(self.tile_size),
+                        gid: Some(gid),
+                        tile,
+                        image: self.tile_image(gid),
+                        class: tile.map_or(empty_class(), |tile| tile.class()),
+                        properties: match tile {
+                            Some(tile) => Cow::Borrowed(tile.properties()),
+                            None => Cow::Owned(PropertyContainer::new()),
+                        },
+                    });
+                }
+            }
+            Layer::Object(objects) => {
+                let offset = to_fvec2(offset + objects.offset);
+                for object in &objects.content {
+                    let tile = object.tile_id.and_then(|gid| self.tile(gid));
+                    let class = match tile {
+                        Some(tile) if object.type_.is_empty() => tile.class(),
+                        _ => &object.type_,
+                    };
+                    spawn(Spawn {
+                        layer,
+                        object: Some(object),
+                        cell: None,
+                        position: offset + object.pos,
+                        size: object.size,
+                        gid: object.tile_id,
+                        tile,
+                        image: object.tile_id.and_then(|gid| self.tile_image(gid)),
+                        class,
+                        properties: merged_properties(tile, object),
+                    });
+                }
+            }
+            Layer::Image(_) => {}
+        }
+    }
+}
+
+/// Class of tiles that are not in any tileset.
+fn empty_class() -> &'static Name {
+    static EMPTY: OnceLock<Name> = OnceLock::new();
+    EMPTY.get_or_init(Name::default)
+}
+
+/// The properties of the tile of an object, overridden by the properties of the object.
+fn merged_properties<'a>(tile: Option<Tile<'a>>, object: &'a Object) -> Cow<'a, PropertyContainer> {
+    let tile_properties = match tile {
+        Some(tile) if tile.properties().iter().next().is_some() => tile.properties(),
+        _ => return Cow::Borrowed(&object.properties),
+    };
+    let mut properties = tile_properties.clone();
+    for property in object.properties.iter() {
+        properties.insert(property.clone());
+    }
+    Cow::Owned(properties)
+}
@@ -0,0 +1,363 @@
+//! Alternative parser for huge maps, based on the [quick_xml] pull parser.
+//!
+//! Instead of building a tree of the whole document, the map is read element by element.
+//! Small elements (e.g. tilesets, properties or single objects) are still parsed with
+//! roxmltree, so that both parsers share the same code for the actual map contents.
+//! Only the elements that get big are handled differently:
+//! the base64 text of `<data>` tags is decoded directly from the reader
+//! and objects are parsed one at a time.
+
+use std::io::{self, BufRead, Read};
+
+use quick_xml::events::{BytesStart, Event};
+
+use crate::xml::{Document, XmlNode};
+use crate::{
+    expected_tiles, read_base64_gids, Error, Layer, LayerId, LoadStage, Map, MapLoadOptions,
+    Object, ResourceManager, Result, VersionPolicy,
+};
+
+impl Map {
+    /// Parse a map from a reader, without keeping the whole document in memory.
+    ///
+    /// This is intended for huge maps, where [Map::from_xml_str] would need several times
+    /// the size of the file in memory.
+    /// Only the decoded tile data and a single object or tileset at a time are kept
+    /// in memory, the base64 encoded tile data is decoded while it is read.
+    ///
+    /// External files are resolved relative to the [base path](ResourceManager::set_base_path)
+    /// of the resource manager, and the [MapLoadOptions](crate::MapLoadOptions) and
+    /// [LoadLimits](crate::LoadLimits) apply like for [Map::from_xml_str].
+    /// The tilesets are only parsed after all layers were read,
+    /// so progress for [LoadStage::TileSets] is reported after [LoadStage::Layers].
+    /// The total number of layers is not known up front,
+    /// so the total reported for the layers is the number of layers read so far.
+    ///
+    /// Requires the `streaming` feature.
+    ///
+    /// ```no_run
+    /// # use std::{fs::File, io::BufReader};
+    /// let mut loader = tego::ResourceManager::default();
+    /// loader.set_base_path("example-maps/island");
+    /// let file = BufReader::new(File::open("example-maps/island/island.tmx")?);
+    /// let map = tego::Map::from_reader(file, &mut loader)?;
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse_map", skip_all, fields(streaming = true), err)
+    )]
+    pub fn from_reader(
+        reader: impl BufRead,
+        resource_manager: &mut ResourceManager,
+    ) -> Result<Self> {
+        Parser::new(reader).map(resource_manager)
+    }
+}
+
+struct Parser<R> {
+    reader: quick_xml::Reader<R>,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> Parser<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader: quick_xml::Reader::from_reader(reader),
+            buffer: Vec::new(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Event<'static>> {
+        self.buffer.clear();
+        Ok(self.reader.read_event_into(&mut self.buffer)?.into_owned())
+    }
+
+    fn map(mut self, resource_manager: &mut ResourceManager) -> Result<Map> {
+        let (start, empty) = loop {
+            match self.next()? {
+                Event::Start(start) => break (start, false),
+                Event::Empty(start) => break (start, true),
+                Event::Eof => {
+                    return Err(Error::StructureError {
+                        tag: "map".into(),
+                        msg: "Document has no root element".into(),
+                    })
+                }
+                _ => {}
+            }
+        };
+        let tag = String::from_utf8_lossy(start.local_name().into_inner()).into_owned();
+        if tag != "map" {
+            return Err(Error::StructureError {
+                msg: format!("Expected tag 'map' at root level, got '{}'.", tag),
+                tag,
+            });
+        }
+
+        // everything except the layers is collected into a smaller document,
+        // which is then parsed like a normal map
+        let mut header = start_tag(&start);
+        let options = resource_manager.load_options().clone();
+        let mut layers = Vec::new();
+        let mut root_layers = Vec::new();
+        while let Some((start, empty)) = self.next_child("map", empty)? {
+            let node = element_node(&start)?;
+            let document = Document::parse(&node)?;
+            let node = document.root_element();
+            if !Layer::is_layer_node(&node) {
+                self.capture(start, empty, &mut header)?;
+            } else if options.header_only || !options.includes_layer(&node) {
+                self.skip(&start, empty)?;
+            } else if let Some(layer) = self.layer(start, empty, resource_manager, &mut layers)? {
+                root_layers.push(layer);
+                let done = root_layers.len();
+                resource_manager.report_progress(LoadStage::Layers, done, done)?;
+            }
+        }
+        header.push_str("</map>");
+
+        // the version is checked once the layers are there, so that their content is listed
+        let version_policy = options.version_policy;
+        resource_manager.set_load_options(MapLoadOptions {
+            version_policy: VersionPolicy::Ignore,
+            ..options.clone()
+        });
+        let map = Map::from_xml_str(&header, resource_manager);
+        resource_manager.set_load_options(MapLoadOptions {
+            version_policy,
+            ..options
+        });
+        let mut map = map?;
+        map.layers = layers;
+        map.root_layers = root_layers;
+        resource_manager.check_version(&map)?;
+        Ok(map)
+    }
+
+    /// Read the start tag of the next child of the current element,
+    /// together with whether the child is an empty element.
+    /// Returns None once the current element ends, or right away if it is empty itself.
+    fn next_child(
+        &mut self,
+        tag: &str,
+        empty: bool,
+    ) -> Result<Option<(BytesStart<'static>, bool)>> {
+        if empty {
+            return Ok(None);
+        }
+        loop {
+            match self.next()? {
+                Event::Start(child) => return Ok(Some((child, false))),
+                Event::Empty(child) => return Ok(Some((child, true))),
+                Event::End(_) => return Ok(None),
+                Event::Eof => return Err(unexpected_eof(tag)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse a layer and add it to the given layers,
+    /// returning None if it is skipped by the load options.
+    fn layer(
+        &mut self,
+        start: BytesStart<'static>,
+        empty: bool,
+        resource_manager: &mut ResourceManager,
+        layers: &mut Vec<Option<Layer>>,
+    ) -> Result<Option<LayerId>> {
+        let tag = String::from_utf8_lossy(start.local_name().into_inner()).into_owned();
+        let skipped = tag == "objectgroup" && resource_manager.load_options().skip_object_layers;
+        if skipped {
+            self.skip(&start, empty)?;
+            return Ok(None);
+        }
+
+        let layer_node = element_node(&start)?;
+        let mut layer = start_tag(&start);
+        let mut decoded = None;
+        let mut objects = Vec::new();
+        let mut sublayers = Vec::new();
+        while let Some((child, child_empty)) = self.next_child(&tag, empty)? {
+            match child.local_name().as_ref() {
+                b"data" if !child_empty => {
+                    let data = element_node(&child)?;
+                    let data_document = Document::parse(&data)?;
+                    let data_node = data_document.root_element();
+                    if data_node.attribute("encoding") == Some("base64") {
+                        let max_bytes = resource_manager.limits().max_layer_bytes;
+                        let layer_document = Document::parse(&layer_node)?;
+                        decoded = Some(read_base64_gids(
+                            Text::new(self.reader.get_mut()),
+                            data_node.attribute("compression"),
+                            expected_tiles(&layer_document.root_element(), max_bytes),
+                            max_bytes,
+                        )?);
+                        layer.push_str(&data);
+                        self.skip(&child, false)?;
+                    } else {
+                        self.capture(child, child_empty, &mut layer)?;
+                    }
+                }
+                b"object" => {
+                    let mut object = String::new();
+                    self.capture(child, child_empty, &mut object)?;
+                    let document = Document::parse(&object)?;
+                    objects.push(Object::from_xml(
+                        &document.root_element(),
+                        resource_manager,
+                    )?);
+                }
+                b"layer" | b"group" | b"objectgroup" | b"imagelayer" => {
+                    if let Some(sublayer) =
+                        self.layer(child, child_empty, resource_manager, layers)?
+                    {
+                        sublayers.push(sublayer);
+                    }
+                }
+                _ => self.capture(child, child_empty, &mut layer)?,
+            }
+        }
+        layer.push_str(&format!("</{}>", tag));
+
+        let document = Document::parse(&layer)?;
+        let node = document.root_element();
+        let decoded = node
+            .children()
+            .find(|n| n.tag() == "data")
+            .zip(decoded)
+            .map(|(data_node, tiles)| (data_node.index(), tiles))
+            .into_iter()
+            .collect();
+        let id = resource_manager.with_decoded_tile_data(decoded, |resource_manager| {
+            Layer::try_from_xml(&node, resource_manager, layers).transpose()
+        })?;
+        if let Some(id) = id {
+            match &mut layers[id.index()] {
+                Some(Layer::Group(group)) => group.content.extend(sublayers),
+                Some(Layer::Object(object_layer)) => object_layer.content.extend(objects),
+                _ => {}
+            }
+        }
+        Ok(id)
+    }
+
+    /// Write the given element including all of its children into the output.
+    fn capture(
+        &mut self,
+        start: BytesStart<'static>,
+        empty: bool,
+        output: &mut String,
+    ) -> Result<()> {
+        let mut bytes = Vec::new();
+        let mut writer = quick_xml::Writer::new(&mut bytes);
+        if empty {
+            writer.write_event(Event::Empty(start))?;
+        } else {
+            writer.write_event(Event::Start(start))?;
+            let mut depth = 1;
+            while depth > 0 {
+                let event = self.next()?;
+                match &event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => depth -= 1,
+                    Event::Eof => return Err(unexpected_eof("map")),
+                    _ => {}
+                }
+                writer.write_event(event)?;
+            }
+        }
+        output.push_str(&String::from_utf8(bytes).map_err(|e| Error::ParseError(Box::new(e)))?);
+        Ok(())
+    }
+
+    /// Skip the given element including all of its children.
+    fn skip(&mut self, start: &BytesStart, empty: bool) -> Result<()> {
+        if !empty {
+            self.buffer.clear();
+            self.reader
+                .read_to_end_into(start.name(), &mut self.buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// The start tag of an element, without closing it.
+fn start_tag(start: &BytesStart) -> String {
+    format!("<{}>", String::from_utf8_lossy(start))
+}
+
+/// An empty element with the attributes of the given start tag,
+/// so that the attributes can be parsed with roxmltree.
+fn element_node(start: &BytesStart) -> Result<String> {
+    let start = std::str::from_utf8(start).map_err(|e| Error::ParseError(Box::new(e)))?;
+    Ok(format!("<{}/>", start))
+}
+
+fn unexpected_eof(tag: &str) -> Error {
+    Error::StructureError {
+        tag: tag.to_string(),
+        msg: "Unexpected end of document".into(),
+    }
+}
+
+/// Reads the text content of an element directly from the underlying reader,
+/// stopping before the next tag and skipping all whitespace.
+///
+/// Afterwards the xml reader continues with the tag that follows the text.
+struct Text<'a, R> {
+    inner: &'a mut R,
+    done: bool,
+}
+
+impl<'a, R> Text<'a, R> {
+    fn new(inner: &'a mut R) -> Self {
+        Self { inner, done: false }
+    }
+}
+
+impl<R: BufRead> Read for Text<'_, R> {
+    fn read(&mut self, output: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < output.len() && !self.done {
+            let available = self.inner.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            let mut consumed = 0;
+            for &byte in available {
+                if byte == b'<' {
+                    self.done = true;
+                    break;
+                }
+                consumed += 1;
+                if !byte.is_ascii_whitespace() {
+                    output[written] = byte;
+                    written += 1;
+                    if written == output.len() {
+                        break;
+                    }
+                }
+            }
+            self.inner.consume(consumed);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_text_until_next_tag() {
+        let mut input = io::BufReader::with_capacity(3, &b" ab\n c d<end/>"[..]);
+        let mut text = String::new();
+        Text::new(&mut input).read_to_string(&mut text).unwrap();
+        assert_eq!(text, "abcd");
+
+        let mut rest = String::new();
+        input.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "<end/>");
+    }
+}
@@ -0,0 +1,209 @@
+//! Export of maps as SVG images, see [Map::to_svg].
+
+use crate::writer::{hex_rgb, Attributes, XmlWriter};
+use crate::{Color, Layer, LayerId, Map, Object, ObjectKind, ObjectLayer, TileLayer};
+
+/// Options for [Map::to_svg].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Draw the outline of every tile of the tile layers.
+    /// Without it, tile layers are left out.
+    pub tile_outlines: bool,
+}
+
+impl Map {
+    /// Draw the objects of this map as SVG document, e.g. for an overview of a level
+    /// without loading any images.
+    ///
+    /// Objects are drawn in the color of their layer, text objects with their text.
+    /// Each layer becomes a group, with the name of the layer in its `data-name` attribute.
+    /// Images are not drawn and tiles are drawn as rectangles, assuming an
+    /// orthogonal map.
+    ///
+    /// ```no_run
+    /// let map = tego::Map::from_file(std::path::Path::new("example-maps/default/objects.tmx"))?;
+    /// let svg = map.to_svg(&tego::SvgOptions { tile_outlines: true });
+    /// std::fs::write("objects.svg", svg)?;
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let size = self.size * self.tile_size;
+        let mut writer = XmlWriter::new(None);
+        writer.start(
+            "svg",
+            Attributes::default()
+                .add("xmlns", "http://www.w3.org/2000/svg")
+                .add("width", size.x)
+                .add("height", size.y)
+                .add("viewBox", format!("0 0 {} {}", size.x, size.y)),
+        );
+        if self.backgroundcolor.alpha() > 0 {
+            writer.empty(
+                "rect",
+                fill(Attributes::default(), self.backgroundcolor)
+                    .add("width", size.x)
+                    .add("height", size.y),
+            );
+        }
+        for &id in self.layer_ids() {
+            self.write_svg_layer(&mut writer, id, options);
+        }
+        writer.end();
+        writer.finish()
+    }
+
+    fn write_svg_layer(&self, writer: &mut XmlWriter, id: LayerId, options: &SvgOptions) {
+        let (name, visible, opacity, offset) = match &self[id] {
+            Layer::Tile(layer) if options.tile_outlines => (
+                &layer.name,
+                layer.visible,
+                layer.opacity,
+                Default::default(),
+            ),
+            Layer::Group(layer) => (&layer.name, layer.visible, layer.opacity, layer.offset),
+            Layer::Object(layer) => (&layer.name, layer.visible, layer.opacity, layer.offset),
+            _ => return,
+        };
+        writer.start(
+            "g",
+            Attributes::default()
+                .add("data-name", name)
+                .add_if(!visible, "display", "none")
+                .add_if(opacity != 1., "opacity", opacity)
+                .add_if(
+                    offset.x != 0 || offset.y != 0,
+                    "transform",
+                    format!("translate({} {})", offset.x, offset.y),
+                ),
+        );
+        match &self[id] {
+            Layer::Tile(layer) => self.write_tile_outlines(writer, layer),
+            Layer::Group(group) => {
+                for &child in &group.content {
+                    self.write_svg_layer(writer, child, options);
+                }
+            }
+            Layer::Object(layer) => write_objects(writer, layer),
+            _ => {}
+        }
+        writer.end();
+    }
+
+    fn write_tile_outlines(&self, writer: &mut XmlWriter, layer: &TileLayer) {
+        for (pos, _) in layer.occupied_tiles() {
+            let pos = pos * self.tile_size;
+            writer.empty(
+                "rect",
+                Attributes::default()
+                    .add("x", pos.x)
+                    .add("y", pos.y)
+                    .add("width", self.tile_size.x)
+                    .add("height", self.tile_size.y)
+                    .add("fill", "none")
+                    .add("stroke", "#808080"),
+            );
+        }
+    }
+}
+
+fn write_objects(writer: &mut XmlWriter, layer: &ObjectLayer) {
+    for object in layer.content.iter().filter(|object| object.visible) {
+        let (x, y) = (object.pos.x, object.pos.y);
+        let (width, height) = (object.size.x, object.size.y);
+        let shape = |attributes: Attributes| {
+            let attributes = fill(attributes, layer.color)
+                .add("fill-opacity", 0.25)
+                .add("stroke", format!("#{}", hex_rgb(layer.color)));
+            rotate(attributes, object)
+        };
+        match &object.kind {
+            // tile objects are anchored at their bottom left corner
+            _ if object.tile_id.is_some() => writer.empty(
+                "rect",
+                shape(Attributes::default())
+                    .add("x", x)
+                    .add("y", y - height)
+                    .add("width", width)
+                    .add("height", height),
+            ),
+            ObjectKind::Rect => writer.empty(
+                "rect",
+                shape(Attributes::default())
+                    .add("x", x)
+                    .add("y", y)
+                    .add("width", width)
+                    .add("height", height),
+            ),
+            ObjectKind::Ellipse => writer.empty(
+                "ellipse",
+                shape(Attributes::default())
+                    .add("cx", x + width / 2.)
+                    .add("cy", y + height / 2.)
+                    .add("rx", width / 2.)
+                    .add("ry", height / 2.),
+            ),
+            ObjectKind::Point => writer.empty(
+                "circle",
+                shape(Attributes::default())
+                    .add("cx", x)
+                    .add("cy", y)
+                    .add("r", 3),
+            ),
+            ObjectKind::Polygon { points } => writer.empty(
+                "polygon",
+                shape(Attributes::default()).add("points", point_list(object, points)),
+            ),
+            ObjectKind::Polyline { points } => writer.empty(
+                "polyline",
+                rotate(Attributes::default(), object)
+                    .add("fill", "none")
+                    .add("stroke", format!("#{}", hex_rgb(layer.color)))
+                    .add("points", point_list(object, points)),
+            ),
+            ObjectKind::Text {
+                content,
+                pixelsize,
+                color,
+                ..
+            } => {
+                writer.start(
+                    "text",
+                    rotate(fill(Attributes::default(), *color), object)
+                        .add("x", x)
+                        .add("y", y + *pixelsize as f32)
+                        .add("font-size", pixelsize),
+                );
+                writer.text(content);
+                writer.end();
+            }
+        }
+    }
+}
+
+/// Add the color as fill, with the alpha of the color as opacity.
+fn fill(attributes: Attributes, color: Color) -> Attributes {
+    attributes
+        .add("fill", format!("#{}", hex_rgb(color)))
+        .add_if(color.alpha() < 255, "opacity", color.alpha() as f32 / 255.)
+}
+
+/// Rotate the shape around the position of the object.
+fn rotate(attributes: Attributes, object: &Object) -> Attributes {
+    attributes.add_if(
+        object.rotation != 0.,
+        "transform",
+        format!(
+            "rotate({} {} {})",
+            object.rotation, object.pos.x, object.pos.y
+        ),
+    )
+}
+
+/// The points of a polygon or polyline in map coordinates, in the format of SVG.
+fn point_list(object: &Object, points: &[crate::math::fvec2]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", object.pos.x + point.x, object.pos.y + point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,75 @@
+//! Export of tilesets as TexturePacker sprite sheet data, see [TileSet::to_texturepacker_json].
+
+use std::fmt::Write;
+
+use crate::geojson::write_string;
+use crate::math::{ivec2, Rect};
+use crate::TileSet;
+
+impl TileSet {
+    /// Describe the tiles of this tileset in the "JSON (Hash)" format of TexturePacker,
+    /// so that sprite pipelines of game engines can load the sprite sheet of a tileset
+    /// like any other texture atlas.
+    ///
+    /// Each tile becomes a frame named after its local id, with the source rectangle of
+    /// [TileSet::tile_rect]. Frames are neither rotated nor trimmed. The `image` of the
+    /// metadata is the file name of the sprite sheet, without its directory.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/default_map.tmx"))?;
+    /// let json = map.tilesets[0].to_texturepacker_json();
+    /// assert!(json.contains(r#""0":{"frame":{"x":0,"y":0,"w":16,"h":16}"#));
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn to_texturepacker_json(&self) -> String {
+        let mut json = String::from(r#"{"frames":{"#);
+        for id in 0..self.tilecount as u32 {
+            if id > 0 {
+                json.push(',');
+            }
+            write_frame(&mut json, id, self.tile_rect(id));
+        }
+        json.push_str(r#"},"meta":{"app":"tego","version":"#);
+        write_string(&mut json, env!("CARGO_PKG_VERSION"));
+        json.push_str(r#","image":"#);
+        let image = self.image_path.file_name().unwrap_or_default();
+        write_string(&mut json, &image.to_string_lossy());
+        let size = self.sheet_size();
+        write!(
+            json,
+            r#","format":"RGBA8888","size":{{"w":{},"h":{}}},"scale":"1"}}}}"#,
+            size.x, size.y
+        )
+        .unwrap();
+        json
+    }
+
+    /// Size of the sprite sheet, computed from the tiles if the tileset doesn't store it.
+    fn sheet_size(&self) -> ivec2 {
+        self.image_size.unwrap_or_else(|| {
+            (0..self.tilecount as u32)
+                .map(|id| {
+                    let rect = self.tile_rect(id);
+                    rect.upper_left + rect.size
+                })
+                .fold(ivec2::new(0, 0), |size, corner| {
+                    ivec2::new(size.x.max(corner.x), size.y.max(corner.y))
+                })
+                + ivec2::new(self.margin as i32, self.margin as i32)
+        })
+    }
+}
+
+fn write_frame(json: &mut String, id: u32, rect: Rect) {
+    let (pos, size) = (rect.upper_left, rect.size);
+    write!(
+        json,
+        concat!(
+            r#""{}":{{"frame":{{"x":{},"y":{},"w":{},"h":{}}},"rotated":false,"trimmed":false,"#,
+            r#""spriteSourceSize":{{"x":0,"y":0,"w":{},"h":{}}},"sourceSize":{{"w":{},"h":{}}}}}"#,
+        ),
+        id, pos.x, pos.y, size.x, size.y, size.x, size.y, size.x, size.y
+    )
+    .unwrap();
+}
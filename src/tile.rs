@@ -0,0 +1,177 @@
+//! This module provides access to the per-tile information stored in a [TileSet],
+//! see [Tile] for details.
+
+use std::{sync::OnceLock, time::Duration};
+
+use crate::xml::{Node, XmlNode};
+use crate::{
+    attribute, attribute_or, Name, ObjectLayer, PropertyContainer, ResourceManager, Result,
+    TileSet, GID,
+};
+
+/// A single frame of a tile animation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct AnimationFrame {
+    /// Local id of the tile that is shown during this frame.
+    pub tile_id: u32,
+
+    /// How long this frame is shown.
+    pub duration: Duration,
+}
+
+impl AnimationFrame {
+    fn from_xml(tmx: &Node) -> Result<Self> {
+        Ok(Self {
+            tile_id: attribute(tmx, "tileid")?,
+            duration: Duration::from_millis(attribute(tmx, "duration")?),
+        })
+    }
+}
+
+/// Extra information about a single tile, as found in the `<tile>` tags of a tileset.
+/// Tiles without such a tag use the default values.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TileData {
+    pub(crate) class: Name,
+    pub(crate) probability: f32,
+    pub(crate) properties: PropertyContainer,
+    pub(crate) animation: Vec<AnimationFrame>,
+    pub(crate) collision: Option<ObjectLayer>,
+}
+
+impl Default for TileData {
+    fn default() -> Self {
+        Self {
+            class: Name::default(),
+            probability: 1.,
+            properties: PropertyContainer::new(),
+            animation: Vec::new(),
+            collision: None,
+        }
+    }
+}
+
+impl TileData {
+    /// Parse a `<tile>` node, returning the local id of the tile together with its data.
+    pub(crate) fn from_xml(tmx: &Node, loader: &mut ResourceManager) -> Result<(u32, Self)> {
+        assert_eq!(tmx.tag(), "tile");
+
+        let animation = match tmx.children().find(|c| c.tag() == "animation") {
+            Some(animation) => animation
+                .children()
+                .filter(|c| c.tag() == "frame")
+                .map(|c| AnimationFrame::from_xml(&c))
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        let collision = tmx
+            .children()
+            .find(|c| c.tag() == "objectgroup")
+            .map(|c| ObjectLayer::from_xml(&c, loader))
+            .transpose()?;
+
+        // Tiled 1.9 renamed the "type" attribute to "class"
+        let class = tmx
+            .attribute("class")
+            .or_else(|| tmx.attribute("type"))
+            .unwrap_or_default();
+
+        Ok((
+            attribute(tmx, "id")?,
+            Self {
+                class: loader.intern(class),
+                probability: attribute_or(tmx, "probability", 1.)?,
+                properties: PropertyContainer::from_xml(tmx, loader)?,
+                animation,
+                collision,
+            },
+        ))
+    }
+}
+
+/// Handle to a single tile inside of a [TileSet].
+///
+/// Bundles all the information Tiled stores per tile (class, properties, animation,
+/// collision shapes, ...). Tiles that have no extra information in the tileset
+/// report the default values.
+///
+/// Use [TileSet::tile] to get a handle.
+#[derive(Clone, Copy)]
+pub struct Tile<'a> {
+    tileset: &'a TileSet,
+    id: u32,
+    data: &'a TileData,
+}
+
+impl<'a> Tile<'a> {
+    pub(crate) fn new(tileset: &'a TileSet, id: u32) -> Self {
+        static DEFAULT: OnceLock<TileData> = OnceLock::new();
+
+        let data = tileset
+            .tile_data
+            .get(&id)
+            .unwrap_or_else(|| DEFAULT.get_or_init(TileData::default));
+        Self { tileset, id, data }
+    }
+
+    /// The local id of this tile inside of its tileset.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The [GID] that references this tile in the map (without any flip flags set).
+    pub fn gid(&self) -> GID {
+        self.tileset.gid_of(self.id)
+    }
+
+    /// The tileset that contains this tile.
+    pub fn tileset(&self) -> &'a TileSet {
+        self.tileset
+    }
+
+    /// The class (called "type" prior to Tiled 1.9) of this tile.
+    /// Empty if no class was set.
+    pub fn class(&self) -> &'a Name {
+        &self.data.class
+    }
+
+    /// Relative probability of this tile to be chosen by the terrain tools.
+    /// Defaults to 1.
+    pub fn probability(&self) -> f32 {
+        self.data.probability
+    }
+
+    /// Custom properties of this tile.
+    pub fn properties(&self) -> &'a PropertyContainer {
+        &self.data.properties
+    }
+
+    /// Frames of the animation of this tile. Empty if the tile is not animated.
+    pub fn animation(&self) -> &'a [AnimationFrame] {
+        &self.data.animation
+    }
+
+    /// The tile that the animation of this tile shows after the given time.
+    /// Animations loop, tiles that are not animated always show themselves.
+    pub fn frame_at(&self, elapsed: Duration) -> Tile<'a> {
+        let total: Duration = self.data.animation.iter().map(|f| f.duration).sum();
+        if total.is_zero() {
+            return *self;
+        }
+        let mut time = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        for frame in &self.data.animation {
+            if time < frame.duration {
+                return Tile::new(self.tileset, frame.tile_id);
+            }
+            time -= frame.duration;
+        }
+        *self
+    }
+
+    /// Collision shapes of this tile, if any were defined in the tile collision editor.
+    /// The object positions are relative to the upper left corner of the tile.
+    pub fn collision(&self) -> Option<&'a ObjectLayer> {
+        self.data.collision.as_ref()
+    }
+}
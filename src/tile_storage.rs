@@ -0,0 +1,152 @@
+//! Storage for the GIDs of a tile layer, optionally using fewer bits per tile.
+
+use std::num::NonZeroU32;
+
+use crate::GID;
+
+/// The GIDs of a tile layer, stored row by row.
+///
+/// Compact storage keeps the raw GID values (including the flip flags) in smaller integers,
+/// with 0 marking an empty cell. It can only be used if all values fit.
+#[derive(Debug, Clone)]
+pub(crate) enum TileStorage {
+    Wide(Vec<Option<GID>>),
+    U16(Vec<u16>),
+    U8(Vec<u8>),
+}
+
+impl Default for TileStorage {
+    fn default() -> Self {
+        Self::Wide(Vec::new())
+    }
+}
+
+impl TileStorage {
+    /// Store the given tiles, using the smallest possible integers if `compact` is set.
+    pub(crate) fn new(tiles: Vec<Option<GID>>, compact: bool) -> Self {
+        if !compact {
+            return Self::Wide(tiles);
+        }
+
+        let max = tiles.iter().flatten().map(GID::as_raw).max().unwrap_or(0);
+        let raw = |tile: &Option<GID>| tile.map_or(0, |gid| gid.as_raw());
+        if max <= u8::MAX as u32 {
+            Self::U8(tiles.iter().map(|t| raw(t) as u8).collect())
+        } else if max <= u16::MAX as u32 {
+            Self::U16(tiles.iter().map(|t| raw(t) as u16).collect())
+        } else {
+            Self::Wide(tiles)
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Wide(tiles) => tiles.len(),
+            Self::U16(tiles) => tiles.len(),
+            Self::U8(tiles) => tiles.len(),
+        }
+    }
+
+    /// The tile at the given index, None if the cell is empty or the index is out of range.
+    pub(crate) fn get(&self, index: usize) -> Option<GID> {
+        let raw = match self {
+            Self::Wide(tiles) => return tiles.get(index).copied().flatten(),
+            Self::U16(tiles) => *tiles.get(index)? as u32,
+            Self::U8(tiles) => *tiles.get(index)? as u32,
+        };
+        NonZeroU32::new(raw).map(GID)
+    }
+
+    /// Replace the tile at the given index.
+    /// Compact storage is widened if the new tile doesn't fit.
+    ///
+    /// # Panics
+    /// If the index is out of range.
+    pub(crate) fn set(&mut self, index: usize, tile: Option<GID>) {
+        let raw = tile.map_or(0, |gid| gid.as_raw());
+        match self {
+            Self::Wide(tiles) => tiles[index] = tile,
+            Self::U16(tiles) if raw <= u16::MAX as u32 => tiles[index] = raw as u16,
+            Self::U8(tiles) if raw <= u8::MAX as u32 => tiles[index] = raw as u8,
+            _ => {
+                let mut tiles: Vec<_> = self.iter().collect();
+                tiles[index] = tile;
+                *self = Self::new(tiles, true);
+            }
+        }
+    }
+
+    /// Remove all tiles, keeping the number of cells.
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Self::Wide(tiles) => tiles.fill(None),
+            Self::U16(tiles) => tiles.fill(0),
+            Self::U8(tiles) => tiles.fill(0),
+        }
+    }
+
+    /// The tiles as they were decoded, None if they are stored compactly.
+    pub(crate) fn as_slice(&self) -> Option<&[Option<GID>]> {
+        match self {
+            Self::Wide(tiles) => Some(tiles),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl ExactSizeIterator<Item = Option<GID>> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+
+    /// Number of bytes used for each tile.
+    pub(crate) fn bytes_per_tile(&self) -> usize {
+        match self {
+            Self::Wide(_) => std::mem::size_of::<Option<GID>>(),
+            Self::U16(_) => std::mem::size_of::<u16>(),
+            Self::U8(_) => std::mem::size_of::<u8>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_choose_smallest_storage() {
+        let gid = |raw: u32| Some(GID(NonZeroU32::new(raw).unwrap()));
+        let small = vec![None, gid(3), gid(255)];
+        let medium = vec![gid(256), None];
+        let flipped = vec![gid(2 | 0x80000000), None];
+
+        for (tiles, bytes) in [(small, 1), (medium, 2), (flipped, 4)] {
+            let storage = TileStorage::new(tiles.clone(), true);
+            assert_eq!(storage.bytes_per_tile(), bytes);
+            assert_eq!(storage.iter().collect::<Vec<_>>(), tiles);
+            assert_eq!(storage.get(tiles.len()), None);
+
+            let wide = TileStorage::new(tiles.clone(), false);
+            assert_eq!(wide.bytes_per_tile(), 4);
+        }
+    }
+
+    #[test]
+    fn test_widen_when_setting_big_tiles() {
+        let gid = |raw: u32| Some(GID(NonZeroU32::new(raw).unwrap()));
+        let mut storage = TileStorage::new(vec![gid(1), None, gid(3)], true);
+        storage.set(1, gid(2));
+        assert_eq!(storage.bytes_per_tile(), 1);
+
+        storage.set(0, gid(1000));
+        assert_eq!(storage.bytes_per_tile(), 2);
+        storage.set(2, gid(0x80000001));
+        assert_eq!(storage.bytes_per_tile(), 4);
+        assert_eq!(
+            storage.iter().collect::<Vec<_>>(),
+            [gid(1000), gid(2), gid(0x80000001)]
+        );
+
+        storage.clear();
+        assert!(storage.iter().all(|tile| tile.is_none()));
+        assert_eq!(storage.len(), 3);
+    }
+}
@@ -0,0 +1,122 @@
+//! Parts of the TMX format that tego doesn't understand, see [UnknownXml].
+
+use crate::xml::{Node, XmlNode};
+use crate::{Layer, Map, SUPPORTED_VERSION};
+
+/// The attributes and child elements of an element that were not parsed,
+/// e.g. features of newer versions of Tiled.
+///
+/// They are written back unchanged by [Map::to_xml_string](crate::Map::to_xml_string),
+/// so that loading and saving a map doesn't lose any data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct UnknownXml {
+    /// Names and values of the unknown attributes, in document order.
+    pub(crate) attributes: Vec<(String, String)>,
+
+    /// The unknown child elements as they were written in the document.
+    pub(crate) elements: Vec<String>,
+}
+
+impl UnknownXml {
+    /// Collect everything of the node that is not in the given lists of
+    /// known attributes and known child elements.
+    pub(crate) fn from_xml(node: &Node, attributes: &[&str], elements: &[&str]) -> Self {
+        let mut unknown = Self::default();
+        unknown.update_from_xml(node, attributes, elements);
+        unknown
+    }
+
+    /// Add the unknown parts of another node, e.g. an object that overrides its template.
+    /// Attributes of the node replace attributes with the same name.
+    pub(crate) fn update_from_xml(&mut self, node: &Node, attributes: &[&str], elements: &[&str]) {
+        for (name, value) in XmlNode::attributes(node) {
+            if attributes.contains(&name) {
+                continue;
+            }
+            self.attributes.retain(|(existing, _)| existing != name);
+            self.attributes.push((name.to_owned(), value.to_owned()));
+        }
+        self.elements.extend(
+            node.children()
+                .filter(|child| !child.tag().is_empty() && !elements.contains(&child.tag()))
+                .map(|child| XmlNode::source(&child).to_owned()),
+        );
+    }
+
+    /// Describe each unknown attribute and element, naming the thing they belong to.
+    fn describe(&self, owner: &str, constructs: &mut Vec<String>) {
+        for (name, _) in &self.attributes {
+            constructs.push(format!("attribute '{}' of {}", name, owner));
+        }
+        for element in &self.elements {
+            let tag = element
+                .trim_start_matches('<')
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .next()
+                .unwrap_or_default();
+            constructs.push(format!("element <{}> in {}", tag, owner));
+        }
+    }
+}
+
+impl Map {
+    /// The attributes and elements of the map that tego doesn't understand,
+    /// e.g. features of newer versions of Tiled, in a readable form like
+    /// `attribute 'parallaxx' of layer 'Ground'`.
+    ///
+    /// They are kept when saving the map, but have no effect otherwise.
+    pub fn unsupported_constructs(&self) -> Vec<String> {
+        let mut constructs = Vec::new();
+        self.unknown.describe("the map", &mut constructs);
+        for tileset in &self.tilesets {
+            let owner = format!("tileset '{}'", tileset.name);
+            tileset.unknown.describe(&owner, &mut constructs);
+        }
+        for (layer, _) in self.iter_layers() {
+            let owner = format!("layer '{}'", layer.name());
+            let unknown = match layer {
+                Layer::Tile(layer) => &layer.unknown,
+                Layer::Object(layer) => {
+                    for object in &layer.content {
+                        let owner = format!("object {} in {}", object.id, owner);
+                        object.unknown.describe(&owner, &mut constructs);
+                    }
+                    &layer.unknown
+                }
+                Layer::Image(layer) => &layer.unknown,
+                Layer::Group(layer) => &layer.unknown,
+            };
+            unknown.describe(&owner, &mut constructs);
+        }
+        constructs
+    }
+
+    /// Whether the map or the Tiled version that saved it is newer than [SUPPORTED_VERSION].
+    pub(crate) fn is_newer_than_supported(&self) -> bool {
+        let newer = |version: crate::Version| {
+            (version.0, version.1) > (SUPPORTED_VERSION.0, SUPPORTED_VERSION.1)
+        };
+        newer(self.version) || self.editor_version.is_some_and(newer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xml::Document;
+
+    #[test]
+    fn test_collect_unknown_xml() {
+        let text = r#"<layer id="1" class="wall" parallaxx="0.5"><properties/><!-- note --><future a="1"><b/></future></layer>"#;
+        let document = Document::parse(text).unwrap();
+        let unknown = UnknownXml::from_xml(&document.root_element(), &["id"], &["properties"]);
+        assert_eq!(
+            unknown.attributes,
+            [
+                ("class".to_owned(), "wall".to_owned()),
+                ("parallaxx".to_owned(), "0.5".to_owned())
+            ]
+        );
+        assert_eq!(unknown.elements, [r#"<future a="1"><b/></future>"#]);
+    }
+}
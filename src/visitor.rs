@@ -0,0 +1,81 @@
+//! Walking over all parts of a map, see [MapVisitor].
+
+use crate::math::ivec2;
+use crate::{GroupLayer, Layer, LayerId, Map, Object, ObjectLayer, TileLayer, GID};
+
+/// Callbacks for the parts of a map, called by [Map::accept].
+///
+/// All methods do nothing by default, so a visitor only implements what it is interested in,
+/// e.g. an exporter or a tool that collects statistics about a map.
+///
+/// ```
+/// # use std::path::Path;
+/// # use tego::{Map, MapVisitor, Object, ObjectLayer};
+/// #[derive(Default)]
+/// struct ObjectNames(Vec<String>);
+///
+/// impl MapVisitor for ObjectNames {
+///     fn visit_object(&mut self, _layer: &ObjectLayer, object: &Object) {
+///         self.0.push(object.name.as_str().to_owned());
+///     }
+/// }
+///
+/// let map = Map::from_file(Path::new("example-maps/default/objects.tmx"))?;
+/// let mut names = ObjectNames::default();
+/// map.accept(&mut names);
+/// assert!(names.0.contains(&"Point".to_string()));
+/// # Ok::<(),tego::Error>(())
+/// ```
+pub trait MapVisitor {
+    /// Called once for the map, before any of its layers.
+    fn visit_map(&mut self, _map: &Map) {}
+
+    /// Called for every layer, including the layers inside of groups, before its content.
+    fn visit_layer(&mut self, _layer: &Layer) {}
+
+    /// Called after all layers inside of a group were visited.
+    fn leave_group(&mut self, _group: &GroupLayer) {}
+
+    /// Called for every object of an object layer.
+    fn visit_object(&mut self, _layer: &ObjectLayer, _object: &Object) {}
+
+    /// Called for every non-empty cell of a tile layer, row by row.
+    fn visit_tile(&mut self, _layer: &TileLayer, _pos: ivec2, _gid: GID) {}
+}
+
+impl Map {
+    /// Pass all parts of this map to the visitor.
+    ///
+    /// Layers are visited in the order in which they are drawn, the layers inside of a
+    /// group directly after the group itself. Each layer is followed by its objects or tiles.
+    pub fn accept(&self, visitor: &mut impl MapVisitor) {
+        visitor.visit_map(self);
+        for &id in self.layer_ids() {
+            self.accept_layer(id, visitor);
+        }
+    }
+
+    fn accept_layer(&self, id: LayerId, visitor: &mut impl MapVisitor) {
+        let layer = &self[id];
+        visitor.visit_layer(layer);
+        match layer {
+            Layer::Group(group) => {
+                for &child in &group.content {
+                    self.accept_layer(child, visitor);
+                }
+                visitor.leave_group(group);
+            }
+            Layer::Object(objects) => {
+                for object in &objects.content {
+                    visitor.visit_object(objects, object);
+                }
+            }
+            Layer::Tile(tiles) => {
+                for (pos, gid) in tiles.occupied_tiles() {
+                    visitor.visit_tile(tiles, pos, gid);
+                }
+            }
+            Layer::Image(_) => {}
+        }
+    }
+}
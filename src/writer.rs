@@ -0,0 +1,764 @@
+//! Serialization of maps back into the TMX format, see [Map::to_xml_string].
+
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use crate::resource_manager::normalize_path;
+use crate::tile::TileData;
+use crate::unknown::UnknownXml;
+use crate::{
+    math, Color, GroupLayer, ImageLayer, Layer, LayerId, Map, Object, ObjectKind, ObjectLayer,
+    Orientation, PropertyContainer, PropertyValue, StaggerAxis, StaggerIndex, TileLayer, TileSet,
+};
+
+impl Map {
+    /// Serialize this map into the TMX format.
+    ///
+    /// Tilesets that were loaded from external files are referenced by their
+    /// [source](TileSet::source), all other tilesets are embedded into the map.
+    /// File paths are written relative to the directory of the file this map was loaded
+    /// from (see [Map::source_path]), or as they are stored if the map was not loaded
+    /// from a file.
+    /// Tile data is written base64 encoded and zlib compressed.
+    ///
+    /// ```no_run
+    /// let map = tego::Map::from_file(std::path::Path::new("example-maps/default/groups.tmx"))?;
+    /// let tmx = map.to_xml_string();
+    /// assert!(tmx.contains(r#"name="Ground""#));
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn to_xml_string(&self) -> String {
+        let directory = self.source_path().and_then(Path::parent);
+        self.to_xml_string_in(directory)
+    }
+
+    /// A hash of the content of this map, e.g. for asset pipelines to detect which maps
+    /// changed since they were processed.
+    ///
+    /// The hash covers everything that [Map::to_xml_string] writes and the content of the
+    /// external tilesets, but not the images. Maps that are loaded from the same files
+    /// have the same hash, on every platform and in every run of the program.
+    /// Tile data is hashed uncompressed, so the hash doesn't depend on the compressor.
+    /// The hash may change with new versions of tego.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let map = tego::Map::from_file(Path::new("example-maps/default/groups.tmx"))?;
+    /// let again = tego::Map::from_file(Path::new("example-maps/default/groups.tmx"))?;
+    /// assert_eq!(map.content_hash(), again.content_hash());
+    /// # Ok::<(),tego::Error>(())
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut writer = XmlWriter::new(self.source_path().and_then(Path::parent));
+        writer.csv_tiles = true;
+        let mut hash = fnv1a(FNV_OFFSET, self.write_xml(writer).as_bytes());
+        for tileset in self.tilesets.iter().filter(|t| t.source.is_some()) {
+            hash = fnv1a(hash, tileset.to_tsx_string().as_bytes());
+        }
+        hash
+    }
+
+    /// Write this map as TMX file to the given path, see [Map::to_xml_string].
+    ///
+    /// File paths inside of the map are written relative to the new file.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path) -> crate::Result<()> {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        std::fs::write(path, self.to_xml_string_in(Some(directory)))?;
+        Ok(())
+    }
+
+    /// Serialize this map, writing file paths relative to the given directory.
+    fn to_xml_string_in(&self, directory: Option<&Path>) -> String {
+        self.write_xml(XmlWriter::new(directory))
+    }
+
+    fn write_xml(&self, mut writer: XmlWriter) -> String {
+        let staggered = matches!(
+            self.orientation,
+            Orientation::Staggered | Orientation::Hexagonal
+        );
+        writer.start(
+            "map",
+            Attributes::default()
+                .add("version", self.version)
+                .add_opt("tiledversion", self.editor_version)
+                .add("orientation", self.orientation)
+                .add("renderorder", self.renderorder)
+                .add("width", self.size.x)
+                .add("height", self.size.y)
+                .add("tilewidth", self.tile_size.x)
+                .add("tileheight", self.tile_size.y)
+                .add_if(
+                    self.orientation == Orientation::Hexagonal,
+                    "hexsidelength",
+                    self.hexsidelength,
+                )
+                .add_if(
+                    staggered,
+                    "staggeraxis",
+                    match self.stagger_axis {
+                        StaggerAxis::X => "x",
+                        StaggerAxis::Y => "y",
+                    },
+                )
+                .add_if(
+                    staggered,
+                    "staggerindex",
+                    match self.stagger_index {
+                        StaggerIndex::Odd => "odd",
+                        StaggerIndex::Even => "even",
+                    },
+                )
+                .add("infinite", 0)
+                .add_if(
+                    self.backgroundcolor != Color::default(),
+                    "backgroundcolor",
+                    color_string(self.backgroundcolor),
+                )
+                .add("nextlayerid", self.next_layer_id())
+                .add("nextobjectid", self.next_object_id())
+                .add_unknown(&self.unknown),
+        );
+        write_properties(&mut writer, &self.properties);
+        writer.unknown_elements(&self.unknown);
+        for tileset in &self.tilesets {
+            write_tileset(&mut writer, tileset);
+        }
+        for &id in self.layer_ids() {
+            self.write_layer(&mut writer, id);
+        }
+        writer.end();
+        writer.finish()
+    }
+
+    fn write_layer(&self, writer: &mut XmlWriter, id: LayerId) {
+        match &self[id] {
+            Layer::Tile(layer) => write_tile_layer(writer, layer),
+            Layer::Group(group) => {
+                write_group_start(writer, group);
+                for &child in &group.content {
+                    self.write_layer(writer, child);
+                }
+                writer.end();
+            }
+            Layer::Object(layer) => write_object_layer(writer, "objectgroup", layer),
+            Layer::Image(layer) => write_image_layer(writer, layer),
+        }
+    }
+}
+
+fn write_tileset(writer: &mut XmlWriter, tileset: &TileSet) {
+    if let Some(source) = &tileset.source {
+        let source = writer.path(source);
+        writer.empty(
+            "tileset",
+            Attributes::default()
+                .add("firstgid", tileset.firstgid.to_id())
+                .add("source", source),
+        );
+        return;
+    }
+    write_tileset_data(writer, tileset, true);
+}
+
+/// Write the contents of a tileset, with its firstgid for tilesets embedded into maps.
+fn write_tileset_data(writer: &mut XmlWriter, tileset: &TileSet, embedded: bool) {
+    writer.start(
+        "tileset",
+        Attributes::default()
+            .add_if(embedded, "firstgid", tileset.firstgid.to_id())
+            .add("name", &tileset.name)
+            .add("tilewidth", tileset.tile_size.x)
+            .add("tileheight", tileset.tile_size.y)
+            .add_if(tileset.spacing != 0, "spacing", tileset.spacing)
+            .add_if(tileset.margin != 0, "margin", tileset.margin)
+            .add("tilecount", tileset.tilecount)
+            .add("columns", tileset.columns)
+            .add_unknown(&tileset.unknown),
+    );
+    write_properties(writer, &tileset.properties);
+    let source = writer.path(&tileset.image_path);
+    writer.empty(
+        "image",
+        Attributes::default()
+            .add("source", source)
+            .add_opt("trans", tileset.transparent.map(hex_rgb))
+            .add_opt("width", tileset.image_size.map(|s| s.x))
+            .add_opt("height", tileset.image_size.map(|s| s.y)),
+    );
+
+    let mut tiles: Vec<_> = tileset.tile_data.iter().collect();
+    tiles.sort_by_key(|(&id, _)| id);
+    for (id, tile) in tiles {
+        write_tile(writer, *id, tile);
+    }
+    writer.unknown_elements(&tileset.unknown);
+    writer.end();
+}
+
+impl TileSet {
+    /// Serialize this tileset into the format of external tileset files (`*.tsx`).
+    ///
+    /// The image path is written relative to the directory of the file this tileset was
+    /// loaded from (see [TileSet::source]), or as it is stored for embedded tilesets.
+    pub fn to_tsx_string(&self) -> String {
+        let directory = self.source.as_deref().and_then(Path::parent);
+        self.to_tsx_string_in(directory)
+    }
+
+    /// Write this tileset as external tileset file to the given path,
+    /// e.g. after changing a tileset that is shared by several maps.
+    ///
+    /// The image path is written relative to the new file.
+    /// Maps keep referencing the tileset by its [source](TileSet::source),
+    /// set it to the new path to reference the new file when saving maps.
+    ///
+    /// Requires the `fs` feature (enabled by default).
+    #[cfg(feature = "fs")]
+    pub fn save_tsx(&self, path: &Path) -> crate::Result<()> {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        std::fs::write(path, self.to_tsx_string_in(Some(directory)))?;
+        Ok(())
+    }
+
+    fn to_tsx_string_in(&self, directory: Option<&Path>) -> String {
+        let mut writer = XmlWriter::new(directory);
+        write_tileset_data(&mut writer, self, false);
+        writer.finish()
+    }
+}
+
+fn write_tile(writer: &mut XmlWriter, id: u32, tile: &TileData) {
+    writer.start(
+        "tile",
+        Attributes::default()
+            .add("id", id)
+            .add_if(!tile.class.is_empty(), "type", &tile.class)
+            .add_if(tile.probability != 1., "probability", tile.probability),
+    );
+    write_properties(writer, &tile.properties);
+    if let Some(collision) = &tile.collision {
+        write_object_layer(writer, "objectgroup", collision);
+    }
+    if !tile.animation.is_empty() {
+        writer.start("animation", Attributes::default());
+        for frame in &tile.animation {
+            writer.empty(
+                "frame",
+                Attributes::default()
+                    .add("tileid", frame.tile_id)
+                    .add("duration", frame.duration.as_millis()),
+            );
+        }
+        writer.end();
+    }
+    writer.end();
+}
+
+/// Attributes shared by all kinds of layers.
+fn layer_attributes(
+    id: Option<usize>,
+    name: &str,
+    offset: math::ivec2,
+    opacity: f32,
+    visible: bool,
+    tintcolor: Color,
+) -> Attributes {
+    Attributes::default()
+        .add_opt("id", id)
+        .add("name", name)
+        .add_if(offset.x != 0, "offsetx", offset.x)
+        .add_if(offset.y != 0, "offsety", offset.y)
+        .add_if(opacity != 1., "opacity", opacity)
+        .add_if(!visible, "visible", 0)
+        .add_if(tintcolor != WHITE, "tintcolor", color_string(tintcolor))
+}
+
+fn write_tile_layer(writer: &mut XmlWriter, layer: &TileLayer) {
+    writer.start(
+        "layer",
+        Attributes::default()
+            .add_opt("id", layer.id)
+            .add("name", &layer.name)
+            .add("width", layer.size.x)
+            .add("height", layer.size.y)
+            .add_if(!layer.visible, "visible", 0)
+            .add_if(layer.opacity != 1., "opacity", layer.opacity)
+            .add_if(
+                layer.tintcolor != WHITE,
+                "tintcolor",
+                color_string(layer.tintcolor),
+            )
+            .add_unknown(&layer.unknown),
+    );
+    write_properties(writer, &layer.properties);
+    writer.unknown_elements(&layer.unknown);
+    if writer.csv_tiles {
+        writer.start("data", Attributes::default().add("encoding", "csv"));
+        writer.text(&layer.to_csv());
+    } else {
+        writer.start(
+            "data",
+            Attributes::default()
+                .add("encoding", "base64")
+                .add("compression", "zlib"),
+        );
+        writer.text(&encode_tiles(layer));
+    }
+    writer.end();
+    writer.end();
+}
+
+/// Encode the tiles of a layer as base64 encoded, zlib compressed little endian GIDs.
+fn encode_tiles(layer: &TileLayer) -> String {
+    let mut encoder = libflate::zlib::Encoder::new(Vec::new()).unwrap();
+    for tile in layer.tiles() {
+        let raw = tile.map_or(0, |gid| gid.as_raw());
+        encoder.write_all(&raw.to_le_bytes()).unwrap();
+    }
+    base64::encode(encoder.finish().into_result().unwrap())
+}
+
+fn write_group_start(writer: &mut XmlWriter, group: &GroupLayer) {
+    writer.start(
+        "group",
+        layer_attributes(
+            group.id,
+            &group.name,
+            group.offset,
+            group.opacity,
+            group.visible,
+            group.tintcolor,
+        )
+        .add_unknown(&group.unknown),
+    );
+    write_properties(writer, &group.properties);
+    writer.unknown_elements(&group.unknown);
+}
+
+fn write_object_layer(writer: &mut XmlWriter, tag: &'static str, layer: &ObjectLayer) {
+    writer.start(
+        tag,
+        layer_attributes(
+            layer.id,
+            &layer.name,
+            layer.offset,
+            layer.opacity,
+            layer.visible,
+            layer.tintcolor,
+        )
+        .add_if(
+            layer.color != Color::from_argb(255, 160, 160, 164),
+            "color",
+            color_string(layer.color),
+        )
+        .add_unknown(&layer.unknown),
+    );
+    write_properties(writer, &layer.properties);
+    writer.unknown_elements(&layer.unknown);
+    for object in &layer.content {
+        write_object(writer, object);
+    }
+    writer.end();
+}
+
+fn write_object(writer: &mut XmlWriter, object: &Object) {
+    writer.start(
+        "object",
+        Attributes::default()
+            .add("id", object.id)
+            .add_if(!object.name.is_empty(), "name", &object.name)
+            .add_if(!object.type_.is_empty(), "type", &object.type_)
+            .add_opt("gid", object.tile_id.map(|gid| gid.as_raw()))
+            .add("x", object.pos.x)
+            .add("y", object.pos.y)
+            .add_if(object.size.x != 0., "width", object.size.x)
+            .add_if(object.size.y != 0., "height", object.size.y)
+            .add_if(object.rotation != 0., "rotation", object.rotation)
+            .add_if(!object.visible, "visible", 0)
+            .add_unknown(&object.unknown),
+    );
+    write_properties(writer, &object.properties);
+    writer.unknown_elements(&object.unknown);
+    match &object.kind {
+        ObjectKind::Rect => {}
+        ObjectKind::Ellipse => writer.empty("ellipse", Attributes::default()),
+        ObjectKind::Point => writer.empty("point", Attributes::default()),
+        ObjectKind::Polygon { points } | ObjectKind::Polyline { points } => {
+            let tag = match object.kind {
+                ObjectKind::Polygon { .. } => "polygon",
+                _ => "polyline",
+            };
+            let points: Vec<_> = points.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+            writer.empty(tag, Attributes::default().add("points", points.join(" ")));
+        }
+        ObjectKind::Text {
+            content,
+            pixelsize,
+            wrap,
+            color,
+            bold,
+            italic,
+            underline,
+            strikeout,
+            kerning,
+        } => {
+            writer.start(
+                "text",
+                Attributes::default()
+                    .add_if(*pixelsize != 16, "pixelsize", pixelsize)
+                    .add_if(*wrap, "wrap", 1)
+                    .add_if(
+                        *color != Color::from_argb(255, 0, 0, 0),
+                        "color",
+                        color_string(*color),
+                    )
+                    .add_if(*bold, "bold", 1)
+                    .add_if(*italic, "italic", 1)
+                    .add_if(*underline, "underline", 1)
+                    .add_if(*strikeout, "strikeout", 1)
+                    .add_if(!*kerning, "kerning", 0),
+            );
+            writer.text(content);
+            writer.end();
+        }
+    }
+    writer.end();
+}
+
+fn write_image_layer(writer: &mut XmlWriter, layer: &ImageLayer) {
+    writer.start(
+        "imagelayer",
+        layer_attributes(
+            layer.id,
+            &layer.name,
+            layer.offset,
+            layer.opacity,
+            layer.visible,
+            layer.tintcolor,
+        )
+        .add_unknown(&layer.unknown),
+    );
+    write_properties(writer, &layer.properties);
+    writer.unknown_elements(&layer.unknown);
+    writer.end();
+}
+
+fn write_properties(writer: &mut XmlWriter, properties: &PropertyContainer) {
+    let mut properties: Vec<_> = properties.iter().collect();
+    if properties.is_empty() {
+        return;
+    }
+    properties.sort_by(|a, b| a.name.cmp(&b.name));
+
+    writer.start("properties", Attributes::default());
+    for property in properties {
+        let attributes = Attributes::default().add("name", &property.name);
+        let (type_, value) = match &property.value {
+            PropertyValue::String(text) if text.contains('\n') => {
+                writer.start("property", attributes);
+                writer.text(text);
+                writer.end();
+                continue;
+            }
+            PropertyValue::String(text) => (None, text.clone()),
+            PropertyValue::Int(value) => (Some("int"), value.to_string()),
+            PropertyValue::Float(value) => (Some("float"), value.to_string()),
+            PropertyValue::Bool(value) => (Some("bool"), value.to_string()),
+            PropertyValue::Color(color) => (Some("color"), color_string(*color)),
+            PropertyValue::File(path) => (Some("file"), path.clone()),
+            PropertyValue::Object(object) => (Some("object"), object.id().to_string()),
+        };
+        writer.empty(
+            "property",
+            attributes.add_opt("type", type_).add("value", value),
+        );
+    }
+    writer.end();
+}
+
+const WHITE: Color = Color(0xFFFFFFFF);
+
+/// Initial state of [fnv1a].
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continue the 64 bit FNV-1a hash `hash` with the given bytes.
+/// Unlike the hasher of the standard library, FNV gives the same result in every build.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Format a color as `#AARRGGBB`, the format Tiled uses for colors with alpha.
+pub(crate) fn color_string(color: Color) -> String {
+    format!("#{:08x}", color.to_u32())
+}
+
+/// Format a color as `RRGGBB`, as used for the transparent color of images.
+pub(crate) fn hex_rgb(color: Color) -> String {
+    format!("{:06x}", color.to_u32() & 0xFFFFFF)
+}
+
+/// Express `path` relative to `directory`.
+/// If that is not possible, the path is returned as absolute path or as it is.
+fn relative_path(path: &Path, directory: &Path) -> PathBuf {
+    let mut path = normalize_path(path);
+    let mut directory = normalize_path(directory);
+    if path.is_absolute() != directory.is_absolute() {
+        // fails if there is no working directory, e.g. on wasm32-unknown-unknown
+        let absolute = |p: &Path| std::path::absolute(p).map(|p| normalize_path(&p));
+        match (absolute(&path), absolute(&directory)) {
+            (Ok(a), Ok(b)) => (path, directory) = (a, b),
+            _ => return path,
+        }
+    }
+
+    let mut path_components = path.components().peekable();
+    let mut directory_components = directory.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), directory_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        directory_components.next();
+    }
+
+    let mut result = PathBuf::new();
+    for component in directory_components {
+        match component {
+            Component::Normal(_) => result.push(".."),
+            // can't go back up from a `..` without knowing the working directory
+            _ => return path,
+        }
+    }
+    result.extend(path_components);
+    result
+}
+
+/// Attributes of an xml element, in the order in which they are written.
+#[derive(Default)]
+pub(crate) struct Attributes(Vec<(Cow<'static, str>, String)>);
+
+impl Attributes {
+    pub(crate) fn add(mut self, name: &'static str, value: impl Display) -> Self {
+        self.0.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Add the attributes that were not parsed when the element was loaded.
+    pub(crate) fn add_unknown(mut self, unknown: &UnknownXml) -> Self {
+        for (name, value) in &unknown.attributes {
+            self.0.push((name.clone().into(), value.clone()));
+        }
+        self
+    }
+
+    /// Add the attribute only if the condition holds, e.g. to skip default values.
+    pub(crate) fn add_if(self, condition: bool, name: &'static str, value: impl Display) -> Self {
+        match condition {
+            true => self.add(name, value),
+            false => self,
+        }
+    }
+
+    pub(crate) fn add_opt(self, name: &'static str, value: Option<impl Display>) -> Self {
+        match value {
+            Some(value) => self.add(name, value),
+            None => self,
+        }
+    }
+}
+
+/// Writes indented xml elements into a string.
+pub(crate) struct XmlWriter<'a> {
+    output: String,
+    /// Tags of the elements that were started but not ended yet.
+    open: Vec<&'static str>,
+    /// Whether the start tag of the innermost element still needs to be closed.
+    pending: bool,
+    /// Whether the innermost element contains text.
+    inline: bool,
+    /// Directory that paths are written relative to.
+    directory: Option<&'a Path>,
+    /// Write tile data as CSV instead of compressed base64, e.g. for hashing.
+    pub(crate) csv_tiles: bool,
+}
+
+impl<'a> XmlWriter<'a> {
+    pub(crate) fn new(directory: Option<&'a Path>) -> Self {
+        Self {
+            output: r#"<?xml version="1.0" encoding="UTF-8"?>"#.into(),
+            open: Vec::new(),
+            pending: false,
+            inline: false,
+            directory,
+            csv_tiles: false,
+        }
+    }
+
+    /// Start an element, following elements are its children until [XmlWriter::end].
+    pub(crate) fn start(&mut self, tag: &'static str, attributes: Attributes) {
+        self.start_tag(tag, attributes);
+        self.open.push(tag);
+        self.pending = true;
+    }
+
+    /// Write an element without children.
+    pub(crate) fn empty(&mut self, tag: &'static str, attributes: Attributes) {
+        self.start_tag(tag, attributes);
+        self.output.push_str("/>");
+    }
+
+    /// Write text into the current element.
+    pub(crate) fn text(&mut self, text: &str) {
+        self.close_start_tag();
+        escape_into(&mut self.output, text, false);
+        self.inline = true;
+    }
+
+    /// Write the child elements that were not parsed when the current element was loaded,
+    /// as they were written in the loaded document.
+    pub(crate) fn unknown_elements(&mut self, unknown: &UnknownXml) {
+        for element in &unknown.elements {
+            self.close_start_tag();
+            self.newline();
+            self.output.push_str(element);
+        }
+    }
+
+    /// End the innermost element.
+    pub(crate) fn end(&mut self) {
+        let tag = self.open.pop().expect("no open element");
+        if self.pending {
+            self.output.push_str("/>");
+            self.pending = false;
+            return;
+        }
+        if !self.inline {
+            self.newline();
+        }
+        self.inline = false;
+        self.output.push_str("</");
+        self.output.push_str(tag);
+        self.output.push('>');
+    }
+
+    /// Format a path for writing, relative to the directory of the written file.
+    /// Always uses `/` as separator.
+    pub(crate) fn path(&self, path: &Path) -> String {
+        let path = match self.directory {
+            Some(directory) => relative_path(path, directory),
+            None => path.to_owned(),
+        };
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        assert!(self.open.is_empty(), "unclosed elements");
+        self.output.push('\n');
+        self.output
+    }
+
+    fn start_tag(&mut self, tag: &str, attributes: Attributes) {
+        self.close_start_tag();
+        self.newline();
+        self.output.push('<');
+        self.output.push_str(tag);
+        for (name, value) in attributes.0 {
+            self.output.push(' ');
+            self.output.push_str(&name);
+            self.output.push_str("=\"");
+            escape_into(&mut self.output, &value, true);
+            self.output.push('"');
+        }
+    }
+
+    fn close_start_tag(&mut self) {
+        if self.pending {
+            self.output.push('>');
+            self.pending = false;
+        }
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.output.push_str(&" ".repeat(self.open.len()));
+    }
+}
+
+/// Append the text with all special characters replaced by entities.
+fn escape_into(output: &mut String, text: &str, attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if attribute => output.push_str("&quot;"),
+            '\n' if attribute => output.push_str("&#10;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_elements() {
+        let mut writer = XmlWriter::new(None);
+        writer.start("map", Attributes::default().add("name", "a \"b\" & c"));
+        writer.start("properties", Attributes::default());
+        writer.end();
+        writer.start("data", Attributes::default());
+        writer.text("x<y");
+        writer.end();
+        writer.empty("layer", Attributes::default().add_if(false, "id", 1));
+        writer.end();
+
+        assert_eq!(
+            writer.finish(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <map name=\"a &quot;b&quot; &amp; c\">\n \
+             <properties/>\n \
+             <data>x&lt;y</data>\n \
+             <layer/>\n\
+             </map>\n"
+        );
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(FNV_OFFSET, b""), FNV_OFFSET);
+        assert_eq!(fnv1a(FNV_OFFSET, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            fnv1a(fnv1a(FNV_OFFSET, b"fo"), b"obar"),
+            0x8594_4171_f739_67e8
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let relative =
+            |path: &str, directory: &str| relative_path(path.as_ref(), directory.as_ref());
+        assert_eq!(relative("maps/tiles.png", "maps"), Path::new("tiles.png"));
+        assert_eq!(
+            relative("maps/a/tiles.png", "maps/b"),
+            Path::new("../a/tiles.png")
+        );
+        assert_eq!(relative("tiles.png", ""), Path::new("tiles.png"));
+        assert_eq!(relative("tiles.png", "../maps"), Path::new("tiles.png"));
+
+        let directory = std::env::current_dir().unwrap().join("maps");
+        assert_eq!(
+            relative("maps/tiles.png", directory.to_str().unwrap()),
+            Path::new("tiles.png")
+        );
+    }
+}
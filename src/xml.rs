@@ -0,0 +1,133 @@
+//! Access to the xml documents of maps, tilesets and templates.
+//!
+//! The rest of the crate only uses the [XmlNode] trait to read the documents,
+//! so that the xml parser can be chosen with cargo features:
+//! roxmltree (the default) or the smaller quick-xml.
+//! The selected backend provides the [Document] and [Node] types.
+
+#[cfg(not(any(feature = "roxmltree", feature = "quick-xml")))]
+compile_error!("Either the `roxmltree` or the `quick-xml` feature must be enabled.");
+
+#[cfg(feature = "roxmltree")]
+pub(crate) use roxmltree::{Document, Node};
+
+#[cfg(all(feature = "quick-xml", not(feature = "roxmltree")))]
+mod quick;
+#[cfg(all(feature = "quick-xml", not(feature = "roxmltree")))]
+pub(crate) use quick::{Document, Node};
+
+/// Read access to a parsed xml element, as needed to parse the Tiled formats.
+// roxmltree nodes have inherent methods of the same names, which take precedence
+#[cfg_attr(feature = "roxmltree", allow(dead_code))]
+pub(crate) trait XmlNode<'a>: Copy + Sized {
+    /// The tag name of this element, without namespace.
+    fn tag(&self) -> &'a str;
+
+    /// The value of the attribute with the given name, with all entities resolved.
+    fn attribute(&self, name: &str) -> Option<&'a str>;
+
+    /// Names and values of all attributes of this element, in document order.
+    fn attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a;
+
+    /// The text directly inside of this element, if it starts with text.
+    fn text(&self) -> Option<&'a str>;
+
+    /// The element containing this element.
+    fn parent(&self) -> Option<Self>;
+
+    /// The children of this element in document order.
+    /// Nodes that are no elements (e.g. text) may be included, their tag is empty.
+    fn children(&self) -> impl Iterator<Item = Self> + 'a;
+
+    /// This element followed by all of its children, recursively, in document order.
+    fn descendants(&self) -> impl Iterator<Item = Self> + 'a;
+
+    /// A number that identifies this node inside of its document.
+    fn index(&self) -> u32;
+
+    /// Position of this element in the parsed text, in bytes,
+    /// from the start of its start tag to the end of its end tag.
+    fn range(&self) -> std::ops::Range<usize>;
+
+    /// The text of this element as it is written in the document, including its children.
+    fn source(&self) -> &'a str;
+}
+
+#[cfg(feature = "roxmltree")]
+impl<'a, 'input: 'a> XmlNode<'a> for roxmltree::Node<'a, 'input> {
+    fn tag(&self) -> &'a str {
+        self.tag_name().name()
+    }
+
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        roxmltree::Node::attribute(self, name)
+    }
+
+    fn attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        roxmltree::Node::attributes(self)
+            .iter()
+            .map(|attribute| (attribute.name(), attribute.value()))
+    }
+
+    fn text(&self) -> Option<&'a str> {
+        roxmltree::Node::text(self)
+    }
+
+    fn parent(&self) -> Option<Self> {
+        roxmltree::Node::parent(self)
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> + 'a {
+        roxmltree::Node::children(self)
+    }
+
+    fn descendants(&self) -> impl Iterator<Item = Self> + 'a {
+        roxmltree::Node::descendants(self)
+    }
+
+    fn index(&self) -> u32 {
+        self.id().get()
+    }
+
+    fn range(&self) -> std::ops::Range<usize> {
+        roxmltree::Node::range(self)
+    }
+
+    fn source(&self) -> &'a str {
+        &self.document().input_text()[roxmltree::Node::range(self)]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_document() {
+        let text = r#"<map name="x &amp; y"><properties><property>a&#10;b</property></properties><layer/>text</map>"#;
+        let document = Document::parse(text).unwrap();
+        let root = document.root_element();
+        assert_eq!(root.tag(), "map");
+        assert_eq!(root.attribute("name"), Some("x & y"));
+        assert_eq!(root.attribute("missing"), None);
+        assert_eq!(
+            XmlNode::attributes(&root).collect::<Vec<_>>(),
+            [("name", "x & y")]
+        );
+
+        let tags: Vec<_> = root
+            .descendants()
+            .map(|n| n.tag())
+            .filter(|t| !t.is_empty())
+            .collect();
+        assert_eq!(tags, ["map", "properties", "property", "layer"]);
+
+        let property = root.descendants().find(|n| n.tag() == "property").unwrap();
+        assert_eq!(property.text(), Some("a\nb"));
+        assert_eq!(property.parent().unwrap().tag(), "properties");
+        assert_ne!(property.index(), root.index());
+        assert_eq!(&text[property.range()], "<property>a&#10;b</property>");
+        assert_eq!(property.source(), "<property>a&#10;b</property>");
+        assert_eq!(root.range(), 0..text.len());
+    }
+}
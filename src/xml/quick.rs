@@ -0,0 +1,226 @@
+//! Minimal element tree built with the quick-xml parser.
+//!
+//! Only stores what the crate needs: tags, attributes and the leading text of elements.
+//! Text borrows from the input whenever it doesn't contain escaped characters.
+
+use std::{borrow::Cow, ops::Range};
+
+use quick_xml::events::{BytesStart, Event};
+
+use super::XmlNode;
+use crate::{Error, Result};
+
+struct Element<'input> {
+    tag: String,
+    attributes: Vec<(String, String)>,
+    text: Option<Cow<'input, str>>,
+    parent: Option<u32>,
+    children: Vec<u32>,
+    range: Range<usize>,
+}
+
+/// A parsed xml document.
+pub struct Document<'input> {
+    text: &'input str,
+    elements: Vec<Element<'input>>,
+}
+
+impl<'input> Document<'input> {
+    pub fn parse(text: &'input str) -> Result<Self> {
+        let mut reader = quick_xml::Reader::from_str(text);
+        let mut elements: Vec<Element> = Vec::new();
+        let mut open: Vec<u32> = Vec::new();
+
+        loop {
+            let position = reader.buffer_position() as usize;
+            let (start, empty) = match reader.read_event()? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+                Event::End(_) => {
+                    if let Some(index) = open.pop() {
+                        elements[index as usize].range.end = reader.buffer_position() as usize;
+                    }
+                    continue;
+                }
+                Event::Text(text) => {
+                    append_text(&mut elements, &open, text.unescape()?);
+                    continue;
+                }
+                Event::CData(data) => {
+                    let data = match data.into_inner() {
+                        Cow::Borrowed(data) => Cow::Borrowed(utf8(data)?),
+                        Cow::Owned(data) => Cow::Owned(utf8(&data)?.to_owned()),
+                    };
+                    append_text(&mut elements, &open, data);
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+
+            if open.is_empty() && !elements.is_empty() {
+                return Err(structure_error("Document has more than one root element"));
+            }
+            let index = elements.len() as u32;
+            let parent = open.last().copied();
+            if let Some(parent) = parent {
+                elements[parent as usize].children.push(index);
+            }
+            let (tag, attributes) = parse_start(start)?;
+            elements.push(Element {
+                tag,
+                attributes,
+                text: None,
+                parent,
+                children: Vec::new(),
+                range: position..reader.buffer_position() as usize,
+            });
+            if !empty {
+                open.push(index);
+            }
+        }
+
+        if elements.is_empty() {
+            return Err(structure_error("Document has no root element"));
+        }
+        if !open.is_empty() {
+            return Err(structure_error("Unexpected end of document"));
+        }
+        Ok(Self { text, elements })
+    }
+
+    pub fn root_element<'a>(&'a self) -> Node<'a, 'input> {
+        Node {
+            document: self,
+            index: 0,
+        }
+    }
+}
+
+/// Split a start tag into the local name and the unescaped attributes.
+fn parse_start(start: BytesStart) -> Result<(String, Vec<(String, String)>)> {
+    let tag = utf8(start.local_name().into_inner())?.to_owned();
+    let attributes = start
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let key = utf8(attribute.key.into_inner())?.to_owned();
+            Ok((key, attribute.unescape_value()?.into_owned()))
+        })
+        .collect::<Result<_>>()?;
+    Ok((tag, attributes))
+}
+
+/// Add text to the currently open element, if that element has no children yet.
+fn append_text<'input>(elements: &mut [Element<'input>], open: &[u32], text: Cow<'input, str>) {
+    let Some(&current) = open.last() else {
+        return;
+    };
+    let element = &mut elements[current as usize];
+    if !element.children.is_empty() {
+        return;
+    }
+    element.text = match element.text.take() {
+        None => Some(text),
+        Some(existing) => Some(Cow::Owned(existing.into_owned() + &text)),
+    };
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| Error::ParseError(Box::new(e)))
+}
+
+fn structure_error(msg: &str) -> Error {
+    Error::StructureError {
+        tag: "xml".into(),
+        msg: msg.into(),
+    }
+}
+
+/// An element inside of a [Document].
+#[derive(Clone, Copy)]
+pub struct Node<'a, 'input> {
+    document: &'a Document<'input>,
+    index: u32,
+}
+
+impl<'a, 'input> Node<'a, 'input> {
+    fn element(&self) -> &'a Element<'input> {
+        &self.document.elements[self.index as usize]
+    }
+
+    fn at(&self, index: u32) -> Self {
+        Self {
+            document: self.document,
+            index,
+        }
+    }
+}
+
+impl<'a, 'input: 'a> XmlNode<'a> for Node<'a, 'input> {
+    fn tag(&self) -> &'a str {
+        &self.element().tag
+    }
+
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.element()
+            .attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.element()
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    fn text(&self) -> Option<&'a str> {
+        self.element().text.as_deref()
+    }
+
+    fn parent(&self) -> Option<Self> {
+        self.element().parent.map(|index| self.at(index))
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> + 'a {
+        let node = *self;
+        self.element()
+            .children
+            .iter()
+            .map(move |&index| node.at(index))
+    }
+
+    fn descendants(&self) -> impl Iterator<Item = Self> + 'a {
+        // elements are stored in document order, so the descendants of a node
+        // are the elements following it until the first one outside of its subtree
+        let node = *self;
+        let document = self.document;
+        (self.index..document.elements.len() as u32)
+            .map(move |index| node.at(index))
+            .take_while(move |candidate| {
+                let mut current = Some(*candidate);
+                while let Some(ancestor) = current {
+                    if ancestor.index == node.index {
+                        return true;
+                    }
+                    current = ancestor.parent();
+                }
+                false
+            })
+    }
+
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn range(&self) -> Range<usize> {
+        self.element().range.clone()
+    }
+
+    fn source(&self) -> &'a str {
+        &self.document.text[self.range()]
+    }
+}
@@ -8,7 +8,7 @@ fn load_default_example_map() {
 
     assert_eq!(map.version, Version(1, 5, None));
     assert_eq!(map.editor_version, Some(Version(1, 7, Some(2))));
-    assert_eq!(map.layers.len(), 1);
+    assert_eq!(map.layers().len(), 1);
 
     assert_eq!(map.tilesets.len(), 1);
     assert!(matches!(
@@ -17,7 +17,7 @@ fn load_default_example_map() {
         if path.downcast_ref::<String>().unwrap() == "example-maps/default/tiles.png"
     ));
 
-    if let Layer::Tile(layer) = &map.layers[0] {
+    if let Layer::Tile(layer) = &map[map.layer_ids()[0]] {
         assert_eq!(layer.size, math::ivec2::new(16, 16));
         for (pos, gid) in layer.tiles_in_renderorder(&map) {
             if gid.is_none() {
@@ -33,25 +33,259 @@ fn load_default_example_map() {
     }
 }
 
+#[test]
+fn load_map_from_embedded_provider() {
+    let mut provider = EmbeddedProvider::new();
+    provider.add(
+        "maps/test.tsx",
+        include_bytes!("../example-maps/default/test.tsx"),
+    );
+    let mut loader = ResourceManager::new(LazyLoader {}, provider);
+    loader.set_base_path("maps");
+
+    let map = Map::from_xml_str(
+        include_str!("../example-maps/default/with_extern_tileset.tmx"),
+        &mut loader,
+    )
+    .unwrap();
+    assert_eq!(map.tilesets[0].name, "TestTileset");
+}
+
+#[test]
+fn load_map_file_through_provider() {
+    let mut provider = EmbeddedProvider::new();
+    provider.add(
+        "maps/map.tmx",
+        include_bytes!("../example-maps/default/with_extern_tileset.tmx"),
+    );
+    provider.add(
+        "maps/test.tsx",
+        include_bytes!("../example-maps/default/test.tsx"),
+    );
+    let mut loader = ResourceManager::new(LazyLoader {}, provider);
+
+    let map = Map::from_file_with_loader(Path::new("maps/map.tmx"), &mut loader).unwrap();
+    assert_eq!(map.tilesets[0].name, "TestTileset");
+    assert_eq!(map.source_path(), Some(Path::new("maps/map.tmx")));
+}
+
+#[test]
+fn share_images_between_maps() {
+    let mut loader = ResourceManager::default();
+    let mut other_loader = loader.clone();
+    assert!(loader.shares_caches_with(&other_loader));
+
+    let first = Map::from_file_with_loader(
+        Path::new("example-maps/default/default_map.tmx"),
+        &mut loader,
+    )
+    .unwrap();
+    let second = Map::from_file_with_loader(
+        Path::new("example-maps/default/groups.tmx"),
+        &mut other_loader,
+    )
+    .unwrap();
+
+    let (ImageStorage::SpriteSheet(a), ImageStorage::SpriteSheet(b)) =
+        (&first.tilesets[0].image, &second.tilesets[0].image)
+    else {
+        panic!("tileset images were not loaded");
+    };
+    assert!(std::rc::Rc::ptr_eq(a, b));
+}
+
+#[test]
+fn load_many_maps_with_shared_caches() {
+    let mut loader = ResourceManager::default();
+    let paths = [
+        "example-maps/default/default_map.tmx",
+        "example-maps/default/groups.tmx",
+        "example-maps/island/island.tmx",
+    ];
+    let maps = Map::load_many(paths, &mut loader).unwrap();
+
+    assert_eq!(maps.len(), 3);
+    for (map, path) in maps.iter().zip(paths) {
+        assert_eq!(map.source_path(), Some(Path::new(path)));
+    }
+    assert_eq!(maps[2].tilesets[0].name, "beach_tileset");
+
+    let (ImageStorage::SpriteSheet(a), ImageStorage::SpriteSheet(b)) =
+        (&maps[0].tilesets[0].image, &maps[1].tilesets[0].image)
+    else {
+        panic!("tileset images were not loaded");
+    };
+    assert!(std::rc::Rc::ptr_eq(a, b));
+
+    assert!(Map::load_many(["example-maps/missing.tmx"], &mut loader).is_err());
+}
+
+#[test]
+fn cache_external_tilesets() {
+    use std::{cell::Cell, rc::Rc};
+
+    /// Provider that counts how often a tileset was read
+    struct CountingProvider(Rc<Cell<usize>>);
+    impl Provider for CountingProvider {
+        fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+            if path.extension().unwrap() == "tsx" {
+                self.0.set(self.0.get() + 1);
+            }
+            FileProvider::new().read(path)
+        }
+    }
+
+    let reads = Rc::new(Cell::new(0));
+    let mut loader = ResourceManager::new(LazyLoader {}, CountingProvider(reads.clone()));
+
+    let path = Path::new("example-maps/default/with_extern_tileset.tmx");
+    let first = Map::from_file_with_loader(path, &mut loader).unwrap();
+    let second = Map::from_file_with_loader(path, &mut loader).unwrap();
+
+    assert_eq!(reads.get(), 1);
+    assert_eq!(first.tilesets[0].name, second.tilesets[0].name);
+}
+
+#[test]
+fn resolve_images_relative_to_tileset() {
+    let map = Map::from_file(Path::new("example-maps/default/with_nested_tileset.tmx")).unwrap();
+
+    assert!(matches!(
+        &map.tilesets[0].image,
+        ImageStorage::SpriteSheet(path)
+        if path.downcast_ref::<String>().unwrap() == "example-maps/default/tiles.png"
+    ));
+}
+
+#[test]
+fn list_map_dependencies() {
+    let dependencies =
+        Map::list_dependencies(Path::new("example-maps/default/with_nested_tileset.tmx")).unwrap();
+    assert_eq!(
+        dependencies,
+        vec![
+            Dependency {
+                kind: DependencyKind::TileSet,
+                path: "example-maps/default/tilesets/nested.tsx".into()
+            },
+            Dependency {
+                kind: DependencyKind::Image,
+                path: "example-maps/default/tiles.png".into()
+            },
+        ]
+    );
+
+    let dependencies =
+        Map::list_dependencies(Path::new("example-maps/default/objects.tmx")).unwrap();
+    assert_eq!(
+        dependencies,
+        vec![Dependency {
+            kind: DependencyKind::Template,
+            path: "example-maps/default/Polygon.tx".into()
+        }]
+    );
+}
+
+#[test]
+fn reload_changed_resources() {
+    use std::{cell::Cell, rc::Rc, time::SystemTime};
+
+    /// Provider that counts reads of tilesets and reports a fake modification time
+    struct FakeTimeProvider {
+        reads: Rc<Cell<usize>>,
+        time: Rc<Cell<u64>>,
+    }
+    impl Provider for FakeTimeProvider {
+        fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+            if path.extension().unwrap() == "tsx" {
+                self.reads.set(self.reads.get() + 1);
+            }
+            FileProvider::new().read(path)
+        }
+
+        fn modified(&mut self, _path: &Path) -> Option<SystemTime> {
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(self.time.get()))
+        }
+    }
+
+    let reads = Rc::new(Cell::new(0));
+    let time = Rc::new(Cell::new(0));
+    let mut loader = ResourceManager::new(
+        LazyLoader {},
+        FakeTimeProvider {
+            reads: reads.clone(),
+            time: time.clone(),
+        },
+    );
+
+    let path = Path::new("example-maps/default/with_extern_tileset.tmx");
+    let mut map = Map::from_file_with_loader(path, &mut loader).unwrap();
+    assert_eq!(map.source_path(), Some(path));
+    assert!(!map.needs_reload(&loader));
+
+    // unchanged resources are reused
+    map.reload(&mut loader).unwrap();
+    assert_eq!(reads.get(), 1);
+
+    time.set(1);
+    assert!(map.needs_reload(&loader));
+    let mut changed = loader.changed_resources();
+    changed.sort();
+    assert_eq!(
+        changed,
+        vec![
+            Path::new("example-maps/default/test.tsx"),
+            Path::new("example-maps/default/tiles.png")
+        ]
+    );
+    map.reload(&mut loader).unwrap();
+    assert_eq!(reads.get(), 2);
+    assert!(!map.needs_reload(&loader));
+}
+
 #[test]
 fn load_group_example_map() {
     let map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
 
-    assert_eq!(map.layers.len(), 2);
-    assert!(matches!(map.layers[0], Layer::Tile(_)));
-    assert!(matches!(map.layers[1], Layer::Group(_)));
+    assert_eq!(map.layers().len(), 2);
+    assert!(matches!(map[map.layer_ids()[0]], Layer::Tile(_)));
+    assert!(matches!(map[map.layer_ids()[1]], Layer::Group(_)));
 
-    if let Layer::Group(ref g) = map.layers[1] {
+    if let Layer::Group(ref g) = map[map.layer_ids()[1]] {
         assert_eq!(g.name, "Objects");
         assert_eq!(g.content.len(), 3);
+        let names: Vec<_> = g.layers(&map).map(|l| l.name().as_str()).collect();
+        assert_eq!(names, ["Goodies", "Pipe", "Doors"]);
+    }
+}
+
+#[test]
+fn look_up_layers_by_id() {
+    let mut map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
+
+    let ids: Vec<_> = map.iter_layer_ids().map(|(id, _)| id).collect();
+    let names: Vec<_> = map.iter_layers().map(|(l, _)| l.name().clone()).collect();
+    assert_eq!(ids.len(), 7);
+    for (&id, name) in ids.iter().zip(&names) {
+        assert_eq!(map.layer(id).unwrap().name(), name);
     }
+
+    let group = map.find_layer("Objects").unwrap();
+    assert_eq!(group, map.layer_ids()[1]);
+    assert_eq!(map.find_layer("does not exist"), None);
+
+    // ids stay valid while layers are modified
+    if let Some(Layer::Group(group)) = map.layer_mut(group) {
+        group.opacity = 0.5;
+    }
+    assert!(matches!(&map[group], Layer::Group(g) if g.opacity == 0.5));
 }
 
 #[test]
 fn load_object_example_map() {
     let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
 
-    assert_eq!(map.layers.len(), 2);
+    assert_eq!(map.layers().len(), 2);
 
     let object_layers: Vec<_> = map
         .iter_layers()
@@ -68,8 +302,20 @@ fn load_object_example_map() {
     assert!(matches!(&objects[0].kind, ObjectKind::Ellipse));
     assert!(matches!(&objects[1].kind, ObjectKind::Polygon { .. }));
 
-    if let ObjectKind::Text { content, .. } = &objects[2].kind {
+    if let ObjectKind::Text {
+        content,
+        pixelsize,
+        wrap,
+        color,
+        bold,
+        kerning,
+        ..
+    } = &objects[2].kind
+    {
         assert_eq!(content, "Hello World");
+        assert_eq!(*pixelsize, 17);
+        assert!(*wrap && *bold && *kerning);
+        assert_eq!(*color, Color::from_argb(255, 0, 0, 0));
     } else {
         panic!("Expected object to be a text object");
     }
@@ -104,9 +350,2934 @@ fn load_object_example_map() {
     );
 }
 
+#[test]
+fn load_tile_objects() {
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="Tiles" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="tiles.png" width="32" height="32"/>
+ </tileset>
+ <objectgroup id="1" name="Objects">
+  <object id="1" name="Chest" type="loot" gid="3" x="16" y="32" width="16" height="16"/>
+  <object id="2" name="Area" x="0" y="0" width="8" height="8"/>
+ </objectgroup>
+</map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+    let objects = match map.iter_layers().next() {
+        Some((Layer::Object(layer), _)) => &layer.content,
+        _ => panic!("Expected an object layer"),
+    };
+
+    assert_eq!(objects[0].type_, "loot");
+    assert_eq!(objects[0].tile_id.map(|gid| gid.to_id()), Some(3));
+    assert_eq!(objects[1].type_, "");
+    assert!(objects[1].tile_id.is_none());
+}
+
+#[test]
+fn tile_image_of_flipped_gids() {
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="First" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="first.png" width="32" height="32"/>
+ </tileset>
+ <tileset firstgid="5" name="Second" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="second.png" width="32" height="32"/>
+ </tileset>
+</map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+
+    let gid = GID::new(6).unwrap().with_flips(true, true, false);
+    assert_eq!(map.tile(gid).unwrap().id(), 1);
+    let (image, rect) = map.tile_image(gid).unwrap();
+    assert!(image
+        .downcast_ref::<String>()
+        .unwrap()
+        .ends_with("second.png"));
+    assert_eq!(rect.upper_left, math::ivec2::new(16, 0));
+
+    let gid = GID::new(2).unwrap().with_flips(false, false, true);
+    let (image, _) = map.tile_image(gid).unwrap();
+    assert!(image
+        .downcast_ref::<String>()
+        .unwrap()
+        .ends_with("first.png"));
+}
+
 #[test]
 fn load_image_example_map() {
     let map = Map::from_file(Path::new("example-maps/default/image_layer.tmx")).unwrap();
 
-    assert_eq!(map.layers.len(), 1);
+    assert_eq!(map.layers().len(), 1);
+}
+
+#[test]
+fn load_tile_animations() {
+    let map = Map::from_file(Path::new("example-maps/island/island.tmx")).unwrap();
+    let tileset = &map.tilesets[0];
+
+    let animated = tileset.tile(37).unwrap();
+    assert_eq!(animated.id(), 37);
+    assert_eq!(animated.animation().len(), 4);
+    assert_eq!(animated.animation()[1].tile_id, 46);
+    assert_eq!(
+        animated.animation()[1].duration,
+        std::time::Duration::from_millis(1000)
+    );
+
+    // tiles without extra information report defaults
+    let plain = tileset.tile(0).unwrap();
+    assert!(plain.animation().is_empty());
+    assert_eq!(plain.class(), "");
+    assert_eq!(plain.probability(), 1.);
+    assert!(plain.collision().is_none());
+
+    assert!(tileset.tile(tileset.tilecount as u32).is_none());
+}
+
+#[test]
+fn iterate_tileset_tiles() {
+    let map = Map::from_file(Path::new("example-maps/island/island.tmx")).unwrap();
+    let tileset = &map.tilesets[0];
+
+    let animated: Vec<_> = tileset
+        .tiles()
+        .filter(|(_, t)| !t.animation().is_empty())
+        .map(|(gid, _)| gid.to_id())
+        .collect();
+    assert_eq!(animated, vec![38, 149]);
+    assert_eq!(tileset.tiles().count(), tileset.tilecount);
+}
+
+#[test]
+fn pass_context_to_image_loader() {
+    struct ContextLoader;
+    impl ImageLoader for ContextLoader {
+        fn load(&mut self, source: ImageSource) -> Result<Box<dyn std::any::Any>> {
+            let context = source.context();
+            assert_eq!(context.tileset, Some("Example Tileset"));
+            assert_eq!(context.tile_size, Some(math::ivec2::new(16, 16)));
+            assert_eq!(context.size, Some(math::ivec2::new(256, 256)));
+            assert_eq!(
+                context.transparent,
+                Some(Color::from_argb(255, 255, 0, 255))
+            );
+            Ok(Box::new(()))
+        }
+    }
+
+    let mut loader = ResourceManager::new(ContextLoader, FileProvider::new());
+    Map::from_file_with_loader(
+        Path::new("example-maps/default/default_map.tmx"),
+        &mut loader,
+    )
+    .unwrap();
+}
+
+#[test]
+fn name_resource_in_load_error() {
+    let mut provider = EmbeddedProvider::new();
+    provider.add(
+        "maps/test.tsx",
+        include_bytes!("../example-maps/default/test.tsx"),
+    );
+    struct FailingLoader;
+    impl ImageLoader for FailingLoader {
+        fn load(&mut self, source: ImageSource) -> Result<Box<dyn std::any::Any>> {
+            Err(Error::UnsupportedFeature(format!(
+                "can't load {}",
+                source.path().display()
+            )))
+        }
+    }
+
+    let mut loader = ResourceManager::new(FailingLoader, provider);
+    loader.set_base_path("maps");
+    let error = Map::from_xml_str(
+        include_str!("../example-maps/default/with_extern_tileset.tmx"),
+        &mut loader,
+    )
+    .err()
+    .unwrap();
+
+    assert!(matches!(error, Error::ResourceLoad { .. }));
+    assert_eq!(
+        error.to_string(),
+        "Failed to load image 'maps/tiles.png' of tileset 'TestTileset' referenced by \
+        'maps/test.tsx': Feature not supported: can't load maps/tiles.png"
+    );
+}
+
+#[test]
+fn load_tileset_images_as_batch() {
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Loader that records the paths of each batch it was asked to load
+    struct BatchLoader(Rc<RefCell<Vec<Vec<String>>>>);
+    impl ImageLoader for BatchLoader {
+        fn load(&mut self, _: ImageSource) -> Result<Box<dyn std::any::Any>> {
+            unreachable!("all images should be loaded as batch");
+        }
+
+        fn load_batch(&mut self, sources: Vec<ImageSource>) -> Vec<Result<Box<dyn std::any::Any>>> {
+            let paths = sources
+                .iter()
+                .map(|s| s.path().to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            self.0.borrow_mut().push(paths);
+            sources
+                .iter()
+                .map(|s| Ok(Box::new(s.context().tileset.unwrap().to_string()) as _))
+                .collect()
+        }
+    }
+
+    let tmx = r#"<map version="1.5" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="first" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="a.png"/>
+            </tileset>
+            <tileset firstgid="2" name="second" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="b.png"/>
+            </tileset>
+            <tileset firstgid="3" name="third" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="a.png"/>
+            </tileset>
+        </map>"#;
+    let batches = Rc::new(RefCell::new(Vec::new()));
+    let mut loader = ResourceManager::new(BatchLoader(batches.clone()), EmbeddedProvider::new());
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+
+    assert_eq!(*batches.borrow(), vec![vec!["a.png", "b.png"]]);
+    // tilesets sharing a sprite sheet share the loaded image
+    let names = map
+        .tilesets
+        .iter()
+        .map(|t| match &t.image {
+            ImageStorage::SpriteSheet(image) => image.downcast_ref::<String>().unwrap().clone(),
+            _ => panic!("image of {} was not loaded", t.name),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["first", "second", "first"]);
+
+    /// Loader that forgets the first image of each batch
+    struct ForgetfulLoader;
+    impl ImageLoader for ForgetfulLoader {
+        fn load(&mut self, _: ImageSource) -> Result<Box<dyn std::any::Any>> {
+            Ok(Box::new(()))
+        }
+
+        fn load_batch(&mut self, sources: Vec<ImageSource>) -> Vec<Result<Box<dyn std::any::Any>>> {
+            sources
+                .iter()
+                .skip(1)
+                .map(|_| Ok(Box::new(()) as _))
+                .collect()
+        }
+    }
+
+    let mut loader = ResourceManager::new(ForgetfulLoader, EmbeddedProvider::new());
+    let error = Map::from_xml_str(tmx, &mut loader).unwrap_err();
+    assert!(matches!(error, Error::InvalidImageLoader(_)), "{}", error);
+}
+
+#[test]
+fn report_load_progress() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut loader = ResourceManager::default();
+    let recorder = events.clone();
+    loader.set_progress_callback(move |stage, done, total| {
+        recorder.borrow_mut().push((stage, done, total))
+    });
+
+    Map::from_file_with_loader(Path::new("example-maps/default/groups.tmx"), &mut loader).unwrap();
+
+    let events = events.borrow();
+    assert_eq!(
+        events[..3],
+        [
+            (LoadStage::TileSets, 1, 1),
+            (LoadStage::Images, 0, 1),
+            (LoadStage::Images, 1, 1),
+        ]
+    );
+    assert!(events[3..]
+        .iter()
+        .all(|(stage, _, _)| *stage == LoadStage::Layers));
+    assert_eq!(events.last().map(|e| e.1), events.last().map(|e| e.2));
+}
+
+#[test]
+fn cancel_loading() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut loader = ResourceManager::default();
+    loader.set_cancel_flag(cancel.clone());
+    let flag = cancel.clone();
+    loader.set_progress_callback(move |stage, _, _| {
+        // simulate the player backing out while the layers are loaded
+        if stage == LoadStage::Layers {
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+
+    let path = Path::new("example-maps/default/groups.tmx");
+    let error = Map::from_file_with_loader(path, &mut loader).err().unwrap();
+    assert!(matches!(error, Error::Cancelled));
+
+    cancel.store(false, Ordering::Relaxed);
+    loader.set_progress_callback(|_, _, _| {});
+    assert!(Map::from_file_with_loader(path, &mut loader).is_ok());
+}
+
+#[test]
+fn enforce_load_limits() {
+    let path = Path::new("example-maps/default/default_map.tmx");
+    let load_with = |limits: LoadLimits| {
+        let mut loader = ResourceManager::default();
+        loader.set_limits(limits);
+        Map::from_file_with_loader(path, &mut loader)
+    };
+
+    // the map is 16x16 tiles with a single tileset, i.e. 1024 bytes of tile data per layer
+    let fitting = LoadLimits {
+        max_map_size: math::ivec2::new(16, 16),
+        max_layer_bytes: 1024,
+        max_tilesets: 1,
+    };
+    assert!(load_with(fitting).is_ok());
+
+    for limits in [
+        LoadLimits {
+            max_map_size: math::ivec2::new(16, 15),
+            ..fitting
+        },
+        LoadLimits {
+            max_layer_bytes: 1023,
+            ..fitting
+        },
+        LoadLimits {
+            max_tilesets: 0,
+            ..fitting
+        },
+    ] {
+        assert!(matches!(load_with(limits), Err(Error::LimitExceeded(_))));
+    }
+}
+
+#[test]
+fn load_parts_of_map() {
+    let load_with = |path: &str, options: MapLoadOptions| {
+        let mut loader = ResourceManager::default();
+        loader.set_load_options(options);
+        Map::from_file_with_loader(Path::new(path), &mut loader).unwrap()
+    };
+
+    let map = load_with(
+        "example-maps/default/objects.tmx",
+        MapLoadOptions {
+            skip_object_layers: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(map.layers().len(), 1);
+    assert!(matches!(&map[map.layer_ids()[0]], Layer::Group(group) if group.content.is_empty()));
+
+    let map = load_with(
+        "example-maps/default/groups.tmx",
+        MapLoadOptions {
+            skip_images: true,
+            layer_names: Some(vec!["Ground".into()]),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(map.tilesets[0].image, ImageStorage::Pending));
+    assert_eq!(map.layers().len(), 1);
+    assert!(matches!(&map[map.layer_ids()[0]], Layer::Tile(layer) if layer.name == "Ground"));
+
+    let map = load_with(
+        "example-maps/default/groups.tmx",
+        MapLoadOptions {
+            header_only: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(map.size, math::ivec2::new(10, 10));
+    assert!(map.tilesets.is_empty());
+    assert!(map.layer_ids().is_empty());
+}
+
+#[test]
+fn store_tiles_compactly() {
+    let path = Path::new("example-maps/default/groups.tmx");
+    let map = Map::from_file(path).unwrap();
+    let mut loader = ResourceManager::default();
+    loader.set_load_options(MapLoadOptions {
+        compact_tiles: true,
+        ..Default::default()
+    });
+    let compact = Map::from_file_with_loader(path, &mut loader).unwrap();
+
+    fn tile_layers(map: &Map) -> impl Iterator<Item = &TileLayer> {
+        map.iter_layers().filter_map(|(layer, _)| match layer {
+            Layer::Tile(layer) => Some(layer),
+            _ => None,
+        })
+    }
+    assert_eq!(tile_layers(&map).count(), tile_layers(&compact).count());
+    for (wide, compact) in tile_layers(&map).zip(tile_layers(&compact)) {
+        assert_eq!(wide.bytes_per_tile(), 4);
+        assert!(compact.bytes_per_tile() < 4);
+        assert!(wide.tiles().eq(compact.tiles()));
+        assert!(wide.tiles().eq(wide.as_slice().unwrap().iter().copied()));
+        assert!(compact.as_slice().is_none());
+        assert!(wide.occupied_tiles().eq(compact.occupied_tiles()));
+    }
+}
+
+#[test]
+fn reparse_changed_layers() {
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let tmx = std::fs::read_to_string("example-maps/default/groups.tmx").unwrap();
+    let mut map = Map::from_xml_str(&tmx, &mut loader).unwrap();
+
+    let tiles_of = |map: &Map, name: &str| match map.find_layer(name).map(|id| &map[id]) {
+        Some(Layer::Tile(layer)) => layer.as_slice().unwrap().as_ptr(),
+        _ => panic!("missing tile layer {}", name),
+    };
+    let ground = tiles_of(&map, "Ground");
+    let magic = tiles_of(&map, "Magic");
+
+    let changed = tmx.replace(r#"name="Ground""#, r#"name="Floor""#);
+    map.reparse(&changed, &mut loader).unwrap();
+    let names: Vec<_> = map
+        .iter_layers()
+        .map(|(l, _)| l.name().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        ["Floor", "Objects", "Goodies", "Q-Blocks", "Magic", "Pipe", "Doors"]
+    );
+    assert_ne!(tiles_of(&map, "Floor"), ground);
+    assert_eq!(tiles_of(&map, "Magic"), magic);
+
+    // layers changed in memory are parsed again, even though their xml is the same
+    let floor = tiles_of(&map, "Floor");
+    let id = map.find_layer("Magic").unwrap();
+    let Layer::Tile(layer) = &mut map[id] else {
+        panic!("Magic is no tile layer");
+    };
+    let original = layer.tile(math::ivec2::new(0, 0));
+    layer
+        .set_tile(math::ivec2::new(0, 0), GID::new(99))
+        .unwrap();
+    map.reparse(&changed, &mut loader).unwrap();
+    let Some(Layer::Tile(layer)) = map.find_layer("Magic").map(|id| &map[id]) else {
+        panic!("missing tile layer Magic");
+    };
+    assert_eq!(layer.tile(math::ivec2::new(0, 0)), original);
+    assert_eq!(tiles_of(&map, "Floor"), floor);
+
+    assert!(map.reparse("<map>", &mut loader).is_err());
+    assert!(map.find_layer("Floor").is_some());
+}
+
+#[test]
+fn write_maps_as_tmx() {
+    fn tile_layers(map: &Map) -> Vec<(&Name, Vec<Option<GID>>)> {
+        map.iter_layers()
+            .filter_map(|(layer, _)| match layer {
+                Layer::Tile(layer) => Some((&layer.name, layer.tiles().collect())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    for path in [
+        "example-maps/default/groups.tmx",
+        "example-maps/default/mirrored_tiles.tmx",
+        "example-maps/default/with_extern_tileset.tmx",
+    ] {
+        let map = Map::from_file(Path::new(path)).unwrap();
+        let mut loader = ResourceManager::default();
+        loader.set_base_path("example-maps/default");
+        let written = Map::from_xml_str(&map.to_xml_string(), &mut loader).unwrap();
+
+        assert_eq!(written.size, map.size);
+        assert_eq!(written.version, map.version);
+        assert_eq!(written.tilesets.len(), map.tilesets.len());
+        for (a, b) in written.tilesets.iter().zip(&map.tilesets) {
+            assert_eq!(
+                (&a.name, a.firstgid, &a.image_path),
+                (&b.name, b.firstgid, &b.image_path)
+            );
+            assert_eq!(a.source, b.source);
+        }
+        let names = |map: &Map| -> Vec<String> {
+            map.iter_layers()
+                .map(|(layer, pops)| format!("{}/{}", layer.name(), pops))
+                .collect()
+        };
+        assert_eq!(names(&written), names(&map));
+        assert_eq!(tile_layers(&written), tile_layers(&map));
+    }
+
+    let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let written = Map::from_xml_str(&map.to_xml_string(), &mut loader).unwrap();
+    let objects = |map: &Map| -> Vec<String> {
+        map.iter_layers()
+            .filter_map(|(layer, _)| match layer {
+                Layer::Object(layer) => Some(layer.content.iter()),
+                _ => None,
+            })
+            .flatten()
+            .map(|o| format!("{} {} {:?} {:?}", o.id, o.name, o.pos, o.kind))
+            .collect()
+    };
+    assert_eq!(objects(&written), objects(&map));
+}
+
+#[test]
+fn save_map_to_file() {
+    let map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
+    let directory = std::env::temp_dir().join(format!("tego-save-{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("maps")).unwrap();
+    let path = directory.join("maps/groups.tmx");
+    map.save(&path).unwrap();
+
+    let saved = Map::from_file(&path).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(saved.layers().len(), map.layers().len());
+    assert_eq!(
+        std::fs::canonicalize(&saved.tilesets[0].image_path).unwrap(),
+        std::fs::canonicalize("example-maps/default/tiles.png").unwrap()
+    );
+}
+
+#[test]
+fn save_external_tileset() {
+    let map = Map::from_file(Path::new("example-maps/default/with_extern_tileset.tmx")).unwrap();
+    let mut tileset = map.tilesets[0].clone();
+    assert!(!tileset.to_tsx_string().contains("firstgid"));
+
+    let directory = std::env::temp_dir().join(format!("tego-tsx-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("renamed.tsx");
+    tileset.name = "Renamed".into();
+    tileset.save_tsx(&path).unwrap();
+
+    let mut map = map;
+    tileset.source = Some(path);
+    map.tilesets[0] = tileset;
+    let map_path = directory.join("map.tmx");
+    map.save(&map_path).unwrap();
+    let saved = Map::from_file(&map_path).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(saved.tilesets[0].name.as_str(), "Renamed");
+    assert_eq!(
+        std::fs::canonicalize(&saved.tilesets[0].image_path).unwrap(),
+        std::fs::canonicalize("example-maps/default/tiles.png").unwrap()
+    );
+}
+
+#[test]
+fn build_tile_meshes() {
+    let map = Map::from_file(Path::new("example-maps/default/mirrored_tiles.tmx")).unwrap();
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("first layer is no tile layer");
+    };
+    let mesh = layer.build_mesh(&map);
+    let tiles = layer.occupied_tiles().count();
+    assert_eq!(mesh.vertices.len(), tiles * 4);
+    assert_eq!(mesh.indices.len(), tiles * 6);
+    assert_eq!(
+        mesh.batches,
+        [MeshBatch {
+            tileset: 0,
+            indices: 0..tiles as u32 * 6
+        }]
+    );
+
+    let (pos, gid) = layer.occupied_tiles().next().unwrap();
+    let rect = map.tilesets[0].tile_rect(gid.to_id() - 1);
+    let corners: Vec<_> = mesh.vertices[..4].iter().map(|v| v.position).collect();
+    let x = (pos.x * 16) as f32;
+    let y = (pos.y * 16) as f32;
+    assert_eq!(
+        corners,
+        [[x, y], [x + 16., y], [x + 16., y + 16.], [x, y + 16.]]
+    );
+    let uvs: Vec<_> = mesh.vertices[..4].iter().map(|v| v.uv).collect();
+    let u = |x: i32| x as f32 / 256.;
+    let (left, top) = (u(rect.upper_left.x), u(rect.upper_left.y));
+    let (right, bottom) = (u(rect.upper_left.x + 16), u(rect.upper_left.y + 16));
+    // the first tile is flipped horizontally and vertically
+    assert!(gid.flip_horizontal() && gid.flip_vertical() && !gid.flip_diagonal());
+    assert_eq!(
+        uvs,
+        [[right, bottom], [left, bottom], [left, top], [right, top]]
+    );
+}
+
+#[test]
+fn batch_tile_meshes_in_render_order() {
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="3" height="1" tilewidth="16" tileheight="16">
+        <tileset firstgid="1" name="first" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+            <image source="first.png" width="32" height="32"/>
+        </tileset>
+        <tileset firstgid="5" name="second" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+            <image source="second.png" width="32" height="32"/>
+        </tileset>
+        <layer id="1" name="Ground" width="3" height="1">
+            <data encoding="csv">1,5,2</data>
+        </layer>
+    </map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("first layer is no tile layer");
+    };
+    let batches: Vec<_> = layer
+        .build_mesh(&map)
+        .batches
+        .into_iter()
+        .map(|batch| (batch.tileset, batch.indices))
+        .collect();
+    assert_eq!(batches, [(0, 0..6), (1, 6..12), (0, 12..18)]);
+}
+
+#[test]
+fn tileset_uv_table() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="spaced" tilewidth="8" tileheight="8" spacing="2" margin="1" tilecount="4" columns="2">
+                <image source="a.png"/>
+            </tileset>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    // the sprite sheet is 20 by 20 pixels: 1 + 8 + 2 + 8 + 1
+    let table = map.tilesets[0].uv_table();
+    assert_eq!(
+        table,
+        [
+            [0.05, 0.05, 0.45, 0.45],
+            [0.55, 0.05, 0.95, 0.45],
+            [0.05, 0.55, 0.45, 0.95],
+            [0.55, 0.55, 0.95, 0.95],
+        ]
+    );
+}
+
+#[test]
+fn extract_collision_shapes() {
+    let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let shapes = map.collision_shapes(|_, object| object.name == "Rectangle");
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].object.id, 1);
+    assert_eq!(
+        shapes[0].shape,
+        Shape::Rect {
+            center: math::fvec2::new(16.3048 + 5., 32.239 + 10.),
+            half_size: math::fvec2::new(5., 10.),
+            rotation: 0.
+        }
+    );
+
+    // tile collisions are moved to the cell of the tile and flipped with it
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="walls" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="a.png"/>
+                <tile id="0">
+                    <objectgroup>
+                        <object id="1" x="0" y="0"><polyline points="0,0 2,8"/></object>
+                    </objectgroup>
+                </tile>
+            </tileset>
+            <layer id="1" name="Walls" width="2" height="1">
+                <data encoding="csv">1,2147483649</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    let shapes = map.collision_shapes(|layer, _| layer.name() == "Walls");
+    let polylines: Vec<_> = shapes.iter().map(|s| (s.tile, s.shape.clone())).collect();
+    let line = |points: [(f32, f32); 2]| Shape::Polyline {
+        points: points
+            .into_iter()
+            .map(|(x, y)| math::fvec2::new(x, y))
+            .collect(),
+    };
+    assert_eq!(
+        polylines,
+        [
+            (Some(math::ivec2::new(0, 0)), line([(0., 0.), (2., 8.)])),
+            (Some(math::ivec2::new(1, 0)), line([(16., 0.), (14., 8.)])),
+        ]
+    );
+}
+
+#[cfg(feature = "parry2d")]
+#[test]
+fn build_colliders_of_tiles() {
+    use parry2d::math::Point;
+
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="walls" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="a.png"/>
+                <tile id="0">
+                    <objectgroup>
+                        <object id="1" x="0" y="0" width="4" height="8"/>
+                        <object id="2" x="0" y="0"><point/></object>
+                    </objectgroup>
+                </tile>
+            </tileset>
+            <layer id="1" name="Walls" width="2" height="2">
+                <data encoding="csv">0,0,0,2147483649</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    // the point has no area and is skipped, the rect is moved to the right half of the cell
+    let colliders = map.colliders(|_, _| true);
+    assert_eq!(colliders.len(), 1);
+    assert_eq!(colliders[0].tile, Some(math::ivec2::new(1, 1)));
+    assert_eq!(colliders[0].object.id, 1);
+    let collider = &colliders[0];
+    let inside = |x, y| {
+        collider
+            .shape
+            .contains_point(&collider.position, &Point::new(x, y))
+    };
+    assert!(inside(14., 12.));
+    assert!(!inside(10., 12.));
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn render_maps_to_images() {
+    let mut loader = ResourceManager::new(RgbaImageLoader::new(), FileProvider::new());
+    let path = Path::new("example-maps/default/mirrored_tiles.tmx");
+    let map = Map::from_file_with_loader(path, &mut loader).unwrap();
+    let image = render::render_to_image(&map).unwrap();
+    assert_eq!(image.dimensions(), (64, 64));
+
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("first layer is no tile layer");
+    };
+    let (pos, gid) = layer.occupied_tiles().next().unwrap();
+    // the first tile is flipped horizontally and vertically
+    assert!(gid.flip_horizontal() && gid.flip_vertical() && !gid.flip_diagonal());
+    let (sheet, rect) = map.tile_image(gid).unwrap();
+    let sheet = sheet.downcast_ref::<image::RgbaImage>().unwrap();
+    let (x, y) = ((pos.x * 16) as u32, (pos.y * 16) as u32);
+    let (u, v) = (0..16 * 16)
+        .map(|i| (i % 16, i / 16))
+        .find(|&(u, v)| {
+            let pixel = sheet.get_pixel(rect.upper_left.x as u32 + u, rect.upper_left.y as u32 + v);
+            pixel[3] == 255
+        })
+        .unwrap();
+    assert_eq!(
+        image.get_pixel(x + 15 - u, y + 15 - v),
+        sheet.get_pixel(rect.upper_left.x as u32 + u, rect.upper_left.y as u32 + v)
+    );
+
+    // a region looks the same as the corresponding part of the whole map
+    let mut region = image::RgbaImage::new(20, 12);
+    let camera = math::Rect::new(math::ivec2::new(10, 30), math::ivec2::new(20, 12));
+    render::render_region(&map, camera, &mut region).unwrap();
+    let expected = image::imageops::crop_imm(&image, 10, 30, 20, 12).to_image();
+    assert_eq!(region, expected);
+
+    // hidden by the opacity of the layer, only the background is left
+    let mut map = map;
+    map.backgroundcolor = Color::from_argb(255, 255, 0, 0);
+    let id = map.layer_ids()[0];
+    let Layer::Tile(layer) = &mut map[id] else {
+        panic!("first layer is no tile layer");
+    };
+    layer.opacity = 0.;
+    let image = render::render_to_image(&map).unwrap();
+    assert!(image.pixels().all(|p| p.0 == [255, 0, 0, 255]));
+
+    let error = render::render_to_image(&Map::from_file(path).unwrap()).unwrap_err();
+    assert!(matches!(error, Error::UnsupportedFeature(_)));
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn render_objects_to_images() {
+    let mut map = Map::from_xml_str(
+        r##"<map version="1.10" orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8">
+            <objectgroup id="1" name="Objects" color="#ff0000">
+                <object id="1" x="2" y="2" width="10" height="6"/>
+                <object id="2" x="16" y="16">
+                    <polyline points="0,0 12,0"/>
+                </object>
+                <object id="3" x="0" y="20" width="20" height="8">
+                    <text pixelsize="7" color="#0000ff">I</text>
+                </object>
+                <object id="4" x="20" y="0" width="8" height="8"/>
+                <object id="5" x="30" y="0">
+                    <polyline points="0,0 0,1000000000"/>
+                </object>
+            </objectgroup>
+        </map>"##,
+        &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    )
+    .unwrap();
+    let id = map.layer_ids()[0];
+    let Layer::Object(layer) = &mut map[id] else {
+        panic!("first layer is no object layer");
+    };
+    layer.content[3].visible = false;
+    let image = render::render_to_image(&map).unwrap();
+    let red = image::Rgba([255, 0, 0, 255]);
+    let blue = image::Rgba([0, 0, 255, 255]);
+    let empty = image::Rgba([0, 0, 0, 0]);
+
+    // outline and translucent area of the rectangle
+    assert_eq!(*image.get_pixel(2, 2), red);
+    assert_eq!(*image.get_pixel(12, 8), red);
+    assert_eq!(*image.get_pixel(6, 5), image::Rgba([255, 0, 0, 64]));
+    assert_eq!(*image.get_pixel(1, 1), empty);
+
+    assert_eq!(*image.get_pixel(20, 16), red);
+    assert_eq!(*image.get_pixel(20, 17), empty);
+
+    // the top bar and the stem of the "I"
+    assert_eq!(*image.get_pixel(0, 21), blue);
+    assert_eq!(*image.get_pixel(2, 21), blue);
+    assert_eq!(*image.get_pixel(1, 22), blue);
+    assert_eq!(*image.get_pixel(0, 22), empty);
+
+    // hidden objects are not drawn
+    assert_eq!(*image.get_pixel(24, 4), empty);
+
+    // lines far beyond the map are clipped to it
+    assert_eq!(*image.get_pixel(30, 31), red);
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn render_flipped_tile_objects_of_several_tilesets() {
+    let mut loader = ResourceManager::new(RgbaImageLoader::new(), FileProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+            <tileset firstgid="1" name="first" tilewidth="16" tileheight="16" tilecount="256" columns="16">
+                <image source="example-maps/default/tiles.png" width="256" height="256"/>
+            </tileset>
+            <tileset firstgid="257" name="second" tilewidth="16" tileheight="16" tilecount="256" columns="16">
+                <image source="example-maps/default/tiles.png" width="256" height="256"/>
+            </tileset>
+            <objectgroup id="1" name="Objects">
+                <object id="1" gid="2147483905" x="0" y="16" width="16" height="16"/>
+            </objectgroup>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    let image = render::render_to_image(&map).unwrap();
+
+    let gid = GID::new(257).unwrap().with_flips(true, false, false);
+    let (sheet, rect) = map.tile_image(gid).unwrap();
+    let sheet = sheet.downcast_ref::<image::RgbaImage>().unwrap();
+    assert_eq!(rect.upper_left, math::ivec2::new(0, 0));
+    for (u, v) in (0..16 * 16).map(|i| (i % 16, i / 16)) {
+        let expected = sheet.get_pixel(15 - u, v);
+        if expected[3] == 255 {
+            assert_eq!(image.get_pixel(u, v), expected);
+        }
+    }
+}
+
+#[test]
+fn render_with_custom_renderer() {
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl TileRenderer for Recorder {
+        fn begin_layer(&mut self, layer: &Layer) {
+            self.0.push(format!("begin {}", layer.name()));
+        }
+
+        fn end_layer(&mut self, layer: &Layer) {
+            self.0.push(format!("end {}", layer.name()));
+        }
+
+        fn draw_tile(
+            &mut self,
+            _image: &dyn std::any::Any,
+            source: math::Rect,
+            target: math::Rect,
+            transform: TileTransform,
+        ) {
+            self.0.push(format!(
+                "tile {:?} at {},{} flipped {}",
+                source.upper_left,
+                target.upper_left.x,
+                target.upper_left.y,
+                transform.flip_horizontal
+            ));
+        }
+
+        fn draw_object(&mut self, _layer: &ObjectLayer, object: &Object, offset: math::ivec2) {
+            self.0
+                .push(format!("object {} at {:?}", object.name, offset));
+        }
+    }
+
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" renderorder="left-up" width="2" height="2" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="16" tilecount="2" columns="2">
+                <image source="a.png"/>
+            </tileset>
+            <group id="1" name="Group" offsetx="1" offsety="2">
+                <layer id="2" name="Tiles" width="2" height="2">
+                    <data encoding="csv">1,0,2147483650,1</data>
+                </layer>
+                <layer id="3" name="Hidden" width="2" height="2" visible="0">
+                    <data encoding="csv">1,1,1,1</data>
+                </layer>
+                <objectgroup id="4" name="Objects" offsetx="10">
+                    <object id="1" name="Spawn" x="0" y="0"/>
+                </objectgroup>
+            </group>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    let mut recorder = Recorder::default();
+    map.render_with(&mut recorder).unwrap();
+    assert_eq!(
+        recorder.0,
+        [
+            "begin Group",
+            "begin Tiles",
+            "tile ivec2 { x: 0, y: 0 } at 9,2 flipped false",
+            "tile ivec2 { x: 8, y: 0 } at 1,2 flipped true",
+            "tile ivec2 { x: 0, y: 0 } at 1,-6 flipped false",
+            "end Tiles",
+            "begin Objects",
+            "object Spawn at ivec2 { x: 11, y: 2 }",
+            "end Objects",
+            "end Group",
+        ]
+    );
+
+    let mut recorder = Recorder::default();
+    let options = RenderOptions {
+        region: Some(math::Rect::new(
+            math::ivec2::new(0, -8),
+            math::ivec2::new(8, 8),
+        )),
+        ..Default::default()
+    };
+    map.render_with_options(&mut recorder, &options).unwrap();
+    assert_eq!(
+        recorder.0,
+        [
+            "begin Group",
+            "begin Tiles",
+            "tile ivec2 { x: 0, y: 0 } at 1,-6 flipped false",
+            "end Tiles",
+            "begin Objects",
+            "end Objects",
+            "end Group",
+        ]
+    );
+}
+
+#[test]
+fn render_flipped_tiles_of_several_tilesets() {
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl TileRenderer for Recorder {
+        fn draw_tile(
+            &mut self,
+            image: &dyn std::any::Any,
+            source: math::Rect,
+            _target: math::Rect,
+            transform: TileTransform,
+        ) {
+            self.0.push(format!(
+                "{} {:?} {:?}",
+                image.downcast_ref::<String>().unwrap(),
+                source.upper_left,
+                transform
+            ));
+        }
+    }
+
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" width="3" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="first" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                <image source="a.png"/>
+            </tileset>
+            <tileset firstgid="3" name="second" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                <image source="b.png"/>
+            </tileset>
+            <layer id="1" name="Tiles" width="3" height="1">
+                <data encoding="csv">2147483650,1073741828,536870915</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    let mut recorder = Recorder::default();
+    map.render_with(&mut recorder).unwrap();
+    let transform = |flip_horizontal, flip_vertical, flip_diagonal| TileTransform {
+        flip_horizontal,
+        flip_vertical,
+        flip_diagonal,
+    };
+    assert_eq!(
+        recorder.0,
+        [
+            format!(
+                "a.png ivec2 {{ x: 8, y: 0 }} {:?}",
+                transform(true, false, false)
+            ),
+            format!(
+                "b.png ivec2 {{ x: 8, y: 0 }} {:?}",
+                transform(false, true, false)
+            ),
+            format!(
+                "b.png ivec2 {{ x: 0, y: 0 }} {:?}",
+                transform(false, false, true)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn flip_and_rotation_of_tile_transforms() {
+    // where the pixel of a 3x2 sprite ends up, following Tiled's order of flips
+    let transformed = |t: TileTransform, (x, y): (i32, i32)| {
+        let (mut x, mut y, w, h) = match t.flip_diagonal {
+            true => (y, x, 2, 3),
+            false => (x, y, 3, 2),
+        };
+        if t.flip_horizontal {
+            x = w - 1 - x;
+        }
+        if t.flip_vertical {
+            y = h - 1 - y;
+        }
+        (x, y)
+    };
+    // the same with mirroring and a clockwise rotation
+    let flipped_and_rotated = |t: TileTransform, (mut x, mut y): (i32, i32)| {
+        let (flip_horizontal, flip_vertical, degrees) = t.to_flip_and_rotation();
+        if flip_horizontal {
+            x = 2 - x;
+        }
+        if flip_vertical {
+            y = 1 - y;
+        }
+        match degrees {
+            0. => (x, y),
+            90. => (1 - y, x),
+            _ => panic!("unexpected rotation {}", degrees),
+        }
+    };
+
+    for flags in 0..8 {
+        let transform = TileTransform {
+            flip_horizontal: flags & 1 != 0,
+            flip_vertical: flags & 2 != 0,
+            flip_diagonal: flags & 4 != 0,
+        };
+        for pixel in [(0, 0), (2, 0), (1, 1)] {
+            assert_eq!(
+                transformed(transform, pixel),
+                flipped_and_rotated(transform, pixel),
+                "{:?}",
+                transform
+            );
+        }
+    }
+}
+
+#[test]
+fn report_tiles_that_cant_be_rendered() {
+    struct NoRenderer;
+
+    impl TileRenderer for NoRenderer {
+        fn draw_tile(
+            &mut self,
+            _image: &dyn std::any::Any,
+            _source: math::Rect,
+            _target: math::Rect,
+            _transform: TileTransform,
+        ) {
+        }
+    }
+
+    let tmx = |data: &str| {
+        format!(
+            r#"<map version="1.5" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+                <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                    <image source="a.png"/>
+                </tileset>
+                <layer id="1" name="Tiles" width="2" height="1">
+                    <data encoding="csv">{}</data>
+                </layer>
+            </map>"#,
+            data
+        )
+    };
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(&tmx("1,2"), &mut loader).unwrap();
+    assert!(map.render_with(&mut NoRenderer).is_ok());
+
+    // the last tileset ends at GID 2
+    let map = Map::from_xml_str(&tmx("1,3"), &mut loader).unwrap();
+    let error = map.render_with(&mut NoRenderer).unwrap_err();
+    assert!(matches!(error, Error::RenderError(_)), "{}", error);
+
+    loader.set_load_options(MapLoadOptions {
+        skip_images: true,
+        ..Default::default()
+    });
+    let map = Map::from_xml_str(&tmx("1,2"), &mut loader).unwrap();
+    let error = map.render_with(&mut NoRenderer).unwrap_err();
+    assert!(error.to_string().contains("preload"), "{}", error);
+}
+
+#[test]
+fn render_animated_tiles() {
+    #[derive(Default)]
+    struct Sources(Vec<math::ivec2>);
+
+    impl TileRenderer for Sources {
+        fn draw_tile(
+            &mut self,
+            _image: &dyn std::any::Any,
+            source: math::Rect,
+            _target: math::Rect,
+            _transform: TileTransform,
+        ) {
+            self.0.push(source.upper_left);
+        }
+    }
+
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="8" tilecount="3" columns="3">
+                <image source="a.png"/>
+                <tile id="0">
+                    <animation>
+                        <frame tileid="1" duration="100"/>
+                        <frame tileid="2" duration="300"/>
+                    </animation>
+                </tile>
+            </tileset>
+            <layer id="1" name="Tiles" width="2" height="1">
+                <data encoding="csv">1,3</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    let sources_at = |millis| {
+        let mut sources = Sources::default();
+        map.render_animated_with(&mut sources, std::time::Duration::from_millis(millis))
+            .unwrap();
+        sources.0
+    };
+    let frame = |x| [math::ivec2::new(x, 0), math::ivec2::new(16, 0)];
+    assert_eq!(sources_at(0), frame(8));
+    assert_eq!(sources_at(99), frame(8));
+    assert_eq!(sources_at(100), frame(16));
+    // animations loop
+    assert_eq!(sources_at(450), frame(8));
+
+    // without a time, the tile itself is drawn
+    let mut sources = Sources::default();
+    map.render_with(&mut sources).unwrap();
+    assert_eq!(sources.0, frame(0));
+
+    let flipped = map.animation_frame("2147483649".parse().unwrap(), std::time::Duration::ZERO);
+    assert_eq!(flipped.to_id(), 2);
+    assert!(flipped.flip_horizontal());
+}
+
+#[test]
+fn spawn_tiles_and_objects() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                <image source="a.png"/>
+                <tile id="1" type="Chest">
+                    <properties>
+                        <property name="gold" type="int" value="10"/>
+                        <property name="locked" type="bool" value="false"/>
+                    </properties>
+                </tile>
+            </tileset>
+            <layer id="1" name="Ground" width="2" height="1">
+                <data encoding="csv">1,0</data>
+            </layer>
+            <group id="2" name="Group" offsetx="100">
+                <objectgroup id="3" name="Objects" offsety="50">
+                    <object id="1" name="Treasure" gid="2" x="16" y="8" width="8" height="8">
+                        <properties>
+                            <property name="gold" type="int" value="99"/>
+                        </properties>
+                    </object>
+                    <object id="2" name="Spawn" type="Player" x="1" y="2"/>
+                </objectgroup>
+            </group>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    let mut spawned = Vec::new();
+    map.spawn_with(|spawn| spawned.push(spawn));
+    assert_eq!(spawned.len(), 3);
+
+    let ground = &spawned[0];
+    assert_eq!(ground.layer.name(), "Ground");
+    assert_eq!(ground.cell, Some(math::ivec2::new(0, 0)));
+    assert!(ground.object.is_none());
+    assert_eq!(ground.class, "");
+    assert!(ground.image.is_some());
+
+    let treasure = &spawned[1];
+    assert_eq!(treasure.object.unwrap().name, "Treasure");
+    assert_eq!(treasure.position, math::fvec2::new(116., 58.));
+    assert_eq!(treasure.gid.unwrap().to_id(), 2);
+    assert_eq!(treasure.tile.unwrap().id(), 1);
+    assert_eq!(treasure.class, "Chest");
+    // the properties of the object override the ones of the tile
+    assert_eq!(treasure.properties["gold"], PropertyValue::Int(99));
+    assert_eq!(treasure.properties["locked"], PropertyValue::Bool(false));
+
+    let player = &spawned[2];
+    assert_eq!(player.class, "Player");
+    assert!(player.gid.is_none() && player.image.is_none());
+    assert_eq!(player.position, math::fvec2::new(101., 52.));
+}
+
+#[test]
+fn spawn_flipped_tiles_of_several_tilesets() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="first" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                <image source="a.png"/>
+            </tileset>
+            <tileset firstgid="3" name="second" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+                <image source="b.png"/>
+                <tile id="1" type="Chest"/>
+            </tileset>
+            <layer id="1" name="Ground" width="1" height="1">
+                <data encoding="csv">1073741827</data>
+            </layer>
+            <objectgroup id="2" name="Objects">
+                <object id="1" name="Treasure" gid="2147483652" x="0" y="8" width="8" height="8"/>
+            </objectgroup>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+
+    let mut spawned = Vec::new();
+    map.spawn_with(|spawn| spawned.push(spawn));
+    assert_eq!(spawned.len(), 2);
+
+    let ground = &spawned[0];
+    assert!(ground.gid.unwrap().flip_vertical());
+    let (image, rect) = ground.image.unwrap();
+    assert_eq!(image.downcast_ref::<String>().unwrap(), "b.png");
+    assert_eq!(rect.upper_left, math::ivec2::new(0, 0));
+
+    let treasure = &spawned[1];
+    assert!(treasure.gid.unwrap().flip_horizontal());
+    assert_eq!(treasure.class, "Chest");
+    let (image, rect) = treasure.image.unwrap();
+    assert_eq!(image.downcast_ref::<String>().unwrap(), "b.png");
+    assert_eq!(rect.upper_left, math::ivec2::new(8, 0));
+}
+
+#[test]
+fn build_navmesh_from_objects() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8">
+            <objectgroup id="1" name="Walkable" offsetx="8">
+                <object id="1" type="floor" x="0" y="0" width="16" height="8"/>
+                <object id="2" type="floor" x="16" y="8">
+                    <polygon points="0,-8 8,-8 8,8 0,0"/>
+                </object>
+                <object id="3" type="wall" x="0" y="16" width="8" height="8"/>
+                <object id="4" type="floor" x="0" y="20">
+                    <polyline points="0,0 8,8"/>
+                </object>
+            </objectgroup>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    let navmesh = map.build_navmesh(|_layer, object| object.type_ == "floor");
+    assert_eq!(navmesh.vertices.len(), 6);
+    assert_eq!(navmesh.triangles.len(), 4);
+
+    // all triangles are connected through the edge between the rectangle and the polygon
+    let connections = navmesh.neighbors.iter().flatten().flatten().count();
+    assert_eq!(connections, 6);
+
+    let start = navmesh.triangle_at(math::fvec2::new(9., 1.)).unwrap();
+    let end = navmesh.triangle_at(math::fvec2::new(31., 14.)).unwrap();
+    assert_ne!(start, end);
+    assert!(navmesh.triangle_at(math::fvec2::new(1., 1.)).is_none());
+    assert!(navmesh.center(end).x > 24.);
+}
+
+#[test]
+fn place_isometric_tiles() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="isometric" width="3" height="2" tilewidth="32" tileheight="16">
+            <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+                <image source="a.png"/>
+            </tileset>
+            <layer id="1" name="Ground" width="3" height="2">
+                <data encoding="csv">1,1,1,1,0,0</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    assert_eq!(map.pixel_size(), math::ivec2::new(80, 40));
+    // the first cell is the top corner of the diamond
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(0, 0)),
+        math::ivec2::new(16, 0)
+    );
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(2, 0)),
+        math::ivec2::new(48, 16)
+    );
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(0, 1)),
+        math::ivec2::new(0, 8)
+    );
+
+    // tiles are drawn from back to front: (0,0), (0,1), (1,0), (2,0)
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("first layer is no tile layer");
+    };
+    let mesh = layer.build_mesh(&map);
+    let corners: Vec<_> = mesh
+        .vertices
+        .iter()
+        .step_by(4)
+        .map(|v| v.position)
+        .collect();
+    assert_eq!(corners, [[16., -16.], [0., -8.], [32., -8.], [48., 0.]]);
+}
+
+#[test]
+fn place_hexagonal_tiles() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="hexagonal" width="3" height="2" tilewidth="32" tileheight="28"
+                hexsidelength="14" staggeraxis="x" staggerindex="even">
+            <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="28" tilecount="1" columns="1">
+                <image source="a.png"/>
+            </tileset>
+            <layer id="1" name="Ground" width="3" height="2">
+                <data encoding="csv">1,1,1,1,0,0</data>
+            </layer>
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    assert_eq!(map.stagger_axis, StaggerAxis::X);
+    assert_eq!(map.stagger_index, StaggerIndex::Even);
+    assert_eq!(map.hexsidelength, 14);
+    assert_eq!(map.pixel_size(), math::ivec2::new(78, 70));
+    // the even columns are shifted down by half a hexagon
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(0, 0)),
+        math::ivec2::new(0, 14)
+    );
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(1, 0)),
+        math::ivec2::new(23, 0)
+    );
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(1, 1)),
+        math::ivec2::new(23, 28)
+    );
+
+    let mut neighbors = map.neighbors(math::ivec2::new(1, 0));
+    neighbors.sort_by_key(|pos| (pos.x, pos.y));
+    assert_eq!(
+        neighbors,
+        [(0, -1), (0, 0), (1, -1), (1, 1), (2, -1), (2, 0)].map(math::ivec2::from)
+    );
+
+    // tiles are drawn from top to bottom: (1,0), (0,0), (2,0), (0,1)
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("first layer is no tile layer");
+    };
+    let mesh = layer.build_mesh(&map);
+    let corners: Vec<_> = mesh
+        .vertices
+        .iter()
+        .step_by(4)
+        .map(|v| v.position)
+        .collect();
+    assert_eq!(corners, [[23., 0.], [0., 14.], [46., 14.], [0., 42.]]);
+
+    // the stagger settings survive saving
+    let saved = Map::from_xml_str(&map.to_xml_string(), &mut loader).unwrap();
+    assert_eq!(saved, map);
+}
+
+#[test]
+fn staggered_neighbors() {
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(
+        r#"<map version="1.5" orientation="staggered" width="4" height="4" tilewidth="32" tileheight="16"
+                staggeraxis="y" staggerindex="odd">
+        </map>"#,
+        &mut loader,
+    )
+    .unwrap();
+    assert_eq!(map.pixel_size(), math::ivec2::new(144, 40));
+    assert_eq!(
+        map.cell_to_pixel(math::ivec2::new(1, 1)),
+        math::ivec2::new(48, 8)
+    );
+    let mut neighbors = map.neighbors(math::ivec2::new(1, 2));
+    neighbors.sort_by_key(|pos| (pos.x, pos.y));
+    assert_eq!(
+        neighbors,
+        [(0, 1), (0, 3), (1, 1), (1, 3)].map(math::ivec2::from)
+    );
+}
+
+#[test]
+fn content_hash_detects_changes() {
+    let path = Path::new("example-maps/default/with_extern_tileset.tmx");
+    let map = Map::from_file(path).unwrap();
+    let hash = map.content_hash();
+    assert_eq!(Map::from_file(path).unwrap().content_hash(), hash);
+
+    // images are not part of the hash
+    let mut loader = ResourceManager::new(LazyLoader {}, FileProvider::new());
+    let lazy = Map::from_file_with_loader(path, &mut loader).unwrap();
+    assert_eq!(lazy.content_hash(), hash);
+
+    let mut changed = Map::from_file(path).unwrap();
+    changed.tilesets[0].name = "renamed".into();
+    assert_ne!(changed.content_hash(), hash);
+
+    let mut changed = Map::from_file(path).unwrap();
+    changed.backgroundcolor = Color::from_argb(255, 1, 2, 3);
+    assert_ne!(changed.content_hash(), hash);
+
+    // tile data is hashed uncompressed, so the hash doesn't depend on the zlib encoder
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+        <layer id="1" name="Ground" width="2" height="1">
+            <data encoding="csv">1,2147483650</data>
+        </layer>
+    </map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+    assert_eq!(map.content_hash(), 5925363214327962051);
+}
+
+#[test]
+fn load_project() {
+    let project = Project::from_file(Path::new("example-maps/example.tiled-project")).unwrap();
+    assert_eq!(
+        project.folders,
+        [
+            Path::new("example-maps/default"),
+            Path::new("example-maps/island")
+        ]
+    );
+    assert_eq!(
+        project.extensions_path,
+        Path::new("example-maps/extensions")
+    );
+    assert_eq!(project.automapping_rules_file, None);
+    assert_eq!(project.compatibility_version, 1100);
+    assert_eq!(
+        project.properties["author"],
+        PropertyValue::String("tego".into())
+    );
+
+    let Some(PropertyType {
+        kind: PropertyTypeKind::Enum {
+            values, storage, ..
+        },
+        ..
+    }) = project.property_type("Direction")
+    else {
+        panic!("Direction is no enum");
+    };
+    assert_eq!(values, &["North", "East", "South", "West"]);
+    assert_eq!(*storage, EnumStorage::String);
+
+    let Some(PropertyType {
+        id: 2,
+        kind: PropertyTypeKind::Class {
+            members, use_as, ..
+        },
+        ..
+    }) = project.property_type("Enemy")
+    else {
+        panic!("Enemy is no class");
+    };
+    assert_eq!(use_as, &["property", "object", "tile"]);
+    assert_eq!(members.len(), 4);
+    assert_eq!(members[0].property_type.as_deref(), Some("Direction"));
+    assert_eq!(members[1].value, Some(PropertyValue::Int(10)));
+    assert_eq!(members[2].value, Some(PropertyValue::Float(1.5)));
+    assert_eq!(
+        members[3].value,
+        Some(PropertyValue::Color(Color::from_argb(255, 0, 255, 0)))
+    );
+
+    let maps = project.maps().unwrap();
+    assert_eq!(maps.len(), 8);
+    assert!(maps.contains(&Path::new("example-maps/island/island.tmx").to_owned()));
+    for map in &maps {
+        Map::from_file(map).unwrap();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn find_project_maps_despite_symlink_cycles() {
+    let directory = std::env::temp_dir().join(format!("tego-project-{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("maps")).unwrap();
+    std::fs::copy(
+        "example-maps/default/groups.tmx",
+        directory.join("maps/groups.tmx"),
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(&directory, directory.join("maps/parent")).unwrap();
+
+    let project = Project::from_json_str(r#"{"folders": ["maps"]}"#, &directory).unwrap();
+    let maps = project.maps();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(maps.unwrap(), [directory.join("maps/groups.tmx")]);
+}
+
+#[test]
+fn export_texturepacker_json() {
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="Sheet" tilewidth="8" tileheight="8" spacing="2" margin="1" tilecount="3" columns="2">
+                <image source="sprites/sheet.png"/>
+            </tileset>
+        </map>"#,
+        &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    )
+    .unwrap();
+    let json = map.tilesets[0].to_texturepacker_json();
+    assert!(
+        json.starts_with(r#"{"frames":{"0":{"frame":{"x":1,"y":1,"w":8,"h":8},"rotated":false"#)
+    );
+    assert!(json.contains(r#""1":{"frame":{"x":11,"y":1,"w":8,"h":8}"#));
+    assert!(json.contains(r#""2":{"frame":{"x":1,"y":11,"w":8,"h":8}"#));
+    assert!(json.contains(r#""sourceSize":{"w":8,"h":8}"#));
+    assert!(json.contains(r#""image":"sheet.png""#));
+    assert!(json.ends_with(r#""size":{"w":20,"h":20},"scale":"1"}}"#));
+}
+
+#[test]
+fn instantiate_objects_by_class() {
+    let map = Map::from_xml_str(
+        r#"<map version="1.10" orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="tiles" tilewidth="8" tileheight="8" tilecount="1" columns="1">
+                <image source="tiles.png"/>
+                <tile id="0" type="Coin">
+                    <properties><property name="value" type="int" value="5"/></properties>
+                </tile>
+            </tileset>
+            <objectgroup id="1" name="Objects">
+                <object id="1" name="Goblin" type="Enemy" x="0" y="0"/>
+                <object id="2" name="Chest" type="Loot" x="8" y="0"/>
+                <object id="3" name="Coin" gid="1" x="16" y="8" width="8" height="8">
+                    <properties><property name="value" type="int" value="10"/></properties>
+                </object>
+                <object id="4" name="Orc" type="Enemy" x="24" y="0"/>
+            </objectgroup>
+        </map>"#,
+        &mut ResourceManager::new(LazyLoader {}, EmbeddedProvider::new()),
+    )
+    .unwrap();
+
+    let mut registry = ClassRegistry::new();
+    registry
+        .on("Enemy", |object, _| format!("enemy {}", object.name))
+        .on("Coin", |_, properties| {
+            format!("coin worth {:?}", properties.get("value").unwrap())
+        });
+    assert!(registry.contains("Enemy") && !registry.contains("Loot"));
+    assert_eq!(
+        map.instantiate(&registry),
+        ["enemy Goblin", "coin worth Int(10)", "enemy Orc"]
+    );
+}
+
+#[test]
+fn version_policy() {
+    let newer = r#"<map version="1.11" tiledversion="1.11.2" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+        <future/>
+        <layer id="1" name="Ground" width="1" height="1" blend="add">
+            <data encoding="csv">0</data>
+        </layer>
+    </map>"#;
+    let load = |map: &str, version_policy| {
+        let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+        loader.set_load_options(MapLoadOptions {
+            version_policy,
+            ..MapLoadOptions::default()
+        });
+        Map::from_xml_str(map, &mut loader)
+    };
+
+    let map = load(newer, VersionPolicy::Ignore).unwrap();
+    let constructs = [
+        "element <future> in the map",
+        "attribute 'blend' of layer 'Ground'",
+    ];
+    assert_eq!(map.unsupported_constructs(), constructs);
+
+    let Err(Error::UnsupportedVersion(error)) = load(newer, VersionPolicy::Error) else {
+        panic!("newer map was loaded");
+    };
+    assert_eq!(error.version, Version(1, 11, None));
+    assert_eq!(error.constructs, constructs);
+    assert_eq!(
+        error.to_string(),
+        "Map version 1.11 (Tiled 1.11.2) is newer than the supported version 1.10, \
+        unsupported: element <future> in the map, attribute 'blend' of layer 'Ground'"
+    );
+
+    // maps of supported versions load, even if they contain unknown parts
+    let supported = newer.replace(
+        r#"version="1.11" tiledversion="1.11.2""#,
+        r#"version="1.10""#,
+    );
+    assert!(load(&supported, VersionPolicy::Error).is_ok());
+
+    let warnings = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    loader.set_load_options(MapLoadOptions {
+        version_policy: VersionPolicy::Warn,
+        ..MapLoadOptions::default()
+    });
+    let collected = warnings.clone();
+    loader
+        .set_version_warning_callback(move |warning| collected.borrow_mut().push(warning.clone()));
+    Map::from_xml_str(newer, &mut loader).unwrap();
+    Map::from_xml_str(&supported, &mut loader).unwrap();
+    assert_eq!(warnings.borrow().len(), 1);
+    assert_eq!(warnings.borrow()[0], error);
+}
+
+#[test]
+fn modify_tile_layers() {
+    let path = Path::new("example-maps/default/groups.tmx");
+    let mut loader = ResourceManager::default();
+    loader.set_load_options(MapLoadOptions {
+        compact_tiles: true,
+        ..Default::default()
+    });
+    let mut map = Map::from_file_with_loader(path, &mut loader).unwrap();
+    let id = map.layer_ids()[0];
+    let Layer::Tile(layer) = &mut map[id] else {
+        panic!("first layer is no tile layer");
+    };
+
+    let gid: GID = "70000".parse().unwrap();
+    let pos = math::ivec2::new(1, 2);
+    layer.set_tile(pos, Some(gid)).unwrap();
+    assert_eq!(layer.tile(pos), Some(gid));
+    assert!(layer.is_occupied(pos));
+    assert_eq!(layer.bytes_per_tile(), 4);
+    assert!(matches!(
+        layer.set_tile(layer.size, Some(gid)),
+        Err(Error::OutOfBounds { .. })
+    ));
+
+    layer.clear();
+    assert_eq!(layer.occupied_tiles().count(), 0);
+    assert_eq!(layer.tiles().len(), 100);
+
+    let rect = math::Rect::new(math::ivec2::new(8, -1), math::ivec2::new(5, 3));
+    layer.fill_rect(rect, Some(gid));
+    let filled: Vec<_> = layer
+        .occupied_tiles()
+        .map(|(pos, _)| (pos.x, pos.y))
+        .collect();
+    assert_eq!(filled, [(8, 0), (9, 0), (8, 1), (9, 1)]);
+    layer.fill_rect(rect, None);
+    assert_eq!(layer.occupied_tiles().count(), 0);
+}
+
+#[test]
+fn restructure_layers() {
+    let mut map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
+    let names = |map: &Map| -> Vec<String> {
+        let mut depth = 0;
+        map.iter_layers()
+            .map(|(layer, pops)| {
+                depth -= pops;
+                let name = format!("{}{}", depth, layer.name());
+                if let Layer::Group(_) = layer {
+                    depth += 1;
+                }
+                name
+            })
+            .collect()
+    };
+    let objects = map.find_layer("Objects").unwrap();
+    let goodies = map.find_layer("Goodies").unwrap();
+    let doors = map.find_layer("Doors").unwrap();
+
+    let top = map
+        .push_layer(Layer::Tile(
+            TileLayer::new("Top", math::ivec2::new(10, 10)).unwrap(),
+        ))
+        .unwrap();
+    assert_eq!(map[top].id(), Some(8));
+    let decor = map
+        .insert_layer(Some(goodies), 1, Layer::Object(ObjectLayer::new("Decor")))
+        .unwrap();
+    assert_eq!(map[decor].id(), Some(9));
+
+    map.move_layer(doors, None, 0).unwrap();
+    map.move_layer(goodies, Some(objects), 1).unwrap();
+    assert_eq!(
+        names(&map),
+        [
+            "0Doors",
+            "0Ground",
+            "0Objects",
+            "1Pipe",
+            "1Goodies",
+            "2Q-Blocks",
+            "2Decor",
+            "2Magic",
+            "0Top"
+        ]
+    );
+
+    for (parent, index) in [(Some(goodies), 0), (Some(decor), 0), (None, 5)] {
+        assert!(matches!(
+            map.move_layer(objects, parent, index),
+            Err(Error::InvalidLayer(_))
+        ));
+    }
+    let group = Layer::Group(GroupLayer::new("Group"));
+    assert!(map.insert_layer(Some(top), 0, group).is_err());
+
+    let Some(Layer::Group(removed)) = map.remove_layer(goodies) else {
+        panic!("removed layer is no group");
+    };
+    assert!(removed.content.is_empty());
+    assert!(map.layer(goodies).is_none());
+    assert!(map.layer(decor).is_none());
+    assert!(map.remove_layer(decor).is_none());
+    assert_eq!(
+        names(&map),
+        ["0Doors", "0Ground", "0Objects", "1Pipe", "0Top"]
+    );
+    assert_eq!(map[doors].name(), "Doors");
+}
+
+#[test]
+fn add_and_remove_tilesets() {
+    let mut map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let tilecount = map.tilesets[0].tilecount as u32;
+    let id = map.layer_ids()[0];
+    let used = match &map[id] {
+        Layer::Tile(layer) => layer.occupied_tiles().count(),
+        _ => panic!("first layer is no tile layer"),
+    };
+    assert!(used > 0);
+
+    let firstgid = map.add_tileset(map.tilesets[0].clone());
+    assert_eq!(firstgid.to_id(), 1 + tilecount);
+    let pos = math::ivec2::new(0, 0);
+    let Layer::Tile(layer) = &mut map[id] else {
+        unreachable!()
+    };
+    let flipped: GID = (0x80000000 | (firstgid.to_id() + 2))
+        .to_string()
+        .parse()
+        .unwrap();
+    layer.set_tile(pos, Some(flipped)).unwrap();
+
+    let removed = map.remove_tileset(0);
+    assert_eq!(removed.firstgid.to_id(), 1);
+    assert_eq!(map.tilesets[0].firstgid.to_id(), 1);
+    let Layer::Tile(layer) = &map[id] else {
+        unreachable!()
+    };
+    assert_eq!(layer.occupied_tiles().count(), 1);
+    let tile = layer.tile(pos).unwrap();
+    assert_eq!(tile.to_id(), 3);
+    assert!(tile.flip_horizontal());
+}
+
+#[test]
+fn merge_maps() {
+    let path = Path::new("example-maps/default/default_map.tmx");
+    let mut map = Map::from_file(path).unwrap();
+    let other = Map::from_file(path).unwrap();
+    let tiles = |map: &Map, id: LayerId| -> Vec<(i32, i32, u32)> {
+        let Layer::Tile(layer) = &map[id] else {
+            panic!("layer is no tile layer");
+        };
+        layer
+            .occupied_tiles()
+            .map(|(pos, gid)| (pos.x, pos.y, gid.to_id()))
+            .collect()
+    };
+    let original = tiles(&map, map.layer_ids()[0]);
+
+    map.merge(&other, math::ivec2::new(-2, 3)).unwrap();
+    assert_eq!(map.size, math::ivec2::new(18, 19));
+    assert_eq!(map.tilesets.len(), 1);
+    let moved: Vec<_> = original
+        .iter()
+        .map(|&(x, y, gid)| (x + 2, y, gid))
+        .collect();
+    assert_eq!(tiles(&map, map.layer_ids()[0]), moved);
+    let merged: Vec<_> = original
+        .iter()
+        .map(|&(x, y, gid)| (x, y + 3, gid))
+        .collect();
+    assert_eq!(tiles(&map, map.layer_ids()[1]), merged);
+    assert_eq!(map[map.layer_ids()[1]].id(), Some(2));
+
+    let objects = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    map.merge(&objects, math::ivec2::new(1, 1)).unwrap();
+    map.merge(&objects, math::ivec2::new(0, 0)).unwrap();
+    let mut ids: Vec<_> = map
+        .iter_layers()
+        .filter_map(|(layer, _)| match layer {
+            Layer::Object(layer) => Some(layer.content.iter().map(|object| object.id)),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let count = ids.len();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+    assert_eq!(
+        map.iter_layers().count(),
+        2 + 2 * objects.iter_layers().count()
+    );
+}
+
+#[test]
+fn crop_maps() {
+    let map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let rect = math::Rect::new(math::ivec2::new(2, 3), math::ivec2::new(4, 5));
+    let chunk = map.crop(rect);
+    assert_eq!(chunk.size, rect.size);
+    assert_eq!(chunk.tilesets.len(), map.tilesets.len());
+
+    let (Layer::Tile(layer), Layer::Tile(cropped)) =
+        (&map[map.layer_ids()[0]], &chunk[chunk.layer_ids()[0]])
+    else {
+        panic!("first layer is no tile layer");
+    };
+    assert_eq!(cropped.size, rect.size);
+    let expected: Vec<_> = layer
+        .occupied_tiles_in(rect)
+        .map(|(pos, gid)| (pos - rect.upper_left, gid))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(cropped.occupied_tiles().collect::<Vec<_>>(), expected);
+
+    let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let rect = math::Rect::new(math::ivec2::new(1, 1), math::ivec2::new(3, 3));
+    let chunk = map.crop(rect);
+    let objects = |map: &Map| -> Vec<Object> {
+        map.iter_layers()
+            .filter_map(|(layer, _)| match layer {
+                Layer::Object(layer) => Some(layer.content.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    };
+    let inside: Vec<_> = objects(&map)
+        .into_iter()
+        .filter(|object| (16. ..64.).contains(&object.pos.x) && (16. ..64.).contains(&object.pos.y))
+        .collect();
+    let cropped = objects(&chunk);
+    assert_eq!(inside.len(), 2);
+    assert_eq!(cropped.len(), inside.len());
+    for (cropped, object) in cropped.iter().zip(&inside) {
+        assert_eq!(cropped.id, object.id);
+        assert_eq!(cropped.pos, object.pos - math::fvec2::new(16., 16.));
+    }
+}
+
+#[test]
+fn flatten_tile_layers() {
+    let mut map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
+    let topmost = |map: &Map, skipped: &[&str]| -> Vec<Option<GID>> {
+        let mut tiles = vec![None; (map.size.x * map.size.y) as usize];
+        for (layer, _) in map.iter_layers() {
+            match layer {
+                Layer::Tile(layer) if !skipped.contains(&layer.name.as_str()) => {
+                    for (tile, gid) in tiles.iter_mut().zip(layer.tiles()) {
+                        *tile = gid.or(*tile);
+                    }
+                }
+                _ => {}
+            }
+        }
+        tiles
+    };
+
+    let flat = map.flatten_tile_layers(|_| true).unwrap();
+    assert_eq!(flat.size, map.size);
+    assert_eq!(flat.tiles().collect::<Vec<_>>(), topmost(&map, &[]));
+
+    let flat = map
+        .flatten_tile_layers(|layer| layer.name != "Ground")
+        .unwrap();
+    assert_eq!(flat.tiles().collect::<Vec<_>>(), topmost(&map, &["Ground"]));
+
+    let goodies = map.find_layer("Goodies").unwrap();
+    let Layer::Group(group) = &mut map[goodies] else {
+        panic!("Goodies is no group");
+    };
+    group.visible = false;
+    let pipe = map.find_layer("Pipe").unwrap();
+    let Layer::Tile(layer) = &mut map[pipe] else {
+        panic!("Pipe is no tile layer");
+    };
+    layer.visible = false;
+    let hidden = ["Q-Blocks", "Magic", "Pipe"];
+    let flat = map.flatten_tile_layers(|_| true).unwrap();
+    assert_eq!(flat.tiles().collect::<Vec<_>>(), topmost(&map, &hidden));
+
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let written = Map::from_xml_str(&map.to_xml_string(), &mut loader).unwrap();
+    let visible: Vec<_> = written
+        .iter_layers()
+        .filter_map(|(layer, _)| match layer {
+            Layer::Tile(layer) => Some(layer.visible),
+            Layer::Group(group) => Some(group.visible),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(visible, [true, true, false, true, true, false, true]);
+}
+
+#[test]
+fn resize_maps() {
+    let mut map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let tile: GID = "1".parse().unwrap();
+    let mut layer = TileLayer::new("Tiles", map.size).unwrap();
+    layer.set_tile(math::ivec2::new(9, 1), Some(tile)).unwrap();
+    layer.set_tile(math::ivec2::new(0, 9), Some(tile)).unwrap();
+    let id = map.push_layer(Layer::Tile(layer)).unwrap();
+    let positions = |map: &Map| -> Vec<math::fvec2> {
+        map.iter_layers()
+            .filter_map(|(layer, _)| match layer {
+                Layer::Object(layer) => Some(layer.content.iter().map(|object| object.pos)),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    };
+    let objects = positions(&map);
+
+    map.resize(math::ivec2::new(14, 7), Anchor::Center);
+    assert_eq!(map.size, math::ivec2::new(14, 7));
+    let Layer::Tile(layer) = &map[id] else {
+        unreachable!()
+    };
+    assert_eq!(layer.size, map.size);
+    let tiles: Vec<_> = layer
+        .occupied_tiles()
+        .map(|(pos, _)| (pos.x, pos.y))
+        .collect();
+    assert_eq!(tiles, [(11, 0)]);
+    let moved: Vec<_> = objects
+        .iter()
+        .map(|&pos| pos + math::fvec2::new(2. * 16., -16.))
+        .collect();
+    assert_eq!(positions(&map), moved);
+
+    map.resize(math::ivec2::new(20, 20), Anchor::BottomRight);
+    let Layer::Tile(layer) = &map[id] else {
+        unreachable!()
+    };
+    let tiles: Vec<_> = layer
+        .occupied_tiles()
+        .map(|(pos, _)| (pos.x, pos.y))
+        .collect();
+    assert_eq!(tiles, [(17, 13)]);
+}
+
+#[test]
+fn remap_gids() {
+    let mut map = Map::from_file(Path::new("example-maps/default/mirrored_tiles.tmx")).unwrap();
+    let id = map.layer_ids()[0];
+    let tiles = |map: &Map| -> Vec<Option<GID>> {
+        let Layer::Tile(layer) = &map[id] else {
+            panic!("first layer is no tile layer");
+        };
+        layer.tiles().collect()
+    };
+    let before = tiles(&map);
+    let flipped = before
+        .iter()
+        .flatten()
+        .find(|gid| gid.flip_horizontal() || gid.flip_vertical() || gid.flip_diagonal())
+        .copied()
+        .unwrap();
+    let target: GID = "42".parse().unwrap();
+    let mapping = std::collections::HashMap::from([(flipped, target)]);
+
+    map.remap_gids(&mapping);
+    for (&old, new) in before.iter().zip(tiles(&map)) {
+        match (old, new) {
+            (Some(old), Some(new)) if old.to_id() == flipped.to_id() => {
+                assert_eq!(new.to_id(), 42);
+                assert_eq!(
+                    (
+                        new.flip_horizontal(),
+                        new.flip_vertical(),
+                        new.flip_diagonal()
+                    ),
+                    (
+                        old.flip_horizontal(),
+                        old.flip_vertical(),
+                        old.flip_diagonal()
+                    )
+                );
+            }
+            _ => assert_eq!(new, old),
+        }
+    }
+}
+
+#[test]
+fn pack_tilesets_into_atlas() {
+    let mut map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let tilecount = map.tilesets[0].tilecount;
+    let second = map.add_tileset(map.tilesets[0].clone());
+    let id = map.layer_ids()[0];
+    let Layer::Tile(layer) = &mut map[id] else {
+        panic!("first layer is no tile layer");
+    };
+    let pos = math::ivec2::new(0, 0);
+    layer.set_tile(pos, Some(second)).unwrap();
+    let before: Vec<_> = layer.tiles().collect();
+
+    let atlas = map.pack_tilesets(&[1, 0], "atlas.png").unwrap();
+    assert_eq!(map.tilesets.len(), 1);
+    let tileset = &map.tilesets[0];
+    assert_eq!(tileset.name, "atlas");
+    assert_eq!(tileset.firstgid.to_id(), 1);
+    assert_eq!(tileset.tilecount, 2 * tilecount);
+    assert_eq!(Some(atlas.size), tileset.image_size);
+    assert_eq!(atlas.tiles.len(), 2 * tilecount);
+    for (local_id, tile) in atlas.tiles.iter().enumerate() {
+        assert_eq!(tile.target, tileset.tile_rect(local_id as u32));
+        assert_eq!(tile.source.size, tileset.tile_size);
+        let end = tile.target.upper_left + tile.target.size;
+        assert!(end.x <= atlas.size.x && end.y <= atlas.size.y);
+    }
+
+    // the second tileset was packed first
+    let Layer::Tile(layer) = &map[id] else {
+        unreachable!()
+    };
+    assert_eq!(layer.tile(pos).unwrap().to_id(), 1);
+    for (old, new) in before.iter().zip(layer.tiles()).skip(1) {
+        assert_eq!(
+            old.map(|gid| gid.to_id() + tilecount as u32),
+            new.map(|gid| gid.to_id())
+        );
+    }
+}
+
+#[test]
+fn report_tileset_usage() {
+    let map = Map::from_file(Path::new("example-maps/island/island.tmx")).unwrap();
+    let firstgid = map.tilesets[0].firstgid.to_id();
+    let placed: std::collections::BTreeSet<u32> = map
+        .iter_layers()
+        .filter_map(|(layer, _)| match layer {
+            Layer::Tile(layer) => Some(layer.occupied_tiles().map(|(_, gid)| gid.to_id())),
+            _ => None,
+        })
+        .flatten()
+        .map(|id| id - firstgid)
+        .collect();
+    assert!(placed.contains(&148));
+
+    let usage = map.tileset_usage();
+    assert_eq!(usage.len(), map.tilesets.len());
+    assert!(usage[0].is_superset(&placed));
+    let frames: Vec<_> = usage[0].difference(&placed).copied().collect();
+    assert!(frames
+        .iter()
+        .all(|id| [46, 55, 64, 157, 166, 175].contains(id)));
+    assert!(usage[0].contains(&157) && usage[0].contains(&175));
+    assert!(usage[0].len() < map.tilesets[0].tilecount);
+}
+
+#[test]
+fn diff_maps() {
+    let path = Path::new("example-maps/default/objects.tmx");
+    let tmx = std::fs::read_to_string(path).unwrap();
+    let load = |tmx: &str| {
+        let mut loader = ResourceManager::default();
+        loader.set_base_path("example-maps/default");
+        Map::from_xml_str(tmx, &mut loader).unwrap()
+    };
+    let old = load(&tmx);
+    assert_eq!(diff(&old, &load(&tmx)), []);
+
+    let edited = tmx
+        .replace(r#"name="Ellipse" x="45.2087""#, r#"name="Ellipse" x="50""#)
+        .replace(r#"value="42""#, r#"value="43""#)
+        .replace(
+            r#"<object id="9" template="Polygon.tx" x="0.555845" y="184.17"/>"#,
+            "",
+        )
+        .replace(r#"name="Top Object Layer""#, r#"name="Top""#);
+    let mut new = load(&edited);
+    let id = new
+        .push_layer(Layer::Tile(TileLayer::new("Ground", new.size).unwrap()))
+        .unwrap();
+    let Layer::Tile(layer) = &mut new[id] else {
+        unreachable!()
+    };
+    let gid: GID = "3".parse().unwrap();
+    layer.set_tile(math::ivec2::new(4, 2), Some(gid)).unwrap();
+
+    let layer = "Group/Child Object Layer".to_string();
+    let differences = diff(&old, &new);
+    assert_eq!(
+        differences,
+        [
+            Difference::ObjectChanged {
+                layer: layer.clone(),
+                id: 5
+            },
+            Difference::PropertyChanged {
+                owner: PropertyOwner::Object {
+                    layer: layer.clone(),
+                    id: 8
+                },
+                name: "The Answer".into(),
+                old: Some(PropertyValue::Int(42)),
+                new: Some(PropertyValue::Int(43)),
+            },
+            Difference::ObjectRemoved { layer, id: 9 },
+            Difference::LayerRemoved {
+                layer: "Top Object Layer".into()
+            },
+            Difference::LayerAdded {
+                layer: "Top".into()
+            },
+            Difference::LayerAdded {
+                layer: "Ground".into()
+            },
+        ]
+    );
+    assert_eq!(
+        differences[1].to_string(),
+        "Property 'The Answer' of object 8 in layer 'Group/Child Object Layer' changed from Int(42) to Int(43)"
+    );
+
+    let mut changed = load(&edited);
+    let ground = TileLayer::new("Ground", changed.size).unwrap();
+    changed.push_layer(Layer::Tile(ground)).unwrap();
+    assert_eq!(
+        diff(&changed, &new),
+        [Difference::TileChanged {
+            layer: "Ground".into(),
+            pos: math::ivec2::new(4, 2),
+            old: None,
+            new: Some(gid)
+        }]
+    );
+}
+
+#[test]
+fn diff_layers_with_the_same_name() {
+    let tmx = |second: &str, properties: &str| {
+        format!(
+            r#"<map version="1.8" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+                <properties>{}</properties>
+                <layer id="1" name="Ground" width="2" height="1"><data encoding="csv">1,1</data></layer>
+                <layer id="2" name="Ground" width="2" height="1"><data encoding="csv">{}</data></layer>
+            </map>"#,
+            properties, second
+        )
+    };
+    let load = |tmx: String| {
+        let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+        Map::from_xml_str(&tmx, &mut loader).unwrap()
+    };
+    let property = |name: &str, value: i32| {
+        format!(
+            r#"<property name="{}" type="int" value="{}"/>"#,
+            name, value
+        )
+    };
+    let old = load(tmx(
+        "0,2",
+        &[property("b", 1), property("c", 1), property("a", 1)].concat(),
+    ));
+    let new = load(tmx(
+        "0,3",
+        &[property("c", 2), property("a", 2), property("b", 2)].concat(),
+    ));
+
+    let changed = |name: &str| Difference::PropertyChanged {
+        owner: PropertyOwner::Map,
+        name: name.into(),
+        old: Some(PropertyValue::Int(1)),
+        new: Some(PropertyValue::Int(2)),
+    };
+    assert_eq!(
+        diff(&old, &new),
+        [
+            changed("a"),
+            changed("b"),
+            changed("c"),
+            Difference::TileChanged {
+                layer: "Ground".into(),
+                pos: math::ivec2::new(1, 0),
+                old: Some("2".parse().unwrap()),
+                new: Some("3".parse().unwrap()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn compare_maps() {
+    let path = Path::new("example-maps/default/groups.tmx");
+    let map = Map::from_file(path).unwrap();
+    let mut loader = ResourceManager::default();
+    loader.set_load_options(MapLoadOptions {
+        compact_tiles: true,
+        ..Default::default()
+    });
+    let mut compact = Map::from_file_with_loader(path, &mut loader).unwrap();
+    assert_eq!(compact, map);
+
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let written = Map::from_xml_str(&map.to_xml_string(), &mut loader).unwrap();
+    assert_eq!(written, map);
+
+    // moving a layer away and back changes the handles, but not the map
+    let doors = compact.find_layer("Doors").unwrap();
+    let objects = compact.find_layer("Objects").unwrap();
+    let removed = compact.remove_layer(doors).unwrap();
+    assert_ne!(compact, map);
+    compact.insert_layer(Some(objects), 2, removed).unwrap();
+    assert_eq!(compact, map);
+
+    let ground = compact.layer_ids()[0];
+    let Layer::Tile(layer) = &mut compact[ground] else {
+        panic!("first layer is no tile layer");
+    };
+    layer.clear();
+    assert_ne!(compact, map);
+}
+
+#[test]
+fn export_objects_as_geojson() {
+    let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let id = map.find_layer("Top Object Layer").unwrap();
+    let Layer::Object(layer) = &map[id] else {
+        panic!("layer is no object layer");
+    };
+    assert_eq!(
+        layer.to_geojson(),
+        concat!(
+            r#"{"type":"FeatureCollection","name":"Top Object Layer","features":["#,
+            r#"{"type":"Feature","id":1,"geometry":{"type":"Polygon","coordinates":"#,
+            r#"[[[16.3048,32.239],[26.3048,32.239],[26.3048,52.239],[16.3048,52.239],[16.3048,32.239]]]},"#,
+            r#""properties":{"name":"Rectangle","type":""}},"#,
+            r#"{"type":"Feature","id":2,"geometry":{"type":"Point","coordinates":[120.248,25.013]},"#,
+            r#""properties":{"name":"Point","type":""}}]}"#,
+        )
+    );
+
+    let id = map.find_layer("Child Object Layer").unwrap();
+    let Layer::Object(layer) = &map[id] else {
+        panic!("layer is no object layer");
+    };
+    let json = layer.to_geojson();
+    assert!(json.contains(
+        r#""name":"Special Polygon","type":"","Overwrite":"Specialization","The Answer":42}"#
+    ));
+    assert_eq!(
+        json.matches(r#""type":"Feature""#).count(),
+        layer.content.len()
+    );
+}
+
+#[test]
+fn export_maps_as_svg() {
+    let map = Map::from_file(Path::new("example-maps/default/objects.tmx")).unwrap();
+    let svg = map.to_svg(&tego::SvgOptions::default());
+    assert!(svg.contains(r#"<svg xmlns="http://www.w3.org/2000/svg""#));
+    assert!(svg.contains(r#"data-name="Top Object Layer""#));
+    assert!(svg.contains(r#"x="16.3048" y="32.239" width="10" height="20""#));
+    assert!(svg.contains(r#"<circle"#));
+    assert!(!svg.contains(r##"stroke="#808080""##));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    let map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let svg = map.to_svg(&tego::SvgOptions {
+        tile_outlines: true,
+    });
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("layer is no tile layer");
+    };
+    assert_eq!(
+        svg.matches(r##"stroke="#808080""##).count(),
+        layer.occupied_tiles().count()
+    );
+}
+
+#[test]
+fn tile_layers_as_csv() {
+    let map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("layer is no tile layer");
+    };
+    let csv = layer.to_csv();
+    assert_eq!(csv.lines().count(), 16);
+
+    let copy = TileLayer::from_csv("Copy", &csv).unwrap();
+    assert_eq!(copy.size, layer.size);
+    assert!(copy.tiles().eq(layer.tiles()));
+
+    // csv encoded tile data in a map
+    let tmx = std::fs::read_to_string("example-maps/default/default_map.tmx").unwrap();
+    let start = tmx.find("<data").unwrap();
+    let end = tmx.find("</data>").unwrap();
+    let tmx = format!(
+        "{}<data encoding=\"csv\">\n{}{}",
+        &tmx[..start],
+        csv,
+        &tmx[end..]
+    );
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let loaded = Map::from_xml_str(&tmx, &mut loader).unwrap();
+    assert_eq!(loaded, map);
+
+    assert!(TileLayer::from_csv("Broken", "1,2,3\n4,5\n").is_err());
+}
+
+#[test]
+fn reject_incomplete_tile_data() {
+    for data in [
+        r#"<data encoding="csv">1,2</data>"#,
+        r#"<data encoding="base64">AQAAAA==</data>"#,
+        "",
+    ] {
+        let tmx = format!(
+            r#"<map version="1.8" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16">
+                <layer id="1" name="Ground" width="3" height="2">{}</layer>
+            </map>"#,
+            data
+        );
+        let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+        let error = Map::from_xml_str(&tmx, &mut loader).unwrap_err();
+        assert!(matches!(error, Error::StructureError { .. }), "{}", error);
+    }
+}
+
+#[test]
+fn reject_huge_tile_layers() {
+    let error = TileLayer::new("Ground", math::ivec2::new(i32::MAX, 2)).unwrap_err();
+    assert!(matches!(error, Error::LimitExceeded(_)), "{}", error);
+
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="100000" height="100000" tilewidth="16" tileheight="16">
+        <layer id="1" name="Ground" width="100000" height="100000">
+            <data encoding="csv">1</data>
+        </layer>
+    </map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let error = Map::from_xml_str(tmx, &mut loader).unwrap_err();
+    assert!(matches!(error, Error::LimitExceeded(_)), "{}", error);
+}
+
+#[test]
+fn reject_tile_data_without_encoding() {
+    let tmx = r#"<map version="1.8" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+        <layer id="1" name="Ground" width="2" height="1">
+            <data><tile gid="1"/><tile/></data>
+        </layer>
+    </map>"#;
+    let mut loader = ResourceManager::new(LazyLoader {}, EmbeddedProvider::new());
+    let error = Map::from_xml_str(tmx, &mut loader).unwrap_err();
+    assert!(matches!(error, Error::UnsupportedFeature(_)), "{}", error);
+}
+
+#[test]
+fn render_tile_layers_as_ascii() {
+    let mut layer = TileLayer::new("Ground", math::ivec2::new(3, 2)).unwrap();
+    layer
+        .set_tile(math::ivec2::new(0, 0), Some("1".parse().unwrap()))
+        .unwrap();
+    layer
+        .set_tile(math::ivec2::new(2, 1), Some("2".parse().unwrap()))
+        .unwrap();
+    let text = layer.render_ascii(|gid| if gid.to_id() == 1 { '#' } else { '~' });
+    assert_eq!(text, "#..\n..~\n");
+
+    let map = Map::from_file(Path::new("example-maps/island/island.tmx")).unwrap();
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("layer is no tile layer");
+    };
+    let text = layer.render_ascii(|gid| match map.tile(gid) {
+        Some(tile) if !tile.animation().is_empty() => '~',
+        Some(_) => '#',
+        None => '?',
+    });
+    assert_eq!(text.lines().count(), layer.size.y as usize);
+    assert!(text.lines().all(|line| line.len() == layer.size.x as usize));
+    assert!(text.contains('~'));
+    assert!(!text.contains('?'));
+}
+
+#[test]
+fn draw_minimaps() {
+    let mut map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let Layer::Tile(layer) = &map[map.layer_ids()[0]] else {
+        panic!("layer is no tile layer");
+    };
+    let occupied: Vec<_> = layer.occupied_tiles().map(|(pos, _)| pos).collect();
+    let red = Color::from_argb(255, 255, 0, 0);
+    let pixels = map.minimap(|_| red);
+    assert_eq!(pixels.len(), (map.size.x * map.size.y * 4) as usize);
+    for y in 0..map.size.y {
+        for x in 0..map.size.x {
+            let index = ((y * map.size.x + x) * 4) as usize;
+            let expected = if occupied.contains(&math::ivec2::new(x, y)) {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            assert_eq!(pixels[index..index + 4], expected);
+        }
+    }
+
+    // half transparent tiles on a blue background
+    map.backgroundcolor = Color::from_argb(255, 0, 0, 255);
+    let pixels = map.minimap(|_| Color::from_argb(128, 255, 0, 0));
+    let pos = occupied[0];
+    let index = ((pos.y * map.size.x + pos.x) * 4) as usize;
+    assert_eq!(pixels[index..index + 4], [128, 0, 127, 255]);
+}
+
+#[test]
+fn visit_maps() {
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        tiles: usize,
+        objects: usize,
+    }
+
+    impl MapVisitor for Recorder {
+        fn visit_map(&mut self, map: &Map) {
+            self.events
+                .push(format!("map {}x{}", map.size.x, map.size.y));
+        }
+        fn visit_layer(&mut self, layer: &Layer) {
+            self.events.push(format!("layer {}", layer.name()));
+        }
+        fn leave_group(&mut self, group: &GroupLayer) {
+            self.events.push(format!("end {}", group.name));
+        }
+        fn visit_object(&mut self, _layer: &ObjectLayer, _object: &Object) {
+            self.objects += 1;
+        }
+        fn visit_tile(&mut self, layer: &TileLayer, pos: math::ivec2, gid: GID) {
+            assert_eq!(layer.tile(pos), Some(gid));
+            self.tiles += 1;
+        }
+    }
+
+    let map = Map::from_file(Path::new("example-maps/default/groups.tmx")).unwrap();
+    let mut recorder = Recorder::default();
+    map.accept(&mut recorder);
+
+    assert_eq!(recorder.events[0], "map 10x10");
+    let layers: Vec<String> = map
+        .iter_layers()
+        .map(|(layer, _)| format!("layer {}", layer.name()))
+        .collect();
+    let visited: Vec<&String> = recorder.events[1..]
+        .iter()
+        .filter(|event| event.starts_with("layer "))
+        .collect();
+    assert_eq!(visited, layers.iter().collect::<Vec<_>>());
+    let groups = map
+        .iter_layers()
+        .filter(|(layer, _)| matches!(layer, Layer::Group(_)))
+        .count();
+    assert_eq!(
+        recorder
+            .events
+            .iter()
+            .filter(|event| event.starts_with("end "))
+            .count(),
+        groups
+    );
+
+    let mut tiles = 0;
+    let mut objects = 0;
+    for (layer, _) in map.iter_layers() {
+        match layer {
+            Layer::Tile(layer) => tiles += layer.occupied_tiles().count(),
+            Layer::Object(layer) => objects += layer.content.len(),
+            _ => {}
+        }
+    }
+    assert!(tiles > 0);
+    assert_eq!(recorder.tiles, tiles);
+    assert_eq!(recorder.objects, objects);
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn load_maps_from_cache() {
+    for path in [
+        "example-maps/default/groups.tmx",
+        "example-maps/default/objects.tmx",
+        "example-maps/default/mirrored_tiles.tmx",
+        "example-maps/island/island.tmx",
+    ] {
+        let map = Map::from_file(Path::new(path)).unwrap();
+        let cache = map.to_cache();
+
+        let mut loader = ResourceManager::default();
+        let loaded = Map::from_cache(&cache, &mut loader).unwrap();
+        assert_eq!(loaded, map, "{}", path);
+        assert!(loaded
+            .tilesets
+            .iter()
+            .all(|tileset| matches!(tileset.image, ImageStorage::SpriteSheet(_))));
+        assert_eq!(loaded.to_cache(), cache);
+
+        assert!(matches!(
+            Map::from_cache(&cache[..cache.len() - 1], &mut loader),
+            Err(Error::InvalidCache(_))
+        ));
+    }
+}
+
+#[test]
+fn preserve_unknown_xml() {
+    let tmx = r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16" class="level" compressionlevel="-1">
+ <editorsettings><export target="level.json" format="json"/></editorsettings>
+ <tileset firstgid="1" name="Example Tileset" tilewidth="16" tileheight="16" tilecount="256" columns="16" objectalignment="bottom">
+  <tileoffset x="0" y="4"/>
+  <image source="tiles.png" width="256" height="256"/>
+ </tileset>
+ <layer id="1" name="Ground" width="2" height="1" opacity="0.5" parallaxx="2">
+  <data encoding="csv">1,2</data>
+ </layer>
+ <group id="2" name="Group" class="decoration">
+  <objectgroup id="3" name="Objects" draworder="index">
+   <object id="1" name="Door" class="door" x="4" y="8"/>
+  </objectgroup>
+ </group>
+ <imagelayer id="4" name="Sky" repeatx="1">
+  <image source="sky.png" width="64" height="64"/>
+ </imagelayer>
+</map>"#;
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+    let map = Map::from_xml_str(tmx, &mut loader).unwrap();
+    let written = map.to_xml_string();
+    for expected in [
+        r#"class="level""#,
+        r#"compressionlevel="-1""#,
+        r#"<export target="level.json" format="json"/>"#,
+        r#"objectalignment="bottom""#,
+        r#"<tileoffset x="0" y="4"/>"#,
+        r#"opacity="0.5""#,
+        r#"parallaxx="2""#,
+        r#"class="decoration""#,
+        r#"draworder="index""#,
+        // the class of objects is written as type, which Tiled still reads
+        r#"type="door""#,
+        r#"repeatx="1""#,
+        r#"<image source="sky.png" width="64" height="64"/>"#,
+    ] {
+        assert!(
+            written.contains(expected),
+            "{} missing in {}",
+            expected,
+            written
+        );
+    }
+
+    // paths are written as they are stored, including the base path
+    let reloaded = Map::from_xml_str(&written, &mut ResourceManager::default()).unwrap();
+    assert_eq!(reloaded, map);
+    assert_eq!(reloaded.to_xml_string(), written);
+}
+
+#[test]
+fn peek_map_info() {
+    let info = MapInfo::peek(Path::new("example-maps/default/groups.tmx")).unwrap();
+
+    assert_eq!(info.version, Version(1, 5, None));
+    assert_eq!(info.editor_version, Some(Version(1, 7, Some(2))));
+    assert_eq!(info.orientation, Orientation::Orthogonal);
+    assert_eq!(info.size, math::ivec2::new(10, 10));
+    assert_eq!(info.tile_size, math::ivec2::new(16, 16));
+    assert_eq!(
+        info.layer_names,
+        ["Ground", "Objects", "Goodies", "Q-Blocks", "Magic", "Pipe", "Doors"]
+    );
+
+    let mut provider = EmbeddedProvider::new();
+    provider.add(
+        "maps/level.tmx",
+        br#"<map version="1.8" orientation="isometric" width="3" height="2" tilewidth="32" tileheight="16">
+            <layer id="1" name="Floor" width="3" height="2"><data encoding="csv">0,0,0,0,0,0</data></layer>
+        </map>"#,
+    );
+    let loader = ResourceManager::new(LazyLoader {}, provider);
+    let info = MapInfo::peek_with(Path::new("maps/level.tmx"), &loader).unwrap();
+    assert_eq!(info.orientation, Orientation::Isometric);
+    assert_eq!(info.layer_names, ["Floor"]);
+}
+
+#[test]
+fn load_object_template() {
+    let mut loader = ResourceManager::default();
+    loader.set_base_path("example-maps/default");
+
+    let template = loader.template("Polygon.tx").unwrap();
+    assert_eq!(template.name, "Polygon");
+    assert!(matches!(template.kind, ObjectKind::Polygon { .. }));
+    assert!(matches!(
+        &template.properties["Overwrite"],
+        PropertyValue::String(value) if value == "Template"
+    ));
+
+    // templates are cached
+    assert!(std::rc::Rc::ptr_eq(
+        &template,
+        &loader.template("./Polygon.tx").unwrap()
+    ));
+}
+
+#[test]
+fn case_insensitive_file_fallback() {
+    let mut provider = FileProvider::new();
+    provider.set_case_insensitive_fallback(true);
+
+    let expected = std::fs::read("example-maps/default/tiles.png").unwrap();
+    let path = Path::new("example-maps/Default/TILES.png");
+    assert_eq!(provider.read(path).unwrap(), expected);
+    assert!(provider.modified(path).is_some());
+    assert!(provider
+        .read(Path::new("example-maps/default/missing.png"))
+        .is_err());
+}
+
+#[test]
+fn override_files_with_providers() {
+    let mut mod_files = EmbeddedProvider::new();
+    mod_files.add(
+        "example-maps/default/test.tsx",
+        br#"<tileset name="ModdedTileset" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+                <image source="modded.png"/>
+            </tileset>"#,
+    );
+
+    let mut loader = ResourceManager::default();
+    loader.push_provider(mod_files);
+
+    let map = Map::from_file_with_loader(
+        Path::new("example-maps/default/with_extern_tileset.tmx"),
+        &mut loader,
+    )
+    .unwrap();
+    assert_eq!(map.tilesets[0].name, "ModdedTileset");
+    assert_eq!(
+        map.tilesets[0].image_path,
+        Path::new("example-maps/default/modded.png")
+    );
+
+    // files that are not overridden are still found in the base provider
+    assert!(loader.load_text("groups.tmx").is_ok());
+
+    // other errors are not hidden by the providers below
+    loader.push_provider(|path: &Path| -> Result<Vec<u8>> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is locked", path.display()),
+        )
+        .into())
+    });
+    let error = loader.load_text("groups.tmx").unwrap_err();
+    assert!(
+        matches!(&error, Error::IO(e) if e.kind() == std::io::ErrorKind::PermissionDenied),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn use_closures_as_loader_and_provider() {
+    use std::{any::Any, path::PathBuf};
+
+    let loader = |path: &Path| -> Result<Box<dyn Any>> { Ok(Box::new(path.to_owned())) };
+    let provider = |path: &Path| -> Result<Vec<u8>> {
+        assert_eq!(path, Path::new("maps/test.tsx"));
+        Ok(include_bytes!("../example-maps/default/test.tsx").to_vec())
+    };
+
+    let mut resource_manager = ResourceManager::new(loader, provider);
+    resource_manager.set_base_path("maps");
+    let map = Map::from_xml_str(
+        include_str!("../example-maps/default/with_extern_tileset.tmx"),
+        &mut resource_manager,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        &map.tilesets[0].image,
+        ImageStorage::SpriteSheet(image)
+        if image.downcast_ref::<PathBuf>().unwrap() == Path::new("maps/tiles.png")
+    ));
+}
+
+#[test]
+fn preload_skipped_images() {
+    let mut loader = ResourceManager::default();
+    loader.set_load_options(MapLoadOptions {
+        skip_images: true,
+        ..Default::default()
+    });
+    let mut map = Map::from_file_with_loader(
+        Path::new("example-maps/default/default_map.tmx"),
+        &mut loader,
+    )
+    .unwrap();
+    let gid: GID = "1".parse().unwrap();
+    assert!(map.tile_image(gid).is_none());
+
+    loader.preload(&mut map).unwrap();
+    assert!(matches!(
+        map.tilesets[0].image,
+        ImageStorage::SpriteSheet(_)
+    ));
+    assert!(map.tile_image(gid).is_some());
+}
+
+#[test]
+fn share_interned_names() {
+    let mut loader = ResourceManager::default();
+    let map = Map::from_file_with_loader(Path::new("example-maps/default/groups.tmx"), &mut loader)
+        .unwrap();
+
+    let tiles = loader.intern("tiles");
+    assert!(Name::ptr_eq(&map.tilesets[0].name, &tiles));
+    assert!(!Name::ptr_eq(&Name::from("tiles"), &tiles));
+}
+
+#[test]
+fn cache_tile_rects() {
+    let mut map = Map::from_file(Path::new("example-maps/default/default_map.tmx")).unwrap();
+    let tileset = &mut map.tilesets[0];
+    tileset.margin = 1;
+    tileset.spacing = 2;
+
+    let computed: Vec<_> = (0..tileset.tilecount as u32)
+        .map(|id| tileset.tile_rect(id))
+        .collect();
+    tileset.cache_tile_rects();
+    let cached: Vec<_> = (0..tileset.tilecount as u32)
+        .map(|id| tileset.tile_rect(id))
+        .collect();
+    assert_eq!(computed, cached);
+
+    // second tile in the second row
+    assert_eq!(
+        tileset.tile_rect(17),
+        math::Rect::new(math::ivec2::new(19, 19), math::ivec2::new(16, 16))
+    );
+}
+
+#[test]
+fn query_occupied_tiles() {
+    let map = Map::from_file(Path::new("example-maps/island/island.tmx")).unwrap();
+    let Some(Layer::Tile(layer)) = map.layers().find(|l| matches!(l, Layer::Tile(_))) else {
+        panic!("island map has no tile layer");
+    };
+
+    let scanned: Vec<_> = (0..layer.size.y)
+        .flat_map(|y| (0..layer.size.x).map(move |x| math::ivec2::new(x, y)))
+        .filter_map(|pos| layer.tile(pos).map(|gid| (pos, gid)))
+        .collect();
+    assert_eq!(layer.occupied_tiles().collect::<Vec<_>>(), scanned);
+    assert_eq!(layer.tiles().flatten().count(), scanned.len());
+
+    let area = math::Rect::new(math::ivec2::new(-2, 3), math::ivec2::new(7, 4));
+    let expected: Vec<_> = scanned
+        .iter()
+        .copied()
+        .filter(|(pos, _)| pos.x < 5 && (3..7).contains(&pos.y))
+        .collect();
+    assert_eq!(layer.occupied_tiles_in(area).collect::<Vec<_>>(), expected);
+
+    assert!(!layer.is_occupied(math::ivec2::new(-1, 0)));
+    assert_eq!(layer.tile(layer.size), None);
+}
+
+#[cfg(feature = "streaming")]
+#[test]
+fn stream_maps_from_reader() {
+    use std::{fs::File, io::BufReader};
+
+    for path in [
+        "example-maps/default/groups.tmx",
+        "example-maps/default/objects.tmx",
+        "example-maps/island/island.tmx",
+    ] {
+        let path = Path::new(path);
+        let expected = Map::from_file(path).unwrap();
+
+        let mut loader = ResourceManager::default();
+        loader.set_base_path(path.parent().unwrap());
+        let reader = BufReader::with_capacity(64, File::open(path).unwrap());
+        let map = Map::from_reader(reader, &mut loader).unwrap();
+
+        assert_eq!(map.size, expected.size);
+        assert_eq!(map.tilesets.len(), expected.tilesets.len());
+        assert_eq!(map.iter_layers().count(), expected.iter_layers().count());
+        for ((layer, pops), (expected_layer, expected_pops)) in
+            map.iter_layers().zip(expected.iter_layers())
+        {
+            assert_eq!(pops, expected_pops);
+            match (layer, expected_layer) {
+                (Layer::Tile(layer), Layer::Tile(expected_layer)) => {
+                    assert_eq!(layer.name, expected_layer.name);
+                    assert!(layer.tiles().eq(expected_layer.tiles()));
+                }
+                (Layer::Object(layer), Layer::Object(expected_layer)) => {
+                    assert_eq!(layer.name, expected_layer.name);
+                    let names = |l: &ObjectLayer| {
+                        l.content.iter().map(|o| o.name.clone()).collect::<Vec<_>>()
+                    };
+                    assert_eq!(names(layer), names(expected_layer));
+                }
+                (Layer::Group(layer), Layer::Group(expected_layer)) => {
+                    assert_eq!(layer.name, expected_layer.name);
+                }
+                (Layer::Image(_), Layer::Image(_)) => {}
+                _ => panic!("layer kinds differ in {}", path.display()),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn trace_loading_maps() {
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the names of all spans.
+    #[derive(Default, Clone)]
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            Id::from_u64(names.len() as u64)
+        }
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+        fn event(&self, _event: &Event<'_>) {}
+        fn enter(&self, _span: &Id) {}
+        fn exit(&self, _span: &Id) {}
+    }
+
+    // global, since the tile data may be decoded on other threads
+    let names = SpanNames::default();
+    tracing::subscriber::set_global_default(names.clone()).unwrap();
+    let mut loader = ResourceManager::new(LazyLoader {}, FileProvider::new());
+    let path = Path::new("example-maps/default/default_map.tmx");
+    Map::from_file_with_loader(path, &mut loader).unwrap();
+
+    let names = names.0.lock().unwrap();
+    for name in [
+        "load_map",
+        "parse_map",
+        "parse_tileset",
+        "decode_tile_data",
+        "load_images",
+    ] {
+        assert!(names.contains(&name), "no span {} in {:?}", name, names);
+    }
 }